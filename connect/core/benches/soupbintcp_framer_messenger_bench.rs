@@ -0,0 +1,89 @@
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use links_core::unittest::setup;
+use soupbintcp_connect_core::prelude::*;
+use std::time::Duration;
+
+const MAX_MSG_SIZE: usize = 1024;
+const N_FRAMES: usize = 1_000;
+
+/// Repeating mix of svc messages as seen by a client during a busy session
+fn svc_mixed_msgs() -> Vec<SvcSoupBinTcpMsg<VecPayload>> {
+    let session_id: SessionId = b"session".as_slice().into();
+    let cycle = [
+        SvcSoupBinTcpMsg::sdata(VecPayload::new(setup::data::random_bytes(40).to_vec())),
+        SvcSoupBinTcpMsg::sdata(VecPayload::new(setup::data::random_bytes(200).to_vec())),
+        SvcSoupBinTcpMsg::udata(VecPayload::new(setup::data::random_bytes(10).to_vec())),
+        SvcSoupBinTcpMsg::hbeat(),
+        LoginAccepted::new(session_id, 1_u64.into()).into(),
+        SvcSoupBinTcpMsg::dbg(b"debug"),
+    ];
+    cycle.iter().cycle().take(N_FRAMES).cloned().collect()
+}
+
+fn soupbintcp_framer_get_frame(c: &mut Criterion) {
+    let mut bytes = BytesMut::new();
+    for msg in svc_mixed_msgs() {
+        let (buf, len) = SvcSoupBinTcpMessenger::<VecPayload, VecPayload>::serialize::<MAX_MSG_SIZE>(&msg).unwrap();
+        bytes.extend_from_slice(&buf[..len]);
+    }
+
+    let mut group = c.benchmark_group("soupbintcp_framer");
+    group.throughput(Throughput::Elements(N_FRAMES as u64));
+    group.bench_function("soupbintcp_framer_get_frame_mixed", |b| {
+        b.iter_batched(
+            || bytes.clone(),
+            |mut bytes| {
+                let mut count = 0_usize;
+                while let Some(frame) = SoupBinTcpFramer::get_frame(&mut bytes) {
+                    black_box(frame);
+                    count += 1;
+                }
+                assert_eq!(count, N_FRAMES);
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn soupbintcp_messenger_round_trip(c: &mut Criterion) {
+    type CltMessenger = CltSoupBinTcpMessenger<VecPayload, VecPayload>;
+    type SvcMessenger = SvcSoupBinTcpMessenger<VecPayload, VecPayload>;
+    let session_id: SessionId = b"session".as_slice().into();
+
+    let mut group = c.benchmark_group("soupbintcp_messenger");
+    let clt_msgs = [
+        (
+            "login",
+            CltSoupBinTcpMsg::login(b"userid".as_slice().into(), b"passwd".as_slice().into(), session_id, 1_u64.into(), Duration::from_secs(1).into()),
+        ),
+        ("udata", CltSoupBinTcpMsg::udata(VecPayload::new(setup::data::random_bytes(100).to_vec()))),
+    ];
+    for (name, msg) in clt_msgs {
+        group.bench_function(format!("soupbintcp_messenger_clt2svc_{}_round_trip", name), |b| {
+            b.iter(|| {
+                let (buf, len) = CltMessenger::serialize::<MAX_MSG_SIZE>(black_box(&msg)).unwrap();
+                let msg: CltSoupBinTcpMsg<VecPayload> = SvcMessenger::deserialize(&buf[..len]).unwrap();
+                black_box(msg)
+            })
+        });
+    }
+    let svc_msgs = [
+        ("login_accepted", LoginAccepted::new(session_id, 1_u64.into()).into()),
+        ("sdata", SvcSoupBinTcpMsg::sdata(VecPayload::new(setup::data::random_bytes(100).to_vec()))),
+    ];
+    for (name, msg) in svc_msgs {
+        group.bench_function(format!("soupbintcp_messenger_svc2clt_{}_round_trip", name), |b| {
+            b.iter(|| {
+                let (buf, len) = SvcMessenger::serialize::<MAX_MSG_SIZE>(black_box(&msg)).unwrap();
+                let msg: SvcSoupBinTcpMsg<VecPayload> = CltMessenger::deserialize(&buf[..len]).unwrap();
+                black_box(msg)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, soupbintcp_framer_get_frame, soupbintcp_messenger_round_trip);
+criterion_main!(benches);
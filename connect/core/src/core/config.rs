@@ -0,0 +1,505 @@
+use crate::{
+    core::protocol::builder::{DEFAULT_IO_TIMEOUT, DEFAULT_MAX_HBEAT_INTERVAL},
+    prelude::*,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::{
+    fmt::Debug,
+    fs,
+    io::{Error, ErrorKind},
+    num::NonZeroUsize,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Declarative configuration of a client session using [CltSoupBinTcpProtocolAuto], can be loaded from a `toml` or `json` file
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+///
+/// let config = CltSoupBinTcpConfig::from_toml_str(
+///     r#"
+///     addr = "127.0.0.1:8080"
+///     username = "userid"
+///     password = "passwd"
+///     session_id = "favsession"
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(config.sequence_number, 0);
+///
+/// let err = CltSoupBinTcpConfig::from_json_str(r#"{ "addr": "127.0.0.1:8080", "username": "too_long", "password": "passwd" }"#).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CltSoupBinTcpConfig {
+    /// address of the server, ex: `127.0.0.1:8080`
+    pub addr: String,
+    /// optional name used to identify the connection in the logs
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(deserialize_with = "deserialize_try_from_str")]
+    pub username: UserName,
+    #[serde(deserialize_with = "deserialize_try_from_str")]
+    pub password: Password,
+    /// blank to log into the currently active session
+    #[serde(default, deserialize_with = "deserialize_try_from_str")]
+    pub session_id: SessionId,
+    /// next sequence number the client wants to receive, `0` to start receiving the most recently generated message
+    #[serde(default)]
+    pub sequence_number: u64,
+    /// timeout for login sequence during [`ProtocolCore::on_connect`] hook
+    #[serde(default = "default_io_timeout_ms")]
+    pub io_timeout_ms: u64,
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    #[serde(default = "default_connect_retry_after_ms")]
+    pub connect_retry_after_ms: u64,
+    /// maximum interval between sending heartbeats, also sent to the server as [LoginRequest::hbeat_timeout_ms] hence must not exceed `u16::MAX`
+    #[serde(default = "default_max_hbeat_interval_ms")]
+    pub clt_max_hbeat_interval_ms: u64,
+    /// maximum interval between receiving heartbeats
+    #[serde(default = "default_max_hbeat_interval_ms")]
+    pub svc_max_hbeat_interval_ms: u64,
+    /// largest frame the session is expected to carry, must fit into `MAX_MSG_SIZE` of the [Clt] it is used with
+    #[serde(default)]
+    pub max_msg_size: Option<usize>,
+}
+impl CltSoupBinTcpConfig {
+    pub fn from_toml_str(toml: &str) -> Result<Self, Error> {
+        let config: Self = parse_toml(toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+    pub fn from_json_str(json: &str) -> Result<Self, Error> {
+        let config: Self = parse_json(json)?;
+        config.validate()?;
+        Ok(config)
+    }
+    /// Loads config from a file, format is determined by the file extension which must be either `toml` or `json`
+    pub fn from_file<F: AsRef<Path>>(path: F) -> Result<Self, Error> {
+        match read_config_file(path.as_ref())? {
+            ConfigFile::Toml(content) => Self::from_toml_str(&content),
+            ConfigFile::Json(content) => Self::from_json_str(&content),
+        }
+    }
+    /// Validates fields which can't be enforced by their types alone
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_addr(&self.addr)?;
+        validate_non_zero("io_timeout_ms", self.io_timeout_ms)?;
+        validate_non_zero("clt_max_hbeat_interval_ms", self.clt_max_hbeat_interval_ms)?;
+        TimeoutMs::try_from_duration(Duration::from_millis(self.clt_max_hbeat_interval_ms))?;
+        validate_non_zero("svc_max_hbeat_interval_ms", self.svc_max_hbeat_interval_ms)?;
+        Ok(())
+    }
+    /// Creates a new [CltSoupBinTcpProtocolAuto] instance using this config
+    pub fn to_protocol<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>>(&self) -> Result<CltSoupBinTcpProtocolAuto<RecvP, SendP>, Error> {
+        self.validate()?;
+        Ok(CltSoupBinTcpProtocolAuto::new(
+            self.username,
+            self.password,
+            self.session_id,
+            self.sequence_number.into(),
+            Duration::from_millis(self.io_timeout_ms),
+            Duration::from_millis(self.clt_max_hbeat_interval_ms),
+            Duration::from_millis(self.svc_max_hbeat_interval_ms),
+        ))
+    }
+    /// Creates a new [CltSoupBinTcpProtocolAuto] instance using this config and connects a [Clt] to the `addr`
+    pub fn connect<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<CltSoupBinTcpProtocolAuto<RecvP, SendP>>, const MAX_MSG_SIZE: usize>(
+        &self,
+        callback: Arc<C>,
+    ) -> Result<Clt<CltSoupBinTcpProtocolAuto<RecvP, SendP>, C, MAX_MSG_SIZE>, Error> {
+        validate_max_msg_size::<MAX_MSG_SIZE>(self.max_msg_size)?;
+        let protocol = self.to_protocol()?;
+        Clt::connect(
+            &self.addr,
+            Duration::from_millis(self.connect_timeout_ms),
+            Duration::from_millis(self.connect_retry_after_ms),
+            callback,
+            protocol,
+            self.name.as_deref(),
+        )
+    }
+    /// Loads config using [`Self::from_file`] and then [`Self::connect`] in one call
+    pub fn connect_from_file<
+        F: AsRef<Path>,
+        RecvP: SoupBinTcpPayload<RecvP>,
+        SendP: SoupBinTcpPayload<SendP>,
+        C: CallbackRecvSend<CltSoupBinTcpProtocolAuto<RecvP, SendP>>,
+        const MAX_MSG_SIZE: usize,
+    >(
+        path: F,
+        callback: Arc<C>,
+    ) -> Result<Clt<CltSoupBinTcpProtocolAuto<RecvP, SendP>, C, MAX_MSG_SIZE>, Error> {
+        Self::from_file(path)?.connect(callback)
+    }
+}
+impl Debug for CltSoupBinTcpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let password = "********".to_owned();
+        f.debug_struct("CltSoupBinTcpConfig")
+            .field("addr", &self.addr)
+            .field("name", &self.name)
+            .field("username", &self.username)
+            .field("password", &password)
+            .field("session_id", &self.session_id)
+            .field("sequence_number", &self.sequence_number)
+            .field("io_timeout_ms", &self.io_timeout_ms)
+            .field("connect_timeout_ms", &self.connect_timeout_ms)
+            .field("connect_retry_after_ms", &self.connect_retry_after_ms)
+            .field("clt_max_hbeat_interval_ms", &self.clt_max_hbeat_interval_ms)
+            .field("svc_max_hbeat_interval_ms", &self.svc_max_hbeat_interval_ms)
+            .field("max_msg_size", &self.max_msg_size)
+            .finish()
+    }
+}
+
+/// Declarative configuration of a server session using [SvcSoupBinTcpProtocolAuto], can be loaded from a `toml` or `json` file
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+///
+/// let config = SvcSoupBinTcpConfig::from_toml_str(
+///     r#"
+///     addr = "127.0.0.1:8080"
+///     max_connections = 10
+///     username = "userid"
+///     password = "passwd"
+///     session_id = "favsession"
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(config.max_connections.get(), 10);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SvcSoupBinTcpConfig {
+    /// address to bind to, ex: `0.0.0.0:8080`
+    pub addr: String,
+    /// optional name used to identify the connection in the logs
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: NonZeroUsize,
+    #[serde(deserialize_with = "deserialize_try_from_str")]
+    pub username: UserName,
+    #[serde(deserialize_with = "deserialize_try_from_str")]
+    pub password: Password,
+    #[serde(deserialize_with = "deserialize_try_from_str")]
+    pub session_id: SessionId,
+    /// timeout for login sequence during [`ProtocolCore::on_connect`] hook
+    #[serde(default = "default_io_timeout_ms")]
+    pub io_timeout_ms: u64,
+    /// maximum interval between sending heartbeats
+    #[serde(default = "default_max_hbeat_interval_ms")]
+    pub svc_max_hbeat_interval_ms: u64,
+    /// largest frame the session is expected to carry, must fit into `MAX_MSG_SIZE` of the [Svc] it is used with
+    #[serde(default)]
+    pub max_msg_size: Option<usize>,
+}
+impl SvcSoupBinTcpConfig {
+    pub fn from_toml_str(toml: &str) -> Result<Self, Error> {
+        let config: Self = parse_toml(toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+    pub fn from_json_str(json: &str) -> Result<Self, Error> {
+        let config: Self = parse_json(json)?;
+        config.validate()?;
+        Ok(config)
+    }
+    /// Loads config from a file, format is determined by the file extension which must be either `toml` or `json`
+    pub fn from_file<F: AsRef<Path>>(path: F) -> Result<Self, Error> {
+        match read_config_file(path.as_ref())? {
+            ConfigFile::Toml(content) => Self::from_toml_str(&content),
+            ConfigFile::Json(content) => Self::from_json_str(&content),
+        }
+    }
+    /// Validates fields which can't be enforced by their types alone
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_addr(&self.addr)?;
+        validate_non_zero("io_timeout_ms", self.io_timeout_ms)?;
+        validate_non_zero("svc_max_hbeat_interval_ms", self.svc_max_hbeat_interval_ms)?;
+        Ok(())
+    }
+    /// Creates a new [SvcSoupBinTcpProtocolAuto] instance using this config
+    pub fn to_protocol<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>>(&self) -> Result<SvcSoupBinTcpProtocolAuto<RecvP, SendP>, Error> {
+        self.validate()?;
+        Ok(SvcSoupBinTcpProtocolAuto::new(
+            self.username,
+            self.password,
+            self.session_id,
+            Duration::from_millis(self.io_timeout_ms),
+            Duration::from_millis(self.svc_max_hbeat_interval_ms),
+        ))
+    }
+    /// Creates a new [SvcSoupBinTcpProtocolAuto] instance using this config and binds a [Svc] to the `addr`
+    pub fn bind<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<SvcSoupBinTcpProtocolAuto<RecvP, SendP>>, const MAX_MSG_SIZE: usize>(
+        &self,
+        callback: Arc<C>,
+    ) -> Result<Svc<SvcSoupBinTcpProtocolAuto<RecvP, SendP>, C, MAX_MSG_SIZE>, Error> {
+        validate_max_msg_size::<MAX_MSG_SIZE>(self.max_msg_size)?;
+        let protocol = self.to_protocol()?;
+        Svc::bind(&self.addr, self.max_connections, callback, protocol, self.name.as_deref())
+    }
+    /// Loads config using [`Self::from_file`] and then [`Self::bind`] in one call
+    pub fn bind_from_file<F: AsRef<Path>, RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<SvcSoupBinTcpProtocolAuto<RecvP, SendP>>, const MAX_MSG_SIZE: usize>(
+        path: F,
+        callback: Arc<C>,
+    ) -> Result<Svc<SvcSoupBinTcpProtocolAuto<RecvP, SendP>, C, MAX_MSG_SIZE>, Error> {
+        Self::from_file(path)?.bind(callback)
+    }
+}
+impl Debug for SvcSoupBinTcpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let password = "********".to_owned();
+        f.debug_struct("SvcSoupBinTcpConfig")
+            .field("addr", &self.addr)
+            .field("name", &self.name)
+            .field("max_connections", &self.max_connections)
+            .field("username", &self.username)
+            .field("password", &password)
+            .field("session_id", &self.session_id)
+            .field("io_timeout_ms", &self.io_timeout_ms)
+            .field("svc_max_hbeat_interval_ms", &self.svc_max_hbeat_interval_ms)
+            .field("max_msg_size", &self.max_msg_size)
+            .finish()
+    }
+}
+
+fn parse_toml<T: DeserializeOwned>(toml: &str) -> Result<T, Error> {
+    toml::from_str(toml).map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid {} toml: {}", short_type_name::<T>(), e)))
+}
+fn parse_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json).map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid {} json: {}", short_type_name::<T>(), e)))
+}
+enum ConfigFile {
+    Toml(String),
+    Json(String),
+}
+fn read_config_file(path: &Path) -> Result<ConfigFile, Error> {
+    let content = fs::read_to_string(path).map_err(|e| Error::new(e.kind(), format!("Failed to read config file: {:?}, error: {}", path, e)))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("toml") => Ok(ConfigFile::Toml(content)),
+        Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(ConfigFile::Json(content)),
+        _ => Err(Error::new(ErrorKind::InvalidInput, format!("Unsupported config file extension: {:?}, expected `toml` or `json`", path))),
+    }
+}
+/// Fixed length ascii fields whose `From` conversions silently truncate or panic, hence are deserialized using their fallible constructors
+trait TryFromStr: Sized {
+    fn try_from_str(value: &str) -> Result<Self, Error>;
+}
+macro_rules! impl_try_from_str {
+    ($($NAME:ident),*) => {
+        $(impl TryFromStr for $NAME {
+            fn try_from_str(value: &str) -> Result<Self, Error> {
+                $NAME::try_from_str(value)
+            }
+        })*
+    };
+}
+impl_try_from_str!(UserName, Password, SessionId);
+fn deserialize_try_from_str<'de, D: Deserializer<'de>, T: TryFromStr>(deserializer: D) -> Result<T, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    T::try_from_str(&value).map_err(serde::de::Error::custom)
+}
+fn validate_addr(addr: &str) -> Result<(), Error> {
+    if addr.trim().is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "Invalid addr: must not be blank"));
+    }
+    Ok(())
+}
+fn validate_non_zero(field: &str, value: u64) -> Result<(), Error> {
+    if value == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid {}: must be greater than 0", field)));
+    }
+    Ok(())
+}
+fn validate_max_msg_size<const MAX_MSG_SIZE: usize>(max_msg_size: Option<usize>) -> Result<(), Error> {
+    match max_msg_size {
+        Some(max_msg_size) if max_msg_size > MAX_MSG_SIZE => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid max_msg_size: {} exceeds MAX_MSG_SIZE: {} of the connection", max_msg_size, MAX_MSG_SIZE),
+        )),
+        _ => Ok(()),
+    }
+}
+fn default_io_timeout_ms() -> u64 {
+    DEFAULT_IO_TIMEOUT.as_millis() as u64
+}
+fn default_connect_timeout_ms() -> u64 {
+    1_000
+}
+fn default_connect_retry_after_ms() -> u64 {
+    200
+}
+fn default_max_hbeat_interval_ms() -> u64 {
+    DEFAULT_MAX_HBEAT_INTERVAL.as_millis() as u64
+}
+fn default_max_connections() -> NonZeroUsize {
+    NonZeroUsize::new(1).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_clt_config_toml_json() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let toml = r#"
+            addr = "127.0.0.1:8080"
+            name = "clt/soupbintcp/config"
+            username = "userid"
+            password = "passwd"
+            session_id = "favsession"
+            sequence_number = 5
+            io_timeout_ms = 500
+            clt_max_hbeat_interval_ms = 1000
+            svc_max_hbeat_interval_ms = 15000
+            max_msg_size = 128
+        "#;
+        let config_toml = CltSoupBinTcpConfig::from_toml_str(toml).unwrap();
+        info!("config_toml: {:?}", config_toml);
+        assert_eq!(config_toml.username, b"userid".as_slice().into());
+        assert_eq!(config_toml.sequence_number, 5);
+        assert_eq!(config_toml.connect_timeout_ms, 1_000);
+        assert!(!format!("{:?}", config_toml).contains("passwd"));
+
+        let json = serde_json::to_string(&config_toml).unwrap();
+        info!("json: {}", json);
+        let config_json = CltSoupBinTcpConfig::from_json_str(&json).unwrap();
+        assert_eq!(config_toml, config_json);
+
+        let protocol = config_json.to_protocol::<SamplePayload, SamplePayload>().unwrap();
+        info!("protocol: {:?}", protocol);
+    }
+
+    #[test]
+    fn test_svc_config_toml_json() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let toml = r#"
+            addr = "0.0.0.0:8080"
+            max_connections = 2
+            username = "userid"
+            password = "passwd"
+            session_id = "favsession"
+        "#;
+        let config_toml = SvcSoupBinTcpConfig::from_toml_str(toml).unwrap();
+        info!("config_toml: {:?}", config_toml);
+        assert_eq!(config_toml.max_connections.get(), 2);
+        assert_eq!(config_toml.svc_max_hbeat_interval_ms, 2_500);
+
+        let json = serde_json::to_string(&config_toml).unwrap();
+        info!("json: {}", json);
+        let config_json = SvcSoupBinTcpConfig::from_json_str(&json).unwrap();
+        assert_eq!(config_toml, config_json);
+    }
+
+    #[test]
+    fn test_config_invalid() {
+        setup::log::configure_compact(LevelFilter::Info);
+        for (i, fail_toml) in [
+            r#" addr = "127.0.0.1:8080"
+                username = "1234567"
+                password = "passwd" "#, // username exceeds 6 chars
+            r#" addr = "127.0.0.1:8080"
+                username = "userid"
+                password = "12345678901" "#, // password exceeds 10 chars
+            r#" addr = "127.0.0.1:8080"
+                username = "userid"
+                password = "passwd"
+                session_id = "12345678901" "#, // session_id exceeds 10 chars
+            r#" addr = "127.0.0.1:8080"
+                username = "usérid"
+                password = "passwd" "#, // username not ascii
+            r#" addr = "127.0.0.1:8080"
+                username = "userid"
+                password = "passwd"
+                clt_max_hbeat_interval_ms = 70000 "#, // exceeds TimeoutMs
+            r#" addr = ""
+                username = "userid"
+                password = "passwd" "#, // blank addr
+            r#" addr = "127.0.0.1:8080"
+                username = "userid"
+                password = "passwd"
+                unknown = 1 "#, // unknown field
+        ]
+        .iter()
+        .enumerate()
+        {
+            info!("=========== {} ===========", i + 1);
+            let err = CltSoupBinTcpConfig::from_toml_str(fail_toml).unwrap_err();
+            info!("err: {}", err);
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+
+        // fixed length fields are rejected while loading, before validate
+        let err = serde_json::from_str::<SvcSoupBinTcpConfig>(r#"{ "addr": "0.0.0.0:8080", "username": "userid", "password": "passwd", "session_id": "12345678901" }"#).unwrap_err();
+        info!("err: {}", err);
+        assert!(err.to_string().contains("SessionId"));
+    }
+
+    #[test]
+    fn test_config_file() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let dir = std::env::temp_dir();
+        let toml_path = dir.join(format!("soupbintcp_clt_config_{}.toml", std::process::id()));
+        std::fs::write(
+            &toml_path,
+            r#" addr = "127.0.0.1:8080"
+                                       username = "userid"
+                                       password = "passwd" "#,
+        )
+        .unwrap();
+        let config = CltSoupBinTcpConfig::from_file(&toml_path).unwrap();
+        info!("config: {:?}", config);
+        std::fs::remove_file(&toml_path).unwrap();
+
+        let json_path = dir.join(format!("soupbintcp_clt_config_{}.json", std::process::id()));
+        std::fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+        assert_eq!(CltSoupBinTcpConfig::from_file(&json_path).unwrap(), config);
+        std::fs::remove_file(&json_path).unwrap();
+
+        let err = CltSoupBinTcpConfig::from_file(dir.join("soupbintcp_clt_config.yaml")).unwrap_err();
+        info!("err: {}", err);
+    }
+
+    #[test]
+    #[cfg(feature = "unittest")]
+    fn test_config_connect() {
+        setup::log::configure_compact(LevelFilter::Info);
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+        let addr = setup::net::rand_avail_addr_port();
+
+        let svc_config = SvcSoupBinTcpConfig::from_json_str(&format!(r#"{{ "addr": "{}", "username": "userid", "password": "passwd", "session_id": "favsession" }}"#, addr)).unwrap();
+        let clt_config = CltSoupBinTcpConfig::from_json_str(&format!(r#"{{ "addr": "{}", "username": "userid", "password": "passwd", "session_id": "favsession" }}"#, addr)).unwrap();
+
+        let mut svc = svc_config
+            .bind::<SamplePayload, SamplePayload, _, SOUP_BIN_MAX_FRAME_SIZE>(LoggerCallback::new_ref())
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let clt = clt_config
+            .connect::<SamplePayload, SamplePayload, _, SOUP_BIN_MAX_FRAME_SIZE>(LoggerCallback::new_ref())
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        assert!(clt.is_connected());
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        let clt_config = CltSoupBinTcpConfig {
+            max_msg_size: Some(SOUP_BIN_MAX_FRAME_SIZE + 1),
+            ..clt_config
+        };
+        let err = clt_config.connect::<SamplePayload, SamplePayload, _, SOUP_BIN_MAX_FRAME_SIZE>(LoggerCallback::new_ref()).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
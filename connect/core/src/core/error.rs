@@ -0,0 +1,268 @@
+use std::{
+    error::Error as StdError,
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+    Inbound,
+    Outbound,
+}
+
+/// Raised when a frame does not fit into the allowed size, carried inside [std::io::Error] and can be recovered using [`SoupBinTcpFrameSizeError::from_io_error`]
+///
+/// * [FrameDirection::Outbound] - message is larger then the `MAX_MSG_SIZE` send buffer, surfaces as [ErrorKind::InvalidInput]
+/// * [FrameDirection::Inbound] - peer announced `packet_length` larger then allowed, surfaces as [ErrorKind::InvalidData] and terminates the connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoupBinTcpFrameSizeError {
+    pub direction: FrameDirection,
+    pub actual: usize,
+    pub allowed: usize,
+}
+impl SoupBinTcpFrameSizeError {
+    pub fn outbound(actual: usize, allowed: usize) -> Self {
+        Self {
+            direction: FrameDirection::Outbound,
+            actual,
+            allowed,
+        }
+    }
+    pub fn inbound(actual: usize, allowed: usize) -> Self {
+        Self {
+            direction: FrameDirection::Inbound,
+            actual,
+            allowed,
+        }
+    }
+    /// Returns [SoupBinTcpFrameSizeError] if it is the inner error of `err`
+    pub fn from_io_error(err: &Error) -> Option<&Self> {
+        err.get_ref().and_then(|inner| inner.downcast_ref::<Self>())
+    }
+}
+impl Display for SoupBinTcpFrameSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.direction {
+            FrameDirection::Outbound => write!(f, "Oversize outbound message, frame size: {} exceeds allowed: {}", self.actual, self.allowed),
+            FrameDirection::Inbound => write!(f, "Oversize inbound frame, frame size: {} exceeds allowed: {}", self.actual, self.allowed),
+        }
+    }
+}
+impl StdError for SoupBinTcpFrameSizeError {}
+impl From<SoupBinTcpFrameSizeError> for Error {
+    fn from(err: SoupBinTcpFrameSizeError) -> Self {
+        match err.direction {
+            FrameDirection::Outbound => Error::new(ErrorKind::InvalidInput, err),
+            FrameDirection::Inbound => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+/// Number of leading frame bytes included in [`SoupBinTcpDecodeError::excerpt`]
+pub const DECODE_ERROR_EXCERPT_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// Frame is shorter then `packet_length` followed by `packet_type`, example `packet_length = 0`, or then the fixed size of its `packet_type`
+    Truncated,
+    /// `packet_type` is not one the peer is allowed to send
+    UnknownPacketType,
+    /// `packet_type` is valid but the message body failed to deserialize
+    Malformed(String),
+}
+
+/// Raised when a received frame can not be decoded, carried inside [std::io::Error] of [ErrorKind::InvalidData]
+/// and can be recovered using [`SoupBinTcpDecodeError::from_io_error`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoupBinTcpDecodeError {
+    pub kind: DecodeErrorKind,
+    pub packet_type: Option<u8>,
+    pub frame_len: usize,
+    /// Hex of up to [DECODE_ERROR_EXCERPT_LEN] leading bytes of the frame
+    pub excerpt: String,
+}
+impl SoupBinTcpDecodeError {
+    pub fn new(kind: DecodeErrorKind, frame: &[u8]) -> Self {
+        let mut excerpt = frame.iter().take(DECODE_ERROR_EXCERPT_LEN).map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" ");
+        if frame.len() > DECODE_ERROR_EXCERPT_LEN {
+            excerpt.push_str(" ..");
+        }
+        Self {
+            kind,
+            packet_type: frame.get(2).copied(),
+            frame_len: frame.len(),
+            excerpt,
+        }
+    }
+    /// Returns [SoupBinTcpDecodeError] if it is the inner error of `err`
+    pub fn from_io_error(err: &Error) -> Option<&Self> {
+        err.get_ref().and_then(|inner| inner.downcast_ref::<Self>())
+    }
+}
+impl Display for SoupBinTcpDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DecodeErrorKind::Truncated => write!(f, "Truncated frame")?,
+            DecodeErrorKind::UnknownPacketType => write!(f, "Unknown packet type")?,
+            DecodeErrorKind::Malformed(reason) => write!(f, "Malformed frame, caused by: [{}]", reason)?,
+        }
+        match self.packet_type {
+            Some(packet_type) => write!(f, ", packet_type: {:?}", packet_type as char)?,
+            None => write!(f, ", packet_type: None")?,
+        }
+        write!(f, ", frame_len: {}, excerpt: [{}]", self.frame_len, self.excerpt)
+    }
+}
+impl StdError for SoupBinTcpDecodeError {}
+impl From<SoupBinTcpDecodeError> for Error {
+    fn from(err: SoupBinTcpDecodeError) -> Self {
+        Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Determines how a protocol reacts to a frame which fails [SoupBinTcpDecodeError] validation. Selected at the type level, see
+/// [`CltSoupBinTcpProtocolAuto::with_decode_error_policy`](crate::prelude::CltSoupBinTcpProtocolAuto::with_decode_error_policy), since frames are split and decoded by the static [`Framer::get_frame`](crate::prelude::Framer::get_frame) & [`Messenger::deserialize`](crate::prelude::Messenger::deserialize)
+/// before the protocol instance sees them, so a decode error is never encoded into a message which could also be received off the wire.
+pub trait DecodeErrorPolicy: Debug + Clone + Send + Sync + 'static {
+    /// `true` drops frames which fail to decode in [`Framer::get_frame`](crate::prelude::Framer::get_frame), `false` returns [SoupBinTcpDecodeError] from [`Messenger::deserialize`](crate::prelude::Messenger::deserialize)
+    const SKIP: bool;
+}
+/// Terminates the connection with [SoupBinTcpDecodeError], default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisconnectOnDecodeError;
+impl DecodeErrorPolicy for DisconnectOnDecodeError {
+    const SKIP: bool = false;
+}
+/// Drops the frame with a warning and continues with the next one, applies to frames which are [DecodeErrorKind::Truncated] or of [DecodeErrorKind::UnknownPacketType].
+/// A [DecodeErrorKind::Malformed] body is only found by [`Messenger::deserialize`](crate::prelude::Messenger::deserialize), hence still terminates the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SkipOnDecodeError;
+impl DecodeErrorPolicy for SkipOnDecodeError {
+    const SKIP: bool = true;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{core::error::DECODE_ERROR_EXCERPT_LEN, prelude::*};
+    use bytes::BytesMut;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_frame_size_outbound() {
+        setup::log::configure_compact(LevelFilter::Info);
+        const MAX_MSG_SIZE: usize = 16;
+        let msg = CltSoupBinTcpMsg::udata(VecPayload::new(vec![0; 20]));
+        let err = CltSoupBinTcpMessenger::<Nil, VecPayload>::serialize::<MAX_MSG_SIZE>(&msg).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(SoupBinTcpFrameSizeError::from_io_error(&err), Some(&SoupBinTcpFrameSizeError::outbound(23, MAX_MSG_SIZE)));
+
+        let msg = SvcSoupBinTcpMsg::udata(VecPayload::new(vec![0; 13]));
+        let (_, len) = SvcSoupBinTcpMessenger::<Nil, VecPayload>::serialize::<MAX_MSG_SIZE>(&msg).unwrap();
+        assert_eq!(len, MAX_MSG_SIZE);
+    }
+
+    #[test]
+    fn test_frame_size_inbound() {
+        setup::log::configure_compact(LevelFilter::Info);
+        SoupBinTcpFramer::validate_frame_size(&[0x00, 0x0E, b'U'], 16).unwrap();
+        let err = SoupBinTcpFramer::validate_frame_size(&[0x00, 0x0F, b'U'], 16).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(SoupBinTcpFrameSizeError::from_io_error(&err), Some(&SoupBinTcpFrameSizeError::inbound(17, 16)));
+        assert_eq!(SoupBinTcpFrameSizeError::from_io_error(&std::io::Error::new(ErrorKind::InvalidData, "other")), None);
+
+        // announced frame larger then the packet type allows is split off as soon as the header arrives and rejected before decoding
+        type SvcMessenger = SvcSoupBinTcpMessenger<SamplePayload, Nil>;
+        let mut bytes = BytesMut::from([0x00, 0xFF, b'U'].as_slice());
+        assert_eq!(SvcMessenger::get_frame_length(&bytes), Some(3));
+        let frame = SvcMessenger::get_frame(&mut bytes).unwrap();
+        let err = SvcMessenger::deserialize(&frame[..]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(SoupBinTcpFrameSizeError::from_io_error(&err), Some(&SoupBinTcpFrameSizeError::inbound(257, 23)));
+        let mut bytes = BytesMut::from([0x00, 0x02, b'R', 0x00].as_slice());
+        assert_eq!(SvcMessenger::get_frame_length(&bytes), Some(3));
+        let err = SvcMessenger::deserialize(&SvcMessenger::get_frame(&mut bytes).unwrap()[..]).unwrap_err();
+        assert_eq!(SoupBinTcpFrameSizeError::from_io_error(&err), Some(&SoupBinTcpFrameSizeError::inbound(4, 3)));
+        // payload with in MAX_BYTE_LEN waits for the complete frame
+        let mut bytes = BytesMut::from([0x00, 0x15, b'U'].as_slice());
+        assert_eq!(SvcMessenger::get_frame_length(&bytes), None);
+        bytes.extend_from_slice(&[b' '; 20]);
+        let frame = SvcMessenger::get_frame(&mut bytes).unwrap();
+        assert!(matches!(SvcMessenger::deserialize(&frame[..]).unwrap(), CltSoupBinTcpMsg::UPayload(_)));
+
+        let err = CltSoupBinTcpProtocolAuto::<Nil, Nil>::builder()
+            .username("userid")
+            .password("passwd")
+            .max_recv_frame_size(2)
+            .build()
+            .unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let protocol = SvcSoupBinTcpProtocolAuto::<Nil, Nil>::builder()
+            .username("userid")
+            .password("passwd")
+            .session_id("session")
+            .max_recv_frame_size(1024)
+            .build()
+            .unwrap();
+        assert_eq!(protocol.max_recv_frame_size(), Some(1024));
+    }
+
+    #[test]
+    fn test_decode_error() {
+        setup::log::configure_compact(LevelFilter::Info);
+        type CltMessenger = CltSoupBinTcpMessenger<Nil, Nil>;
+        type SvcMessenger = SvcSoupBinTcpMessenger<Nil, Nil>;
+
+        // packet_length = 0
+        let err = SvcMessenger::deserialize(&[0x00, 0x00]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = SoupBinTcpDecodeError::from_io_error(&err).unwrap();
+        assert_eq!(err.kind, DecodeErrorKind::Truncated);
+        assert_eq!(err.packet_type, None);
+
+        // svc only packet type received by svc
+        let err = SvcMessenger::deserialize(&[0x00, 0x01, b'H']).unwrap_err();
+        info!("err: {}", err);
+        let err = SoupBinTcpDecodeError::from_io_error(&err).unwrap();
+        assert_eq!(err.kind, DecodeErrorKind::UnknownPacketType);
+        assert_eq!(err.packet_type, Some(b'H'));
+        assert_eq!(err.excerpt, "00 01 48");
+        CltMessenger::deserialize(&[0x00, 0x01, b'H']).unwrap();
+
+        // valid packet type with truncated body
+        let err = SvcMessenger::deserialize(&[0x00, 0x02, b'L', b'u']).unwrap_err();
+        info!("err: {}", err);
+        let err = SoupBinTcpDecodeError::from_io_error(&err).unwrap();
+        assert!(matches!(err.kind, DecodeErrorKind::Malformed(_)));
+        assert_eq!(err.packet_type, Some(b'L'));
+
+        // LoginRejected without reason
+        let err = CltMessenger::deserialize(&[0x00, 0x01, b'J']).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(SoupBinTcpDecodeError::from_io_error(&err).unwrap().kind, DecodeErrorKind::Truncated);
+
+        // packet_length smaller then packet_type, or then the fixed part of the packet, must not underflow while computing the body length
+        for frame in [[0x00, 0x00, b'+'], [0x00, 0x00, b'S'], [0x00, 0x00, b'U']] {
+            for err in [CltMessenger::deserialize(&frame).unwrap_err(), SvcMessenger::deserialize(&frame).unwrap_err()] {
+                info!("frame: {:?}, err: {}", frame, err);
+                let err = SoupBinTcpDecodeError::from_io_error(&err).unwrap();
+                assert_eq!(err.kind, DecodeErrorKind::Truncated);
+                assert_eq!(err.packet_type, Some(frame[2]));
+            }
+        }
+        let err = CltMessenger::deserialize(&[0x00, 0x01, b'J', b'A']).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(SoupBinTcpDecodeError::from_io_error(&err).unwrap().kind, DecodeErrorKind::Truncated);
+
+        let frame = [0xff_u8; DECODE_ERROR_EXCERPT_LEN + 1];
+        let err = SoupBinTcpDecodeError::new(DecodeErrorKind::UnknownPacketType, &frame);
+        assert!(err.excerpt.ends_with(" .."));
+        assert_eq!(err.frame_len, DECODE_ERROR_EXCERPT_LEN + 1);
+    }
+}
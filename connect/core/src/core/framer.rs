@@ -1,7 +1,74 @@
 use crate::prelude::*;
 use bytes::BytesMut;
+use std::io::Error;
 
+#[derive(Debug)]
 pub struct SoupBinTcpFramer;
+impl SoupBinTcpFramer {
+    /// Packet types a client may send, see [CltSoupBinTcpMsg]
+    pub const CLT_PACKET_TYPES: &'static [u8] = b"USR+LO";
+    /// Packet types a server may send, see [SvcSoupBinTcpMsg]
+    pub const SVC_PACKET_TYPES: &'static [u8] = b"H+AJZUS";
+
+    /// Checks that `frame` holds at least `packet_length` & `packet_type`, that `packet_type` is one of `packet_types`
+    /// and that neither `frame` nor the frame announced by `packet_length` is shorter then [min_frame_len] of its `packet_type`
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData](std::io::ErrorKind::InvalidData) with [SoupBinTcpDecodeError] as the inner error
+    #[inline(always)]
+    pub fn validate_frame(frame: &[u8], packet_types: &[u8]) -> Result<(), Error> {
+        if frame.len() < PACKET_HEADER_BYTE_LEN {
+            return Err(SoupBinTcpDecodeError::new(DecodeErrorKind::Truncated, frame).into());
+        }
+        if !packet_types.contains(&frame[2]) {
+            return Err(SoupBinTcpDecodeError::new(DecodeErrorKind::UnknownPacketType, frame).into());
+        }
+        let announced_len = PACKET_LENGTH_BYTE_LEN + u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        if frame.len() < min_frame_len(frame[2]) || announced_len < min_frame_len(frame[2]) {
+            return Err(SoupBinTcpDecodeError::new(DecodeErrorKind::Truncated, frame).into());
+        }
+        Ok(())
+    }
+    /// Checks `packet_length` announced in the header of `frame` against [max_frame_len] of its `packet_type`, frames shorter then the header are
+    /// left to [`Self::validate_frame`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData](std::io::ErrorKind::InvalidData) with [SoupBinTcpFrameSizeError] as the inner error if the announced frame size exceeds [max_frame_len]
+    #[inline(always)]
+    pub fn validate_packet_length(frame: &[u8], max_payload_len: usize) -> Result<(), Error> {
+        if frame.len() < PACKET_HEADER_BYTE_LEN {
+            return Ok(());
+        }
+        Self::validate_frame_size(frame, max_frame_len(frame[2], max_payload_len))
+    }
+    /// Same as [Framer::get_frame_length] except that once the header shows that the announced frame exceeds [max_frame_len] of its `packet_type`
+    /// only the header is returned as a frame, without waiting for the rest of the frame to arrive, so that [`Self::validate_packet_length`] rejects it before decoding
+    #[inline(always)]
+    pub fn get_frame_length_bounded(bytes: &BytesMut, max_payload_len: usize) -> Option<usize> {
+        if bytes.len() >= PACKET_HEADER_BYTE_LEN {
+            let frame_size = u16::from_be_bytes([bytes[0], bytes[1]]) as usize + PACKET_LENGTH_BYTE_LEN;
+            if frame_size > max_frame_len(bytes[2], max_payload_len) {
+                return Some(PACKET_HEADER_BYTE_LEN);
+            }
+        }
+        Self::get_frame_length(bytes)
+    }
+    /// Checks `packet_length` announced in the header of `frame` against `max_frame_size`, which includes the two byte length prefix
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData](std::io::ErrorKind::InvalidData) with [SoupBinTcpFrameSizeError] as the inner error if the announced frame size exceeds `max_frame_size`
+    #[inline(always)]
+    pub fn validate_frame_size(frame: &[u8], max_frame_size: usize) -> Result<(), Error> {
+        if frame.len() < PACKET_LENGTH_BYTE_LEN {
+            return Ok(());
+        }
+        let frame_size = u16::from_be_bytes([frame[0], frame[1]]) as usize + PACKET_LENGTH_BYTE_LEN;
+        if frame_size > max_frame_size {
+            return Err(SoupBinTcpFrameSizeError::inbound(frame_size, max_frame_size).into());
+        }
+        Ok(())
+    }
+}
 
 impl Framer for SoupBinTcpFramer {
     #[inline(always)]
@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::prelude::*;
-use byteserde::prelude::{from_slice, to_bytes_stack};
+use byteserde::prelude::{from_slice, to_bytes_stack, ByteSerializedLenOf};
 
 /// Performs two tasks
 ///  * Divides [bytes::BytesMut] into frames and deserializes into a [SvcSoupBinTcpMsg] type
@@ -18,11 +18,20 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBi
     // pub fn new() -> Self {
     //     Self { phantom: PhantomData }
     // }
+
+    /// Zero copy alternative to [Messenger::deserialize], applies the same frame validation but leaves the payload to be decoded lazily
+    /// using [SoupBinTcpMsgView::decode_payload]
+    #[inline(always)]
+    pub fn deserialize_view(frame: &[u8]) -> Result<SoupBinTcpMsgView<'_>, Error> {
+        SoupBinTcpFramer::validate_packet_length(frame, RecvP::MAX_BYTE_LEN)?;
+        SoupBinTcpFramer::validate_frame(frame, SoupBinTcpFramer::SVC_PACKET_TYPES)?;
+        SoupBinTcpMsgView::try_new(frame)
+    }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for CltSoupBinTcpMessenger<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        SoupBinTcpFramer::get_frame_length_bounded(bytes, RecvP::MAX_BYTE_LEN)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for CltSoupBinTcpMessenger<RecvP, SendP> {
@@ -31,17 +40,23 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger
 
     #[inline(always)]
     fn serialize<const MAX_MSG_SIZE: usize>(msg: &Self::SendT) -> Result<([u8; MAX_MSG_SIZE], usize), std::io::Error> {
+        validate_msg_size::<MAX_MSG_SIZE>(msg.byte_len())?;
         match to_bytes_stack::<MAX_MSG_SIZE, Self::SendT>(msg) {
             Ok(res) => Ok(res),
             Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
         }
     }
 
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData] with [SoupBinTcpFrameSizeError] as the inner error if the announced frame exceeds [max_frame_len],
+    /// example [SPayload] longer then [SoupBinTcpPayload::MAX_BYTE_LEN], or with [SoupBinTcpDecodeError] as the inner error
     #[inline(always)]
     fn deserialize(frame: &[u8]) -> Result<Self::RecvT, Error> {
+        SoupBinTcpFramer::validate_packet_length(frame, RecvP::MAX_BYTE_LEN)?;
+        SoupBinTcpFramer::validate_frame(frame, SoupBinTcpFramer::SVC_PACKET_TYPES)?;
         match from_slice::<Self::RecvT>(frame) {
             Ok(res) => Ok(res),
-            Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+            Err(e) => Err(SoupBinTcpDecodeError::new(DecodeErrorKind::Malformed(e.message), frame).into()),
         }
     }
 }
@@ -57,11 +72,20 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBi
     pub fn new_ref() -> Self {
         Self { phantom: PhantomData }
     }
+
+    /// Zero copy alternative to [Messenger::deserialize], applies the same frame validation but leaves the payload to be decoded lazily
+    /// using [SoupBinTcpMsgView::decode_payload]
+    #[inline(always)]
+    pub fn deserialize_view(frame: &[u8]) -> Result<SoupBinTcpMsgView<'_>, Error> {
+        SoupBinTcpFramer::validate_packet_length(frame, RecvP::MAX_BYTE_LEN)?;
+        SoupBinTcpFramer::validate_frame(frame, SoupBinTcpFramer::CLT_PACKET_TYPES)?;
+        SoupBinTcpMsgView::try_new(frame)
+    }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for SvcSoupBinTcpMessenger<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        SoupBinTcpFramer::get_frame_length_bounded(bytes, RecvP::MAX_BYTE_LEN)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for SvcSoupBinTcpMessenger<RecvP, SendP> {
@@ -70,21 +94,36 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger
 
     #[inline(always)]
     fn serialize<const MAX_MSG_SIZE: usize>(msg: &Self::SendT) -> Result<([u8; MAX_MSG_SIZE], usize), std::io::Error> {
+        validate_msg_size::<MAX_MSG_SIZE>(msg.byte_len())?;
         match to_bytes_stack::<MAX_MSG_SIZE, Self::SendT>(msg) {
             Ok(res) => Ok(res),
             Err(e) => Err(Error::new(std::io::ErrorKind::Other, e)),
         }
     }
 
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData] with [SoupBinTcpFrameSizeError] as the inner error if the announced frame exceeds [max_frame_len],
+    /// example [SPayload] longer then [SoupBinTcpPayload::MAX_BYTE_LEN], or with [SoupBinTcpDecodeError] as the inner error
     #[inline(always)]
     fn deserialize(frame: &[u8]) -> Result<Self::RecvT, Error> {
+        SoupBinTcpFramer::validate_packet_length(frame, RecvP::MAX_BYTE_LEN)?;
+        SoupBinTcpFramer::validate_frame(frame, SoupBinTcpFramer::CLT_PACKET_TYPES)?;
         match from_slice::<Self::RecvT>(frame) {
             Ok(res) => Ok(res),
-            Err(e) => Err(Error::new(std::io::ErrorKind::Other, e)),
+            Err(e) => Err(SoupBinTcpDecodeError::new(DecodeErrorKind::Malformed(e.message), frame).into()),
         }
     }
 }
 
+/// Returns [ErrorKind::InvalidInput] with [SoupBinTcpFrameSizeError] as the inner error if `msg_size` exceeds `MAX_MSG_SIZE`
+#[inline(always)]
+fn validate_msg_size<const MAX_MSG_SIZE: usize>(msg_size: usize) -> Result<(), Error> {
+    if msg_size > MAX_MSG_SIZE {
+        return Err(SoupBinTcpFrameSizeError::outbound(msg_size, MAX_MSG_SIZE).into());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[cfg(feature = "unittest")]
 mod test {
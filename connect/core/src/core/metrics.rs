@@ -0,0 +1,650 @@
+use crate::prelude::*;
+use byteserde::prelude::ByteSerializedLenOf;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Packet types tracked by [SoupBinTcpMetricsCallback], any other packet type is counted under `'?'`
+const PACKET_TYPES: [u8; 11] = [b'+', b'A', b'H', b'J', b'L', b'O', b'R', b'S', b'U', b'Z', b'?'];
+
+#[inline(always)]
+fn packet_type_index(packet_type: u8) -> usize {
+    PACKET_TYPES.iter().position(|p| *p == packet_type).unwrap_or(PACKET_TYPES.len() - 1)
+}
+
+/// Session level meaning of a message, used by [SoupBinTcpMetricsCallback] in addition to packet type & length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsEvent {
+    Other,
+    LoginRequest,
+    LoginAccepted {
+        sequence_number: Option<u64>,
+    },
+    LoginRejected {
+        not_authorized: bool,
+    },
+    /// [SPayload] sent by the server, advances the sequence number
+    Sequenced,
+}
+
+/// Implemented by [CltSoupBinTcpMsg] & [SvcSoupBinTcpMsg] so that [SoupBinTcpMetricsCallback] can be used on either side of the session
+pub trait SoupBinTcpMetricsMsg {
+    fn packet_type(&self) -> u8;
+    /// Length of the frame including the two byte `packet_length` field
+    fn frame_len(&self) -> usize;
+    fn metrics_event(&self) -> MetricsEvent;
+}
+impl<P: SoupBinTcpPayload<P>> SoupBinTcpMetricsMsg for CltSoupBinTcpMsg<P> {
+    #[inline(always)]
+    fn packet_type(&self) -> u8 {
+        match self {
+            Self::HBeat(_) => b'R',
+            Self::Dbg(_) => b'+',
+            Self::LoginRequest(_) => b'L',
+            Self::LogoutRequest(_) => b'O',
+            Self::SPayload(_) => b'S',
+            Self::UPayload(_) => b'U',
+        }
+    }
+    #[inline(always)]
+    fn frame_len(&self) -> usize {
+        self.byte_len()
+    }
+    #[inline(always)]
+    fn metrics_event(&self) -> MetricsEvent {
+        match self {
+            Self::LoginRequest(_) => MetricsEvent::LoginRequest,
+            _ => MetricsEvent::Other,
+        }
+    }
+}
+impl<P: SoupBinTcpPayload<P>> SoupBinTcpMetricsMsg for SvcSoupBinTcpMsg<P> {
+    #[inline(always)]
+    fn packet_type(&self) -> u8 {
+        match self {
+            Self::HBeat(_) => b'H',
+            Self::Dbg(_) => b'+',
+            Self::LoginAccepted(_) => b'A',
+            Self::LoginRejected(_) => b'J',
+            Self::EndOfSession(_) => b'Z',
+            Self::SPayload(_) => b'S',
+            Self::UPayload(_) => b'U',
+        }
+    }
+    #[inline(always)]
+    fn frame_len(&self) -> usize {
+        self.byte_len()
+    }
+    #[inline(always)]
+    fn metrics_event(&self) -> MetricsEvent {
+        match self {
+            Self::LoginAccepted(msg) => MetricsEvent::LoginAccepted {
+                sequence_number: msg.sequence_number().try_to_u64().ok(),
+            },
+            Self::LoginRejected(msg) => MetricsEvent::LoginRejected {
+                not_authorized: msg.is_not_authorized(),
+            },
+            Self::SPayload(_) => MetricsEvent::Sequenced,
+            _ => MetricsEvent::Other,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DirectionCounters {
+    messages: [AtomicU64; PACKET_TYPES.len()],
+    bytes: [AtomicU64; PACKET_TYPES.len()],
+}
+impl DirectionCounters {
+    #[inline(always)]
+    fn on_msg(&self, packet_type: u8, frame_len: usize) {
+        let idx = packet_type_index(packet_type);
+        self.messages[idx].fetch_add(1, Relaxed);
+        self.bytes[idx].fetch_add(frame_len as u64, Relaxed);
+    }
+    fn snapshot(&self) -> DirectionMetrics {
+        let mut snapshot = DirectionMetrics::default();
+        for (idx, packet_type) in PACKET_TYPES.iter().enumerate() {
+            let packet = PacketMetrics {
+                messages: self.messages[idx].load(Relaxed),
+                bytes: self.bytes[idx].load(Relaxed),
+            };
+            if packet.messages == 0 {
+                continue;
+            }
+            snapshot.messages += packet.messages;
+            snapshot.bytes += packet.bytes;
+            if matches!(packet_type, b'H' | b'R') {
+                snapshot.hbeats += packet.messages;
+            }
+            snapshot.by_packet_type.insert(*packet_type as char, packet);
+        }
+        snapshot
+    }
+}
+
+/// Packet types after which the connection is terminated by either side
+#[inline(always)]
+fn is_terminal_packet_type(packet_type: u8) -> bool {
+    matches!(packet_type, b'J' | b'O' | b'Z')
+}
+
+/// Value `0` means not set, so that optional values can be stored in an [AtomicU64]
+#[derive(Debug, Default)]
+struct OptionalU64(AtomicU64);
+impl OptionalU64 {
+    #[inline(always)]
+    fn set(&self, value: u64) {
+        self.0.store(value.saturating_add(1), Relaxed);
+    }
+    #[inline(always)]
+    fn get(&self) -> Option<u64> {
+        self.0.load(Relaxed).checked_sub(1)
+    }
+}
+
+#[derive(Debug)]
+struct ConnectionCounters {
+    con_id: ConId,
+    recv: DirectionCounters,
+    sent: DirectionCounters,
+    next_sequence_number: OptionalU64,
+    replay_size: OptionalU64,
+    last_recv_nanos: OptionalU64,
+    events: EventCounters,
+    is_closed: AtomicBool,
+}
+impl ConnectionCounters {
+    fn new(con_id: ConId) -> Self {
+        Self {
+            con_id,
+            recv: Default::default(),
+            sent: Default::default(),
+            next_sequence_number: Default::default(),
+            replay_size: Default::default(),
+            last_recv_nanos: Default::default(),
+            events: Default::default(),
+            is_closed: AtomicBool::new(false),
+        }
+    }
+    #[inline(always)]
+    fn on_msg(&self, direction: &DirectionCounters, packet_type: u8, frame_len: usize) {
+        direction.on_msg(packet_type, frame_len);
+        if is_terminal_packet_type(packet_type) {
+            self.is_closed.store(true, Relaxed);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LoginCounters {
+    requested: AtomicU64,
+    accepted: AtomicU64,
+    rejected_not_authorized: AtomicU64,
+    rejected_session_not_available: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct EventCounters {
+    hbeat_timeouts: AtomicU64,
+    frames_too_large: AtomicU64,
+    peer_logouts: AtomicU64,
+}
+impl EventCounters {
+    fn snapshot(&self) -> EventMetrics {
+        EventMetrics {
+            hbeat_timeouts: self.hbeat_timeouts.load(Relaxed),
+            frames_too_large: self.frames_too_large.load(Relaxed),
+            peer_logouts: self.peer_logouts.load(Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketMetrics {
+    pub messages: u64,
+    /// Includes the two byte `packet_length` field
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectionMetrics {
+    pub messages: u64,
+    pub bytes: u64,
+    /// [CltHeartbeat] & [SvcHeartbeat], also included in `by_packet_type` under `'R'` & `'H'`
+    pub hbeats: u64,
+    /// Only packet types which were seen at least once
+    pub by_packet_type: BTreeMap<char, PacketMetrics>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginMetrics {
+    pub requested: u64,
+    pub accepted: u64,
+    pub rejected_not_authorized: u64,
+    pub rejected_session_not_available: u64,
+}
+
+/// Counts of [SoupBinTcpEvent]s, only collected when [SoupBinTcpMetricsCallback] is also registered as the protocol's [SoupBinTcpEventCallback]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMetrics {
+    /// [SoupBinTcpEvent::PeerHeartbeatTimeout]
+    pub hbeat_timeouts: u64,
+    /// [SoupBinTcpEvent::InboundFrameTooLarge]
+    pub frames_too_large: u64,
+    /// [SoupBinTcpEvent::PeerLogout]
+    pub peer_logouts: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionMetrics {
+    pub con_id: String,
+    /// `false` once [LoginRejected], [LogoutRequest] or [EndOfSession] was exchanged or a terminating [SoupBinTcpEvent] was raised
+    pub is_open: bool,
+    pub recv: DirectionMetrics,
+    pub sent: DirectionMetrics,
+    /// Sequence number of the last [SPayload] sent by the server, `0` if none were sent since [LoginAccepted]
+    pub current_sequence_number: Option<u64>,
+    /// Sequence number of the next [SPayload], starts at the one in [LoginAccepted]
+    pub next_sequence_number: Option<u64>,
+    /// Number of [SPayload]s the server had to replay after [LoginAccepted], only known on the server side
+    pub replay_size: Option<u64>,
+    /// Number of [SPayload]s the connection is behind the highest sequence number sent on any connection, only known on the server side
+    pub sequence_lag: Option<u64>,
+    pub since_last_recv: Option<Duration>,
+    pub events: EventMetrics,
+}
+
+/// Point in time copy of all metrics collected by [SoupBinTcpMetricsCallback]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoupBinTcpMetricsSnapshot {
+    pub logins: LoginMetrics,
+    /// Highest [SPayload] sequence number sent on any connection, only known on the server side
+    pub session_sequence_number: Option<u64>,
+    pub connections: Vec<ConnectionMetrics>,
+}
+impl SoupBinTcpMetricsSnapshot {
+    pub fn open_connections(&self) -> usize {
+        self.connections.iter().filter(|c| c.is_open).count()
+    }
+    /// Sum of [EventMetrics] across all connections
+    pub fn events(&self) -> EventMetrics {
+        self.connections.iter().fold(EventMetrics::default(), |mut total, c| {
+            total.hbeat_timeouts += c.events.hbeat_timeouts;
+            total.frames_too_large += c.events.frames_too_large;
+            total.peer_logouts += c.events.peer_logouts;
+            total
+        })
+    }
+    pub fn connection(&self, con_id: &ConId) -> Option<&ConnectionMetrics> {
+        let con_id = con_id.to_string();
+        self.connections.iter().find(|c| c.con_id == con_id)
+    }
+}
+
+/// Collects per connection metrics of a SoupBinTcp session, can be chained with other callbacks using [ChainCallback] and shared by all
+/// connections of a [Svc], in which case `replay_size` is derived from the highest sequence number sent on any connection.
+///
+/// Counters are atomics, connections are looked up under a read lock so that only the first message of a new connection takes the write lock.
+///
+/// # Important
+/// Closed connections, see [ConnectionMetrics::is_open], remain tracked until [Self::snapshot_and_prune], [Self::remove_closed] or [Self::remove]
+/// drop them, hence the owner must call one of them periodically for the number of tracked connections not to grow with every reconnect.
+/// [Self::snapshot] never drops connections so that any number of readers, ex: a metrics exporter, can take snapshots independently.
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+///
+/// let metrics = SoupBinTcpMetricsCallback::<SvcSoupBinTcpProtocolAuto<Nil, Nil>>::new_ref();
+/// let con_id = ConId::svc(Some("svc"), "127.0.0.1:8080", None);
+/// CallbackSend::on_sent(metrics.as_ref(), &con_id, &SvcSoupBinTcpMsg::hbeat());
+///
+/// let snapshot = metrics.snapshot();
+/// assert_eq!(snapshot.connection(&con_id).unwrap().sent.hbeats, 1);
+///
+/// let json = serde_json::to_value(&snapshot).unwrap();
+/// assert_eq!(json["connections"][0]["is_open"], true);
+/// assert_eq!(json["connections"][0]["sent"]["by_packet_type"]["H"], serde_json::json!({ "messages": 1, "bytes": 3 }));
+/// ```
+pub struct SoupBinTcpMetricsCallback<M: Messenger, C: Clock = MonotonicClock> {
+    connections: RwLock<Vec<Arc<ConnectionCounters>>>,
+    logins: LoginCounters,
+    session_next_sequence_number: AtomicU64,
+    clock: C,
+    start: Instant,
+    phantom: PhantomData<M>,
+}
+impl<M: Messenger> SoupBinTcpMetricsCallback<M> {
+    pub fn new() -> Self {
+        Self::with_clock(MonotonicClock)
+    }
+    pub fn new_ref() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+}
+impl<M: Messenger> Default for SoupBinTcpMetricsCallback<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<M: Messenger, C: Clock> SoupBinTcpMetricsCallback<M, C> {
+    /// Uses `clock` to measure time since the last inbound frame, see [ManualClock]
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            connections: RwLock::new(vec![]),
+            logins: LoginCounters::default(),
+            session_next_sequence_number: AtomicU64::new(0),
+            start: clock.now(),
+            clock,
+            phantom: PhantomData,
+        }
+    }
+    #[inline(always)]
+    fn connection(&self, con_id: &ConId) -> Arc<ConnectionCounters> {
+        if let Some(con) = self.connections.read().unwrap().iter().find(|con| con.con_id == *con_id) {
+            return con.clone();
+        }
+        let mut connections = self.connections.write().unwrap();
+        match connections.iter().find(|con| con.con_id == *con_id) {
+            Some(con) => con.clone(),
+            None => {
+                let con = Arc::new(ConnectionCounters::new(con_id.clone()));
+                connections.push(con.clone());
+                con
+            }
+        }
+    }
+    #[inline(always)]
+    fn on_event(&self, con: &ConnectionCounters, event: MetricsEvent, is_sent: bool) {
+        match event {
+            MetricsEvent::Other => {}
+            MetricsEvent::LoginRequest => {
+                self.logins.requested.fetch_add(1, Relaxed);
+            }
+            MetricsEvent::LoginAccepted { sequence_number } => {
+                self.logins.accepted.fetch_add(1, Relaxed);
+                if let Some(sequence_number) = sequence_number {
+                    con.next_sequence_number.set(sequence_number);
+                    if is_sent {
+                        let session_next = self.session_next_sequence_number.load(Relaxed);
+                        con.replay_size.set(session_next.saturating_sub(sequence_number));
+                    }
+                }
+            }
+            MetricsEvent::LoginRejected { not_authorized: true } => {
+                self.logins.rejected_not_authorized.fetch_add(1, Relaxed);
+            }
+            MetricsEvent::LoginRejected { not_authorized: false } => {
+                self.logins.rejected_session_not_available.fetch_add(1, Relaxed);
+            }
+            MetricsEvent::Sequenced => {
+                if let Some(next) = con.next_sequence_number.get() {
+                    con.next_sequence_number.set(next + 1);
+                    if is_sent {
+                        self.session_next_sequence_number.fetch_max(next + 1, Relaxed);
+                    }
+                }
+            }
+        }
+    }
+    /// Stops tracking the connection regardless of whether it is closed, its messages remain included in the login totals
+    pub fn remove(&self, con_id: &ConId) {
+        self.connections.write().unwrap().retain(|con| con.con_id != *con_id);
+    }
+    /// Stops tracking closed connections without reporting them, returns the number of connections removed
+    pub fn remove_closed(&self) -> usize {
+        let mut connections = self.connections.write().unwrap();
+        let len = connections.len();
+        connections.retain(|con| !con.is_closed.load(Relaxed));
+        len - connections.len()
+    }
+    /// Reports all tracked connections, including closed ones which remain tracked, see [Self::snapshot_and_prune]
+    pub fn snapshot(&self) -> SoupBinTcpMetricsSnapshot {
+        let connections = self.connections.read().unwrap().iter().map(|con| (con.clone(), !con.is_closed.load(Relaxed))).collect::<Vec<_>>();
+        self.to_snapshot(connections)
+    }
+    /// Same as [Self::snapshot] except that closed connections are reported one last time and stop being tracked
+    pub fn snapshot_and_prune(&self) -> SoupBinTcpMetricsSnapshot {
+        // is_open is read once so that a connection closing concurrently is either kept or reported as closed, but never dropped unreported
+        let mut connections = vec![];
+        self.connections.write().unwrap().retain(|con| {
+            let is_open = !con.is_closed.load(Relaxed);
+            connections.push((con.clone(), is_open));
+            is_open
+        });
+        self.to_snapshot(connections)
+    }
+    fn to_snapshot(&self, connections: Vec<(Arc<ConnectionCounters>, bool)>) -> SoupBinTcpMetricsSnapshot {
+        let session_next_sequence_number = self.session_next_sequence_number.load(Relaxed);
+        SoupBinTcpMetricsSnapshot {
+            logins: LoginMetrics {
+                requested: self.logins.requested.load(Relaxed),
+                accepted: self.logins.accepted.load(Relaxed),
+                rejected_not_authorized: self.logins.rejected_not_authorized.load(Relaxed),
+                rejected_session_not_available: self.logins.rejected_session_not_available.load(Relaxed),
+            },
+            session_sequence_number: session_next_sequence_number.checked_sub(1),
+            connections: connections
+                .iter()
+                .map(|(con, is_open)| {
+                    let next_sequence_number = con.next_sequence_number.get();
+                    let replay_size = con.replay_size.get();
+                    ConnectionMetrics {
+                        con_id: con.con_id.to_string(),
+                        is_open: *is_open,
+                        recv: con.recv.snapshot(),
+                        sent: con.sent.snapshot(),
+                        current_sequence_number: next_sequence_number.map(|next| next.saturating_sub(1)),
+                        next_sequence_number,
+                        replay_size,
+                        sequence_lag: replay_size.and(next_sequence_number).map(|next| session_next_sequence_number.saturating_sub(next)),
+                        since_last_recv: con.last_recv_nanos.get().map(|nanos| self.clock.elapsed(self.start + Duration::from_nanos(nanos))),
+                        events: con.events.snapshot(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+impl<M: Messenger, C: Clock> Debug for SoupBinTcpMetricsCallback<M, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SoupBinTcpMetricsCallback")
+            .field("connections", &self.connections.read().unwrap().len())
+            .field("logins", &self.logins)
+            .finish()
+    }
+}
+impl<M: Messenger, C: Clock> Display for SoupBinTcpMetricsCallback<M, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}<connections: {}>",
+            asserted_short_name!("SoupBinTcpMetricsCallback", Self),
+            self.connections.read().unwrap().len()
+        )
+    }
+}
+impl<M: Messenger, C: Clock> CallbackRecv<M> for SoupBinTcpMetricsCallback<M, C>
+where
+    M::RecvT: SoupBinTcpMetricsMsg,
+{
+    fn on_recv(&self, con_id: &ConId, msg: &M::RecvT) {
+        let con = self.connection(con_id);
+        con.on_msg(&con.recv, msg.packet_type(), msg.frame_len());
+        con.last_recv_nanos.set(u64::try_from(self.clock.elapsed(self.start).as_nanos()).unwrap_or(u64::MAX));
+        self.on_event(&con, msg.metrics_event(), false);
+    }
+}
+impl<M: Messenger, C: Clock> CallbackSend<M> for SoupBinTcpMetricsCallback<M, C>
+where
+    M::SendT: SoupBinTcpMetricsMsg,
+{
+    fn on_sent(&self, con_id: &ConId, msg: &M::SendT) {
+        let con = self.connection(con_id);
+        con.on_msg(&con.sent, msg.packet_type(), msg.frame_len());
+        self.on_event(&con, msg.metrics_event(), true);
+    }
+}
+impl<M: Messenger, C: Clock> CallbackRecvSend<M> for SoupBinTcpMetricsCallback<M, C>
+where
+    M::RecvT: SoupBinTcpMetricsMsg,
+    M::SendT: SoupBinTcpMetricsMsg,
+{
+}
+impl<M: Messenger, C: Clock> SoupBinTcpEventCallback for SoupBinTcpMetricsCallback<M, C> {
+    fn on_event(&self, con_id: &ConId, event: &SoupBinTcpEvent) {
+        let con = self.connection(con_id);
+        let (counter, is_terminal) = match event {
+            SoupBinTcpEvent::PeerHeartbeatTimeout { .. } => (&con.events.hbeat_timeouts, true),
+            SoupBinTcpEvent::InboundFrameTooLarge { .. } => (&con.events.frames_too_large, true),
+            SoupBinTcpEvent::PeerLogout => (&con.events.peer_logouts, true),
+        };
+        counter.fetch_add(1, Relaxed);
+        if is_terminal {
+            con.is_closed.store(true, Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use std::time::Duration;
+
+    #[test]
+    fn test_metrics_svc() {
+        setup::log::configure_compact(LevelFilter::Info);
+        type Protocol = SvcSoupBinTcpProtocolAuto<Nil, VecPayload>;
+        let clock = ManualClock::default();
+        let metrics = SoupBinTcpMetricsCallback::<Protocol, _>::with_clock(clock.clone());
+        let session_id: SessionId = b"session".as_slice().into();
+        let login = LoginRequest::new(b"userid".as_slice().into(), b"passwd".as_slice().into(), session_id, 1_u64.into(), Duration::from_secs(1).into());
+
+        // first connection, logs in at 1 and receives 3 live messages
+        let con_1 = ConId::svc(Some("svc"), "127.0.0.1:8080", Some("127.0.0.1:1001"));
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_1, &login.into());
+        CallbackSend::<Protocol>::on_sent(&metrics, &con_1, &LoginAccepted::new(session_id, 1_u64.into()).into());
+        for _ in 0..3 {
+            CallbackSend::<Protocol>::on_sent(&metrics, &con_1, &SvcSoupBinTcpMsg::sdata(VecPayload::new(vec![0; 10])));
+        }
+        CallbackSend::<Protocol>::on_sent(&metrics, &con_1, &SvcSoupBinTcpMsg::hbeat());
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_1, &CltSoupBinTcpMsg::hbeat());
+        clock.advance(Duration::from_secs(2));
+
+        // second connection, logs in at 2 hence 2 messages are replayed, third is rejected
+        let con_2 = ConId::svc(Some("svc"), "127.0.0.1:8080", Some("127.0.0.1:1002"));
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_2, &login.into());
+        CallbackSend::<Protocol>::on_sent(&metrics, &con_2, &LoginAccepted::new(session_id, 2_u64.into()).into());
+        let con_3 = ConId::svc(Some("svc"), "127.0.0.1:8080", Some("127.0.0.1:1003"));
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_3, &login.into());
+        CallbackSend::<Protocol>::on_sent(&metrics, &con_3, &LoginRejected::not_authorized().into());
+
+        let snapshot = metrics.snapshot();
+        info!("snapshot: {}", serde_json::to_string_pretty(&snapshot).unwrap());
+        assert_eq!(
+            snapshot.logins,
+            LoginMetrics {
+                requested: 3,
+                accepted: 2,
+                rejected_not_authorized: 1,
+                rejected_session_not_available: 0
+            }
+        );
+        assert_eq!(snapshot.session_sequence_number, Some(3));
+        let con = snapshot.connection(&con_1).unwrap();
+        assert_eq!(con.sent.messages, 5);
+        assert_eq!(con.sent.hbeats, 1);
+        assert_eq!(con.sent.by_packet_type[&'S'], PacketMetrics { messages: 3, bytes: 3 * 13 });
+        assert_eq!(con.sent.bytes, 33 + 3 * 13 + 3);
+        assert_eq!(con.recv.messages, 2);
+        assert_eq!(con.recv.hbeats, 1);
+        assert_eq!(con.recv.by_packet_type[&'L'].bytes, 52 + 2);
+        assert_eq!(con.current_sequence_number, Some(3));
+        assert_eq!(con.next_sequence_number, Some(4));
+        assert_eq!(con.replay_size, Some(0));
+        assert_eq!(con.since_last_recv, Some(Duration::from_secs(2)));
+        assert_eq!(con.sequence_lag, Some(0));
+        assert!(con.is_open);
+
+        let con = snapshot.connection(&con_2).unwrap();
+        assert_eq!(con.replay_size, Some(2));
+        assert_eq!(con.sequence_lag, Some(2));
+        assert!(con.is_open);
+        assert_eq!(con.next_sequence_number, Some(2));
+        assert_eq!(con.since_last_recv, Some(Duration::ZERO));
+
+        let con = snapshot.connection(&con_3).unwrap();
+        assert_eq!(con.next_sequence_number, None);
+        assert_eq!(con.replay_size, None);
+        assert!(!con.is_open);
+        assert_eq!(snapshot.open_connections(), 2);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<SoupBinTcpMetricsSnapshot>(&json).unwrap(), snapshot);
+
+        // snapshot does not prune, closed con_3 is reported again
+        assert_eq!(metrics.snapshot(), snapshot);
+
+        // closed con_3 is reported one last time by snapshot_and_prune and no longer tracked afterwards
+        let snapshot = metrics.snapshot_and_prune();
+        assert_eq!(snapshot.connections.len(), 3);
+        assert!(!snapshot.connection(&con_3).unwrap().is_open);
+        SoupBinTcpEventCallback::on_event(&metrics, &con_1, &SoupBinTcpEvent::PeerHeartbeatTimeout { elapsed: Duration::from_secs(3), recv_timeout: Duration::from_secs(2) });
+        let snapshot = metrics.snapshot();
+        assert!(snapshot.connection(&con_3).is_none());
+        assert_eq!(snapshot.connections.len(), 2);
+        assert_eq!(snapshot.open_connections(), 1);
+        assert_eq!(snapshot.events().hbeat_timeouts, 1);
+
+        // closed con_1 is removed without being reported
+        assert_eq!(metrics.remove_closed(), 1);
+        assert_eq!(metrics.remove_closed(), 0);
+        metrics.remove(&con_2);
+        let snapshot = metrics.snapshot();
+        assert!(snapshot.connections.is_empty());
+        assert_eq!(snapshot.logins.accepted, 2);
+    }
+
+    #[test]
+    fn test_metrics_clt() {
+        setup::log::configure_compact(LevelFilter::Info);
+        type Protocol = CltSoupBinTcpProtocolAuto<VecPayload, Nil>;
+        let metrics = SoupBinTcpMetricsCallback::<Protocol>::new();
+        let session_id: SessionId = b"session".as_slice().into();
+        let con_id = ConId::clt(Some("clt"), None, "127.0.0.1:8080");
+
+        CallbackSend::<Protocol>::on_sent(
+            &metrics,
+            &con_id,
+            &CltSoupBinTcpMsg::login(b"userid".as_slice().into(), b"passwd".as_slice().into(), session_id, 0_u64.into(), Duration::from_secs(1).into()),
+        );
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_id, &LoginAccepted::new(session_id, 10_u64.into()).into());
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_id, &SvcSoupBinTcpMsg::sdata(VecPayload::new(vec![1, 2])));
+        CallbackRecv::<Protocol>::on_recv(&metrics, &con_id, &SvcSoupBinTcpMsg::udata(VecPayload::new(vec![1, 2])));
+        CallbackSend::<Protocol>::on_sent(&metrics, &con_id, &CltSoupBinTcpMsg::hbeat());
+
+        let snapshot = metrics.snapshot();
+        info!("snapshot: {:?}", snapshot);
+        assert_eq!(snapshot.logins.requested, 1);
+        assert_eq!(snapshot.logins.accepted, 1);
+        assert_eq!(snapshot.session_sequence_number, None);
+        let con = snapshot.connection(&con_id).unwrap();
+        assert_eq!(con.current_sequence_number, Some(10));
+        assert_eq!(con.next_sequence_number, Some(11));
+        assert_eq!(con.replay_size, None);
+        assert_eq!(con.sent.hbeats, 1);
+        assert_eq!(con.sent.by_packet_type[&'R'].messages, 1);
+        assert_eq!(con.recv.by_packet_type.keys().collect::<String>(), "ASU");
+    }
+}
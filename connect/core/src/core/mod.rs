@@ -1,3 +1,7 @@
+pub mod config;
+pub mod error;
 pub mod framer;
 pub mod messenger;
-pub mod protocol;
\ No newline at end of file
+pub mod metrics;
+pub mod protocol;
+pub mod view;
\ No newline at end of file
@@ -1,8 +1,10 @@
 use crate::prelude::*;
+use byteserde::prelude::ByteSerializedLenOf;
 use std::{
     fmt::Debug,
     io::{Error, ErrorKind},
     marker::PhantomData,
+    sync::Arc,
     time::Duration,
 };
 
@@ -11,21 +13,29 @@ use std::{
 /// # [ProtocolCore] Features
 /// * [`Self::on_connect`]
 /// * [`Self::on_recv`]
+/// * [`Self::on_sent`]
 /// * [`Self::is_connected`]
 ///
 /// # [Protocol] Features
 /// * [`Self::conf_heart_beat_interval`]
-/// * [`Self::send_heart_beat`]
+/// * [`Self::send_heart_beat`] - only when there was no other outbound traffic, see [`Self::hbeat_stats`]
+/// * [`Self::send_reply`] - only validates inbound frames, see [`Self::with_max_recv_frame_size`] & [`Self::with_decode_error_policy`]
 #[derive(Debug, Clone)]
-pub struct CltSoupBinTcpProtocolAuto<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+pub struct CltSoupBinTcpProtocolAuto<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock = MonotonicClock, Dec: DecodeErrorPolicy = DisconnectOnDecodeError> {
     username: UserName,
     password: Password,
     session_id: SessionId,
     sequence_number: SequenceNumber,
     io_timeout: Duration,
     max_hbeat_send_interval: Duration,
-    recv_con_state: ProtocolConnectionState<CltSoupBinTcpRecvConnectionState>,
-    phantom: PhantomData<(RecvP, SendP)>,
+    hbeat_policy: HeartbeatPolicy,
+    hbeat_send_state: ProtocolConnectionState<HeartbeatSendState<Clk>>,
+    hbeat_stats: ProtocolSessionState<HeartbeatStats>,
+    event_callback: Option<Arc<dyn SoupBinTcpEventCallback>>,
+    max_recv_frame_size: Option<usize>,
+    recv_con_state: ProtocolConnectionState<CltSoupBinTcpRecvConnectionState<Clk>>,
+    clock: Clk,
+    phantom: PhantomData<(RecvP, SendP, Dec)>,
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBinTcpProtocolAuto<RecvP, SendP> {
     /// Creates new instance
@@ -36,9 +46,11 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBi
     /// * `session_id` - session_id to be used during authentication
     /// * `sequence_num` - sequence_num that client wants to start receiving messages from
     /// * `io_timeout` - timeout for login sequence during [`Self::on_connect`] hook
-    /// * `clt_max_hbeat_interval` - maximum interval between sending heartbeats, will result in [`Self::conf_heart_beat_interval`] be 2.5 times faster,
-    /// so if max is set to 25 seconds then heartbeats will be sent every 10 seconds
+    /// * `clt_max_hbeat_interval` - maximum interval between sending heartbeats, advertised to the server in [LoginRequest] and will result in
+    ///   [`Self::conf_heart_beat_interval`] be 2.5 times faster, so if max is set to 25 seconds then heartbeats will be sent every 10 seconds
     /// * `svc_max_hbeat_interval` - maximum interval between receiving heartbeats, if exceeded [`Self::is_connected`] returns `false`
+    ///
+    /// Use [`Self::with_hbeat_policy`] to set send interval, receive timeout and grace period explicitly
     pub fn new(
         username: UserName,
         password: Password,
@@ -55,18 +67,103 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBi
             sequence_number,
             io_timeout,
             max_hbeat_send_interval: clt_max_hbeat_interval,
+            hbeat_policy: HeartbeatPolicy::from_max_interval(clt_max_hbeat_interval, svc_max_hbeat_interval),
+            hbeat_send_state: HeartbeatSendState::default().into(),
+            hbeat_stats: ProtocolSessionState::new(HeartbeatStats::default()),
+            event_callback: None,
+            max_recv_frame_size: None,
             recv_con_state: CltSoupBinTcpRecvConnectionState::new(svc_max_hbeat_interval).into(),
+            clock: MonotonicClock,
             phantom: PhantomData,
         }
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for CltSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    /// Replaces [MonotonicClock] used by heartbeat expiry & staleness checks, example with a [ManualClock] so that timeouts can be tested without sleeping
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> CltSoupBinTcpProtocolAuto<RecvP, SendP, C2, Dec> {
+        self.map_params(|_, _, recv_con_state| {
+            let recv_con_state = (*recv_con_state.lock()).clone().with_clock(clock.clone()).into();
+            (clock.clone(), HeartbeatSendState::new_with_clock(clock).into(), recv_con_state)
+        })
+    }
+    /// Replaces heartbeat rules derived in [`Self::new`], `clt_max_hbeat_interval` is still advertised to the server in [LoginRequest]
+    /// so it should not be shorter then [`HeartbeatPolicy::send_interval`]
+    pub fn with_hbeat_policy(mut self, hbeat_policy: HeartbeatPolicy) -> Self {
+        self.hbeat_policy = hbeat_policy;
+        self.recv_con_state = CltSoupBinTcpRecvConnectionState::new_with_clock(hbeat_policy.recv_timeout_with_grace(), self.clock.clone()).into();
+        self
+    }
+    #[inline(always)]
+    pub fn hbeat_policy(&self) -> &HeartbeatPolicy {
+        &self.hbeat_policy
+    }
+    /// Registers a callback which is notified of [SoupBinTcpEvent]s before the connection is terminated
+    pub fn with_event_callback(mut self, event_callback: Arc<dyn SoupBinTcpEventCallback>) -> Self {
+        self.event_callback = Some(event_callback);
+        self
+    }
+    /// Limits the size of frames accepted from the server, including the two byte length prefix, typically set to `MAX_MSG_SIZE` of [Clt].
+    /// A larger frame terminates the connection, see [`Self::send_reply`]
+    pub fn with_max_recv_frame_size(mut self, max_recv_frame_size: usize) -> Self {
+        self.max_recv_frame_size = Some(max_recv_frame_size);
+        self
+    }
+    #[inline(always)]
+    pub fn max_recv_frame_size(&self) -> Option<usize> {
+        self.max_recv_frame_size
+    }
+    /// Replaces [DisconnectOnDecodeError] with [SkipOnDecodeError] or any other [DecodeErrorPolicy], which determines whether a frame which fails to decode
+    /// terminates the connection with [SoupBinTcpDecodeError] or is dropped by [`Self::get_frame`] before it reaches [`Self::on_recv`]
+    pub fn with_decode_error_policy<D2: DecodeErrorPolicy>(self) -> CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, D2> {
+        self.map_params(|clock, hbeat_send_state, recv_con_state| (clock, hbeat_send_state, recv_con_state))
+    }
+    /// Moves all fields into an instance with a different [Clock] and/or [DecodeErrorPolicy], `map` converts the clock and the state which depends on it
+    #[allow(clippy::type_complexity)]
+    fn map_params<C2: Clock, D2: DecodeErrorPolicy>(
+        self,
+        map: impl FnOnce(
+            Clk,
+            ProtocolConnectionState<HeartbeatSendState<Clk>>,
+            ProtocolConnectionState<CltSoupBinTcpRecvConnectionState<Clk>>,
+        ) -> (C2, ProtocolConnectionState<HeartbeatSendState<C2>>, ProtocolConnectionState<CltSoupBinTcpRecvConnectionState<C2>>),
+    ) -> CltSoupBinTcpProtocolAuto<RecvP, SendP, C2, D2> {
+        let (clock, hbeat_send_state, recv_con_state) = map(self.clock, self.hbeat_send_state, self.recv_con_state);
+        CltSoupBinTcpProtocolAuto {
+            username: self.username,
+            password: self.password,
+            session_id: self.session_id,
+            sequence_number: self.sequence_number,
+            io_timeout: self.io_timeout,
+            max_hbeat_send_interval: self.max_hbeat_send_interval,
+            hbeat_policy: self.hbeat_policy,
+            hbeat_send_state,
+            hbeat_stats: self.hbeat_stats,
+            event_callback: self.event_callback,
+            max_recv_frame_size: self.max_recv_frame_size,
+            recv_con_state,
+            clock,
+            phantom: PhantomData,
+        }
+    }
+    /// Returns a snapshot of heartbeats sent and skipped across all connections, including reconnects, stats are shared by all clones of this instance
+    /// so a clone retained before calling [`Clt::connect`] will observe them
+    #[inline(always)]
+    pub fn hbeat_stats(&self) -> HeartbeatStats {
+        *self.hbeat_stats.lock()
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Framer for CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        CltSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
+    }
+    /// Drops frames which fail to decode when `Dec` is [SkipOnDecodeError], see [`Self::with_decode_error_policy`]
+    #[inline(always)]
+    fn get_frame(bytes: &mut bytes::BytesMut) -> Option<bytes::Bytes> {
+        get_frame_or_skip::<CltSoupBinTcpMessenger<RecvP, SendP>, Dec>(bytes, SoupBinTcpFramer::SVC_PACKET_TYPES)
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for CltSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Messenger for CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     type RecvT = <CltSoupBinTcpMessenger<RecvP, SendP> as Messenger>::RecvT;
     type SendT = <CltSoupBinTcpMessenger<RecvP, SendP> as Messenger>::SendT;
 
@@ -74,12 +171,13 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger
     fn serialize<const MAX_MSG_SIZE: usize>(msg: &Self::SendT) -> Result<([u8; MAX_MSG_SIZE], usize), std::io::Error> {
         CltSoupBinTcpMessenger::<RecvP, SendP>::serialize(msg)
     }
+    /// Fails with [SoupBinTcpDecodeError], which terminates the connection, unless the frame was already dropped by [`Self::get_frame`]
     #[inline(always)]
     fn deserialize(frame: &[u8]) -> Result<Self::RecvT, Error> {
         CltSoupBinTcpMessenger::<RecvP, SendP>::deserialize(frame)
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolCore for CltSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> ProtocolCore for CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     /// handles [LoginRequest]/[LoginAccepted][LoginRejected] authentication sequence
     #[inline(always)]
     fn on_connect<C: SendNonBlocking<<Self as Messenger>::SendT> + ReSendNonBlocking<<Self as Messenger>::SendT> + RecvNonBlocking<<Self as Messenger>::RecvT> + ConnectionId>(
@@ -107,23 +205,77 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
         (*self.recv_con_state.lock()).on_recv(msg);
     }
 
+    /// Will delegate to [`HeartbeatSendState::on_sent`] for payload messages, [UPayload], [SPayload] & [Debug](crate::prelude::Debug), session messages
+    /// such as [LoginRequest] do not suppress heartbeats
+    #[allow(unused_variables)] // when compiled in release mode `who` is not used
+    #[inline(always)]
+    fn on_sent<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::SendT) {
+        #[cfg(debug_assertions)]
+        log::debug!("{}::on_sent: con_id: {}, msg: {:?}", asserted_short_name!("CltSoupBinTcpProtocolAuto", Self), who.con_id(), msg);
+
+        if matches!(msg, CltSoupBinTcpMsg::UPayload(_) | CltSoupBinTcpMsg::SPayload(_) | CltSoupBinTcpMsg::Dbg(_)) {
+            (*self.hbeat_send_state.lock()).on_sent();
+        }
+    }
+
     /// Will delegate to [`CltSoupBinTcpRecvConnectionState::is_connected`]
     #[inline(always)]
     fn is_connected(&self) -> bool {
         (*self.recv_con_state.lock()).is_connected()
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Protocol for CltSoupBinTcpProtocolAuto<RecvP, SendP> {
-    /// Configures interval from arguments of [`Self::new`]
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Protocol for CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    /// Configures interval from [`HeartbeatPolicy::send_interval`]
     #[inline(always)]
     fn conf_heart_beat_interval(&self) -> Option<Duration> {
-        Some(self.max_hbeat_send_interval.div_f64(2.5))
+        Some(self.hbeat_policy.send_interval())
     }
 
-    /// sends [CltHeartbeat] instance to the server
+    /// sends [CltHeartbeat] instance to the server, unless a payload message was sent with in [`HeartbeatPolicy::send_interval`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::TimedOut] once the peer was silent for longer then [`HeartbeatPolicy::recv_timeout_with_grace`], which terminates the connection.
+    /// Registered [SoupBinTcpEventCallback] is notified with [SoupBinTcpEvent::PeerHeartbeatTimeout] first.
     #[inline(always)]
     fn send_heart_beat<S: SendNonBlocking<Self::SendT> + ConnectionId>(&self, sender: &mut S) -> Result<SendStatus, Error> {
-        sender.send(&mut CltSoupBinTcpMsg::hbeat())
+        let hbeat_timeout = (*self.recv_con_state.lock()).hbeat_timeout();
+        if let Some(event) = hbeat_timeout {
+            if let Some(event_callback) = &self.event_callback {
+                event_callback.on_event(sender.con_id(), &event);
+            }
+            return Err(Error::new(ErrorKind::TimedOut, format!("{} con_id: {}", event, sender.con_id())));
+        }
+        if !(*self.hbeat_send_state.lock()).is_idle(self.hbeat_policy.send_interval()) {
+            (*self.hbeat_stats.lock()).on_skipped();
+            return Ok(SendStatus::Completed);
+        }
+        let status = sender.send(&mut CltSoupBinTcpMsg::hbeat())?;
+        if let SendStatus::Completed = status {
+            (*self.hbeat_stats.lock()).on_sent();
+        }
+        Ok(status)
+    }
+
+    /// Validates that the received frame does not exceed [`Self::max_recv_frame_size`], frames which failed to decode never reach this point, see [`Self::with_decode_error_policy`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData], which terminates the connection, if the frame exceeds [`Self::max_recv_frame_size`].
+    /// Registered [SoupBinTcpEventCallback] is notified with [SoupBinTcpEvent::InboundFrameTooLarge] first.
+    #[inline(always)]
+    fn send_reply<S: SendNonBlocking<<Self as Messenger>::SendT> + ConnectionId>(&self, msg: &<Self as Messenger>::RecvT, sender: &mut S) -> Result<(), Error> {
+        validate_recv_frame_size(self.max_recv_frame_size, msg.byte_len(), &self.event_callback, sender.con_id())
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> SoupBinTcpViewProtocol for CltSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    #[inline(always)]
+    fn deserialize_view(frame: &[u8]) -> Result<SoupBinTcpMsgView<'_>, Error> {
+        CltSoupBinTcpMessenger::<RecvP, SendP>::deserialize_view(frame)
+    }
+    /// Will delegate to [`CltSoupBinTcpRecvConnectionState::on_recv_payload`] and validate the frame in the same way as [`Self::send_reply`]
+    #[inline(always)]
+    fn on_recv_payload_view<I: ConnectionId>(&self, who: &I, view: &SoupBinTcpMsgView<'_>) -> Result<(), Error> {
+        (*self.recv_con_state.lock()).on_recv_payload();
+        validate_recv_frame_size(self.max_recv_frame_size, view.frame().len(), &self.event_callback, who.con_id())
     }
 }
 
@@ -138,18 +290,27 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Protocol
 ///
 /// # [Protocol] Features
 /// * [`Self::conf_heart_beat_interval`]
-/// * [`Self::send_heart_beat`]
+/// * [`Self::send_heart_beat`] - only when there was no other outbound traffic, see [`Self::hbeat_stats`]
+/// * [`Self::send_reply`] - terminates the connection on [LogoutRequest] and validates inbound frames, see [`Self::with_max_recv_frame_size`] & [`Self::with_decode_error_policy`]
 #[derive(Debug, Clone)]
-pub struct SvcSoupBinTcpProtocolAuto<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+pub struct SvcSoupBinTcpProtocolAuto<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock = MonotonicClock, Dec: DecodeErrorPolicy = DisconnectOnDecodeError> {
     username: UserName,
     password: Password,
-    session_id: SessionId,
+    session_id: ProtocolSessionState<SessionId>,
+    session_open: ProtocolSessionState<bool>,
+    login_session_id: ProtocolConnectionState<Option<SessionId>>,
     io_timeout: Duration,
-    svc_max_hbeat_interval: Duration,
-    recv_con_state: ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState>,
-    send_con_state: ProtocolConnectionState<SvcSoupBinTcpSendConnectionState>,
+    hbeat_policy: HeartbeatPolicy,
+    hbeat_send_state: ProtocolConnectionState<HeartbeatSendState<Clk>>,
+    hbeat_stats: ProtocolSessionState<HeartbeatStats>,
+    event_callback: Option<Arc<dyn SoupBinTcpEventCallback>>,
+    max_recv_frame_size: Option<usize>,
+    recv_con_state: ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState<Clk>>,
+    send_con_state: ProtocolConnectionState<SvcSoupBinTcpSendConnectionState<Clk>>,
+    clock: Clk,
     send_ses_state: ProtocolSessionState<SvcSoupBinTcpSendSessionState<SendP, InMemoryMessageLog<SvcSoupBinTcpMsg<SendP>>>>, // TODO make generic to allow for file based message log
-    phantom: PhantomData<(RecvP, SendP)>,
+    archived_session: ProtocolSessionState<Option<Arc<SvcSoupBinTcpSessionArchive<SendP>>>>,
+    phantom: PhantomData<(RecvP, SendP, Dec)>,
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
     /// Creates new instance
@@ -160,29 +321,203 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBi
     /// * `session_id` - session_id to be used during authentication
     /// * `io_timeout` - timeout for login sequence during [`Self::on_connect`] hook
     /// * `svc_max_hbeat_interval` - maximum interval between sending heartbeats, will result in [`Self::conf_heart_beat_interval`] be 2.5 times faster, so if max is set to 25 seconds then heartbeats will be sent every 10 seconds
+    ///
+    /// Receive timeout is determined by [LoginRequest::hbeat_timeout_ms] advertised by the client, use [`Self::with_hbeat_policy`] to set it explicitly
     pub fn new(username: UserName, password: Password, session_id: SessionId, io_timeout: Duration, svc_max_hbeat_interval: Duration) -> Self {
         let session_storage = InMemoryMessageLog::<SvcSoupBinTcpMsg<SendP>>::default();
         let session_state = SvcSoupBinTcpSendSessionState::new(session_storage);
         Self {
             username,
             password,
-            session_id,
+            session_id: ProtocolSessionState::new(session_id),
+            session_open: ProtocolSessionState::new(true),
+            login_session_id: ProtocolConnectionState::new(None),
             io_timeout,
-            svc_max_hbeat_interval,
+            hbeat_policy: HeartbeatPolicy::from_max_interval(svc_max_hbeat_interval, svc_max_hbeat_interval),
+            hbeat_send_state: HeartbeatSendState::default().into(),
+            hbeat_stats: ProtocolSessionState::new(HeartbeatStats::default()),
+            event_callback: None,
+            max_recv_frame_size: None,
             recv_con_state: SvcSoupBinTcpRecvConnectionState::default().into(),
             send_con_state: SvcSoupBinTcpSendConnectionState::default().into(),
             send_ses_state: ProtocolSessionState::new(session_state),
+            archived_session: ProtocolSessionState::new(None),
+            clock: MonotonicClock,
             phantom: PhantomData,
         }
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    /// Replaces [MonotonicClock] used by heartbeat expiry & staleness checks, example with a [ManualClock] so that timeouts can be tested without sleeping
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> SvcSoupBinTcpProtocolAuto<RecvP, SendP, C2, Dec> {
+        self.map_params(|_, _, recv_con_state, _| {
+            let recv_con_state = (*recv_con_state.lock()).clone().with_clock(clock.clone()).into();
+            let send_con_state = SvcSoupBinTcpSendConnectionState::new_with_clock(clock.clone()).into();
+            (clock.clone(), HeartbeatSendState::new_with_clock(clock).into(), recv_con_state, send_con_state)
+        })
+    }
+    /// Replaces heartbeat rules derived in [`Self::new`], [`HeartbeatPolicy::recv_timeout`] takes precedence over [LoginRequest::hbeat_timeout_ms] advertised by the client
+    pub fn with_hbeat_policy(mut self, hbeat_policy: HeartbeatPolicy) -> Self {
+        self.hbeat_policy = hbeat_policy;
+        self.recv_con_state = SvcSoupBinTcpRecvConnectionState::with_hbeat_policy(&hbeat_policy).with_clock(self.clock.clone()).into();
+        self
+    }
+    #[inline(always)]
+    pub fn hbeat_policy(&self) -> &HeartbeatPolicy {
+        &self.hbeat_policy
+    }
+    /// Registers a callback which is notified of [SoupBinTcpEvent]s before [EndOfSession] is sent and the connection is terminated
+    pub fn with_event_callback(mut self, event_callback: Arc<dyn SoupBinTcpEventCallback>) -> Self {
+        self.event_callback = Some(event_callback);
+        self
+    }
+    /// Limits the size of frames accepted from clients, including the two byte length prefix, typically set to `MAX_MSG_SIZE` of [Svc].
+    /// A larger frame terminates the connection, see [`Self::send_reply`]
+    pub fn with_max_recv_frame_size(mut self, max_recv_frame_size: usize) -> Self {
+        self.max_recv_frame_size = Some(max_recv_frame_size);
+        self
+    }
+    #[inline(always)]
+    pub fn max_recv_frame_size(&self) -> Option<usize> {
+        self.max_recv_frame_size
+    }
+    /// Replaces [DisconnectOnDecodeError] with [SkipOnDecodeError] or any other [DecodeErrorPolicy], which determines whether a frame which fails to decode
+    /// terminates the connection with [SoupBinTcpDecodeError] or is dropped by [`Self::get_frame`] before it reaches [`Self::on_recv`]
+    pub fn with_decode_error_policy<D2: DecodeErrorPolicy>(self) -> SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, D2> {
+        self.map_params(|clock, hbeat_send_state, recv_con_state, send_con_state| (clock, hbeat_send_state, recv_con_state, send_con_state))
+    }
+    /// Moves all fields into an instance with a different [Clock] and/or [DecodeErrorPolicy], `map` converts the clock and the state which depends on it
+    #[allow(clippy::type_complexity)]
+    fn map_params<C2: Clock, D2: DecodeErrorPolicy>(
+        self,
+        map: impl FnOnce(
+            Clk,
+            ProtocolConnectionState<HeartbeatSendState<Clk>>,
+            ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState<Clk>>,
+            ProtocolConnectionState<SvcSoupBinTcpSendConnectionState<Clk>>,
+        ) -> (
+            C2,
+            ProtocolConnectionState<HeartbeatSendState<C2>>,
+            ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState<C2>>,
+            ProtocolConnectionState<SvcSoupBinTcpSendConnectionState<C2>>,
+        ),
+    ) -> SvcSoupBinTcpProtocolAuto<RecvP, SendP, C2, D2> {
+        let (clock, hbeat_send_state, recv_con_state, send_con_state) = map(self.clock, self.hbeat_send_state, self.recv_con_state, self.send_con_state);
+        SvcSoupBinTcpProtocolAuto {
+            username: self.username,
+            password: self.password,
+            session_id: self.session_id,
+            session_open: self.session_open,
+            login_session_id: self.login_session_id,
+            io_timeout: self.io_timeout,
+            hbeat_policy: self.hbeat_policy,
+            hbeat_send_state,
+            hbeat_stats: self.hbeat_stats,
+            event_callback: self.event_callback,
+            max_recv_frame_size: self.max_recv_frame_size,
+            recv_con_state,
+            send_con_state,
+            send_ses_state: self.send_ses_state,
+            archived_session: self.archived_session,
+            clock,
+            phantom: PhantomData,
+        }
+    }
+    /// Returns a snapshot of heartbeats sent and skipped across all connections, stats are shared by all clones of this instance so a clone retained before
+    /// calling [`Svc::bind`] will observe them
+    #[inline(always)]
+    pub fn hbeat_stats(&self) -> HeartbeatStats {
+        *self.hbeat_stats.lock()
+    }
+    /// Currently active session, changed by [`Self::rollover`]
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        *self.session_id.lock()
+    }
+    /// Ends the current session and starts `session_id` at sequence number `1`, typically at the end of the trading day with [`SessionId::try_from_date`].
+    /// Session is shared by all clones of this instance so a clone retained before calling [`Svc::bind`] can be used to roll over all connections.
+    /// The returned archive is also retained until the next rollover, see [`Self::archived_session`].
+    ///
+    /// Connections logged into the previous session are no longer [`Self::is_connected`], messages sent to them are not stored in the new session,
+    /// and they are terminated with [EndOfSession] on their next [`Self::send_heart_beat`]. Logins into the previous session are rejected with
+    /// [`LoginRejected::session_not_available`]. Does not change [`Self::is_session_open`].
+    ///
+    /// # Note
+    /// [EndOfSession] is not sent by this call since the protocol holds no reference to the connections, instead it is delivered by
+    /// [static@links_nonblocking::connect::DEFAULT_HBEAT_HANDLER] with in one [`HeartbeatPolicy::send_interval`] regardless of any other traffic on the connection.
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidInput] if `session_id` is blank or is the currently active session
+    pub fn rollover(&self, session_id: SessionId) -> Result<Arc<SvcSoupBinTcpSessionArchive<SendP>>, Error> {
+        if session_id == SessionId::default() || session_id == self.session_id() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid rollover session_id: {:?}, current session_id: {:?}", session_id, self.session_id())));
+        }
+        let mut send_ses_state = self.send_ses_state.lock();
+        let session_state = std::mem::replace(&mut *send_ses_state, SvcSoupBinTcpSendSessionState::new(InMemoryMessageLog::default()));
+        let previous_session_id = std::mem::replace(&mut *self.session_id.lock(), session_id);
+        let archive = Arc::new(SvcSoupBinTcpSessionArchive::new(previous_session_id, session_state));
+        // set before the log lock is released so that anyone observing the new session in `visit_sequenced` also finds the archive
+        *self.archived_session.lock() = Some(archive.clone());
+        Ok(archive)
+    }
+    /// Session most recently ended by [`Self::rollover`], `None` until the first rollover. Shared by all clones of this instance in the same way as [`Self::rollover`]
+    /// so that [SPayload]s appended to the session just before it ended can still be disseminated over other transports, ex: MoldUDP64
+    #[inline(always)]
+    pub fn archived_session(&self) -> Option<Arc<SvcSoupBinTcpSessionArchive<SendP>>> {
+        self.archived_session.lock().clone()
+    }
+    /// Visits [SPayload]s of the current session starting at `sequence_number`, the first one has sequence number `1`, for as long as `visit` returns `true`
+    /// and returns the [SessionId] they belong to. Session log is shared by all clones of this instance in the same way as [`Self::rollover`] so a clone
+    /// retained before calling [`Svc::bind`] can disseminate it over other transports, ex: MoldUDP64
+    pub fn visit_sequenced<F: FnMut(&SPayload<SendP>) -> bool>(&self, sequence_number: usize, mut visit: F) -> SessionId {
+        let send_ses_state = self.send_ses_state.lock();
+        let session_id = self.session_id();
+        let mut sequence_number = sequence_number.max(1);
+        while let Some(msg) = send_ses_state.get_sequenced(sequence_number) {
+            if !visit(msg) {
+                break;
+            }
+            sequence_number += 1;
+        }
+        session_id
+    }
+    /// `false` once [`Self::close`] was called, sessions start open
+    #[inline(always)]
+    pub fn is_session_open(&self) -> bool {
+        *self.session_open.lock()
+    }
+    /// Accepts logins into the current session again after [`Self::close`]
+    pub fn open(&self) {
+        *self.session_open.lock() = true;
+    }
+    /// Rejects all logins with [`LoginRejected::session_not_available`] until [`Self::open`] is called, connected clients are no longer
+    /// [`Self::is_connected`] and are terminated with [EndOfSession] on their next [`Self::send_heart_beat`], see note on [`Self::rollover`].
+    /// Shared by all clones of this instance in the same way as [`Self::rollover`], see [SvcSoupBinTcpSessionScheduler] to follow trading hours.
+    pub fn close(&self) {
+        *self.session_open.lock() = false;
+    }
+    #[inline(always)]
+    pub(crate) fn clock(&self) -> &Clk {
+        &self.clock
+    }
+    /// `true` if this connection logged into a session which has since been ended by [`Self::rollover`] or [`Self::close`]
+    #[inline(always)]
+    fn is_session_ended(&self) -> bool {
+        matches!(*self.login_session_id.lock(), Some(login_session_id) if login_session_id != *self.session_id.lock() || !*self.session_open.lock())
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Framer for SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
+    }
+    /// Drops frames which fail to decode when `Dec` is [SkipOnDecodeError], see [`Self::with_decode_error_policy`]
+    #[inline(always)]
+    fn get_frame(bytes: &mut bytes::BytesMut) -> Option<bytes::Bytes> {
+        get_frame_or_skip::<SvcSoupBinTcpMessenger<RecvP, SendP>, Dec>(bytes, SoupBinTcpFramer::CLT_PACKET_TYPES)
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Messenger for SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     type RecvT = <SvcSoupBinTcpMessenger<RecvP, SendP> as Messenger>::RecvT;
     type SendT = <SvcSoupBinTcpMessenger<RecvP, SendP> as Messenger>::SendT;
 
@@ -191,12 +526,13 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger
         SvcSoupBinTcpMessenger::<RecvP, SendP>::serialize(msg)
     }
 
+    /// Fails with [SoupBinTcpDecodeError], which terminates the connection, unless the frame was already dropped by [`Self::get_frame`]
     #[inline(always)]
     fn deserialize(frame: &[u8]) -> Result<Self::RecvT, Error> {
         SvcSoupBinTcpMessenger::<RecvP, SendP>::deserialize(frame)
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolCore for SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> ProtocolCore for SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
     /// handles [LoginRequest]/[LoginAccepted][LoginRejected] authentication sequence
     #[inline(always)]
     fn on_connect<C: SendNonBlocking<<Self as Messenger>::SendT> + ReSendNonBlocking<<Self as Messenger>::SendT> + RecvNonBlocking<<Self as Messenger>::RecvT> + ConnectionId>(
@@ -205,8 +541,20 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
     ) -> Result<(), Error> {
         match con.recv_busywait_timeout(self.io_timeout)? {
             RecvStatus::Completed(Some(CltSoupBinTcpMsg::LoginRequest(msg))) => {
-                if msg.username == self.username && msg.password == self.password && (msg.session_id == self.session_id || msg.session_id == SessionId::default()) {
-                    let clt_next_sequenced_payload_number: usize = msg.sequence_number.into();
+                let session_id = self.session_id();
+                if !self.is_session_open() {
+                    con.send_busywait_timeout(&mut LoginRejected::session_not_available().into(), self.io_timeout)?;
+                    return Err(Error::new(ErrorKind::NotConnected, format!("Session is closed session_id: {:?} msg: {:?}", session_id, msg)));
+                }
+                if msg.username == self.username && msg.password == self.password && (msg.session_id == session_id || msg.session_id == SessionId::default()) {
+                    // numeric fields are not validated during deserialization, reject instead of panicking on malformed values
+                    let clt_next_sequenced_payload_number = match msg.hbeat_timeout_ms.try_to_duration().and_then(|_| msg.sequence_number.try_to_usize()) {
+                        Ok(clt_next_sequenced_payload_number) => clt_next_sequenced_payload_number,
+                        Err(err) => {
+                            con.send_busywait_timeout(&mut LoginRejected::not_authorized().into(), self.io_timeout)?;
+                            return Err(err);
+                        }
+                    };
 
                     let svc_next_sequenced_payload_number = (*self.send_ses_state.lock()).next_sequenced_payload_number();
                     let effective_next_sequence_number = {
@@ -217,16 +565,19 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
                         }
                     };
 
-                    let mut msg = LoginAccepted::new(self.session_id, effective_next_sequence_number.into()).into();
+                    let mut msg = LoginAccepted::new(session_id, effective_next_sequence_number.into()).into();
                     match con.send_busywait_timeout(&mut msg, self.io_timeout)? {
                         SendStatus::Completed => {
                             if effective_next_sequence_number < svc_next_sequenced_payload_number {
-                                for re_msg in (*self.send_ses_state.lock())
-                                    .get_storage()
-                                    .iter()
-                                    .filter(|msg| matches!(msg, SvcSoupBinTcpMsg::SPayload(_)))
-                                    .skip(effective_next_sequence_number - 1)
-                                {
+                                // copied out of the session log so that it is not locked while waiting on the socket, including payloads sent since LoginAccepted
+                                let re_msgs = {
+                                    let send_ses_state = self.send_ses_state.lock();
+                                    (effective_next_sequence_number..)
+                                        .map_while(|sequence_number| send_ses_state.get_sequenced(sequence_number))
+                                        .map(|msg| SvcSoupBinTcpMsg::SPayload(msg.clone()))
+                                        .collect::<Vec<_>>()
+                                };
+                                for re_msg in re_msgs.iter() {
                                     if let SendStatus::WouldBlock = con.re_send_busywait_timeout(re_msg, self.io_timeout)? {
                                         return Err(Error::new(ErrorKind::TimedOut, format!("Failed to resend msg: {:?}", re_msg)));
                                     }
@@ -236,11 +587,11 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
                         }
                         SendStatus::WouldBlock => Err(Error::new(ErrorKind::TimedOut, format!("Failed to send login: {:?}", msg))),
                     }
-                } else if msg.session_id != self.session_id {
+                } else if msg.session_id != session_id {
                     con.send_busywait_timeout(&mut LoginRejected::session_not_available().into(), self.io_timeout)?;
                     Err(Error::new(
                         ErrorKind::NotConnected,
-                        format!("Invalid session_id expected: {:?} received: {:?}", self.session_id, msg.session_id),
+                        format!("Invalid session_id expected: {:?} received: {:?}", session_id, msg.session_id),
                     ))
                 } else {
                     con.send_busywait_timeout(&mut LoginRejected::not_authorized().into(), self.io_timeout)?;
@@ -261,6 +612,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
         (*self.recv_con_state.lock()).on_recv(msg);
     }
     /// updates internal timestamp of when [LoginAccepted] and [EndOfSession] where sent detect connection loss via [`Self::is_connected`]
+    /// and of the last payload message, [SPayload], [UPayload] or [Debug](crate::prelude::Debug), to suppress heartbeats via [`Self::send_heart_beat`]
     #[allow(unused_variables)] // when compiled in release mode `who` is not used
     #[inline(always)]
     fn on_sent<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::SendT) {
@@ -268,42 +620,154 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
         log::debug!("{}::on_sent: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolAuto", Self), who.con_id(), msg);
 
         (*self.send_con_state.lock()).on_sent(msg);
-        (*self.send_ses_state.lock()).on_sent(msg);
+        if let SvcSoupBinTcpMsg::LoginAccepted(msg) = msg {
+            *self.login_session_id.lock() = Some(msg.session_id());
+        }
+        // lock the log before checking the session so that a concurrent `rollover` can not swap it in between and have this msg stored in the new session
+        let mut send_ses_state = self.send_ses_state.lock();
+        if !self.is_session_ended() {
+            send_ses_state.on_sent(msg);
+        }
+        drop(send_ses_state);
+        if matches!(msg, SvcSoupBinTcpMsg::SPayload(_) | SvcSoupBinTcpMsg::UPayload(_) | SvcSoupBinTcpMsg::Dbg(_)) {
+            (*self.hbeat_send_state.lock()).on_sent();
+        }
     }
 
     /// Will returns `true` if all of below are `true`
     /// * [`crate::prelude::SvcSoupBinTcpRecvConnectionState::is_connected`]
     /// * [`crate::prelude::SvcSoupBinTcpSendConnectionState::is_connected`]
+    /// * session logged into was not ended by [`Self::rollover`] or [`Self::close`]
     #[inline(always)]
     fn is_connected(&self) -> bool {
-        (*self.recv_con_state.lock()).is_connected() && (*self.send_con_state.lock()).is_connected()
+        (*self.recv_con_state.lock()).is_connected() && (*self.send_con_state.lock()).is_connected() && !self.is_session_ended()
     }
     /// Returns a tuple of
     /// * [Duration] - the timeout during which the [CltSender] will wait while delivering final message before disconnecting
     /// * [EndOfSession] - the message to be sent to the client
+    ///
+    /// Returns `None` if [LoginAccepted] was never sent, example after [LoginRejected], in which case the connection is closed without [EndOfSession]
     #[inline(always)]
     fn on_disconnect(&self) -> Option<(Duration, <Self as Messenger>::SendT)> {
+        if !(*self.send_con_state.lock()).is_login_accepted() {
+            return None;
+        }
         Some((self.io_timeout, EndOfSession::default().into()))
     }
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Protocol for SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> Protocol for SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    /// Configures interval from [`HeartbeatPolicy::send_interval`]
     #[inline(always)]
     fn conf_heart_beat_interval(&self) -> Option<Duration> {
-        Some(self.svc_max_hbeat_interval.div_f64(2.5))
+        Some(self.hbeat_policy.send_interval())
     }
-    /// sends [SvcHeartbeat] instance to the client
+    /// sends [SvcHeartbeat] instance to the client, unless a payload message was sent with in [`HeartbeatPolicy::send_interval`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::TimedOut] once the peer was silent for longer then [`HeartbeatPolicy::recv_timeout_with_grace`], which terminates the connection, which
+    /// will result in [EndOfSession] being sent via [`Self::on_disconnect`].
+    /// Registered [SoupBinTcpEventCallback] is notified with [SoupBinTcpEvent::PeerHeartbeatTimeout] first.
+    ///
+    /// Returns [ErrorKind::ConnectionAborted] once the session this connection logged into was ended by [`Self::rollover`] or [`Self::close`], which terminates the connection
+    /// with [EndOfSession] in the same way.
     #[inline(always)]
     fn send_heart_beat<S: SendNonBlocking<Self::SendT> + ConnectionId>(&self, sender: &mut S) -> Result<SendStatus, Error> {
-        sender.send(&mut SvcSoupBinTcpMsg::hbeat())
+        if self.is_session_ended() {
+            return Err(Error::new(ErrorKind::ConnectionAborted, format!("Session ended, current session_id: {:?} is_open: {} con_id: {}", self.session_id(), self.is_session_open(), sender.con_id())));
+        }
+        let hbeat_timeout = (*self.recv_con_state.lock()).hbeat_timeout();
+        if let Some(event) = hbeat_timeout {
+            if let Some(event_callback) = &self.event_callback {
+                event_callback.on_event(sender.con_id(), &event);
+            }
+            return Err(Error::new(ErrorKind::TimedOut, format!("{} con_id: {}", event, sender.con_id())));
+        }
+        if !(*self.hbeat_send_state.lock()).is_idle(self.hbeat_policy.send_interval()) {
+            (*self.hbeat_stats.lock()).on_skipped();
+            return Ok(SendStatus::Completed);
+        }
+        let status = sender.send(&mut SvcSoupBinTcpMsg::hbeat())?;
+        if let SendStatus::Completed = status {
+            (*self.hbeat_stats.lock()).on_sent();
+        }
+        Ok(status)
+    }
+
+    /// Validates that the received frame does not exceed [`Self::max_recv_frame_size`], frames which failed to decode never reach this point, see [`Self::with_decode_error_policy`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData], which terminates the connection, if the frame exceeds [`Self::max_recv_frame_size`].
+    /// Registered [SoupBinTcpEventCallback] is notified with [SoupBinTcpEvent::InboundFrameTooLarge] first.
+    ///
+    /// Returns [ErrorKind::ConnectionAborted] on [LogoutRequest], which per spec terminates the connection immediately, after notifying [SoupBinTcpEvent::PeerLogout].
+    #[inline(always)]
+    fn send_reply<S: SendNonBlocking<<Self as Messenger>::SendT> + ConnectionId>(&self, msg: &<Self as Messenger>::RecvT, sender: &mut S) -> Result<(), Error> {
+        if let CltSoupBinTcpMsg::LogoutRequest(_) = msg {
+            let event = SoupBinTcpEvent::PeerLogout;
+            if let Some(event_callback) = &self.event_callback {
+                event_callback.on_event(sender.con_id(), &event);
+            }
+            return Err(Error::new(ErrorKind::ConnectionAborted, format!("{} con_id: {}", event, sender.con_id())));
+        }
+        validate_recv_frame_size(self.max_recv_frame_size, msg.byte_len(), &self.event_callback, sender.con_id())
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> SoupBinTcpViewProtocol for SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec> {
+    #[inline(always)]
+    fn deserialize_view(frame: &[u8]) -> Result<SoupBinTcpMsgView<'_>, Error> {
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::deserialize_view(frame)
+    }
+    /// Will delegate to [`SvcSoupBinTcpRecvConnectionState::on_recv_payload`] and validate the frame in the same way as [`Self::send_reply`]
+    #[inline(always)]
+    fn on_recv_payload_view<I: ConnectionId>(&self, who: &I, view: &SoupBinTcpMsgView<'_>) -> Result<(), Error> {
+        (*self.recv_con_state.lock()).on_recv_payload();
+        validate_recv_frame_size(self.max_recv_frame_size, view.frame().len(), &self.event_callback, who.con_id())
+    }
+}
+
+/// Splits the next frame using `M`, when [`DecodeErrorPolicy::SKIP`] frames whose header fails [`SoupBinTcpFramer::validate_frame`] against `packet_types`
+/// are dropped with a warning. Only the header is checked so that frames are not decoded twice, once here and once by [Messenger::deserialize]. Oversize frames
+/// fail with [SoupBinTcpFrameSizeError] instead and are never skipped since the rest of the frame was not consumed.
+#[inline(always)]
+fn get_frame_or_skip<M: Framer, Dec: DecodeErrorPolicy>(bytes: &mut bytes::BytesMut, packet_types: &[u8]) -> Option<bytes::Bytes> {
+    loop {
+        let frame = M::get_frame(bytes)?;
+        // a frame shorter then announced is the header of an oversize frame split off by the framer
+        let is_oversize = frame.len() < PACKET_LENGTH_BYTE_LEN || frame.len() != PACKET_LENGTH_BYTE_LEN + u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        if !Dec::SKIP || is_oversize {
+            return Some(frame);
+        }
+        match SoupBinTcpFramer::validate_frame(&frame[..], packet_types) {
+            Err(e) => log::warn!("Skipped frame, caused by: [{}]", e),
+            Ok(()) => return Some(frame),
+        }
+    }
+}
+#[inline(always)]
+fn validate_recv_frame_size(max_recv_frame_size: Option<usize>, frame_size: usize, event_callback: &Option<Arc<dyn SoupBinTcpEventCallback>>, con_id: &ConId) -> Result<(), Error> {
+    match max_recv_frame_size {
+        Some(max_frame_size) if frame_size > max_frame_size => {
+            if let Some(event_callback) = event_callback {
+                event_callback.on_event(con_id, &SoupBinTcpEvent::InboundFrameTooLarge { frame_size, max_frame_size });
+            }
+            Err(SoupBinTcpFrameSizeError::inbound(frame_size, max_frame_size).into())
+        }
+        _ => Ok(()),
     }
 }
 
 #[cfg(test)]
 #[cfg(feature = "unittest")]
-mod test {
+pub(crate) mod test {
 
     use crate::prelude::*;
-    use std::{num::NonZeroUsize, time::Duration};
+    use std::{
+        fmt::Debug,
+        io::{Error, ErrorKind},
+        num::NonZeroUsize,
+        sync::Arc,
+        time::Duration,
+    };
 
     use links_core::unittest::setup;
     use log::info;
@@ -491,4 +955,633 @@ mod test {
 
         assert!(!clt_reconnect.is_connected());
     }
+
+    #[test]
+    fn test_protocol_hbeat_suppressed() {
+        setup::log::configure_level(log::LevelFilter::Info);
+
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_secs(1));
+        let clock = ManualClock::default();
+
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1)).with_hbeat_policy(hbeat_policy).with_clock(clock.clone());
+        let mut svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol.clone(), Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let clt_protocol = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1))
+            .with_hbeat_policy(hbeat_policy)
+            .with_clock(clock.clone());
+        let mut clt = Clt::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            clt_protocol.clone(),
+            Some("clt/soupbintcp/auto"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // busy clt, clock stands still after a payload so every scheduled hbeat is expected to be skipped
+        let mut msg = CltSoupBinTcpMsg::udata(SamplePayload::default());
+        clt.send_busywait_timeout(&mut msg, io_timeout).unwrap().unwrap_completed();
+        let busy_stats = clt_protocol.hbeat_stats();
+        assert!(busywait_timeout(io_timeout, || clt_protocol.hbeat_stats().skipped() > busy_stats.skipped() + 2));
+        let busy_stats = (busy_stats, clt_protocol.hbeat_stats());
+        info!("clt busy hbeat_stats before: {}, after: {}", busy_stats.0, busy_stats.1);
+        assert_eq!(busy_stats.1.sent(), busy_stats.0.sent());
+
+        // idle clt & svc, every scheduled hbeat is expected to be sent
+        clock.advance(hbeat_policy.send_interval());
+        assert!(busywait_timeout(io_timeout, || clt_protocol.hbeat_stats().sent() > busy_stats.1.sent() && svc_protocol.hbeat_stats().sent() > 0));
+        let clt_stats = clt_protocol.hbeat_stats();
+        let svc_stats = svc_protocol.hbeat_stats();
+        info!("clt idle hbeat_stats: {}, svc idle hbeat_stats: {}", clt_stats, svc_stats);
+        assert!(clt.is_connected());
+    }
+
+    /// Spins until `predicate` is `true` or `timeout` elapses, used while [ManualClock] drives the protocol but heartbeats are still sent by another thread
+    fn busywait_timeout(timeout: Duration, mut predicate: impl FnMut() -> bool) -> bool {
+        let start = std::time::Instant::now();
+        loop {
+            if predicate() {
+                return true;
+            }
+            if start.elapsed() > timeout {
+                return false;
+            }
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct EventStore(std::sync::Mutex<Vec<SoupBinTcpEvent>>);
+    impl SoupBinTcpEventCallback for EventStore {
+        fn on_event(&self, con_id: &ConId, event: &SoupBinTcpEvent) {
+            info!("{} on_event: {}", con_id, event);
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+    impl EventStore {
+        fn count_busywait_timeout(&self, timeout: Duration, predicate: impl Fn(&SoupBinTcpEvent) -> bool) -> usize {
+            let start = std::time::Instant::now();
+            loop {
+                let count = self.0.lock().unwrap().iter().filter(|event| predicate(event)).count();
+                if count > 0 || start.elapsed() > timeout {
+                    return count;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_protocol_svc_hbeat_timeout() {
+        setup::log::configure_level(log::LevelFilter::Info);
+
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Duration::from_secs(1);
+
+        let svc_events = Arc::new(EventStore::default());
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1))
+            .with_hbeat_policy(HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_millis(150)))
+            .with_event_callback(svc_events.clone());
+        let mut svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol, Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        // silent clt, never sends a heartbeat during the test
+        let clt_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_protocol = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(25), Duration::from_secs(10));
+        let clt = Clt::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            StoreCallback::new_ref(clt_store.clone()),
+            clt_protocol,
+            Some("clt/soupbintcp/auto"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        assert_eq!(svc_events.count_busywait_timeout(wait_timeout, |event| matches!(event, SoupBinTcpEvent::PeerHeartbeatTimeout { .. })), 1);
+        let found = clt_store.find_recv(
+            "clt/soupbintcp/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::EndOfSession(_))),
+            Some(wait_timeout),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        assert!(!clt.is_connected());
+        assert!(!svc.all_connected());
+    }
+
+    #[test]
+    fn test_protocol_clt_hbeat_timeout() {
+        setup::log::configure_level(log::LevelFilter::Info);
+
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Duration::from_secs(1);
+
+        // silent svc, only sends a heartbeat right after login
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(25));
+        let mut svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol, Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let clt_events = Arc::new(EventStore::default());
+        let clock = ManualClock::default();
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_millis(100)).with_grace_period(Duration::from_millis(50));
+        let clt_protocol = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1))
+            .with_hbeat_policy(hbeat_policy)
+            .with_event_callback(clt_events.clone())
+            .with_clock(clock.clone());
+        let mut clt = Clt::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            clt_protocol,
+            Some("clt/soupbintcp/auto"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // keeps advancing since the svc heartbeat sent right after login may only be delivered after the first advance
+        let is_timed_out = busywait_timeout(wait_timeout, || {
+            clock.advance(hbeat_policy.recv_timeout_with_grace());
+            clt_events.count_busywait_timeout(hbeat_policy.send_interval(), |event| matches!(event, SoupBinTcpEvent::PeerHeartbeatTimeout { .. })) > 0
+        });
+        assert!(is_timed_out);
+        assert_eq!(clt_events.count_busywait_timeout(wait_timeout, |event| matches!(event, SoupBinTcpEvent::PeerHeartbeatTimeout { .. })), 1);
+        // connection is shutdown once the terminated heartbeat task releases its sender, which happens after the event is emitted
+        let mut msg = CltSoupBinTcpMsg::udata(SamplePayload::default());
+        let is_shutdown = busywait_timeout(io_timeout, || clt.send(&mut msg).is_err());
+        info!("send after hbeat timeout is_shutdown: {}", is_shutdown);
+        assert!(is_shutdown);
+    }
+
+    #[test]
+    fn test_protocol_svc_inbound_frame_too_large() {
+        setup::log::configure_level(log::LevelFilter::Info);
+
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = 1024;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Duration::from_secs(1);
+        let max_recv_frame_size = 128;
+
+        let svc_events = Arc::new(EventStore::default());
+        let svc_protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, Nil>::new(username, password, session_id, io_timeout, Duration::from_secs(1))
+            .with_max_recv_frame_size(max_recv_frame_size)
+            .with_event_callback(svc_events.clone());
+        let mut svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol, Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let clt_protocol = CltSoupBinTcpProtocolAuto::<Nil, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+        let mut clt = Clt::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            clt_protocol,
+            Some("clt/soupbintcp/auto"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // outbound is rejected before it is written to the socket
+        let err = clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(VecPayload::new(vec![0; SOUP_BIN_MAX_FRAME_SIZE])), io_timeout).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = SoupBinTcpFrameSizeError::from_io_error(&err).unwrap();
+        assert_eq!(err, &SoupBinTcpFrameSizeError::outbound(SOUP_BIN_MAX_FRAME_SIZE + 3, SOUP_BIN_MAX_FRAME_SIZE));
+
+        clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(VecPayload::new(vec![0; 64])), io_timeout).unwrap().unwrap_completed();
+        clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(VecPayload::new(vec![0; max_recv_frame_size])), io_timeout).unwrap().unwrap_completed();
+        let count = svc_events.count_busywait_timeout(wait_timeout, |event| matches!(event, SoupBinTcpEvent::InboundFrameTooLarge { max_frame_size, .. } if *max_frame_size == max_recv_frame_size));
+        assert_eq!(count, 1);
+
+        // svc terminated the connection hence clt will eventually fail to send
+        let start = std::time::Instant::now();
+        let mut res = Ok(SendStatus::Completed);
+        while res.is_ok() && start.elapsed() < wait_timeout {
+            res = clt.send(&mut CltSoupBinTcpMsg::hbeat());
+        }
+        info!("res: {:?}", res);
+        assert!(res.is_err());
+    }
+
+    /// Returns svc which must be kept alive for the duration of the test and a raw clt logged into it, so that malformed frames can be written
+    fn svc_with_raw_clt<Dec: DecodeErrorPolicy>() -> (impl Sized, Arc<CanonicalEntryStore<UnitMsg>>, std::net::TcpStream) {
+        use std::io::{Read, Write};
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = 1024;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+
+        let svc_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1)).with_decode_error_policy::<Dec>();
+        let svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), StoreCallback::new_ref(svc_store.clone()), svc_protocol, Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let mut clt = std::net::TcpStream::connect(addr).unwrap();
+        clt.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let login = LoginRequest::new(username, password, session_id, 0_u64.into(), Duration::from_secs(1).into()).into();
+        let (buf, len) = CltSoupBinTcpMessenger::<SamplePayload, SamplePayload>::serialize::<SOUP_BIN_MAX_FRAME_SIZE>(&login).unwrap();
+        clt.write_all(&buf[..len]).unwrap();
+        let mut login_accepted = [0_u8; 3];
+        clt.read_exact(&mut login_accepted).unwrap();
+        assert_eq!(login_accepted[2], b'A');
+        (svc, svc_store, clt)
+    }
+    fn svc_recv_malformed_frame<Dec: DecodeErrorPolicy>() -> (impl Sized, Arc<CanonicalEntryStore<UnitMsg>>, std::net::TcpStream) {
+        use std::io::Write;
+        let (svc, svc_store, mut clt) = svc_with_raw_clt::<Dec>();
+        clt.write_all(&[0x00, 0x02, b'Q', 0x00]).unwrap();
+        let (buf, len) = CltSoupBinTcpMessenger::<SamplePayload, SamplePayload>::serialize::<1024>(&CltSoupBinTcpMsg::udata(SamplePayload::default())).unwrap();
+        clt.write_all(&buf[..len]).unwrap();
+        (svc, svc_store, clt)
+    }
+    /// svc terminated the connection, read until EOF or reset, skipping any heartbeats
+    fn assert_raw_clt_terminated(clt: &mut std::net::TcpStream) {
+        use std::io::Read;
+        let mut buf = [0_u8; 1024];
+        let res = loop {
+            match clt.read(&mut buf) {
+                Ok(0) => break Ok(0),
+                Ok(_) => continue,
+                Err(e) => break Err(e),
+            }
+        };
+        info!("res: {:?}", res);
+        assert!(matches!(res, Ok(0)) || matches!(res, Err(ref e) if e.kind() == std::io::ErrorKind::ConnectionReset));
+    }
+
+    #[test]
+    fn test_protocol_svc_decode_error_skip() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let wait_timeout = Duration::from_secs(1);
+        let (_svc, svc_store, _clt) = svc_recv_malformed_frame::<SkipOnDecodeError>();
+
+        let found = svc_store.find_recv("svc/soupbintcp/auto", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::UPayload(_))), Some(wait_timeout));
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        // skipped frame is dropped by the framer and never reaches callbacks in any form
+        let found = svc_store.find_recv("svc/soupbintcp/auto", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::Dbg(_))), Some(Duration::from_millis(100)));
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn test_protocol_svc_decode_error_disconnect() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let (_svc, svc_store, mut clt) = svc_recv_malformed_frame::<DisconnectOnDecodeError>();
+
+        let found = svc_store.find_recv("svc/soupbintcp/auto", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::UPayload(_) | CltSoupBinTcpMsg::Dbg(_))), Some(Duration::from_millis(100)));
+        assert!(found.is_none());
+        assert_raw_clt_terminated(&mut clt);
+    }
+
+    #[test]
+    fn test_protocol_svc_inbound_packet_length_too_large() {
+        use std::io::Write;
+        setup::log::configure_level(log::LevelFilter::Info);
+        // no max_recv_frame_size set, [SamplePayload] limits [UPayload] frames to 23 bytes, only the header is written so the svc must not wait for the rest
+        // and an oversize frame is never skipped
+        fn send_oversize_header<Dec: DecodeErrorPolicy>() {
+            let (_svc, svc_store, mut clt) = svc_with_raw_clt::<Dec>();
+            clt.write_all(&[0x00, 0xFF, b'U']).unwrap();
+            assert_raw_clt_terminated(&mut clt);
+            let found = svc_store.find_recv("svc/soupbintcp/auto", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::UPayload(_))), Some(Duration::from_millis(100)));
+            assert!(found.is_none());
+        }
+        send_oversize_header::<DisconnectOnDecodeError>();
+        send_oversize_header::<SkipOnDecodeError>();
+    }
+
+    #[test]
+    fn test_protocol_svc_login_malformed_fields() {
+        use std::io::{Read, Write};
+        setup::log::configure_level(log::LevelFilter::Info);
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = 1024;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let _svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol, Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        // valid credentials with non numeric hbeat_timeout_ms must be rejected instead of panicking the svc
+        let mut clt = std::net::TcpStream::connect(addr).unwrap();
+        clt.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let login = LoginRequest::new(username, password, session_id, 0_u64.into(), b"abcde".as_slice().into()).into();
+        let (buf, len) = CltSoupBinTcpMessenger::<SamplePayload, SamplePayload>::serialize::<SOUP_BIN_MAX_FRAME_SIZE>(&login).unwrap();
+        clt.write_all(&buf[..len]).unwrap();
+        let mut login_rejected = [0_u8; 4];
+        clt.read_exact(&mut login_rejected).unwrap();
+        info!("login_rejected: {:?}", login_rejected);
+        assert_eq!(login_rejected, [0x00, 0x02, b'J', b'A']);
+    }
+
+    /// Socket free sender, records messages so that protocol hooks can be driven directly
+    #[derive(Debug)]
+    pub(crate) struct RecordingSender<T> {
+        con_id: ConId,
+        pub(crate) sent: Vec<T>,
+    }
+    impl<T> RecordingSender<T> {
+        pub(crate) fn new(con_id: ConId) -> Self {
+            Self { con_id, sent: vec![] }
+        }
+    }
+    impl<T> std::fmt::Display for RecordingSender<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "RecordingSender({})", self.con_id)
+        }
+    }
+    impl<T> ConnectionId for RecordingSender<T> {
+        fn con_id(&self) -> &ConId {
+            &self.con_id
+        }
+    }
+    impl<T: Clone + Debug> SendNonBlocking<T> for RecordingSender<T> {
+        fn send(&mut self, msg: &mut T) -> Result<SendStatus, Error> {
+            self.sent.push(msg.clone());
+            Ok(SendStatus::Completed)
+        }
+    }
+
+    #[test]
+    fn test_protocol_clt_manual_clock() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let policy = HeartbeatPolicy::new(Duration::from_secs(1), Duration::from_secs(3));
+        let clock = ManualClock::default();
+        let events = Arc::new(EventStore::default());
+
+        let clt = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), Duration::from_secs(1), Duration::from_secs(1), Duration::from_secs(1))
+            .with_hbeat_policy(policy)
+            .with_event_callback(events.clone())
+            .with_clock(clock.clone());
+        let mut sender = RecordingSender::new(ConId::clt(Some("clt/soupbintcp/clock"), None, "127.0.0.1:0"));
+        assert!(!clt.is_connected());
+
+        clt.on_recv(&sender, &LoginAccepted::new(session_id, 1_u64.into()).into());
+        assert!(clt.is_connected());
+
+        // idle, login does not count as traffic so heartbeat is due
+        clt.on_sent(&sender, &LoginRequest::default().into());
+        clt.send_heart_beat(&mut sender).unwrap().unwrap_completed();
+        // busy, heartbeat suppressed until send_interval elapses
+        clt.on_sent(&sender, &CltSoupBinTcpMsg::udata(SamplePayload::default()));
+        clt.send_heart_beat(&mut sender).unwrap().unwrap_completed();
+        clock.advance(policy.send_interval());
+        clt.send_heart_beat(&mut sender).unwrap().unwrap_completed();
+        info!("hbeat_stats: {}", clt.hbeat_stats());
+        assert_eq!(sender.sent.len(), 2);
+        assert_eq!(clt.hbeat_stats().skipped(), 1);
+
+        // inbound staleness
+        clock.advance(policy.recv_timeout() - policy.send_interval() - Duration::from_nanos(1));
+        assert!(clt.is_connected());
+        clock.advance(Duration::from_nanos(1));
+        assert!(!clt.is_connected());
+        let err = clt.send_heart_beat(&mut sender).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(
+            events.0.lock().unwrap().as_slice(),
+            &[SoupBinTcpEvent::PeerHeartbeatTimeout {
+                elapsed: policy.recv_timeout(),
+                recv_timeout: policy.recv_timeout()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_protocol_svc_manual_clock() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let hbeat_timeout = Duration::from_secs(2);
+        let clock = ManualClock::default();
+
+        let svc = SvcProtocolAuto::new(username, password, session_id, Duration::from_secs(1), Duration::from_secs(1)).with_clock(clock.clone());
+        let mut sender = RecordingSender::new(ConId::svc(Some("svc/soupbintcp/clock"), "127.0.0.1:0", None));
+        assert!(!svc.is_connected());
+
+        // receive timeout is advertised by the client
+        svc.on_recv(&sender, &LoginRequest::new(username, password, session_id, 0_u64.into(), hbeat_timeout.into()).into());
+        svc.on_sent(&sender, &LoginAccepted::new(session_id, 1_u64.into()).into());
+        assert!(svc.is_connected());
+
+        clock.advance(hbeat_timeout / 2);
+        svc.on_recv(&sender, &CltSoupBinTcpMsg::hbeat());
+        clock.advance(hbeat_timeout / 2);
+        assert!(svc.is_connected());
+        svc.send_heart_beat(&mut sender).unwrap().unwrap_completed();
+        assert_eq!(sender.sent.len(), 1);
+
+        clock.advance(hbeat_timeout / 2);
+        assert!(!svc.is_connected());
+        let err = svc.send_heart_beat(&mut sender).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        // session ended
+        svc.on_recv(&sender, &CltSoupBinTcpMsg::hbeat());
+        assert!(svc.is_connected());
+        svc.on_sent(&sender, &EndOfSession::default().into());
+        assert!(!svc.is_connected());
+    }
+
+    #[test]
+    fn test_protocol_svc_logout() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let events = Arc::new(EventStore::default());
+
+        let svc = SvcProtocolAuto::new(username, password, session_id, Duration::from_secs(1), Duration::from_secs(1)).with_event_callback(events.clone());
+        let mut sender = RecordingSender::new(ConId::svc(Some("svc/soupbintcp/logout"), "127.0.0.1:0", None));
+        svc.on_sent(&sender, &LoginAccepted::new(session_id, 1_u64.into()).into());
+
+        // logout terminates the connection without a reply
+        let err = svc.send_reply(&LogoutRequest::default().into(), &mut sender).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::ConnectionAborted);
+        assert!(sender.sent.is_empty());
+        assert_eq!(events.0.lock().unwrap().as_slice(), &[SoupBinTcpEvent::PeerLogout]);
+
+        // other messages need no reply
+        svc.send_reply(&CltSoupBinTcpMsg::hbeat(), &mut sender).unwrap();
+        assert!(sender.sent.is_empty());
+    }
+
+    #[test]
+    fn test_protocol_svc_on_disconnect() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = Duration::from_secs(1);
+
+        // rejected connection is closed without EndOfSession
+        let svc = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let sender = RecordingSender::<SvcSoupBinTcpMsg<SamplePayload>>::new(ConId::svc(Some("svc/soupbintcp/rejected"), "127.0.0.1:0", None));
+        assert_eq!(svc.on_disconnect(), None);
+        svc.on_sent(&sender, &LoginRejected::not_authorized().into());
+        assert_eq!(svc.on_disconnect(), None);
+
+        // accepted connection ends with EndOfSession
+        let svc = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        svc.on_sent(&sender, &LoginAccepted::new(session_id, 1_u64.into()).into());
+        assert_eq!(svc.on_disconnect(), Some((io_timeout, EndOfSession::default().into())));
+    }
+
+    #[test]
+    fn test_protocol_svc_rollover() {
+        setup::log::configure_level(log::LevelFilter::Info);
+
+        const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_a = SessionId::try_from_date(2024, 1, 2).unwrap();
+        let session_b = SessionId::try_from_date(2024, 1, 3).unwrap();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Duration::from_secs(1);
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_secs(1));
+
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_a, io_timeout, Duration::from_secs(1)).with_hbeat_policy(hbeat_policy);
+        let mut svc = Svc::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(3).unwrap(), DevNullCallback::new_ref(), svc_protocol.clone(), Some("svc/soupbintcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let connect = |session_id: SessionId, sequence_number: u64, clt_store: Arc<CanonicalEntryStore<UnitMsg>>| {
+            let clt_protocol = CltProtocolAuto::new(username, password, session_id, sequence_number.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1)).with_hbeat_policy(hbeat_policy);
+            Clt::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+                addr,
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                StoreCallback::new_ref(clt_store),
+                clt_protocol,
+                Some("clt/soupbintcp/auto"),
+            )
+            .map(|clt| clt.into_sender_with_spawned_recver_ref())
+        };
+
+        // session a
+        let clt_a_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_a = connect(session_a, 0, clt_a_store.clone()).unwrap();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+        const N_SEQUENCED_PAYLOADS: usize = 3;
+        for i in 1..=N_SEQUENCED_PAYLOADS {
+            let mut msg = SvcSoupBinTcpMsg::sdata(SamplePayload::new(format!("#{} session a", i).as_bytes().into()));
+            svc.send_busywait_timeout(&mut msg, io_timeout).unwrap().unwrap_completed();
+        }
+
+        let mut visited = vec![];
+        let session_id = svc_protocol.visit_sequenced(2, |msg| {
+            visited.push(msg.payload.clone());
+            true
+        });
+        assert_eq!(session_id, session_a);
+        assert_eq!(visited, vec![SamplePayload::new(b"#2 session a".as_slice().into()), SamplePayload::new(b"#3 session a".as_slice().into())]);
+        assert_eq!(svc_protocol.visit_sequenced(1, |_| false), session_a);
+
+        // rollover
+        assert_eq!(svc_protocol.rollover(session_a).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(svc_protocol.rollover(SessionId::default()).unwrap_err().kind(), ErrorKind::InvalidInput);
+        let archive = svc_protocol.rollover(session_b).unwrap();
+        info!("archive: {:?}", archive);
+        assert_eq!(archive.session_id(), session_a);
+        assert_eq!(archive.sequence_number(), N_SEQUENCED_PAYLOADS);
+        assert_eq!(archive.sequenced().count(), N_SEQUENCED_PAYLOADS);
+        assert!(Arc::ptr_eq(&svc_protocol.archived_session().unwrap(), &archive));
+        assert_eq!(svc_protocol.session_id(), session_b);
+        assert_eq!(svc_protocol.visit_sequenced(1, |msg| panic!("unexpected msg: {:?}", msg)), session_b);
+
+        // EndOfSession is delivered by the heartbeat thread with in one send interval without any other svc traffic
+        let found = clt_a_store.find_recv(
+            "clt/soupbintcp/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::EndOfSession(_))),
+            Some(hbeat_policy.send_interval() * 10),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        assert!(!clt_a.is_connected());
+
+        // terminated clt a is only evicted from the svc pool once sending to it fails
+        let err = svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::hbeat(), io_timeout).unwrap_err();
+        info!("err: {}", err);
+
+        // session b starts at sequence number 1
+        let clt_b_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_b = connect(session_b, 1, clt_b_store.clone()).unwrap();
+        assert!(clt_b.is_connected());
+        let found = clt_b_store.find_recv(
+            "clt/soupbintcp/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::LoginAccepted(msg)) if msg.session_id() == session_b && msg.sequence_number() == 1_u64.into()),
+            Some(wait_timeout),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+
+        let payload = SamplePayload::new(b"#1 session b".as_slice().into());
+        svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        let found = clt_b_store.find_recv(
+            "clt/soupbintcp/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::SPayload(SPayload { payload: p, .. })) if p == &payload),
+            setup::net::optional_find_timeout(),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+
+        // login into ended session is rejected
+        let clt_c_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let err = connect(session_a, 1, clt_c_store.clone()).unwrap_err();
+        info!("err: {}", err);
+        let found = clt_c_store.find_recv(
+            "clt/soupbintcp/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::LoginRejected(msg)) if msg == &LoginRejected::session_not_available()),
+            Some(wait_timeout),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        assert!(clt_b.is_connected());
+    }
 }
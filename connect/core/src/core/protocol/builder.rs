@@ -0,0 +1,397 @@
+use crate::prelude::*;
+use std::{
+    io::{Error, ErrorKind},
+    marker::PhantomData,
+    sync::Arc,
+    time::Duration,
+};
+
+pub(crate) const DEFAULT_IO_TIMEOUT: Duration = Duration::from_secs(1);
+pub(crate) const DEFAULT_MAX_HBEAT_INTERVAL: Duration = Duration::from_millis(2_500);
+
+/// Fluent builder for [CltSoupBinTcpProtocolAuto] which validates fixed width fields and returns a descriptive error before any connection is attempted
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+/// use std::time::Duration;
+///
+/// let protocol = CltSoupBinTcpProtocolAuto::<Nil, Nil>::builder()
+///     .username("userid")
+///     .password("passwd")
+///     .session_id("favsession")
+///     .sequence_number(1)
+///     .io_timeout(Duration::from_secs(1))
+///     .build()
+///     .unwrap();
+///
+/// let err = CltSoupBinTcpProtocolAuto::<Nil, Nil>::builder().username("too_long").password("passwd").build().unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+#[derive(Clone)]
+pub struct CltSoupBinTcpProtocolAutoBuilder<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+    username: Option<Vec<u8>>,
+    password: Option<Vec<u8>>,
+    session_id: Vec<u8>,
+    sequence_number: u64,
+    io_timeout: Duration,
+    clt_max_hbeat_interval: Duration,
+    svc_max_hbeat_interval: Duration,
+    hbeat_policy: Option<HeartbeatPolicy>,
+    event_callback: Option<Arc<dyn SoupBinTcpEventCallback>>,
+    max_recv_frame_size: Option<usize>,
+    phantom: PhantomData<(RecvP, SendP)>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default for CltSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+    fn default() -> Self {
+        Self {
+            username: None,
+            password: None,
+            session_id: vec![],
+            sequence_number: 0,
+            io_timeout: DEFAULT_IO_TIMEOUT,
+            clt_max_hbeat_interval: DEFAULT_MAX_HBEAT_INTERVAL,
+            svc_max_hbeat_interval: DEFAULT_MAX_HBEAT_INTERVAL,
+            hbeat_policy: None,
+            event_callback: None,
+            max_recv_frame_size: None,
+            phantom: PhantomData,
+        }
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+    /// Required, up to 6 ascii characters
+    pub fn username<T: AsRef<[u8]>>(mut self, username: T) -> Self {
+        self.username = Some(username.as_ref().to_vec());
+        self
+    }
+    /// Required, up to 10 ascii characters
+    pub fn password<T: AsRef<[u8]>>(mut self, password: T) -> Self {
+        self.password = Some(password.as_ref().to_vec());
+        self
+    }
+    /// Optional, up to 10 ascii characters, defaults to blank which logs into the currently active session
+    pub fn session_id<T: AsRef<[u8]>>(mut self, session_id: T) -> Self {
+        self.session_id = session_id.as_ref().to_vec();
+        self
+    }
+    /// Optional, defaults to `0` which starts receiving the most recently generated message
+    pub fn sequence_number(mut self, sequence_number: u64) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+    /// Optional, timeout for login sequence during [`ProtocolCore::on_connect`] hook
+    pub fn io_timeout(mut self, io_timeout: Duration) -> Self {
+        self.io_timeout = io_timeout;
+        self
+    }
+    /// Optional, maximum interval between sending heartbeats, must not exceed `u16::MAX` milliseconds since it is sent to the server as [TimeoutMs]
+    pub fn clt_max_hbeat_interval(mut self, clt_max_hbeat_interval: Duration) -> Self {
+        self.clt_max_hbeat_interval = clt_max_hbeat_interval;
+        self
+    }
+    /// Optional, maximum interval between receiving heartbeats
+    pub fn svc_max_hbeat_interval(mut self, svc_max_hbeat_interval: Duration) -> Self {
+        self.svc_max_hbeat_interval = svc_max_hbeat_interval;
+        self
+    }
+    /// Optional, overrides send interval and receive timeout derived from `clt_max_hbeat_interval` & `svc_max_hbeat_interval`,
+    /// send interval must not exceed `clt_max_hbeat_interval`, see [`CltSoupBinTcpProtocolAuto::with_hbeat_policy`]
+    pub fn hbeat_policy(mut self, hbeat_policy: HeartbeatPolicy) -> Self {
+        self.hbeat_policy = Some(hbeat_policy);
+        self
+    }
+    /// Optional, see [`CltSoupBinTcpProtocolAuto::with_event_callback`]
+    pub fn event_callback(mut self, event_callback: Arc<dyn SoupBinTcpEventCallback>) -> Self {
+        self.event_callback = Some(event_callback);
+        self
+    }
+    /// Optional, must be at least `3` bytes, see [`CltSoupBinTcpProtocolAuto::with_max_recv_frame_size`]
+    pub fn max_recv_frame_size(mut self, max_recv_frame_size: usize) -> Self {
+        self.max_recv_frame_size = Some(max_recv_frame_size);
+        self
+    }
+    /// Validates all fields and creates a new [CltSoupBinTcpProtocolAuto] instance
+    pub fn build(self) -> Result<CltSoupBinTcpProtocolAuto<RecvP, SendP>, Error> {
+        let username = UserName::try_from_slice(required("username", &self.username)?)?;
+        let password = Password::try_from_slice(required("password", &self.password)?)?;
+        let session_id = SessionId::try_from_slice(&self.session_id)?;
+        TimeoutMs::try_from_duration(self.clt_max_hbeat_interval)?;
+        non_zero("io_timeout", self.io_timeout)?;
+        non_zero("clt_max_hbeat_interval", self.clt_max_hbeat_interval)?;
+        non_zero("svc_max_hbeat_interval", self.svc_max_hbeat_interval)?;
+        if let Some(hbeat_policy) = &self.hbeat_policy {
+            validate_hbeat_policy(hbeat_policy, "clt_max_hbeat_interval", self.clt_max_hbeat_interval)?;
+        }
+        if let Some(max_recv_frame_size) = self.max_recv_frame_size {
+            validate_max_recv_frame_size(max_recv_frame_size)?;
+        }
+
+        let protocol = CltSoupBinTcpProtocolAuto::new(
+            username,
+            password,
+            session_id,
+            self.sequence_number.into(),
+            self.io_timeout,
+            self.clt_max_hbeat_interval,
+            self.svc_max_hbeat_interval,
+        );
+        let protocol = match self.hbeat_policy {
+            Some(hbeat_policy) => protocol.with_hbeat_policy(hbeat_policy),
+            None => protocol,
+        };
+        let protocol = match self.event_callback {
+            Some(event_callback) => protocol.with_event_callback(event_callback),
+            None => protocol,
+        };
+        Ok(match self.max_recv_frame_size {
+            Some(max_recv_frame_size) => protocol.with_max_recv_frame_size(max_recv_frame_size),
+            None => protocol,
+        })
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBinTcpProtocolAuto<RecvP, SendP> {
+    /// Creates a new [CltSoupBinTcpProtocolAutoBuilder]
+    pub fn builder() -> CltSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+        CltSoupBinTcpProtocolAutoBuilder::default()
+    }
+}
+
+/// Fluent builder for [SvcSoupBinTcpProtocolAuto] which validates fixed width fields and returns a descriptive error before any socket is bound
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+///
+/// let protocol = SvcSoupBinTcpProtocolAuto::<Nil, Nil>::builder().username("userid").password("passwd").session_id("favsession").build().unwrap();
+///
+/// let err = SvcSoupBinTcpProtocolAuto::<Nil, Nil>::builder().username("userid").password("passwd").build().unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+#[derive(Clone)]
+pub struct SvcSoupBinTcpProtocolAutoBuilder<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+    username: Option<Vec<u8>>,
+    password: Option<Vec<u8>>,
+    session_id: Option<Vec<u8>>,
+    io_timeout: Duration,
+    svc_max_hbeat_interval: Duration,
+    hbeat_policy: Option<HeartbeatPolicy>,
+    event_callback: Option<Arc<dyn SoupBinTcpEventCallback>>,
+    max_recv_frame_size: Option<usize>,
+    phantom: PhantomData<(RecvP, SendP)>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default for SvcSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+    fn default() -> Self {
+        Self {
+            username: None,
+            password: None,
+            session_id: None,
+            io_timeout: DEFAULT_IO_TIMEOUT,
+            svc_max_hbeat_interval: DEFAULT_MAX_HBEAT_INTERVAL,
+            hbeat_policy: None,
+            event_callback: None,
+            max_recv_frame_size: None,
+            phantom: PhantomData,
+        }
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+    /// Required, up to 6 ascii characters
+    pub fn username<T: AsRef<[u8]>>(mut self, username: T) -> Self {
+        self.username = Some(username.as_ref().to_vec());
+        self
+    }
+    /// Required, up to 10 ascii characters
+    pub fn password<T: AsRef<[u8]>>(mut self, password: T) -> Self {
+        self.password = Some(password.as_ref().to_vec());
+        self
+    }
+    /// Required, up to 10 ascii characters
+    pub fn session_id<T: AsRef<[u8]>>(mut self, session_id: T) -> Self {
+        self.session_id = Some(session_id.as_ref().to_vec());
+        self
+    }
+    /// Optional, timeout for login sequence during [`ProtocolCore::on_connect`] hook
+    pub fn io_timeout(mut self, io_timeout: Duration) -> Self {
+        self.io_timeout = io_timeout;
+        self
+    }
+    /// Optional, maximum interval between sending heartbeats
+    pub fn svc_max_hbeat_interval(mut self, svc_max_hbeat_interval: Duration) -> Self {
+        self.svc_max_hbeat_interval = svc_max_hbeat_interval;
+        self
+    }
+    /// Optional, overrides send interval derived from `svc_max_hbeat_interval` and receive timeout advertised by the client,
+    /// send interval must not exceed `svc_max_hbeat_interval`, see [`SvcSoupBinTcpProtocolAuto::with_hbeat_policy`]
+    pub fn hbeat_policy(mut self, hbeat_policy: HeartbeatPolicy) -> Self {
+        self.hbeat_policy = Some(hbeat_policy);
+        self
+    }
+    /// Optional, see [`SvcSoupBinTcpProtocolAuto::with_event_callback`]
+    pub fn event_callback(mut self, event_callback: Arc<dyn SoupBinTcpEventCallback>) -> Self {
+        self.event_callback = Some(event_callback);
+        self
+    }
+    /// Optional, must be at least `3` bytes, see [`SvcSoupBinTcpProtocolAuto::with_max_recv_frame_size`]
+    pub fn max_recv_frame_size(mut self, max_recv_frame_size: usize) -> Self {
+        self.max_recv_frame_size = Some(max_recv_frame_size);
+        self
+    }
+    /// Validates all fields and creates a new [SvcSoupBinTcpProtocolAuto] instance
+    pub fn build(self) -> Result<SvcSoupBinTcpProtocolAuto<RecvP, SendP>, Error> {
+        let username = UserName::try_from_slice(required("username", &self.username)?)?;
+        let password = Password::try_from_slice(required("password", &self.password)?)?;
+        let session_id = SessionId::try_from_slice(required("session_id", &self.session_id)?)?;
+        non_zero("io_timeout", self.io_timeout)?;
+        non_zero("svc_max_hbeat_interval", self.svc_max_hbeat_interval)?;
+        if let Some(hbeat_policy) = &self.hbeat_policy {
+            validate_hbeat_policy(hbeat_policy, "svc_max_hbeat_interval", self.svc_max_hbeat_interval)?;
+        }
+        if let Some(max_recv_frame_size) = self.max_recv_frame_size {
+            validate_max_recv_frame_size(max_recv_frame_size)?;
+        }
+
+        let protocol = SvcSoupBinTcpProtocolAuto::new(username, password, session_id, self.io_timeout, self.svc_max_hbeat_interval);
+        let protocol = match self.hbeat_policy {
+            Some(hbeat_policy) => protocol.with_hbeat_policy(hbeat_policy),
+            None => protocol,
+        };
+        let protocol = match self.event_callback {
+            Some(event_callback) => protocol.with_event_callback(event_callback),
+            None => protocol,
+        };
+        Ok(match self.max_recv_frame_size {
+            Some(max_recv_frame_size) => protocol.with_max_recv_frame_size(max_recv_frame_size),
+            None => protocol,
+        })
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpProtocolAuto<RecvP, SendP> {
+    /// Creates a new [SvcSoupBinTcpProtocolAutoBuilder]
+    pub fn builder() -> SvcSoupBinTcpProtocolAutoBuilder<RecvP, SendP> {
+        SvcSoupBinTcpProtocolAutoBuilder::default()
+    }
+}
+
+fn required<'a>(field: &str, value: &'a Option<Vec<u8>>) -> Result<&'a [u8], Error> {
+    value.as_deref().ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Missing required field: {}", field)))
+}
+fn validate_hbeat_policy(hbeat_policy: &HeartbeatPolicy, max_field: &str, max_hbeat_interval: Duration) -> Result<(), Error> {
+    non_zero("hbeat_policy.send_interval", hbeat_policy.send_interval())?;
+    non_zero("hbeat_policy.recv_timeout", hbeat_policy.recv_timeout())?;
+    // sending heartbeats any slower than the max interval would get the connection timed out by the peer
+    if hbeat_policy.send_interval() > max_hbeat_interval {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid hbeat_policy.send_interval: {:?} must not exceed {}: {:?}", hbeat_policy.send_interval(), max_field, max_hbeat_interval),
+        ));
+    }
+    Ok(())
+}
+fn validate_max_recv_frame_size(max_recv_frame_size: usize) -> Result<(), Error> {
+    // smallest valid frame is `packet_length` followed by `packet_type`
+    if max_recv_frame_size < PACKET_HEADER_BYTE_LEN {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid max_recv_frame_size: {} must be at least {} bytes", max_recv_frame_size, PACKET_HEADER_BYTE_LEN)));
+    }
+    Ok(())
+}
+fn non_zero(field: &str, value: Duration) -> Result<(), Error> {
+    if value.is_zero() {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid {}: must be greater than zero", field)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use std::{io::ErrorKind, time::Duration};
+
+    type CltProtocolAuto = CltSoupBinTcpProtocolAuto<SamplePayload, SamplePayload>;
+    type SvcProtocolAuto = SvcSoupBinTcpProtocolAuto<SamplePayload, SamplePayload>;
+
+    #[test]
+    fn test_clt_builder() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let protocol = CltProtocolAuto::builder().username("userid").password(b"passwd").sequence_number(10).build().unwrap();
+        info!("protocol: {:?}", protocol);
+        assert_eq!(protocol.conf_heart_beat_interval(), Some(Duration::from_secs(1)));
+
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_millis(500), Duration::from_secs(15));
+        let protocol = CltProtocolAuto::builder().username("userid").password("passwd").hbeat_policy(hbeat_policy).build().unwrap();
+        info!("protocol: {:?}", protocol);
+        assert_eq!(protocol.hbeat_policy(), &hbeat_policy);
+        assert_eq!(protocol.conf_heart_beat_interval(), Some(Duration::from_millis(500)));
+
+        for (i, builder) in [
+            CltProtocolAuto::builder().password("passwd"),                                                                    // missing username
+            CltProtocolAuto::builder().username("userid"),                                                                    // missing password
+            CltProtocolAuto::builder().username("1234567").password("passwd"),                                                // username exceeds 6 chars
+            CltProtocolAuto::builder().username("userid").password("12345678901"),                                            // password exceeds 10 chars
+            CltProtocolAuto::builder().username("userid").password("passwd").session_id("12345678901"),                       // session_id exceeds 10 chars
+            CltProtocolAuto::builder().username("usérid").password("passwd"),                                                 // username not ascii
+            CltProtocolAuto::builder().username("userid").password("passwd").clt_max_hbeat_interval(Duration::from_secs(66)), // exceeds TimeoutMs
+            CltProtocolAuto::builder().username("userid").password("passwd").io_timeout(Duration::ZERO),                      // zero timeout
+            CltProtocolAuto::builder()
+                .username("userid")
+                .password("passwd")
+                .hbeat_policy(HeartbeatPolicy::new(Duration::ZERO, Duration::from_secs(1))), // zero send interval
+            CltProtocolAuto::builder()
+                .username("userid")
+                .password("passwd")
+                .clt_max_hbeat_interval(Duration::from_secs(1))
+                .hbeat_policy(HeartbeatPolicy::new(Duration::from_secs(2), Duration::from_secs(15))), // send interval exceeds max
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let err = builder.build().unwrap_err();
+            info!("{} err: {}", i + 1, err);
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_svc_builder() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let protocol = SvcProtocolAuto::builder().username("userid").password("passwd").session_id("favsession").build().unwrap();
+        info!("protocol: {:?}", protocol);
+        assert_eq!(protocol.conf_heart_beat_interval(), Some(Duration::from_secs(1)));
+
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_secs(1), Duration::from_secs(15)).with_grace_period(Duration::from_secs(1));
+        let protocol = SvcProtocolAuto::builder()
+            .username("userid")
+            .password("passwd")
+            .session_id("favsession")
+            .hbeat_policy(hbeat_policy)
+            .build()
+            .unwrap();
+        info!("protocol: {:?}", protocol);
+        assert_eq!(protocol.hbeat_policy(), &hbeat_policy);
+
+        for (i, builder) in [
+            SvcProtocolAuto::builder().username("userid").password("passwd"),                           // missing session_id
+            SvcProtocolAuto::builder().username("userid").password("passwd").session_id("12345678901"), // session_id exceeds 10 chars
+            SvcProtocolAuto::builder()
+                .username("userid")
+                .password("passwd")
+                .session_id("favsession")
+                .svc_max_hbeat_interval(Duration::ZERO), // zero interval
+            SvcProtocolAuto::builder()
+                .username("userid")
+                .password("passwd")
+                .session_id("favsession")
+                .svc_max_hbeat_interval(Duration::from_secs(1))
+                .hbeat_policy(HeartbeatPolicy::new(Duration::from_secs(2), Duration::from_secs(15))), // send interval exceeds max
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let err = builder.build().unwrap_err();
+            info!("{} err: {}", i + 1, err);
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+}
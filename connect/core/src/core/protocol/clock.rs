@@ -0,0 +1,118 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Source of time for connection states & auto protocols, allows heartbeat expiry & staleness to be tested without sleeping, see [ManualClock]
+pub trait Clock: Debug + Clone + Send + Sync + 'static {
+    fn now(&self) -> Instant;
+    /// Wall clock time used by calendar based schedules, see [SvcSoupBinTcpSessionScheduler](crate::prelude::SvcSoupBinTcpSessionScheduler)
+    #[inline(always)]
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+    /// Time elapsed since `earlier`, zero if `earlier` is in the future
+    #[inline(always)]
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// Default [Clock], delegates to [Instant::now]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MonotonicClock;
+impl Clock for MonotonicClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// [Clock] which only moves when [`Self::advance`] is called, all clones share the same time so a clone retained before handing the clock to a
+/// protocol can be used to drive it.
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::default();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(10));
+/// assert_eq!(clock.elapsed(start), Duration::from_secs(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: Instant,
+    start_system_time: SystemTime,
+    offset_nanos: Arc<AtomicU64>,
+}
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+    /// Same as [`Self::new`] but [`Clock::system_time`] starts at `system_time`, example the moment before a venue opens
+    pub fn starting_at(system_time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            start_system_time: system_time,
+            offset_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+    /// Moves time forward for this instance and all of its clones
+    pub fn advance(&self, duration: Duration) {
+        self.offset_nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+    /// Total time advanced since creation
+    pub fn offset(&self) -> Duration {
+        Duration::from_nanos(self.offset_nanos.load(Ordering::SeqCst))
+    }
+}
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clock for ManualClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        self.start + self.offset()
+    }
+    #[inline(always)]
+    fn system_time(&self) -> SystemTime {
+        self.start_system_time + self.offset()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+
+    #[test]
+    fn test_manual_clock() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let clock = ManualClock::default();
+        let clone = clock.clone();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clone.advance(Duration::from_millis(1_500));
+        info!("clock: {:?}", clock);
+        assert_eq!(clock.elapsed(start), Duration::from_millis(1_500));
+        assert_eq!(clock.offset(), Duration::from_millis(1_500));
+        assert_eq!(clone.elapsed(clock.now() + Duration::from_secs(1)), Duration::ZERO);
+
+        let clock = ManualClock::starting_at(std::time::UNIX_EPOCH);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(clock.system_time(), std::time::UNIX_EPOCH + Duration::from_secs(60));
+
+        let clock = MonotonicClock;
+        assert!(clock.now() >= start);
+    }
+}
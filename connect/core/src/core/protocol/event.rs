@@ -0,0 +1,52 @@
+use crate::prelude::*;
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Connection level events raised by the protocol which are not represented by a SoupBinTcp message and hence are not visible to [CallbackRecvSend]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SoupBinTcpEvent {
+    /// No message was received from the peer with in the receive timeout of the [HeartbeatPolicy], the connection is being terminated
+    PeerHeartbeatTimeout { elapsed: Duration, recv_timeout: Duration },
+    /// Peer sent a frame larger then the maximum receive frame size of the protocol, the connection is being terminated
+    InboundFrameTooLarge { frame_size: usize, max_frame_size: usize },
+    /// Peer sent [LogoutRequest], the connection is being terminated
+    PeerLogout,
+}
+impl Display for SoupBinTcpEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PeerHeartbeatTimeout { elapsed, recv_timeout } => write!(f, "Peer heartbeat timeout, elapsed: {:?} exceeds recv_timeout: {:?}", elapsed, recv_timeout),
+            Self::InboundFrameTooLarge { frame_size, max_frame_size } => write!(f, "Inbound frame too large, frame size: {} exceeds max_frame_size: {}", frame_size, max_frame_size),
+            Self::PeerLogout => write!(f, "Peer logout requested"),
+        }
+    }
+}
+
+/// Receives [SoupBinTcpEvent]s from protocols which support them, for example [CltSoupBinTcpProtocolAuto::with_event_callback]
+///
+/// A separate trait rather than a [CallbackRecvSend] extension, since [CallbackRecvSend] is owned by the connection and only ever receives `RecvT` & `SendT`
+/// messages while the events are raised by the protocol, often from the heartbeat thread, which holds no reference to that callback. Delivering them through
+/// [CallbackRecvSend] would require encoding each event into a message which could also be received off the wire.
+///
+/// # Important
+/// Called from the heartbeat thread, implementation must not block
+pub trait SoupBinTcpEventCallback: Debug + Send + Sync + 'static {
+    fn on_event(&self, con_id: &ConId, event: &SoupBinTcpEvent);
+}
+
+/// Logs every [SoupBinTcpEvent] at the `warn` level
+#[derive(Debug, Default)]
+pub struct LoggerEventCallback;
+impl LoggerEventCallback {
+    pub fn new_ref() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+impl SoupBinTcpEventCallback for LoggerEventCallback {
+    fn on_event(&self, con_id: &ConId, event: &SoupBinTcpEvent) {
+        log::warn!("{} {}", con_id, event);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::prelude::*;
+use std::time::{Duration, Instant};
+
+/// Ratio used by [`HeartbeatPolicy::from_max_interval`] to derive the send interval from the maximum interval advertised to the peer.
+pub const DEFAULT_HBEAT_SEND_RATIO: f64 = 2.5;
+
+/// Heartbeat rules of a single side of a session.
+///
+/// * `send_interval` - interval of outbound silence after which a heartbeat is due, drives [`links_nonblocking::prelude::Protocol::conf_heart_beat_interval`]
+/// * `recv_timeout` - interval of inbound silence after which the peer is considered stale and `is_connected` returns `false`
+/// * `grace_period` - optional allowance added on top of `recv_timeout`, to absorb network jitter or slow peers
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+/// use std::time::Duration;
+///
+/// // send after 1 s of outbound silence, drop after 15 s of inbound silence
+/// let policy = HeartbeatPolicy::new(Duration::from_secs(1), Duration::from_secs(15)).with_grace_period(Duration::from_millis(500));
+/// assert_eq!(policy.send_interval(), Duration::from_secs(1));
+/// assert_eq!(policy.recv_timeout_with_grace(), Duration::from_millis(15_500));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeartbeatPolicy {
+    send_interval: Duration,
+    recv_timeout: Duration,
+    grace_period: Option<Duration>,
+}
+impl HeartbeatPolicy {
+    pub fn new(send_interval: Duration, recv_timeout: Duration) -> Self {
+        Self {
+            send_interval,
+            recv_timeout,
+            grace_period: None,
+        }
+    }
+    /// Creates a policy matching legacy behavior, where heartbeats are sent [DEFAULT_HBEAT_SEND_RATIO] times faster then `max_send_interval`
+    /// so if max is set to 25 seconds then heartbeats will be sent every 10 seconds
+    pub fn from_max_interval(max_send_interval: Duration, recv_timeout: Duration) -> Self {
+        Self::new(max_send_interval.div_f64(DEFAULT_HBEAT_SEND_RATIO), recv_timeout)
+    }
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = Some(grace_period);
+        self
+    }
+    #[inline(always)]
+    pub fn send_interval(&self) -> Duration {
+        self.send_interval
+    }
+    #[inline(always)]
+    pub fn recv_timeout(&self) -> Duration {
+        self.recv_timeout
+    }
+    #[inline(always)]
+    pub fn grace_period(&self) -> Option<Duration> {
+        self.grace_period
+    }
+    /// Returns `recv_timeout` plus `grace_period` if one is set, this is the actual window used to detect a stale peer
+    #[inline(always)]
+    pub fn recv_timeout_with_grace(&self) -> Duration {
+        self.recv_timeout + self.grace_period.unwrap_or(Duration::ZERO)
+    }
+}
+impl Default for HeartbeatPolicy {
+    /// Sends every `1 s` and considers peer stale after `2.5 s` of silence
+    fn default() -> Self {
+        Self::from_max_interval(Duration::from_secs_f64(2.5), Duration::from_secs_f64(2.5))
+    }
+}
+
+/// Helper to suppress heartbeats while the session is busy, uses sent payload messages to check.
+/// Heartbeats are excluded so that jitter of the heartbeat timer does not cause every other heartbeat to be skipped, and login messages
+/// so that the first heartbeat after login is sent.
+#[derive(Debug, Clone, Default)]
+pub struct HeartbeatSendState<C: Clock = MonotonicClock> {
+    any_msg_sent: Option<Instant>,
+    clock: C,
+}
+impl<C: Clock> HeartbeatSendState<C> {
+    pub fn new_with_clock(clock: C) -> Self {
+        Self { any_msg_sent: None, clock }
+    }
+    /// Must be called for every payload message sent
+    #[inline(always)]
+    pub fn on_sent(&mut self) {
+        self.any_msg_sent = Some(self.clock.now());
+    }
+    /// Returns `true` if no message was sent with in `send_interval`, hence heartbeat is due
+    #[inline(always)]
+    pub fn is_idle(&self, send_interval: Duration) -> bool {
+        match self.any_msg_sent {
+            Some(any_msg_sent) => self.clock.elapsed(any_msg_sent) >= send_interval,
+            None => true,
+        }
+    }
+}
+impl<C: Clock> From<HeartbeatSendState<C>> for ProtocolConnectionState<HeartbeatSendState<C>> {
+    fn from(state: HeartbeatSendState<C>) -> Self {
+        ProtocolConnectionState::new(state)
+    }
+}
+
+/// Counters of heartbeats which were due according to [`HeartbeatPolicy::send_interval`], either sent or skipped because of other outbound traffic
+///
+/// # Important
+/// Counters are kept per protocol session and accumulate across all of its connections rather than being reset on each connect, since a per connection
+/// state is not observable from the protocol instance retained by the caller
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeartbeatStats {
+    sent: u64,
+    skipped: u64,
+}
+impl HeartbeatStats {
+    #[inline(always)]
+    pub(crate) fn on_sent(&mut self) {
+        self.sent += 1;
+    }
+    #[inline(always)]
+    pub(crate) fn on_skipped(&mut self) {
+        self.skipped += 1;
+    }
+    #[inline(always)]
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+    #[inline(always)]
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+    #[inline(always)]
+    pub fn scheduled(&self) -> u64 {
+        self.sent + self.skipped
+    }
+    /// Ratio of skipped to scheduled heartbeats, `0.0` if none were scheduled
+    pub fn skip_rate(&self) -> f64 {
+        match self.scheduled() {
+            0 => 0.0,
+            scheduled => self.skipped as f64 / scheduled as f64,
+        }
+    }
+}
+impl std::fmt::Display for HeartbeatStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sent: {}, skipped: {}, skip_rate: {:.2}", self.sent, self.skipped, self.skip_rate())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+
+    #[test]
+    fn test_hbeat_policy() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let policy = HeartbeatPolicy::default();
+        info!("policy: {:?}", policy);
+        assert_eq!(policy.send_interval(), Duration::from_secs(1));
+        assert_eq!(policy.recv_timeout(), Duration::from_millis(2_500));
+        assert_eq!(policy.grace_period(), None);
+        assert_eq!(policy.recv_timeout_with_grace(), policy.recv_timeout());
+
+        let policy = HeartbeatPolicy::from_max_interval(Duration::from_secs(25), Duration::from_secs(15)).with_grace_period(Duration::from_secs(1));
+        info!("policy: {:?}", policy);
+        assert_eq!(policy.send_interval(), Duration::from_secs(10));
+        assert_eq!(policy.recv_timeout(), Duration::from_secs(15));
+        assert_eq!(policy.grace_period(), Some(Duration::from_secs(1)));
+        assert_eq!(policy.recv_timeout_with_grace(), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn test_hbeat_policy_grace_period() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let policy = HeartbeatPolicy::new(Duration::from_secs(1), Duration::from_secs(5)).with_grace_period(Duration::from_secs(10));
+        let clock = ManualClock::default();
+
+        let mut clt_state = CltSoupBinTcpRecvConnectionState::with_hbeat_policy(&policy).with_clock(clock.clone());
+        let mut svc_state = SvcSoupBinTcpRecvConnectionState::with_hbeat_policy(&policy).with_clock(clock.clone());
+        assert!(!clt_state.is_connected());
+        assert!(!svc_state.is_connected());
+
+        clt_state.on_recv::<Nil>(&LoginAccepted::new(SessionId::default(), 1_u64.into()).into());
+        svc_state.on_recv::<Nil>(
+            &LoginRequest::new(
+                b"userid".as_slice().into(),
+                b"passwd".as_slice().into(),
+                SessionId::default(),
+                1_u64.into(),
+                Duration::from_secs(1).into(),
+            )
+            .into(),
+        );
+        assert!(clt_state.is_connected());
+        assert!(svc_state.is_connected());
+
+        clock.advance(policy.recv_timeout()); // past recv_timeout but with in grace_period
+        info!("clt_state: {:?}, svc_state: {:?}", clt_state, svc_state);
+        assert!(clt_state.is_connected());
+        assert!(svc_state.is_connected());
+        assert_eq!(clt_state.hbeat_timeout(), None);
+        assert_eq!(svc_state.hbeat_timeout(), None);
+
+        clock.advance(policy.grace_period().unwrap()); // past grace_period
+        info!("clt_state: {:?}, svc_state: {:?}", clt_state, svc_state);
+        assert!(!clt_state.is_connected());
+        assert!(!svc_state.is_connected());
+        let expected = Some(SoupBinTcpEvent::PeerHeartbeatTimeout {
+            elapsed: policy.recv_timeout_with_grace(),
+            recv_timeout: policy.recv_timeout_with_grace(),
+        });
+        assert_eq!(clt_state.hbeat_timeout(), expected);
+        assert_eq!(svc_state.hbeat_timeout(), expected);
+
+        // any message resets staleness
+        clt_state.on_recv::<Nil>(&SvcHeartbeat::default().into());
+        assert!(clt_state.is_connected());
+        assert_eq!(clt_state.hbeat_timeout(), None);
+    }
+
+    #[test]
+    fn test_hbeat_send_state() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let send_interval = Duration::from_secs(1);
+        let clock = ManualClock::default();
+        let mut state = HeartbeatSendState::new_with_clock(clock.clone());
+        let mut stats = HeartbeatStats::default();
+        assert_eq!(stats.skip_rate(), 0.0);
+        assert!(state.is_idle(send_interval));
+
+        state.on_sent();
+        assert!(!state.is_idle(send_interval));
+        stats.on_skipped();
+
+        clock.advance(send_interval - Duration::from_nanos(1));
+        assert!(!state.is_idle(send_interval));
+        clock.advance(Duration::from_nanos(1));
+        assert!(state.is_idle(send_interval));
+        stats.on_sent();
+
+        info!("stats: {}", stats);
+        assert_eq!(stats.scheduled(), 2);
+        assert_eq!(stats.skip_rate(), 0.5);
+    }
+}
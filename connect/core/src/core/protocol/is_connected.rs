@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use std::{fmt::Debug, io::Error, marker::PhantomData, time::Duration};
+use std::{fmt::Debug, io::Error, marker::PhantomData};
 
 /// Implements SoupBinTcp protocol for client side.
 ///
@@ -14,19 +14,25 @@ pub struct CltSoupBinTcpProtocolIsConnected<RecvP: SoupBinTcpPayload<RecvP>, Sen
     recv_con_state: ProtocolConnectionState<CltSoupBinTcpRecvConnectionState>,
     phantom: PhantomData<(RecvP, SendP)>,
 }
-impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default for CltSoupBinTcpProtocolIsConnected<RecvP, SendP> {
-    fn default() -> Self {
-        let max_recv_interval = Duration::from_secs_f64(2.5);
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CltSoupBinTcpProtocolIsConnected<RecvP, SendP> {
+    /// Creates new instance which uses [`HeartbeatPolicy::recv_timeout_with_grace`] as the receive staleness window
+    pub fn new(hbeat_policy: HeartbeatPolicy) -> Self {
         Self {
-            recv_con_state: CltSoupBinTcpRecvConnectionState::new(max_recv_interval).into(),
+            recv_con_state: CltSoupBinTcpRecvConnectionState::with_hbeat_policy(&hbeat_policy).into(),
             phantom: PhantomData,
         }
     }
 }
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default for CltSoupBinTcpProtocolIsConnected<RecvP, SendP> {
+    /// Uses [`HeartbeatPolicy::default`]
+    fn default() -> Self {
+        Self::new(HeartbeatPolicy::default())
+    }
+}
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for CltSoupBinTcpProtocolIsConnected<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        CltSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for CltSoupBinTcpProtocolIsConnected<RecvP, SendP> {
@@ -47,7 +53,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
     #[inline(always)]
     fn on_recv<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::RecvT) {
         #[cfg(debug_assertions)]
-        log::debug!("{}::on_recv: con_id: {}, msg: {:?}", asserted_short_name!("CltSoupBinTcpProtocolManual", Self), who.con_id(), msg);
+        log::debug!("{}::on_recv: con_id: {}, msg: {:?}", asserted_short_name!("CltSoupBinTcpProtocolIsConnected", Self), who.con_id(), msg);
 
         (*self.recv_con_state.lock()).on_recv(msg);
     }
@@ -75,6 +81,17 @@ pub struct SvcSoupBinTcpProtocolIsConnected<RecvP: SoupBinTcpPayload<RecvP>, Sen
     send_con_state: ProtocolConnectionState<SvcSoupBinTcpSendConnectionState>,
     phantom: PhantomData<(RecvP, SendP)>,
 }
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpProtocolIsConnected<RecvP, SendP> {
+    /// Creates new instance which uses [`HeartbeatPolicy::recv_timeout_with_grace`] as the receive staleness window instead of
+    /// [LoginRequest::hbeat_timeout_ms] advertised by the client
+    pub fn new(hbeat_policy: HeartbeatPolicy) -> Self {
+        Self {
+            recv_con_state: SvcSoupBinTcpRecvConnectionState::with_hbeat_policy(&hbeat_policy).into(),
+            send_con_state: SvcSoupBinTcpSendConnectionState::default().into(),
+            phantom: PhantomData,
+        }
+    }
+}
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default for SvcSoupBinTcpProtocolIsConnected<RecvP, SendP> {
     fn default() -> Self {
         Self {
@@ -87,7 +104,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default f
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for SvcSoupBinTcpProtocolIsConnected<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for SvcSoupBinTcpProtocolIsConnected<RecvP, SendP> {
@@ -110,7 +127,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
     #[inline(always)]
     fn on_recv<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::RecvT) {
         #[cfg(debug_assertions)]
-        log::debug!("{}::on_recv: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolManual", Self), who.con_id(), msg);
+        log::debug!("{}::on_recv: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolIsConnected", Self), who.con_id(), msg);
 
         (*self.recv_con_state.lock()).on_recv(msg);
     }
@@ -120,7 +137,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolC
     #[inline(always)]
     fn on_sent<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::SendT) {
         #[cfg(debug_assertions)]
-        log::debug!("{}::on_sent: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolManual", Self), who.con_id(), msg);
+        log::debug!("{}::on_sent: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolIsConnected", Self), who.con_id(), msg);
 
         (*self.send_con_state.lock()).on_sent(msg);
     }
@@ -20,7 +20,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default f
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for CltSoupBinTcpProtocolManual<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        CltSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for CltSoupBinTcpProtocolManual<RecvP, SendP> {
@@ -61,7 +61,7 @@ impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Default f
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for SvcSoupBinTcpProtocolManual<RecvP, SendP> {
     #[inline(always)]
     fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
-        SoupBinTcpFramer::get_frame_length(bytes)
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
     }
 }
 impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for SvcSoupBinTcpProtocolManual<RecvP, SendP> {
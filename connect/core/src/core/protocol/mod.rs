@@ -1,6 +1,12 @@
 pub mod auto;
+pub mod builder;
+pub mod clock;
+pub mod event;
+pub mod hbeat;
 pub mod is_connected;
 pub mod manual;
+pub mod relay;
+pub mod schedule;
 
 use crate::prelude::*;
 use std::{
@@ -12,27 +18,49 @@ use std::{
 /// Confirms that last message received is with in `svc_max_recv_interval` [Duration].
 /// In addition, confirms that [LoginAccepted] was received and [EndOfSession] was not received.
 #[derive(Debug, Clone)]
-pub struct CltSoupBinTcpRecvConnectionState {
+pub struct CltSoupBinTcpRecvConnectionState<C: Clock = MonotonicClock> {
     svc_max_recv_interval: Duration,
     login_accepted: Option<Instant>,
     login_rejected: Option<Instant>,
     end_of_session: Option<Instant>,
     any_msg_recved: Option<Instant>,
+    clock: C,
 }
 impl CltSoupBinTcpRecvConnectionState {
     pub fn new(svc_max_recv_interval: Duration) -> Self {
+        Self::new_with_clock(svc_max_recv_interval, MonotonicClock)
+    }
+    /// Uses [`HeartbeatPolicy::recv_timeout_with_grace`] as `svc_max_recv_interval`
+    pub fn with_hbeat_policy(hbeat_policy: &HeartbeatPolicy) -> Self {
+        Self::new(hbeat_policy.recv_timeout_with_grace())
+    }
+}
+impl<C: Clock> CltSoupBinTcpRecvConnectionState<C> {
+    pub fn new_with_clock(svc_max_recv_interval: Duration, clock: C) -> Self {
         Self {
             svc_max_recv_interval,
             login_accepted: None,
             login_rejected: None,
             end_of_session: None,
             any_msg_recved: None,
+            clock,
+        }
+    }
+    /// Replaces the [Clock], timestamps already recorded are retained
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> CltSoupBinTcpRecvConnectionState<C2> {
+        CltSoupBinTcpRecvConnectionState {
+            svc_max_recv_interval: self.svc_max_recv_interval,
+            login_accepted: self.login_accepted,
+            login_rejected: self.login_rejected,
+            end_of_session: self.end_of_session,
+            any_msg_recved: self.any_msg_recved,
+            clock,
         }
     }
     #[inline(always)]
     pub fn on_recv<RecvP: SoupBinTcpPayload<RecvP>>(&mut self, msg: &SvcSoupBinTcpMsg<RecvP>) {
         use SvcSoupBinTcpMsg::*;
-        let now = Instant::now();
+        let now = self.clock.now();
         match msg {
             LoginAccepted(_) => self.login_accepted = Some(now),
             LoginRejected(_) => self.login_rejected = Some(now),
@@ -41,8 +69,25 @@ impl CltSoupBinTcpRecvConnectionState {
         }
         self.any_msg_recved = Some(now);
     }
+    /// Same as [`Self::on_recv`] for [SPayload] & [UPayload] which were received as a [SoupBinTcpMsgView] and never deserialized
+    #[inline(always)]
+    pub fn on_recv_payload(&mut self) {
+        self.any_msg_recved = Some(self.clock.now());
+    }
+    /// Returns [SoupBinTcpEvent::PeerHeartbeatTimeout] if [LoginAccepted] was received, session was not ended by [EndOfSession]
+    /// and time elapsed from the last message received is no longer less then `svc_max_recv_interval`
+    #[inline(always)]
+    pub fn hbeat_timeout(&self) -> Option<SoupBinTcpEvent> {
+        match (self.login_accepted, self.any_msg_recved, self.login_rejected, self.end_of_session) {
+            (Some(_), Some(any_msg_recved), None, None) if self.clock.elapsed(any_msg_recved) >= self.svc_max_recv_interval => Some(SoupBinTcpEvent::PeerHeartbeatTimeout {
+                elapsed: self.clock.elapsed(any_msg_recved),
+                recv_timeout: self.svc_max_recv_interval,
+            }),
+            _ => None,
+        }
+    }
 }
-impl ConnectionStatus for CltSoupBinTcpRecvConnectionState {
+impl<C: Clock> ConnectionStatus for CltSoupBinTcpRecvConnectionState<C> {
     /// Will returns `true` if all of below are `true`
     /// * [LoginAccepted] was received
     /// * time elapsed from the last message received is less then `max_recv_interval` which is an argument of [`Self::new`]
@@ -50,13 +95,13 @@ impl ConnectionStatus for CltSoupBinTcpRecvConnectionState {
     /// * [EndOfSession] was `NOT` received
     fn is_connected(&self) -> bool {
         match (self.login_accepted, self.any_msg_recved, self.login_rejected, self.end_of_session) {
-            (Some(_), Some(any_msg_recved), None, None) => any_msg_recved.elapsed() < self.svc_max_recv_interval,
+            (Some(_), Some(any_msg_recved), None, None) => self.clock.elapsed(any_msg_recved) < self.svc_max_recv_interval,
             _ => false,
         }
     }
 }
-impl From<CltSoupBinTcpRecvConnectionState> for ProtocolConnectionState<CltSoupBinTcpRecvConnectionState> {
-    fn from(state: CltSoupBinTcpRecvConnectionState) -> Self {
+impl<C: Clock> From<CltSoupBinTcpRecvConnectionState<C>> for ProtocolConnectionState<CltSoupBinTcpRecvConnectionState<C>> {
+    fn from(state: CltSoupBinTcpRecvConnectionState<C>) -> Self {
         ProtocolConnectionState::new(state)
     }
 }
@@ -64,17 +109,51 @@ impl From<CltSoupBinTcpRecvConnectionState> for ProtocolConnectionState<CltSoupB
 /// Helper to establish connection status of Svc, uses all recved messages to check.
 /// Confirms that last message received with in `max_recv_interval` duration.
 #[derive(Debug, Clone, Default)]
-pub struct SvcSoupBinTcpRecvConnectionState {
+pub struct SvcSoupBinTcpRecvConnectionState<C: Clock = MonotonicClock> {
     max_recv_interval: Option<Duration>, // arrives from client in LoginRequest
+    recv_timeout: Option<Duration>,      // overrides max_recv_interval when set via HeartbeatPolicy
+    grace_period: Duration,
     any_msg_recved: Option<Instant>,
+    clock: C,
 }
 impl SvcSoupBinTcpRecvConnectionState {
+    /// Uses [`HeartbeatPolicy::recv_timeout`] instead of [LoginRequest::hbeat_timeout_ms] advertised by the client
+    /// and extends it by [`HeartbeatPolicy::grace_period`]
+    pub fn with_hbeat_policy(hbeat_policy: &HeartbeatPolicy) -> Self {
+        Self {
+            recv_timeout: Some(hbeat_policy.recv_timeout()),
+            grace_period: hbeat_policy.grace_period().unwrap_or(Duration::ZERO),
+            ..Default::default()
+        }
+    }
+}
+impl<C: Clock> SvcSoupBinTcpRecvConnectionState<C> {
+    pub fn new_with_clock(clock: C) -> Self {
+        Self {
+            max_recv_interval: None,
+            recv_timeout: None,
+            grace_period: Duration::ZERO,
+            any_msg_recved: None,
+            clock,
+        }
+    }
+    /// Replaces the [Clock], timestamps already recorded are retained
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> SvcSoupBinTcpRecvConnectionState<C2> {
+        SvcSoupBinTcpRecvConnectionState {
+            max_recv_interval: self.max_recv_interval,
+            recv_timeout: self.recv_timeout,
+            grace_period: self.grace_period,
+            any_msg_recved: self.any_msg_recved,
+            clock,
+        }
+    }
     #[inline(always)]
     pub fn on_recv<RecvP: SoupBinTcpPayload<RecvP>>(&mut self, msg: &CltSoupBinTcpMsg<RecvP>) {
         use CltSoupBinTcpMsg::*;
-        let now = Instant::now();
+        let now = self.clock.now();
         if let LoginRequest(msg) = msg {
-            self.max_recv_interval = Some(msg.hbeat_timeout_ms.into())
+            // malformed value leaves heartbeat monitoring off, the login itself is rejected by the protocol
+            self.max_recv_interval = msg.hbeat_timeout_ms.try_to_duration().ok()
         }
         // match msg {
         //     Login(msg) => self.max_recv_interval = Some(msg.hbeat_timeout_ms.into()),
@@ -82,21 +161,43 @@ impl SvcSoupBinTcpRecvConnectionState {
         // }
         self.any_msg_recved = Some(now);
     }
+    /// Same as [`Self::on_recv`] for [SPayload] & [UPayload] which were received as a [SoupBinTcpMsgView] and never deserialized
+    #[inline(always)]
+    pub fn on_recv_payload(&mut self) {
+        self.any_msg_recved = Some(self.clock.now());
+    }
+    /// Returns [SoupBinTcpEvent::PeerHeartbeatTimeout] if [LoginRequest] was received and time elapsed from the last message received
+    /// is no longer less then `max_recv_interval`
+    #[inline(always)]
+    pub fn hbeat_timeout(&self) -> Option<SoupBinTcpEvent> {
+        match (self.any_msg_recved, self.max_recv_interval) {
+            (Some(any_msg_recved), Some(max_recv_interval)) => {
+                let recv_timeout = self.recv_timeout.unwrap_or(max_recv_interval) + self.grace_period;
+                let elapsed = self.clock.elapsed(any_msg_recved);
+                if elapsed >= recv_timeout {
+                    Some(SoupBinTcpEvent::PeerHeartbeatTimeout { elapsed, recv_timeout })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
 }
-impl ConnectionStatus for SvcSoupBinTcpRecvConnectionState {
+impl<C: Clock> ConnectionStatus for SvcSoupBinTcpRecvConnectionState<C> {
     /// Will returns `true` if all of below are `true`
     /// * [LoginRequest] was received
     /// * time elapsed from the last message received is less then `max_recv_interval` which is determine by
-    /// [LoginRequest::hbeat_timeout_ms] from client side.
+    ///   [LoginRequest::hbeat_timeout_ms] from client side, unless overridden by [`Self::with_hbeat_policy`]
     fn is_connected(&self) -> bool {
         match (self.any_msg_recved, self.max_recv_interval) {
-            (Some(any_msg_recved), Some(max_recv_interval)) => any_msg_recved.elapsed() < max_recv_interval,
+            (Some(any_msg_recved), Some(max_recv_interval)) => self.clock.elapsed(any_msg_recved) < self.recv_timeout.unwrap_or(max_recv_interval) + self.grace_period,
             _ => false,
         }
     }
 }
-impl From<SvcSoupBinTcpRecvConnectionState> for ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState> {
-    fn from(state: SvcSoupBinTcpRecvConnectionState) -> Self {
+impl<C: Clock> From<SvcSoupBinTcpRecvConnectionState<C>> for ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState<C>> {
+    fn from(state: SvcSoupBinTcpRecvConnectionState<C>) -> Self {
         ProtocolConnectionState::new(state)
     }
 }
@@ -104,23 +205,44 @@ impl From<SvcSoupBinTcpRecvConnectionState> for ProtocolConnectionState<SvcSoupB
 /// Helper to establish connection status of Svc, uses all sent messages to check.
 /// Confirms that [LoginAccepted] was sent and [EndOfSession] was not sent.
 #[derive(Debug, Clone, Default)]
-pub struct SvcSoupBinTcpSendConnectionState {
+pub struct SvcSoupBinTcpSendConnectionState<C: Clock = MonotonicClock> {
     login_accepted: Option<Instant>,
     end_of_session: Option<Instant>,
+    clock: C,
 }
-impl SvcSoupBinTcpSendConnectionState {
+impl<C: Clock> SvcSoupBinTcpSendConnectionState<C> {
+    pub fn new_with_clock(clock: C) -> Self {
+        Self {
+            login_accepted: None,
+            end_of_session: None,
+            clock,
+        }
+    }
+    /// Replaces the [Clock], timestamps already recorded are retained
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> SvcSoupBinTcpSendConnectionState<C2> {
+        SvcSoupBinTcpSendConnectionState {
+            login_accepted: self.login_accepted,
+            end_of_session: self.end_of_session,
+            clock,
+        }
+    }
     #[inline(always)]
     pub fn on_sent<SendP: SoupBinTcpPayload<SendP>>(&mut self, msg: &SvcSoupBinTcpMsg<SendP>) {
         use SvcSoupBinTcpMsg::*;
-        let now = Instant::now();
+        let now = self.clock.now();
         match msg {
             LoginAccepted(_) => self.login_accepted = Some(now),
             EndOfSession(_) => self.end_of_session = Some(now),
             _ => {}
         }
     }
+    /// `true` once [LoginAccepted] was sent, [EndOfSession] must not be sent to a client which was never logged in
+    #[inline(always)]
+    pub fn is_login_accepted(&self) -> bool {
+        self.login_accepted.is_some()
+    }
 }
-impl ConnectionStatus for SvcSoupBinTcpSendConnectionState {
+impl<C: Clock> ConnectionStatus for SvcSoupBinTcpSendConnectionState<C> {
     /// Will returns `true` if all of below are `true`
     /// * [LoginAccepted] was sent
     /// * [EndOfSession] was NOT sent
@@ -128,8 +250,8 @@ impl ConnectionStatus for SvcSoupBinTcpSendConnectionState {
         matches!((self.login_accepted, self.end_of_session), (Some(_), None))
     }
 }
-impl From<SvcSoupBinTcpSendConnectionState> for ProtocolConnectionState<SvcSoupBinTcpSendConnectionState> {
-    fn from(state: SvcSoupBinTcpSendConnectionState) -> Self {
+impl<C: Clock> From<SvcSoupBinTcpSendConnectionState<C>> for ProtocolConnectionState<SvcSoupBinTcpSendConnectionState<C>> {
+    fn from(state: SvcSoupBinTcpSendConnectionState<C>) -> Self {
         ProtocolConnectionState::new(state)
     }
 }
@@ -137,19 +259,34 @@ impl From<SvcSoupBinTcpSendConnectionState> for ProtocolConnectionState<SvcSoupB
 #[derive(Debug)]
 pub struct SvcSoupBinTcpSendSessionState<SendP: SoupBinTcpPayload<SendP>, Storage: ProtocolStorage<Item = SvcSoupBinTcpMsg<SendP>>> {
     sequenced_payload_number: usize,
+    sequenced_positions: Vec<usize>, // position in storage of each SPayload, indexed by sequence number - 1
     storage: Storage,
 }
 impl<SendP: SoupBinTcpPayload<SendP>, Storage: ProtocolStorage<Item = SvcSoupBinTcpMsg<SendP>>> SvcSoupBinTcpSendSessionState<SendP, Storage> {
     pub fn new(storage: Storage) -> Self {
-        Self { sequenced_payload_number: 0, storage }
+        Self {
+            sequenced_payload_number: 0,
+            sequenced_positions: Vec::new(),
+            storage,
+        }
     }
     #[inline(always)]
     pub fn on_sent(&mut self, msg: &SvcSoupBinTcpMsg<SendP>) {
         if let SvcSoupBinTcpMsg::SPayload(_) = msg {
             self.sequenced_payload_number += 1;
+            self.sequenced_positions.push(self.storage.len());
         }
         self.storage.store(msg.clone());
     }
+    /// [SPayload] with the given sequence number, the first one has sequence number `1`
+    #[inline(always)]
+    pub fn get_sequenced(&self, sequence_number: usize) -> Option<&SPayload<SendP>> {
+        let position = *self.sequenced_positions.get(sequence_number.checked_sub(1)?)?;
+        match self.storage.iter().as_slice().get(position) {
+            Some(SvcSoupBinTcpMsg::SPayload(msg)) => Some(msg),
+            _ => None,
+        }
+    }
     #[inline(always)]
     pub fn current_sequence_payload_number(&self) -> usize {
         self.sequenced_payload_number
@@ -162,6 +299,52 @@ impl<SendP: SoupBinTcpPayload<SendP>, Storage: ProtocolStorage<Item = SvcSoupBin
     pub fn get_storage(&self) -> &Storage {
         &self.storage
     }
+    #[inline(always)]
+    pub fn into_storage(self) -> Storage {
+        self.storage
+    }
+}
+
+/// Messages sent during a session which was ended by [`SvcSoupBinTcpProtocolAuto::rollover`]
+#[derive(Debug)]
+pub struct SvcSoupBinTcpSessionArchive<SendP: SoupBinTcpPayload<SendP>> {
+    session_id: SessionId,
+    sequence_number: usize,
+    storage: InMemoryMessageLog<SvcSoupBinTcpMsg<SendP>>,
+}
+impl<SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpSessionArchive<SendP> {
+    pub fn new(session_id: SessionId, session_state: SvcSoupBinTcpSendSessionState<SendP, InMemoryMessageLog<SvcSoupBinTcpMsg<SendP>>>) -> Self {
+        Self {
+            session_id,
+            sequence_number: session_state.current_sequence_payload_number(),
+            storage: session_state.into_storage(),
+        }
+    }
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+    /// Sequence number of the last [SPayload] sent during the session, `0` if none were sent
+    #[inline(always)]
+    pub fn sequence_number(&self) -> usize {
+        self.sequence_number
+    }
+    /// All messages sent during the session, in the order they were sent
+    #[inline(always)]
+    pub fn get_storage(&self) -> &InMemoryMessageLog<SvcSoupBinTcpMsg<SendP>> {
+        &self.storage
+    }
+    /// [SPayload]s sent during the session, the first one has sequence number `1`
+    pub fn sequenced(&self) -> impl Iterator<Item = &SPayload<SendP>> {
+        self.storage.iter().filter_map(|msg| match msg {
+            SvcSoupBinTcpMsg::SPayload(msg) => Some(msg),
+            _ => None,
+        })
+    }
+    #[inline(always)]
+    pub fn into_storage(self) -> InMemoryMessageLog<SvcSoupBinTcpMsg<SendP>> {
+        self.storage
+    }
 }
 // impl<SendP: SoupBinTcpPayload<SendP>,  Storage: ProtocolStorage<Item = SvcSoupBinTcpMsg<SendP>> > From<SvcSoupBinTcpSendSessionState<SendP, Storage> for ProtocolSessionState<SvcSoupBinTcpSendSessionState<SendP, Storage>> {
 //     fn from(state: SvcSoupBinTcpSendSessionState<SendP, Storage>) -> Self {
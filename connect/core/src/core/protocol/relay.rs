@@ -0,0 +1,378 @@
+use crate::prelude::*;
+use std::{
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind},
+    marker::PhantomData,
+    sync::Arc,
+    time::Duration,
+};
+
+/// [SPayload]s received from an upstream session, in the order received & addressed by their upstream sequence number
+#[derive(Debug)]
+pub struct SoupBinTcpRelayLog<P: SoupBinTcpPayload<P>> {
+    session_id: Option<SessionId>,
+    first_sequence_number: usize,
+    sequenced: Vec<SPayload<P>>,
+    is_ended: bool,
+}
+impl<P: SoupBinTcpPayload<P>> Default for SoupBinTcpRelayLog<P> {
+    fn default() -> Self {
+        Self {
+            session_id: None,
+            first_sequence_number: 1,
+            sequenced: Vec::new(),
+            is_ended: false,
+        }
+    }
+}
+impl<P: SoupBinTcpPayload<P>> SoupBinTcpRelayLog<P> {
+    /// Starts the log at the session & sequence number of the upstream [LoginAccepted], ignored once the session is known
+    pub fn on_login_accepted(&mut self, session_id: SessionId, sequence_number: usize) {
+        if self.session_id.is_none() {
+            self.session_id = Some(session_id);
+            self.first_sequence_number = sequence_number.max(1);
+        }
+    }
+    /// Appends `msg` with [`Self::next_sequence_number`], ignored once the log [`Self::is_ended`]
+    #[inline(always)]
+    pub fn append(&mut self, msg: SPayload<P>) {
+        if !self.is_ended {
+            self.sequenced.push(msg);
+        }
+    }
+    /// Marks the upstream session as ended, no further [SPayload]s are appended
+    pub fn end(&mut self) {
+        self.is_ended = true;
+    }
+    /// Upstream session, `None` until [`Self::on_login_accepted`]
+    #[inline(always)]
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+    #[inline(always)]
+    pub fn is_ended(&self) -> bool {
+        self.is_ended
+    }
+    /// Upstream sequence number of the first [SPayload] in the log
+    #[inline(always)]
+    pub fn first_sequence_number(&self) -> usize {
+        self.first_sequence_number
+    }
+    /// Upstream sequence number the next appended [SPayload] will have
+    #[inline(always)]
+    pub fn next_sequence_number(&self) -> usize {
+        self.first_sequence_number + self.sequenced.len()
+    }
+    /// [SPayload] with the given upstream sequence number, `None` if it precedes [`Self::first_sequence_number`] or was not received yet
+    #[inline(always)]
+    pub fn get(&self, sequence_number: usize) -> Option<&SPayload<P>> {
+        self.sequenced.get(sequence_number.checked_sub(self.first_sequence_number)?)
+    }
+}
+
+/// Callback of the upstream [CltSoupBinTcpProtocolAuto] connection which appends every [SPayload] received to [SoupBinTcpRelayLog],
+/// see [`SvcSoupBinTcpProtocolRelay::upstream_callback`]
+#[derive(Debug)]
+pub struct SoupBinTcpRelayUpstreamCallback<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+    log: ProtocolSessionState<SoupBinTcpRelayLog<SendP>>,
+    phantom: PhantomData<RecvP>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CallbackRecv<CltSoupBinTcpProtocolAuto<SendP, RecvP>> for SoupBinTcpRelayUpstreamCallback<RecvP, SendP> {
+    fn on_recv(&self, _con_id: &ConId, msg: &<CltSoupBinTcpProtocolAuto<SendP, RecvP> as Messenger>::RecvT) {
+        match msg {
+            SvcSoupBinTcpMsg::SPayload(msg) => self.log.lock().append(msg.clone()),
+            SvcSoupBinTcpMsg::LoginAccepted(msg) => match msg.sequence_number().try_to_usize() {
+                Ok(sequence_number) => self.log.lock().on_login_accepted(msg.session_id(), sequence_number),
+                Err(e) => {
+                    log::warn!("{} ending relay log due to invalid LoginAccepted: {:?}, error: {}", self, msg, e);
+                    self.log.lock().end();
+                }
+            },
+            SvcSoupBinTcpMsg::EndOfSession(_) => self.log.lock().end(),
+            _ => {}
+        }
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CallbackSend<CltSoupBinTcpProtocolAuto<SendP, RecvP>> for SoupBinTcpRelayUpstreamCallback<RecvP, SendP> {
+    fn on_sent(&self, _con_id: &ConId, _msg: &<CltSoupBinTcpProtocolAuto<SendP, RecvP> as Messenger>::SendT) {}
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> CallbackRecvSend<CltSoupBinTcpProtocolAuto<SendP, RecvP>> for SoupBinTcpRelayUpstreamCallback<RecvP, SendP> {}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Display for SoupBinTcpRelayUpstreamCallback<RecvP, SendP> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", asserted_short_name!("SoupBinTcpRelayUpstreamCallback", Self))
+    }
+}
+
+/// Implements SoupBinTcp protocol for server side of a relay, which serves [SPayload]s of an upstream session from [SoupBinTcpRelayLog]
+/// rather then storing the messages it sends, so that every client receives the upstream sequence numbers.
+///
+/// [SPayload]s are not sent by this protocol, replay & live delivery are driven by the owner of [`Self::relay_log`], see `SvcSoupBinTcpRelay`
+/// in `soupbintcp_connect_nonblocking`, which uses [`Self::take_accepted_sequence_number`] to find where each accepted client starts.
+///
+/// # [ProtocolCore] Features
+/// * [`Self::on_connect`]
+/// * [`Self::on_recv`]
+/// * [`Self::on_sent`]
+/// * [`Self::is_connected`]
+/// * [`Self::on_disconnect`]
+///
+/// # [Protocol] Features
+/// * [`Self::conf_heart_beat_interval`]
+/// * [`Self::send_heart_beat`] - only when there was no other outbound traffic
+/// * [`Self::send_reply`] - terminates the connection on [LogoutRequest]
+#[derive(Debug, Clone)]
+pub struct SvcSoupBinTcpProtocolRelay<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> {
+    username: UserName,
+    password: Password,
+    io_timeout: Duration,
+    hbeat_policy: HeartbeatPolicy,
+    hbeat_send_state: ProtocolConnectionState<HeartbeatSendState>,
+    recv_con_state: ProtocolConnectionState<SvcSoupBinTcpRecvConnectionState>,
+    send_con_state: ProtocolConnectionState<SvcSoupBinTcpSendConnectionState>,
+    relay_log: ProtocolSessionState<SoupBinTcpRelayLog<SendP>>,
+    accepted_sequence_numbers: ProtocolSessionState<Vec<(ConId, usize)>>,
+    phantom: PhantomData<(RecvP, SendP)>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> SvcSoupBinTcpProtocolRelay<RecvP, SendP> {
+    /// Creates new instance
+    ///
+    /// # Arguments
+    /// * `username` - username downstream clients must authenticate with, independent of the upstream login
+    /// * `password` - password downstream clients must authenticate with, independent of the upstream login
+    /// * `io_timeout` - timeout for login sequence during [`Self::on_connect`] hook
+    /// * `svc_max_hbeat_interval` - maximum interval between sending heartbeats, will result in [`Self::conf_heart_beat_interval`] be 2.5 times faster
+    ///
+    /// Session id is learned from the upstream [LoginAccepted], use [`Self::with_hbeat_policy`] to set heartbeat rules explicitly
+    pub fn new(username: UserName, password: Password, io_timeout: Duration, svc_max_hbeat_interval: Duration) -> Self {
+        Self {
+            username,
+            password,
+            io_timeout,
+            hbeat_policy: HeartbeatPolicy::from_max_interval(svc_max_hbeat_interval, svc_max_hbeat_interval),
+            hbeat_send_state: HeartbeatSendState::default().into(),
+            recv_con_state: SvcSoupBinTcpRecvConnectionState::default().into(),
+            send_con_state: SvcSoupBinTcpSendConnectionState::default().into(),
+            relay_log: ProtocolSessionState::new(SoupBinTcpRelayLog::default()),
+            accepted_sequence_numbers: ProtocolSessionState::new(Vec::new()),
+            phantom: PhantomData,
+        }
+    }
+    /// Replaces heartbeat rules derived in [`Self::new`], [`HeartbeatPolicy::recv_timeout`] takes precedence over [LoginRequest::hbeat_timeout_ms] advertised by the client
+    pub fn with_hbeat_policy(mut self, hbeat_policy: HeartbeatPolicy) -> Self {
+        self.hbeat_policy = hbeat_policy;
+        self.recv_con_state = SvcSoupBinTcpRecvConnectionState::with_hbeat_policy(&hbeat_policy).into();
+        self
+    }
+    #[inline(always)]
+    pub fn hbeat_policy(&self) -> &HeartbeatPolicy {
+        &self.hbeat_policy
+    }
+    #[inline(always)]
+    pub fn io_timeout(&self) -> Duration {
+        self.io_timeout
+    }
+    /// Log shared by all clones of this instance, so a clone retained before calling [`Svc::bind`] observes it
+    #[inline(always)]
+    pub fn relay_log(&self) -> &ProtocolSessionState<SoupBinTcpRelayLog<SendP>> {
+        &self.relay_log
+    }
+    /// Callback for the upstream [CltSoupBinTcpProtocolAuto] connection which feeds [`Self::relay_log`]
+    pub fn upstream_callback(&self) -> Arc<SoupBinTcpRelayUpstreamCallback<RecvP, SendP>> {
+        Arc::new(SoupBinTcpRelayUpstreamCallback {
+            log: self.relay_log.clone(),
+            phantom: PhantomData,
+        })
+    }
+    /// Sequence number of the first [SPayload] due to the client accepted by [`Self::on_connect`] with `con_id`, `None` if it was already taken.
+    /// Shared by all clones of this instance, hence must be taken once the connection is accepted so that it is not retained.
+    pub fn take_accepted_sequence_number(&self, con_id: &ConId) -> Option<usize> {
+        let mut accepted_sequence_numbers = self.accepted_sequence_numbers.lock();
+        let position = accepted_sequence_numbers.iter().position(|(accepted, _)| accepted == con_id)?;
+        Some(accepted_sequence_numbers.swap_remove(position).1)
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Framer for SvcSoupBinTcpProtocolRelay<RecvP, SendP> {
+    #[inline(always)]
+    fn get_frame_length(bytes: &bytes::BytesMut) -> Option<usize> {
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::get_frame_length(bytes)
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Messenger for SvcSoupBinTcpProtocolRelay<RecvP, SendP> {
+    type RecvT = <SvcSoupBinTcpMessenger<RecvP, SendP> as Messenger>::RecvT;
+    type SendT = <SvcSoupBinTcpMessenger<RecvP, SendP> as Messenger>::SendT;
+
+    #[inline(always)]
+    fn serialize<const MAX_MSG_SIZE: usize>(msg: &Self::SendT) -> Result<([u8; MAX_MSG_SIZE], usize), std::io::Error> {
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::serialize(msg)
+    }
+    #[inline(always)]
+    fn deserialize(frame: &[u8]) -> Result<Self::RecvT, Error> {
+        SvcSoupBinTcpMessenger::<RecvP, SendP>::deserialize(frame)
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> ProtocolCore for SvcSoupBinTcpProtocolRelay<RecvP, SendP> {
+    /// handles [LoginRequest]/[LoginAccepted][LoginRejected] authentication sequence against the upstream session of [`Self::relay_log`],
+    /// logins are rejected with [`LoginRejected::session_not_available`] until the upstream session is known and once it ended
+    #[inline(always)]
+    fn on_connect<C: SendNonBlocking<<Self as Messenger>::SendT> + ReSendNonBlocking<<Self as Messenger>::SendT> + RecvNonBlocking<<Self as Messenger>::RecvT> + ConnectionId>(
+        &self,
+        con: &mut C,
+    ) -> Result<(), Error> {
+        match con.recv_busywait_timeout(self.io_timeout)? {
+            RecvStatus::Completed(Some(CltSoupBinTcpMsg::LoginRequest(msg))) => {
+                let (session_id, first_sequence_number, next_sequence_number) = {
+                    let relay_log = self.relay_log.lock();
+                    match relay_log.session_id() {
+                        Some(session_id) if !relay_log.is_ended() => (session_id, relay_log.first_sequence_number(), relay_log.next_sequence_number()),
+                        _ => {
+                            drop(relay_log);
+                            con.send_busywait_timeout(&mut LoginRejected::session_not_available().into(), self.io_timeout)?;
+                            return Err(Error::new(ErrorKind::NotConnected, format!("Upstream session not available msg: {:?}", msg)));
+                        }
+                    }
+                };
+                if msg.username == self.username && msg.password == self.password && (msg.session_id == session_id || msg.session_id == SessionId::default()) {
+                    // numeric fields are not validated during deserialization, reject instead of panicking on malformed values
+                    let clt_next_sequenced_payload_number = match msg.hbeat_timeout_ms.try_to_duration().and_then(|_| msg.sequence_number.try_to_usize()) {
+                        Ok(clt_next_sequenced_payload_number) => clt_next_sequenced_payload_number,
+                        Err(err) => {
+                            con.send_busywait_timeout(&mut LoginRejected::not_authorized().into(), self.io_timeout)?;
+                            return Err(err);
+                        }
+                    };
+                    // messages preceding the upstream login are not in the log, replay starts at the first one available
+                    let effective_next_sequence_number = if clt_next_sequenced_payload_number == 0 {
+                        next_sequence_number
+                    } else {
+                        clt_next_sequenced_payload_number.max(first_sequence_number)
+                    };
+
+                    let mut msg = LoginAccepted::new(session_id, effective_next_sequence_number.into()).into();
+                    match con.send_busywait_timeout(&mut msg, self.io_timeout)? {
+                        SendStatus::Completed => {
+                            self.accepted_sequence_numbers.lock().push((con.con_id().clone(), effective_next_sequence_number));
+                            Ok(())
+                        }
+                        SendStatus::WouldBlock => Err(Error::new(ErrorKind::TimedOut, format!("Failed to send login: {:?}", msg))),
+                    }
+                } else if msg.session_id != session_id {
+                    con.send_busywait_timeout(&mut LoginRejected::session_not_available().into(), self.io_timeout)?;
+                    Err(Error::new(
+                        ErrorKind::NotConnected,
+                        format!("Invalid session_id expected: {:?} received: {:?}", session_id, msg.session_id),
+                    ))
+                } else {
+                    con.send_busywait_timeout(&mut LoginRejected::not_authorized().into(), self.io_timeout)?;
+                    Err(Error::new(ErrorKind::NotConnected, format!("Not Authorized msg: {:?}", msg)))
+                }
+            }
+            RecvStatus::Completed(msg) => Err(Error::new(ErrorKind::Other, format!("Expected LoginRequest instead got msg:{:?}", msg))),
+            RecvStatus::WouldBlock => Err(Error::new(ErrorKind::TimedOut, format!("Did not get LoginRequest during timeout: {:?}", self.io_timeout))),
+        }
+    }
+    /// Will delegate to [`SvcSoupBinTcpRecvConnectionState::on_recv`]
+    #[allow(unused_variables)] // when compiled in release mode `who` is not used
+    #[inline(always)]
+    fn on_recv<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::RecvT) {
+        #[cfg(debug_assertions)]
+        log::debug!("{}::on_recv: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolRelay", Self), who.con_id(), msg);
+
+        (*self.recv_con_state.lock()).on_recv(msg);
+    }
+    /// Will delegate to [`SvcSoupBinTcpSendConnectionState::on_sent`] and to [`HeartbeatSendState::on_sent`] for payload messages
+    #[allow(unused_variables)] // when compiled in release mode `who` is not used
+    #[inline(always)]
+    fn on_sent<I: ConnectionId>(&self, who: &I, msg: &<Self as Messenger>::SendT) {
+        #[cfg(debug_assertions)]
+        log::debug!("{}::on_sent: con_id: {}, msg: {:?}", asserted_short_name!("SvcSoupBinTcpProtocolRelay", Self), who.con_id(), msg);
+
+        (*self.send_con_state.lock()).on_sent(msg);
+        if matches!(msg, SvcSoupBinTcpMsg::SPayload(_) | SvcSoupBinTcpMsg::UPayload(_) | SvcSoupBinTcpMsg::Dbg(_)) {
+            (*self.hbeat_send_state.lock()).on_sent();
+        }
+    }
+    /// Will returns `true` if all of below are `true`
+    /// * [`crate::prelude::SvcSoupBinTcpRecvConnectionState::is_connected`]
+    /// * [`crate::prelude::SvcSoupBinTcpSendConnectionState::is_connected`]
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        (*self.recv_con_state.lock()).is_connected() && (*self.send_con_state.lock()).is_connected()
+    }
+    /// Returns [EndOfSession] with [`Self::io_timeout`] to deliver it, or `None` if [LoginAccepted] was never sent
+    #[inline(always)]
+    fn on_disconnect(&self) -> Option<(Duration, <Self as Messenger>::SendT)> {
+        if !(*self.send_con_state.lock()).is_login_accepted() {
+            return None;
+        }
+        Some((self.io_timeout, EndOfSession::default().into()))
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>> Protocol for SvcSoupBinTcpProtocolRelay<RecvP, SendP> {
+    /// Configures interval from [`HeartbeatPolicy::send_interval`]
+    #[inline(always)]
+    fn conf_heart_beat_interval(&self) -> Option<Duration> {
+        Some(self.hbeat_policy.send_interval())
+    }
+    /// sends [SvcHeartbeat] instance to the client, unless a payload message was sent with in [`HeartbeatPolicy::send_interval`]
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::TimedOut] once the peer was silent for longer then the receive timeout, which terminates the connection with [EndOfSession]
+    #[inline(always)]
+    fn send_heart_beat<S: SendNonBlocking<Self::SendT> + ConnectionId>(&self, sender: &mut S) -> Result<SendStatus, Error> {
+        let hbeat_timeout = (*self.recv_con_state.lock()).hbeat_timeout();
+        if let Some(event) = hbeat_timeout {
+            return Err(Error::new(ErrorKind::TimedOut, format!("{} con_id: {}", event, sender.con_id())));
+        }
+        if !(*self.hbeat_send_state.lock()).is_idle(self.hbeat_policy.send_interval()) {
+            return Ok(SendStatus::Completed);
+        }
+        sender.send(&mut SvcSoupBinTcpMsg::hbeat())
+    }
+    /// # Errors
+    /// Returns [ErrorKind::ConnectionAborted] on [LogoutRequest], which per spec terminates the connection immediately
+    #[inline(always)]
+    fn send_reply<S: SendNonBlocking<<Self as Messenger>::SendT> + ConnectionId>(&self, msg: &<Self as Messenger>::RecvT, sender: &mut S) -> Result<(), Error> {
+        if let CltSoupBinTcpMsg::LogoutRequest(_) = msg {
+            return Err(Error::new(ErrorKind::ConnectionAborted, format!("{} con_id: {}", SoupBinTcpEvent::PeerLogout, sender.con_id())));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use super::*;
+    use links_core::unittest::setup;
+    use log::info;
+
+    #[test]
+    fn test_relay_log() {
+        setup::log::configure();
+        let session_id: SessionId = b"session #1".as_slice().into();
+        let mut relay_log = SoupBinTcpRelayLog::<SamplePayload>::default();
+        assert_eq!(relay_log.session_id(), None);
+        assert_eq!(relay_log.next_sequence_number(), 1);
+
+        relay_log.on_login_accepted(session_id, 5);
+        relay_log.on_login_accepted(SessionId::default(), 1);
+        assert_eq!(relay_log.session_id(), Some(session_id));
+        assert_eq!(relay_log.first_sequence_number(), 5);
+
+        let payloads = (5..=7).map(|i| SPayload::new(SamplePayload::new(format!("#{}", i).as_bytes().into()))).collect::<Vec<_>>();
+        for payload in payloads.iter() {
+            relay_log.append(payload.clone());
+        }
+        info!("relay_log: {:?}", relay_log);
+        assert_eq!(relay_log.next_sequence_number(), 8);
+        assert_eq!(relay_log.get(4), None);
+        assert_eq!(relay_log.get(5), Some(&payloads[0]));
+        assert_eq!(relay_log.get(7), Some(&payloads[2]));
+        assert_eq!(relay_log.get(8), None);
+
+        relay_log.end();
+        relay_log.append(payloads[0].clone());
+        assert!(relay_log.is_ended());
+        assert_eq!(relay_log.next_sequence_number(), 8);
+    }
+}
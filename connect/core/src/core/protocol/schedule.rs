@@ -0,0 +1,427 @@
+use crate::prelude::*;
+use std::{
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+impl Weekday {
+    pub const WORKING_DAYS: [Weekday; 5] = [Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday, Weekday::Friday];
+    const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+}
+
+/// Proleptic gregorian calendar date, used to express venue holidays & to derive [SessionId] via [`Self::session_id`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+impl CalendarDate {
+    /// Returns [ErrorKind::InvalidInput] if `year`, `month` & `day` are not a valid calendar date between years `1970` and `9999`
+    pub fn new(year: u16, month: u8, day: u8) -> Result<Self, Error> {
+        let date = Self { year, month, day };
+        if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || day == 0 || Self::from_days_since_epoch(date.days_since_epoch()) != date {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("year: {} month: {} day: {} is not a valid date", year, month, day)));
+        }
+        Ok(date)
+    }
+    #[inline(always)]
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+    #[inline(always)]
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+    #[inline(always)]
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+    /// `YYYYMMDD` session id, see [`SessionId::try_from_date`]
+    pub fn session_id(&self) -> SessionId {
+        SessionId::try_from_date(self.year, self.month, self.day).expect("CalendarDate is always a valid date")
+    }
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::ALL[(self.days_since_epoch() + 3).rem_euclid(7) as usize]
+    }
+    pub fn next_day(&self) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + 1)
+    }
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    fn days_since_epoch(&self) -> i64 {
+        let (month, day) = (self.month as i64, self.day as i64);
+        let year = self.year as i64 - (month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+}
+impl Display for CalendarDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Venue calendar, sessions are open between `open` & `close` time of day on trading days, which are [`Weekday::WORKING_DAYS`] unless
+/// changed with [`Self::with_trading_days`], excluding holidays added with [`Self::with_holiday`].
+///
+/// Time of day is in UTC unless changed with [`Self::with_utc_offset`], sessions spanning midnight are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingHours {
+    open: Duration,
+    close: Duration,
+    utc_offset_secs: i32,
+    trading_days: Vec<Weekday>,
+    holidays: Vec<CalendarDate>,
+}
+impl TradingHours {
+    /// # Arguments
+    /// * `open` - time of day since midnight at which logins are accepted
+    /// * `close` - time of day since midnight at which the session ends
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidInput] unless `open` is before `close` and `close` is within the day
+    pub fn new(open: Duration, close: Duration) -> Result<Self, Error> {
+        if open >= close || close > Duration::from_secs(SECONDS_PER_DAY as u64) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid trading hours open: {:?} close: {:?}", open, close)));
+        }
+        Ok(Self {
+            open,
+            close,
+            utc_offset_secs: 0,
+            trading_days: Weekday::WORKING_DAYS.to_vec(),
+            holidays: vec![],
+        })
+    }
+    /// Offset of the venue local time from UTC, example `-5 * 3600` for New York standard time
+    pub fn with_utc_offset(self, utc_offset_secs: i32) -> Self {
+        Self { utc_offset_secs, ..self }
+    }
+    pub fn with_trading_days(self, trading_days: &[Weekday]) -> Self {
+        Self {
+            trading_days: trading_days.to_vec(),
+            ..self
+        }
+    }
+    pub fn with_holiday(mut self, date: CalendarDate) -> Self {
+        self.holidays.push(date);
+        self
+    }
+    pub fn is_trading_date(&self, date: CalendarDate) -> bool {
+        self.trading_days.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+    /// Venue local date & time of day
+    pub fn local_date_time(&self, time: SystemTime) -> (CalendarDate, Duration) {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        } + self.utc_offset_secs as i64;
+        let date = CalendarDate::from_days_since_epoch(secs.div_euclid(SECONDS_PER_DAY));
+        let time_of_day = Duration::from_secs(secs.rem_euclid(SECONDS_PER_DAY) as u64) + Duration::from_nanos(time.duration_since(UNIX_EPOCH).map_or(0, |since| since.subsec_nanos() as u64));
+        (date, time_of_day)
+    }
+    pub fn is_open_at(&self, time: SystemTime) -> bool {
+        let (date, time_of_day) = self.local_date_time(time);
+        self.is_trading_date(date) && self.open <= time_of_day && time_of_day < self.close
+    }
+    /// Date of the current session if open at `time`, otherwise of the next one to open, `None` if there are no trading dates in the following ten years
+    pub fn session_date(&self, time: SystemTime) -> Option<CalendarDate> {
+        let (date, time_of_day) = self.local_date_time(time);
+        let mut date = if time_of_day < self.close { date } else { date.next_day() };
+        for _ in 0..3_660 {
+            if self.is_trading_date(date) {
+                return Some(date);
+            }
+            date = date.next_day();
+        }
+        None
+    }
+}
+
+/// Emitted by [`SvcSoupBinTcpSessionScheduler::poll`] when the session is opened or closed
+#[derive(Debug)]
+pub enum SessionScheduleEvent<SendP: SoupBinTcpPayload<SendP>> {
+    Opened {
+        session_id: SessionId,
+    },
+    /// `archive` is only present when [`SvcSoupBinTcpSessionScheduler::with_rollover_on_close`] is enabled, in which case `session_id` is the session the
+    /// clients will log into once it opens
+    Closed {
+        session_id: SessionId,
+        archive: Option<Arc<SvcSoupBinTcpSessionArchive<SendP>>>,
+    },
+}
+impl<SendP: SoupBinTcpPayload<SendP>> Display for SessionScheduleEvent<SendP> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Opened { session_id } => write!(f, "Opened session_id: {:?}", session_id),
+            Self::Closed { session_id, archive: Some(archive) } => write!(
+                f,
+                "Closed session_id: {:?} with {} sequenced messages, rolled over to session_id: {:?}",
+                archive.session_id(),
+                archive.sequence_number(),
+                session_id
+            ),
+            Self::Closed { session_id, archive: None } => write!(f, "Closed session_id: {:?}", session_id),
+        }
+    }
+}
+
+/// Opens & closes sessions of [SvcSoupBinTcpProtocolAuto] according to [TradingHours], using [`Clock::system_time`] of the protocol so that a
+/// [ManualClock] can be used to test the venue calendar without waiting.
+///
+/// Outside trading hours logins are rejected with [`LoginRejected::session_not_available`], at close connected clients are sent [EndOfSession], see
+/// [`SvcSoupBinTcpProtocolAuto::close`]. Call [`Self::poll`] periodically or use [`Self::spawn`].
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_core::prelude::*;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let hours = TradingHours::new(Duration::from_secs(9 * 3600), Duration::from_secs(16 * 3600)).unwrap();
+/// let clock = ManualClock::starting_at(UNIX_EPOCH); // Thursday 1970-01-01 00:00 UTC
+/// let protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(b"userid".as_slice().into(), b"passwd".as_slice().into(), Default::default(), Duration::from_secs(1), Duration::from_secs(1)).with_clock(clock.clone());
+/// let mut scheduler = SvcSoupBinTcpSessionScheduler::new(protocol.clone(), hours);
+///
+/// assert!(matches!(scheduler.poll(), Some(SessionScheduleEvent::Closed { .. })));
+/// assert!(!protocol.is_session_open());
+///
+/// clock.advance(Duration::from_secs(9 * 3600));
+/// assert!(matches!(scheduler.poll(), Some(SessionScheduleEvent::Opened { .. })));
+/// assert!(protocol.is_session_open());
+/// ```
+#[derive(Debug)]
+pub struct SvcSoupBinTcpSessionScheduler<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock = MonotonicClock, Dec: DecodeErrorPolicy = DisconnectOnDecodeError> {
+    protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>,
+    hours: TradingHours,
+    rollover_on_close: bool,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy> SvcSoupBinTcpSessionScheduler<RecvP, SendP, Clk, Dec> {
+    /// `protocol` is typically a clone of the instance passed to [`Svc::bind`], since the session state is shared by all clones
+    pub fn new(protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>, hours: TradingHours) -> Self {
+        Self {
+            protocol,
+            hours,
+            rollover_on_close: false,
+        }
+    }
+    /// At close, [`SvcSoupBinTcpProtocolAuto::rollover`] to the [SessionId] of the next trading date, see [`TradingHours::session_date`]
+    pub fn with_rollover_on_close(self, rollover_on_close: bool) -> Self {
+        Self { rollover_on_close, ..self }
+    }
+    #[inline(always)]
+    pub fn hours(&self) -> &TradingHours {
+        &self.hours
+    }
+    /// Opens or closes the session if it does not match [`TradingHours::is_open_at`] the current [`Clock::system_time`], sessions start open
+    /// so the first poll outside trading hours closes the session.
+    pub fn poll(&mut self) -> Option<SessionScheduleEvent<SendP>> {
+        let now = self.protocol.clock().system_time();
+        let is_open = self.hours.is_open_at(now);
+        if is_open == self.protocol.is_session_open() {
+            return None;
+        }
+        if is_open {
+            self.protocol.open();
+            return Some(SessionScheduleEvent::Opened {
+                session_id: self.protocol.session_id(),
+            });
+        }
+
+        self.protocol.close();
+        let next_session_id = self.hours.session_date(now).map(|date| date.session_id());
+        let archive = match next_session_id {
+            Some(next_session_id) if self.rollover_on_close && next_session_id != self.protocol.session_id() => self.protocol.rollover(next_session_id).ok(),
+            _ => None,
+        };
+        Some(SessionScheduleEvent::Closed {
+            session_id: self.protocol.session_id(),
+            archive,
+        })
+    }
+    /// Polls on a dedicated thread every `poll_interval` until the returned [SessionSchedulerHandle] is dropped
+    pub fn spawn<F: FnMut(SessionScheduleEvent<SendP>) + Send + 'static>(mut self, poll_interval: Duration, mut on_event: F) -> Result<SessionSchedulerHandle, Error> {
+        let is_running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let is_running = is_running.clone();
+            thread::Builder::new().name("soupbintcp-session-scheduler".to_owned()).spawn(move || {
+                while is_running.load(Ordering::Relaxed) {
+                    if let Some(event) = self.poll() {
+                        on_event(event);
+                    }
+                    thread::sleep(poll_interval);
+                }
+            })?
+        };
+        Ok(SessionSchedulerHandle { is_running, thread: Some(thread) })
+    }
+}
+
+/// Stops the thread started by [`SvcSoupBinTcpSessionScheduler::spawn`] when dropped
+#[derive(Debug)]
+pub struct SessionSchedulerHandle {
+    is_running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Drop for SessionSchedulerHandle {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use super::*;
+    use crate::core::protocol::auto::test::RecordingSender;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+
+    type SvcProtocolAuto<Clk> = SvcSoupBinTcpProtocolAuto<SamplePayload, SamplePayload, Clk>;
+
+    const HOUR: u64 = 3_600;
+
+    #[test]
+    fn test_calendar_date() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let date = CalendarDate::new(2024, 2, 28).unwrap();
+        info!("date: {}", date);
+        assert_eq!(date.weekday(), Weekday::Wednesday);
+        assert_eq!(date.next_day(), CalendarDate::new(2024, 2, 29).unwrap());
+        assert_eq!(date.next_day().next_day(), CalendarDate::new(2024, 3, 1).unwrap());
+        assert_eq!(CalendarDate::new(2024, 12, 31).unwrap().next_day(), CalendarDate::new(2025, 1, 1).unwrap());
+        assert_eq!(CalendarDate::new(1970, 1, 1).unwrap().weekday(), Weekday::Thursday);
+        assert_eq!(date.session_id(), b"20240228".as_slice().into());
+        for (year, month, day) in [(2023, 2, 29), (2024, 4, 31), (2024, 0, 1), (1969, 12, 31)] {
+            let err = CalendarDate::new(year, month, day).unwrap_err();
+            info!("err: {}", err);
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_trading_hours() {
+        setup::log::configure_compact(LevelFilter::Info);
+        assert_eq!(
+            TradingHours::new(Duration::from_secs(16 * HOUR), Duration::from_secs(9 * HOUR)).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        // Friday 2024-03-01 is a holiday, New York standard time
+        let hours = TradingHours::new(Duration::from_secs(9 * HOUR), Duration::from_secs(16 * HOUR))
+            .unwrap()
+            .with_utc_offset(-5 * HOUR as i32)
+            .with_holiday(CalendarDate::new(2024, 3, 1).unwrap());
+        let thursday_midnight_utc = UNIX_EPOCH + Duration::from_secs(CalendarDate::new(2024, 2, 29).unwrap().days_since_epoch() as u64 * SECONDS_PER_DAY as u64);
+        let at = |hours_since_midnight_utc: u64| thursday_midnight_utc + Duration::from_secs(hours_since_midnight_utc * HOUR);
+
+        assert_eq!(hours.local_date_time(at(0)), (CalendarDate::new(2024, 2, 28).unwrap(), Duration::from_secs(19 * HOUR)));
+        assert!(!hours.is_open_at(at(13)));
+        assert!(hours.is_open_at(at(14)));
+        assert!(hours.is_open_at(at(20)));
+        assert!(!hours.is_open_at(at(21)));
+        assert!(!hours.is_open_at(at(24 + 14))); // holiday
+        assert!(!hours.is_open_at(at(2 * 24 + 14))); // saturday
+
+        assert_eq!(hours.session_date(at(13)), Some(CalendarDate::new(2024, 2, 29).unwrap()));
+        assert_eq!(hours.session_date(at(21)), Some(CalendarDate::new(2024, 3, 4).unwrap()));
+        assert_eq!(hours.clone().with_trading_days(&[]).session_date(at(21)), None);
+    }
+
+    #[test]
+    fn test_session_scheduler() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id = CalendarDate::new(2024, 2, 29).unwrap().session_id();
+        let hours = TradingHours::new(Duration::from_secs(9 * HOUR), Duration::from_secs(16 * HOUR)).unwrap();
+
+        // Thursday 2024-02-29 08:00 UTC
+        let clock = ManualClock::starting_at(UNIX_EPOCH + Duration::from_secs(CalendarDate::new(2024, 2, 29).unwrap().days_since_epoch() as u64 * SECONDS_PER_DAY as u64 + 8 * HOUR));
+        let protocol: SvcProtocolAuto<_> = SvcSoupBinTcpProtocolAuto::new(username, password, session_id, Duration::from_secs(1), Duration::from_secs(1)).with_clock(clock.clone());
+        let mut scheduler = SvcSoupBinTcpSessionScheduler::new(protocol.clone(), hours).with_rollover_on_close(true);
+
+        // before open, session of the day is already current so there is nothing to roll over
+        let event = scheduler.poll().unwrap();
+        info!("event: {}", event);
+        assert!(matches!(event, SessionScheduleEvent::Closed { session_id: id, archive: None } if id == session_id));
+        assert!(!protocol.is_session_open());
+        assert!(scheduler.poll().is_none());
+
+        clock.advance(Duration::from_secs(HOUR));
+        let event = scheduler.poll().unwrap();
+        info!("event: {}", event);
+        assert!(matches!(event, SessionScheduleEvent::Opened { session_id: id } if id == session_id));
+        assert!(protocol.is_session_open());
+
+        // logged in client is disconnected at close
+        let mut sender = RecordingSender::new(ConId::svc(Some("svc/soupbintcp/schedule"), "127.0.0.1:0", None));
+        protocol.on_recv(&sender, &LoginRequest::new(username, password, session_id, 0_u64.into(), Duration::from_secs(1).into()).into());
+        protocol.on_sent(&sender, &LoginAccepted::new(session_id, 1_u64.into()).into());
+        protocol.on_sent(&sender, &SvcSoupBinTcpMsg::sdata(SamplePayload::default()));
+        assert!(protocol.is_connected());
+
+        clock.advance(Duration::from_secs(7 * HOUR));
+        let event = scheduler.poll().unwrap();
+        info!("event: {}", event);
+        let next_session_id = CalendarDate::new(2024, 3, 1).unwrap().session_id();
+        assert!(
+            matches!(&event, SessionScheduleEvent::Closed { session_id: id, archive: Some(archive) } if *id == next_session_id && archive.session_id() == session_id && archive.sequence_number() == 1)
+        );
+        assert!(!protocol.is_session_open());
+        assert_eq!(protocol.session_id(), next_session_id);
+        assert!(!protocol.is_connected());
+        let err = protocol.send_heart_beat(&mut sender).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::ConnectionAborted);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::prelude::*;
+use std::{
+    fmt::Display,
+    io::Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// [Protocol] which can drive a [SoupBinTcpViewRecver], implemented by [CltSoupBinTcpProtocolAuto] & [SvcSoupBinTcpProtocolAuto]
+pub trait SoupBinTcpViewProtocol: Protocol {
+    /// Zero copy counterpart of [Messenger::deserialize], see [CltSoupBinTcpMessenger::deserialize_view] & [SvcSoupBinTcpMessenger::deserialize_view]
+    fn deserialize_view(frame: &[u8]) -> Result<SoupBinTcpMsgView<'_>, Error>;
+    /// Counterpart of [ProtocolCore::on_recv] & [Protocol::send_reply] for [SPayload] & [UPayload] frames, which are never deserialized
+    ///
+    /// # Errors
+    /// Any error terminates the connection, same as [Protocol::send_reply]
+    fn on_recv_payload_view<I: ConnectionId>(&self, who: &I, view: &SoupBinTcpMsgView<'_>) -> Result<(), Error>;
+}
+
+/// Non blocking receiver which hands each frame to user code as a [SoupBinTcpMsgView] borrowed from the read buffer,
+/// instead of deserializing it into an owned [SvcSoupBinTcpMsg] or [CltSoupBinTcpMsg].
+///
+/// Intended for latency sensitive consumers which only decode the payloads they are interested in. The session is driven by `P`
+/// the same way as it is by [Clt]/[Svc]
+/// * [ProtocolCore::on_connect] is issued by [`Self::from_stream`], ex: [LoginRequest] & [LoginAccepted] exchange
+/// * session frames, such as [SvcHeartbeat] & [EndOfSession], are deserialized and passed to [ProtocolCore::on_recv] & [Protocol::send_reply] before
+///   their view is handed to user code
+/// * [SPayload] & [UPayload] frames are only passed to [SoupBinTcpViewProtocol::on_recv_payload_view]
+/// * [Protocol::send_heart_beat] is issued by [`Self::recv_view`] once every [Protocol::conf_heart_beat_interval], hence it must be polled at least as often
+/// * [ProtocolCore::on_disconnect] is issued when dropped, ex: [EndOfSession]
+///
+/// Outbound messages are sent by a links [CltSender], so send, busywait & disconnect behave exactly as they do for [Clt]/[Svc]
+#[derive(Debug)]
+pub struct SoupBinTcpViewRecver<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> {
+    // CRITICAL sender must be dropped before reader so that it is able to deliver ProtocolCore::on_disconnect message
+    sender: CltSender<P, DevNullCallback<P>, MAX_MSG_SIZE>, // DON'T MOVE below reader
+    reader: FrameReader<P, MAX_MSG_SIZE>,
+    protocol: Arc<P>,
+    next_hbeat: Instant,
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    /// Splits `stream` into a paired [FrameReader] & [FrameWriter], see [into_split_framer], and issues [ProtocolCore::on_connect]
+    pub fn from_stream(mut con_id: ConId, stream: std::net::TcpStream, protocol: P) -> Result<Self, Error> {
+        con_id.set_local(stream.local_addr()?);
+        con_id.set_peer(stream.peer_addr()?);
+        let (reader, writer) = into_split_framer::<P, MAX_MSG_SIZE>(con_id, stream);
+        let protocol = Arc::new(protocol);
+        let mut con = Self {
+            // sends are not observed by a callback, the session is driven by `P` alone
+            sender: CltSender::new(writer, DevNullCallback::new_ref(), protocol.clone(), None),
+            reader,
+            protocol: protocol.clone(),
+            next_hbeat: Instant::now(),
+        };
+        protocol.on_connect(&mut con)?;
+        Ok(con)
+    }
+    #[inline(always)]
+    pub fn protocol(&self) -> &P {
+        &self.protocol
+    }
+    /// Frames larger then `MAX_MSG_SIZE` are rejected with [SoupBinTcpFrameSizeError] since the recver is expected to be dropped on error
+    #[inline(always)]
+    fn on_recv_frame<'a>(&mut self, frame: &'a [u8]) -> Result<SoupBinTcpMsgView<'a>, Error> {
+        SoupBinTcpFramer::validate_frame_size(frame, MAX_MSG_SIZE)?;
+        let view = P::deserialize_view(frame)?;
+        if view.payload().is_some() {
+            self.protocol.on_recv_payload_view(&self.sender, &view)?;
+        } else {
+            let msg = P::deserialize(frame)?;
+            self.protocol.on_recv(&self.sender, &msg);
+            self.protocol.send_reply(&msg, &mut self.sender)?;
+        }
+        Ok(view)
+    }
+    /// Issues [Protocol::send_heart_beat] if [Protocol::conf_heart_beat_interval] elapsed since the last completed attempt
+    #[inline(always)]
+    fn send_heart_beat_if_due(&mut self) -> Result<(), Error> {
+        if let Some(interval) = self.protocol.conf_heart_beat_interval() {
+            let now = Instant::now();
+            if now >= self.next_hbeat {
+                if let SendStatus::Completed = self.protocol.send_heart_beat(&mut self.sender)? {
+                    self.next_hbeat = now + interval;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Reads at most one frame and if one is available calls `on_view` with it, returning its result.
+    /// [RecvStatus::Completed(None)] indicates the peer closed the connection.
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidData](std::io::ErrorKind::InvalidData) with [SoupBinTcpFrameSizeError] as the inner error if the frame exceeds `MAX_MSG_SIZE`,
+    /// as well as any error raised by `P`, ex: [ErrorKind::TimedOut](std::io::ErrorKind::TimedOut) once the peer stopped sending heartbeats
+    #[inline(always)]
+    pub fn recv_view<R>(&mut self, on_view: impl FnOnce(SoupBinTcpMsgView<'_>) -> R) -> Result<RecvStatus<R>, Error> {
+        self.send_heart_beat_if_due()?;
+        match self.reader.read_frame()? {
+            RecvStatus::Completed(Some(frame)) => Ok(RecvStatus::Completed(Some(on_view(self.on_recv_frame(&frame[..])?)))),
+            RecvStatus::Completed(None) => Ok(RecvStatus::Completed(None)),
+            RecvStatus::WouldBlock => Ok(RecvStatus::WouldBlock),
+        }
+    }
+    /// Will call [Self::recv_view] until it returns [RecvStatus::Completed] or [RecvStatus::WouldBlock] after the timeout.
+    pub fn recv_view_busywait_timeout<R>(&mut self, timeout: Duration, on_view: impl FnOnce(SoupBinTcpMsgView<'_>) -> R) -> Result<RecvStatus<R>, Error> {
+        let start = Instant::now();
+        loop {
+            self.send_heart_beat_if_due()?;
+            match self.reader.read_frame()? {
+                RecvStatus::Completed(Some(frame)) => return Ok(RecvStatus::Completed(Some(on_view(self.on_recv_frame(&frame[..])?)))),
+                RecvStatus::Completed(None) => return Ok(RecvStatus::Completed(None)),
+                RecvStatus::WouldBlock => {
+                    if start.elapsed() > timeout {
+                        return Ok(RecvStatus::WouldBlock);
+                    }
+                }
+            }
+        }
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> RecvNonBlocking<P::RecvT> for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    /// Deserializes every frame and only issues [ProtocolCore::on_recv], same as [CltRecver], used by [ProtocolCore::on_connect], use [`Self::recv_view`] instead
+    #[inline(always)]
+    fn recv(&mut self) -> Result<RecvStatus<P::RecvT>, Error> {
+        match self.reader.read_frame()? {
+            RecvStatus::Completed(Some(frame)) => {
+                let msg = P::deserialize(&frame)?;
+                self.protocol.on_recv(&self.sender, &msg);
+                Ok(RecvStatus::Completed(Some(msg)))
+            }
+            RecvStatus::Completed(None) => Ok(RecvStatus::Completed(None)),
+            RecvStatus::WouldBlock => Ok(RecvStatus::WouldBlock),
+        }
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> SendNonBlocking<P::SendT> for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn send(&mut self, msg: &mut P::SendT) -> Result<SendStatus, Error> {
+        self.sender.send(msg)
+    }
+    #[inline(always)]
+    fn send_busywait_timeout(&mut self, msg: &mut P::SendT, timeout: Duration) -> Result<SendStatus, Error> {
+        self.sender.send_busywait_timeout(msg, timeout)
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> ReSendNonBlocking<P::SendT> for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    fn re_send(&mut self, msg: &P::SendT) -> Result<SendStatus, Error> {
+        self.sender.re_send(msg)
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> ConnectionId for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        self.sender.con_id()
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> ConnectionStatus for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.protocol.is_connected()
+    }
+}
+impl<P: SoupBinTcpViewProtocol, const MAX_MSG_SIZE: usize> Display for SoupBinTcpViewRecver<P, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(f, "SoupBinTcpViewRecver<{}, RecvT:{}, SendT:{}, {}> {{ {} }}", self.con_id(), recv_t, send_t, MAX_MSG_SIZE, self.reader)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use std::{num::NonZeroUsize, time::Duration};
+
+    type CltProtocolAuto = CltSoupBinTcpProtocolAuto<SamplePayload, SamplePayload>;
+    type SvcProtocolAuto = SvcSoupBinTcpProtocolAuto<SamplePayload, SamplePayload>;
+    type UnitMsg = UniSoupBinTcpMsg<SamplePayload, SamplePayload>;
+    const MAX_MSG_SIZE: usize = 128;
+
+    #[test]
+    fn test_view_recver_clt_session() {
+        setup::log::configure_level(LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let hbeat_policy = HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_secs(1));
+
+        let svc_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1)).with_hbeat_policy(hbeat_policy);
+        let mut svc = Svc::<_, _, MAX_MSG_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), StoreCallback::new_ref(svc_store.clone()), svc_protocol.clone(), Some("svc/soupbintcp/view"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let clt_protocol = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1)).with_hbeat_policy(hbeat_policy);
+        let stream = std::net::TcpStream::connect(addr).unwrap();
+        let mut clt = SoupBinTcpViewRecver::<_, MAX_MSG_SIZE>::from_stream(ConId::clt(Some("clt/soupbintcp/view"), None, addr), stream, clt_protocol.clone()).unwrap();
+        info!("clt: {}", clt);
+        assert!(svc.all_connected_busywait_timeout(io_timeout));
+
+        const N: usize = 10;
+        for _ in 0..N {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::SPayload(SPayload::new(SamplePayload::default())), io_timeout).unwrap().unwrap_completed();
+        }
+
+        // payloads are decoded lazily while the session, login & heartbeats in both directions, is maintained by the protocol
+        let (mut payloads, mut hbeats) = (0, 0);
+        let start = std::time::Instant::now();
+        while start.elapsed() < hbeat_policy.send_interval() * 10 {
+            if let RecvStatus::Completed(Some(packet_type)) = clt
+                .recv_view(|view| {
+                    if let Some(payload) = view.decode_payload::<SamplePayload>() {
+                        assert_eq!(payload.unwrap(), SamplePayload::default());
+                    }
+                    view.packet_type()
+                })
+                .unwrap()
+            {
+                match packet_type {
+                    b'S' => payloads += 1,
+                    b'H' => hbeats += 1,
+                    _ => {}
+                }
+            }
+        }
+        info!("payloads: {}, hbeats: {}, clt hbeat_stats: {}", payloads, hbeats, clt_protocol.hbeat_stats());
+        assert_eq!(payloads, N);
+        assert!(hbeats > 0);
+        assert!(clt_protocol.hbeat_stats().sent() > 0);
+        assert!(clt.is_connected());
+        svc_store
+            .find_recv("svc/soupbintcp/view", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::HBeat(_))), setup::net::optional_find_timeout())
+            .unwrap();
+        assert!(svc.all_connected());
+
+        clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(SamplePayload::default()), io_timeout).unwrap().unwrap_completed();
+        svc_store
+            .find_recv("svc/soupbintcp/view", |msg| matches!(msg, UniSoupBinTcpMsg::Clt(CltSoupBinTcpMsg::UPayload(_))), setup::net::optional_find_timeout())
+            .unwrap();
+
+        // svc terminates with EndOfSession which is also handed to the user as a view
+        drop(svc);
+        let end_of_session = loop {
+            match clt.recv_view_busywait_timeout(io_timeout, |view| view.packet_type()).unwrap() {
+                RecvStatus::Completed(Some(b'Z')) => break true,
+                RecvStatus::Completed(Some(_)) => continue,
+                _ => break false,
+            }
+        };
+        assert!(end_of_session);
+        assert!(!clt.is_connected());
+    }
+
+    #[test]
+    fn test_view_recver_svc_session() {
+        setup::log::configure_level(LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+
+        let listener = std::net::TcpListener::bind(addr).unwrap();
+        let svc_protocol = SvcProtocolAuto::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let svc = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut svc = SoupBinTcpViewRecver::<_, MAX_MSG_SIZE>::from_stream(ConId::svc(Some("svc/soupbintcp/view"), addr, None), stream, svc_protocol).unwrap();
+            info!("svc: {}", svc);
+            loop {
+                if let RecvStatus::Completed(Some(Some(payload))) = svc.recv_view_busywait_timeout(io_timeout, |view| view.decode_payload::<SamplePayload>()).unwrap() {
+                    break payload.unwrap();
+                }
+            }
+        });
+
+        let clt_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_protocol = CltProtocolAuto::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+        let mut clt = Clt::<_, _, MAX_MSG_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            StoreCallback::new_ref(clt_store.clone()),
+            clt_protocol,
+            Some("clt/soupbintcp/view"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        clt_store
+            .find_recv("clt/soupbintcp/view", |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::LoginAccepted(_))), setup::net::optional_find_timeout())
+            .unwrap();
+
+        clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(SamplePayload::default()), io_timeout).unwrap().unwrap_completed();
+        assert_eq!(svc.join().unwrap(), SamplePayload::default());
+
+        // svc view recver was dropped and issued EndOfSession
+        clt_store
+            .find_recv("clt/soupbintcp/view", |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::EndOfSession(_))), setup::net::optional_find_timeout())
+            .unwrap();
+    }
+}
@@ -1,2 +1,6 @@
 pub mod core;
 pub mod prelude;
+
+/// Allows `#[soupbintcp(crate = soupbintcp_connect_core)]` to resolve dependencies referenced by code generated with `soupbintcp_derive`
+#[doc(hidden)]
+pub use soupbintcp_model::__private;
@@ -1,11 +1,21 @@
 pub use links_nonblocking::prelude::*;
 pub use soupbintcp_model::prelude::*;
 
+pub use crate::core::config::{CltSoupBinTcpConfig, SvcSoupBinTcpConfig};
+pub use crate::core::error::{DecodeErrorKind, DecodeErrorPolicy, DisconnectOnDecodeError, FrameDirection, SkipOnDecodeError, SoupBinTcpDecodeError, SoupBinTcpFrameSizeError};
 pub use crate::core::framer::SoupBinTcpFramer;
 pub use crate::core::messenger::{CltSoupBinTcpMessenger, SvcSoupBinTcpMessenger};
+pub use crate::core::metrics::{ConnectionMetrics, DirectionMetrics, EventMetrics, LoginMetrics, MetricsEvent, PacketMetrics, SoupBinTcpMetricsCallback, SoupBinTcpMetricsMsg, SoupBinTcpMetricsSnapshot};
 pub use crate::core::protocol::{
     auto::{CltSoupBinTcpProtocolAuto, SvcSoupBinTcpProtocolAuto},
+    builder::{CltSoupBinTcpProtocolAutoBuilder, SvcSoupBinTcpProtocolAutoBuilder},
+    clock::{Clock, ManualClock, MonotonicClock},
+    event::{LoggerEventCallback, SoupBinTcpEvent, SoupBinTcpEventCallback},
+    hbeat::{HeartbeatPolicy, HeartbeatSendState, HeartbeatStats},
     is_connected::{CltSoupBinTcpProtocolIsConnected, SvcSoupBinTcpProtocolIsConnected},
     manual::{CltSoupBinTcpProtocolManual, SvcSoupBinTcpProtocolManual},
-    CltSoupBinTcpRecvConnectionState, SvcSoupBinTcpRecvConnectionState, SvcSoupBinTcpSendConnectionState, SvcSoupBinTcpSendSessionState,
+    relay::{SoupBinTcpRelayLog, SoupBinTcpRelayUpstreamCallback, SvcSoupBinTcpProtocolRelay},
+    schedule::{CalendarDate, SessionScheduleEvent, SessionSchedulerHandle, SvcSoupBinTcpSessionScheduler, TradingHours, Weekday},
+    CltSoupBinTcpRecvConnectionState, SvcSoupBinTcpRecvConnectionState, SvcSoupBinTcpSendConnectionState, SvcSoupBinTcpSendSessionState, SvcSoupBinTcpSessionArchive,
 };
+pub use crate::core::view::{SoupBinTcpViewProtocol, SoupBinTcpViewRecver};
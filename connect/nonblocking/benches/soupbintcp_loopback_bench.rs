@@ -0,0 +1,190 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode, Throughput};
+use links_core::unittest::setup;
+use log::{info, LevelFilter};
+use soupbintcp_connect_nonblocking::prelude::*;
+use std::{
+    num::NonZeroUsize,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+static LOG_LEVEL: LevelFilter = LevelFilter::Error;
+const MAX_MSG_SIZE: usize = 1024;
+const PAYLOAD_SIZE: usize = 100;
+const THROUGHPUT_BATCH: usize = 100;
+const N_REPLAY: usize = 1_000_000;
+
+fn credentials() -> (UserName, Password, SessionId) {
+    (b"userid".as_slice().into(), b"passwd".as_slice().into(), b"session".as_slice().into())
+}
+
+/// Accepts a single connection & echoes every [CltSoupBinTcpMsg::UPayload] back as [SvcSoupBinTcpMsg::UPayload] until the client disconnects
+fn spawn_echo_svc<SvcP>(addr: &'static str, protocol: SvcP) -> JoinHandle<usize>
+where
+    SvcP: Protocol<RecvT = CltSoupBinTcpMsg<VecPayload>, SendT = SvcSoupBinTcpMsg<VecPayload>>,
+{
+    thread::Builder::new()
+        .name("svc-echo-thread".to_owned())
+        .spawn(move || {
+            let svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/bench")).unwrap();
+            let mut clt = svc.accept_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_accepted();
+            let mut echo_count = 0_usize;
+            loop {
+                match clt.recv_busywait() {
+                    Ok(Some(CltSoupBinTcpMsg::UPayload(msg))) => {
+                        clt.send_busywait(&mut SvcSoupBinTcpMsg::UPayload(msg)).unwrap();
+                        echo_count += 1;
+                    }
+                    Ok(Some(_)) => continue, // heartbeats, login
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            echo_count
+        })
+        .unwrap()
+}
+
+/// Client & echo server busy wait on separate threads, run on a host with at least two idle cores otherwise latency is dominated by the os scheduler
+fn loopback<CltP, SvcP>(c: &mut Criterion, name: &str, clt_protocol: CltP, svc_protocol: SvcP)
+where
+    CltP: Protocol<RecvT = SvcSoupBinTcpMsg<VecPayload>, SendT = CltSoupBinTcpMsg<VecPayload>>,
+    SvcP: Protocol<RecvT = CltSoupBinTcpMsg<VecPayload>, SendT = SvcSoupBinTcpMsg<VecPayload>>,
+{
+    setup::log::configure_level(LOG_LEVEL);
+    let addr = setup::net::rand_avail_addr_port();
+    let svc_jh = spawn_echo_svc(addr, svc_protocol);
+
+    let mut clt = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+        addr,
+        setup::net::default_connect_timeout(),
+        setup::net::default_connect_retry_after(),
+        DevNullCallback::new_ref(),
+        clt_protocol,
+        Some("clt/soupbintcp/bench"),
+    )
+    .unwrap();
+
+    let mut msg = CltSoupBinTcpMsg::udata(VecPayload::new(setup::data::random_bytes(PAYLOAD_SIZE).to_vec()));
+    let recv_echo = |clt: &mut CltSoupBinTcp<CltP, DevNullCallback<CltP>, MAX_MSG_SIZE>| loop {
+        match clt.recv_busywait().unwrap() {
+            Some(SvcSoupBinTcpMsg::UPayload(msg)) => break black_box(msg),
+            Some(_) => continue, // heartbeats, login accepted
+            None => panic!("svc closed connection, clt: {}", clt),
+        }
+    };
+
+    let mut send_count = 0_usize;
+    let mut group = c.benchmark_group("soupbintcp_loopback");
+    group.bench_function(BenchmarkId::new("round_trip_latency", name), |b| {
+        b.iter(|| {
+            clt.send_busywait(&mut msg).unwrap();
+            recv_echo(&mut clt);
+            send_count += 1;
+        })
+    });
+    group.throughput(Throughput::Elements(THROUGHPUT_BATCH as u64));
+    group.bench_function(BenchmarkId::new("round_trip_throughput", name), |b| {
+        b.iter(|| {
+            for _ in 0..THROUGHPUT_BATCH {
+                clt.send_busywait(&mut msg).unwrap();
+            }
+            for _ in 0..THROUGHPUT_BATCH {
+                recv_echo(&mut clt);
+            }
+            send_count += THROUGHPUT_BATCH;
+        })
+    });
+    group.finish();
+
+    drop(clt);
+    let echo_count = svc_jh.join().unwrap();
+    info!("{} send_count: {}, echo_count: {}", name, send_count, echo_count);
+    assert_eq!(send_count, echo_count);
+}
+
+fn soupbintcp_loopback_manual(c: &mut Criterion) {
+    loopback(
+        c,
+        "manual",
+        CltSoupBinTcpProtocolManual::<VecPayload, VecPayload>::default(),
+        SvcSoupBinTcpProtocolManual::<VecPayload, VecPayload>::default(),
+    );
+}
+
+fn soupbintcp_loopback_is_connected(c: &mut Criterion) {
+    loopback(
+        c,
+        "is_connected",
+        CltSoupBinTcpProtocolIsConnected::<VecPayload, VecPayload>::default(),
+        SvcSoupBinTcpProtocolIsConnected::<VecPayload, VecPayload>::default(),
+    );
+}
+
+fn soupbintcp_loopback_auto(c: &mut Criterion) {
+    let (username, password, session_id) = credentials();
+    let io_timeout = setup::net::default_connect_timeout();
+    let hbeat_interval = Duration::from_secs(10);
+    loopback(
+        c,
+        "auto",
+        CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, hbeat_interval, hbeat_interval),
+        SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, hbeat_interval),
+    );
+}
+
+/// Measures how fast [SvcSoupBinTcpProtocolAuto] replays [N_REPLAY] stored sequenced messages to a client logging in at sequence number `1`
+fn soupbintcp_replay(c: &mut Criterion) {
+    setup::log::configure_level(LOG_LEVEL);
+    let addr = setup::net::rand_avail_addr_port();
+    let (username, password, session_id) = credentials();
+    let io_timeout = setup::net::default_connect_timeout();
+    let hbeat_interval = Duration::from_secs(10);
+    let connect = |sequence_number: u64| {
+        CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, sequence_number.into(), io_timeout, hbeat_interval, hbeat_interval),
+            Some("clt/soupbintcp/bench"),
+        )
+        .unwrap()
+    };
+
+    let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, hbeat_interval);
+    let mut svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(addr, NonZeroUsize::new(2).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/bench"))
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+
+    // store sequenced messages in the svc session by publishing them to a draining client
+    let drain = connect(0).into_sender_with_spawned_recver_ref();
+    assert!(svc.all_connected_busywait_timeout(setup::net::default_connect_timeout()));
+    let mut msg = SvcSoupBinTcpMsg::sdata(VecPayload::new(setup::data::random_bytes(PAYLOAD_SIZE).to_vec()));
+    for _ in 0..N_REPLAY {
+        svc.send_busywait(&mut msg).unwrap();
+    }
+    drop(drain);
+
+    let mut group = c.benchmark_group("soupbintcp_replay");
+    group.sample_size(10).sampling_mode(SamplingMode::Flat).throughput(Throughput::Elements(N_REPLAY as u64));
+    group.bench_function(BenchmarkId::new("replay", N_REPLAY), |b| {
+        b.iter(|| {
+            let mut clt = connect(1);
+            let mut replay_count = 0_usize;
+            while replay_count < N_REPLAY {
+                match clt.recv_busywait().unwrap() {
+                    Some(SvcSoupBinTcpMsg::SPayload(msg)) => {
+                        black_box(msg);
+                        replay_count += 1;
+                    }
+                    Some(_) => continue, // heartbeats, login accepted
+                    None => panic!("svc closed connection after replay_count: {}, clt: {}", replay_count, clt),
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, soupbintcp_loopback_manual, soupbintcp_loopback_is_connected, soupbintcp_loopback_auto, soupbintcp_replay);
+criterion_main!(benches);
@@ -0,0 +1,565 @@
+use crate::prelude::*;
+use std::{
+    fmt::{Debug, Display},
+    time::{Duration, Instant},
+};
+
+type HarnessProtocol = CltSoupBinTcpProtocolManual<VecPayload, VecPayload>;
+type HarnessClt<const MAX_MSG_SIZE: usize> = CltSoupBinTcp<HarnessProtocol, DevNullCallback<HarnessProtocol>, MAX_MSG_SIZE>;
+type HarnessMsg = SvcSoupBinTcpMsg<VecPayload>;
+
+/// Spec requires the server to send [SvcHeartbeat] anytime more then 1 second has passed since it last sent anything
+pub const SPEC_SVC_HBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Scripted scenarios of [ConformanceHarness], each one runs on its own connection, see `model/docs` for the spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConformanceScenario {
+    BadPassword,
+    UnknownSession,
+    BlankSession,
+    LoginSequenceZero,
+    LoginSequenceOne,
+    LoginSequenceN,
+    LoginBeyondEnd,
+    HeartbeatSilence,
+    Logout,
+    EndOfSession,
+}
+impl ConformanceScenario {
+    /// All scenarios, in the order they are run by [`ConformanceHarness::run`], [ConformanceScenario::EndOfSession] is last since it ends the session
+    pub const ALL: [ConformanceScenario; 10] = [
+        Self::BadPassword,
+        Self::UnknownSession,
+        Self::BlankSession,
+        Self::LoginSequenceZero,
+        Self::LoginSequenceOne,
+        Self::LoginSequenceN,
+        Self::LoginBeyondEnd,
+        Self::HeartbeatSilence,
+        Self::Logout,
+        Self::EndOfSession,
+    ];
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::BadPassword => "invalid password is rejected with reason 'A' and the socket is closed",
+            Self::UnknownSession => "unknown session is rejected with reason 'S' and the socket is closed",
+            Self::BlankSession => "blank session logs into the currently active session",
+            Self::LoginSequenceZero => "sequence 0 is accepted with the next sequence number to be sent",
+            Self::LoginSequenceOne => "sequence 1 is accepted and replays all sequenced messages",
+            Self::LoginSequenceN => "sequence N is accepted and replays the same messages as sequence 1 does from N",
+            Self::LoginBeyondEnd => "sequence beyond the end is rejected or accepted without replay",
+            Self::HeartbeatSilence => "server heartbeats at least every second and closes the socket once the client heartbeat timeout expires",
+            Self::Logout => "logout request closes the socket",
+            Self::EndOfSession => "end of session is the last message and is followed by the socket being closed",
+        }
+    }
+}
+impl Display for ConformanceScenario {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConformanceOutcome {
+    Pass,
+    Fail(String),
+    /// Scenario could not be exercised, example the server did not end the session with in [`ConformanceConfig::end_of_session_timeout`]
+    Skip(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceResult {
+    pub scenario: ConformanceScenario,
+    pub outcome: ConformanceOutcome,
+    pub elapsed: Duration,
+}
+impl Display for ConformanceResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.outcome {
+            ConformanceOutcome::Pass => write!(f, "PASS {} ({:?}) - {}", self.scenario, self.elapsed, self.scenario.description()),
+            ConformanceOutcome::Fail(reason) => write!(f, "FAIL {} ({:?}) - {}, reason: {}", self.scenario, self.elapsed, self.scenario.description(), reason),
+            ConformanceOutcome::Skip(reason) => write!(f, "SKIP {} ({:?}) - {}, reason: {}", self.scenario, self.elapsed, self.scenario.description(), reason),
+        }
+    }
+}
+
+/// Pass/fail report of [ConformanceHarness], [Display] renders one line per scenario preceded by a summary line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub results: Vec<ConformanceResult>,
+}
+impl ConformanceReport {
+    pub fn push(&mut self, result: ConformanceResult) {
+        self.results.push(result);
+    }
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome == ConformanceOutcome::Pass).count()
+    }
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|r| matches!(r.outcome, ConformanceOutcome::Fail(_))).count()
+    }
+    pub fn skipped(&self) -> usize {
+        self.results.iter().filter(|r| matches!(r.outcome, ConformanceOutcome::Skip(_))).count()
+    }
+    /// `true` if no scenario failed, skipped scenarios do not fail the report
+    pub fn is_pass(&self) -> bool {
+        self.failed() == 0
+    }
+}
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SoupBinTcp conformance {}, passed: {}, failed: {}, skipped: {}",
+            if self.is_pass() { "PASS" } else { "FAIL" },
+            self.passed(),
+            self.failed(),
+            self.skipped()
+        )?;
+        for result in self.results.iter() {
+            write!(f, "\n  {}", result)?;
+        }
+        Ok(())
+    }
+}
+
+/// Server under test & timeouts used by [ConformanceHarness]
+#[derive(Debug, Clone)]
+pub struct ConformanceConfig {
+    pub addr: String,
+    pub username: UserName,
+    pub password: Password,
+    /// Session expected to be active on the server, must not be blank
+    pub session_id: SessionId,
+    /// Timeout for establishing a connection and for each expected server message
+    pub io_timeout: Duration,
+    /// Advertised in [LoginRequest], harness does not send heartbeats so each scenario must complete with in this timeout
+    pub hbeat_timeout: Duration,
+    /// Advertised in [LoginRequest] by [ConformanceScenario::HeartbeatSilence]
+    pub silence_hbeat_timeout: Duration,
+    /// Requested sequence number of [ConformanceScenario::LoginSequenceN], defaults to the mid point of the sequenced messages available
+    pub sequence_number_n: Option<u64>,
+    /// Time given to the server to send [EndOfSession] before [ConformanceScenario::EndOfSession] is skipped
+    pub end_of_session_timeout: Duration,
+}
+impl ConformanceConfig {
+    pub fn new(addr: &str, username: UserName, password: Password, session_id: SessionId) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            username,
+            password,
+            session_id,
+            io_timeout: Duration::from_secs(1),
+            hbeat_timeout: Duration::from_secs(30),
+            silence_hbeat_timeout: Duration::from_secs(1),
+            sequence_number_n: None,
+            end_of_session_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+enum Inbound {
+    Msg(HarnessMsg),
+    Closed,
+    TimedOut,
+}
+
+/// Certifies a SoupBinTcp server against the spec by running [ConformanceScenario]s using a [CltSoupBinTcp] with [CltSoupBinTcpProtocolManual],
+/// so that every session message is scripted by the harness. Application payloads are treated as opaque [VecPayload].
+///
+/// Sequence scenarios expect the server to have some sequenced messages in the session, scenarios which reject a login expect the server to keep
+/// accepting new connections afterwards.
+///
+/// # Example
+/// ```no_run
+/// use soupbintcp_connect_nonblocking::prelude::*;
+///
+/// let config = ConformanceConfig::new("127.0.0.1:8080", b"userid".into(), b"passwd".as_slice().into(), b"session".as_slice().into());
+/// let report = ConformanceHarness::<1024>::new(config).run();
+/// println!("{}", report);
+/// assert!(report.is_pass());
+/// ```
+pub struct ConformanceHarness<const MAX_MSG_SIZE: usize> {
+    config: ConformanceConfig,
+    end_of_session_trigger: Option<Box<dyn Fn() + Send + Sync>>,
+}
+impl<const MAX_MSG_SIZE: usize> ConformanceHarness<MAX_MSG_SIZE> {
+    pub fn new(config: ConformanceConfig) -> Self {
+        Self { config, end_of_session_trigger: None }
+    }
+    /// Called by [ConformanceScenario::EndOfSession] once logged in, intended for servers which can be told to end the session, example by dropping
+    /// an in process [SvcSoupBinTcp]. Without a trigger the scenario waits for [`ConformanceConfig::end_of_session_timeout`]
+    pub fn with_end_of_session_trigger(mut self, trigger: impl Fn() + Send + Sync + 'static) -> Self {
+        self.end_of_session_trigger = Some(Box::new(trigger));
+        self
+    }
+    #[inline(always)]
+    pub fn config(&self) -> &ConformanceConfig {
+        &self.config
+    }
+    /// Runs [`ConformanceScenario::ALL`]
+    pub fn run(&self) -> ConformanceReport {
+        self.run_scenarios(&ConformanceScenario::ALL)
+    }
+    pub fn run_scenarios(&self, scenarios: &[ConformanceScenario]) -> ConformanceReport {
+        let mut report = ConformanceReport::default();
+        for scenario in scenarios {
+            report.push(self.run_scenario(*scenario));
+        }
+        report
+    }
+    pub fn run_scenario(&self, scenario: ConformanceScenario) -> ConformanceResult {
+        use ConformanceScenario::*;
+        let start = Instant::now();
+        let res = match scenario {
+            BadPassword => self.bad_password(),
+            UnknownSession => self.unknown_session(),
+            BlankSession => self.blank_session(),
+            LoginSequenceZero => self.login_sequence_zero(),
+            LoginSequenceOne => self.login_sequence_one(),
+            LoginSequenceN => self.login_sequence_n(),
+            LoginBeyondEnd => self.login_beyond_end(),
+            HeartbeatSilence => self.hbeat_silence(),
+            Logout => self.logout(),
+            EndOfSession => self.end_of_session(),
+        };
+        let outcome = match res {
+            Ok(outcome) => outcome,
+            Err(reason) => ConformanceOutcome::Fail(reason),
+        };
+        ConformanceResult {
+            scenario,
+            outcome,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    // scenarios
+    fn bad_password(&self) -> Result<ConformanceOutcome, String> {
+        let password = if self.config.password == Password::from(b"conformnce".as_slice()) {
+            b"CONFORMNCE".as_slice()
+        } else {
+            b"conformnce".as_slice()
+        };
+        let mut clt = self.send_login(self.config.session_id, password.into(), 1, self.config.hbeat_timeout)?;
+        match self.recv(&mut clt, self.config.io_timeout) {
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginRejected(msg)) if msg.is_not_authorized() => {}
+            inbound => return Err(format!("expected LoginRejected not authorized, got: {}", inbound_to_string(&inbound))),
+        }
+        self.expect_closed(&mut clt, self.config.io_timeout, |_| false)?;
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn unknown_session(&self) -> Result<ConformanceOutcome, String> {
+        let session_id = if self.config.session_id == SessionId::from(b"conformnce".as_slice()) {
+            b"CONFORMNCE".as_slice()
+        } else {
+            b"conformnce".as_slice()
+        };
+        let mut clt = self.send_login(session_id.into(), self.config.password, 1, self.config.hbeat_timeout)?;
+        match self.recv(&mut clt, self.config.io_timeout) {
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginRejected(msg)) if msg.is_session_not_available() => {}
+            inbound => return Err(format!("expected LoginRejected session not available, got: {}", inbound_to_string(&inbound))),
+        }
+        self.expect_closed(&mut clt, self.config.io_timeout, |_| false)?;
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn blank_session(&self) -> Result<ConformanceOutcome, String> {
+        let (_clt, accepted) = self.login(SessionId::default(), 0)?;
+        if accepted.session_id() != self.config.session_id {
+            return Err(format!("expected LoginAccepted into session: {}, got: {}", self.config.session_id, accepted));
+        }
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn login_sequence_zero(&self) -> Result<ConformanceOutcome, String> {
+        let (_clt, accepted) = self.login(self.config.session_id, 0)?;
+        let next = parse_sequence_number(&accepted)?;
+        if next < 1 {
+            return Err(format!("expected LoginAccepted with sequence number of at least 1, got: {}", accepted));
+        }
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn login_sequence_one(&self) -> Result<ConformanceOutcome, String> {
+        let next = self.next_sequence_number()?;
+        self.replay(1, next - 1)?;
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn login_sequence_n(&self) -> Result<ConformanceOutcome, String> {
+        let next = self.next_sequence_number()?;
+        let n = self.config.sequence_number_n.unwrap_or((next + 1) / 2).max(1);
+        if n > next {
+            return Err(format!("configured sequence_number_n: {} is beyond the next sequence number: {}", n, next));
+        }
+        let from_one = self.replay(1, next - 1)?;
+        let from_n = self.replay(n, next - n)?;
+        if from_one[(n - 1) as usize..] != from_n[..] {
+            return Err(format!("messages replayed from sequence {} differ from those replayed from sequence 1", n));
+        }
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn login_beyond_end(&self) -> Result<ConformanceOutcome, String> {
+        let next = self.next_sequence_number()?;
+        let requested = next + 1_000;
+        let mut clt = self.send_login(self.config.session_id, self.config.password, requested, self.config.hbeat_timeout)?;
+        match self.recv(&mut clt, self.config.io_timeout) {
+            // spec does not define the outcome, rejecting is acceptable
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginRejected(_)) => self.expect_closed(&mut clt, self.config.io_timeout, |_| false)?,
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginAccepted(accepted)) => {
+                let accepted_sequence_number = parse_sequence_number(&accepted)?;
+                if accepted_sequence_number > requested {
+                    return Err(format!("expected LoginAccepted with sequence number no greater then requested: {}, got: {}", requested, accepted));
+                }
+                // a server which resets to the next sequence number may legitimately send new messages
+                if accepted_sequence_number == requested {
+                    self.expect_no_sequenced(&mut clt, self.config.io_timeout)?;
+                }
+            }
+            inbound => return Err(format!("expected LoginAccepted or LoginRejected, got: {}", inbound_to_string(&inbound))),
+        }
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn hbeat_silence(&self) -> Result<ConformanceOutcome, String> {
+        let silence_hbeat_timeout = self.config.silence_hbeat_timeout;
+        let mut clt = self.send_login(self.config.session_id, self.config.password, 0, silence_hbeat_timeout)?;
+        match self.recv(&mut clt, self.config.io_timeout) {
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginAccepted(_)) => {}
+            inbound => return Err(format!("expected LoginAccepted, got: {}", inbound_to_string(&inbound))),
+        }
+        // client stays silent, server must keep sending heartbeats until it closes the socket
+        let max_gap = SPEC_SVC_HBEAT_INTERVAL + self.config.io_timeout;
+        let deadline = Instant::now() + silence_hbeat_timeout * 2 + self.config.io_timeout;
+        let mut last_recv = Instant::now();
+        loop {
+            match self.recv(&mut clt, max_gap) {
+                Inbound::Msg(_) => {
+                    last_recv = Instant::now();
+                    if last_recv > deadline {
+                        return Err(format!(
+                            "socket not closed with in {:?} of silence, heartbeat timeout advertised: {:?}",
+                            deadline.elapsed() + silence_hbeat_timeout,
+                            silence_hbeat_timeout
+                        ));
+                    }
+                }
+                Inbound::Closed => return Ok(ConformanceOutcome::Pass),
+                Inbound::TimedOut => {
+                    return Err(format!(
+                        "no heartbeat or other message for {:?}, expected at least every {:?}",
+                        last_recv.elapsed(),
+                        SPEC_SVC_HBEAT_INTERVAL
+                    ))
+                }
+            }
+        }
+    }
+    fn logout(&self) -> Result<ConformanceOutcome, String> {
+        let (mut clt, _) = self.login(self.config.session_id, 0)?;
+        self.send(&mut clt, &mut LogoutRequest::default().into())?;
+        // messages already in flight are acceptable
+        self.expect_closed(&mut clt, self.config.io_timeout, |_| true)?;
+        Ok(ConformanceOutcome::Pass)
+    }
+    fn end_of_session(&self) -> Result<ConformanceOutcome, String> {
+        let (mut clt, _) = self.login(self.config.session_id, 0)?;
+        if let Some(trigger) = &self.end_of_session_trigger {
+            trigger();
+        }
+        let deadline = Instant::now() + self.config.end_of_session_timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.recv(&mut clt, timeout) {
+                Inbound::Msg(SvcSoupBinTcpMsg::EndOfSession(_)) => break,
+                Inbound::Msg(_) => continue,
+                Inbound::Closed => return Err("socket closed without EndOfSession".to_owned()),
+                Inbound::TimedOut => return Ok(ConformanceOutcome::Skip(format!("EndOfSession not received with in {:?}", self.config.end_of_session_timeout))),
+            }
+        }
+        self.expect_closed(&mut clt, self.config.io_timeout, |msg| matches!(msg, SvcSoupBinTcpMsg::HBeat(_)))?;
+        Ok(ConformanceOutcome::Pass)
+    }
+
+    // helpers
+    fn connect(&self) -> Result<HarnessClt<MAX_MSG_SIZE>, String> {
+        HarnessClt::<MAX_MSG_SIZE>::connect(
+            &self.config.addr,
+            self.config.io_timeout,
+            self.config.io_timeout / 10,
+            DevNullCallback::new_ref(),
+            HarnessProtocol::default(),
+            Some("soupbintcp/conformance"),
+        )
+        .map_err(|e| format!("failed to connect to {}, error: {}", self.config.addr, e))
+    }
+    fn send(&self, clt: &mut HarnessClt<MAX_MSG_SIZE>, msg: &mut CltSoupBinTcpMsg<VecPayload>) -> Result<(), String> {
+        match clt.send_busywait_timeout(msg, self.config.io_timeout) {
+            Ok(SendStatus::Completed) => Ok(()),
+            Ok(SendStatus::WouldBlock) => Err(format!("timed out sending: {:?}", msg)),
+            Err(e) => Err(format!("failed sending: {:?}, error: {}", msg, e)),
+        }
+    }
+    fn send_login(&self, session_id: SessionId, password: Password, sequence_number: u64, hbeat_timeout: Duration) -> Result<HarnessClt<MAX_MSG_SIZE>, String> {
+        let hbeat_timeout_ms = TimeoutMs::try_from_duration(hbeat_timeout).map_err(|e| e.to_string())?;
+        let mut clt = self.connect()?;
+        self.send(
+            &mut clt,
+            &mut LoginRequest::new(self.config.username, password, session_id, sequence_number.into(), hbeat_timeout_ms).into(),
+        )?;
+        Ok(clt)
+    }
+    fn login(&self, session_id: SessionId, sequence_number: u64) -> Result<(HarnessClt<MAX_MSG_SIZE>, LoginAccepted), String> {
+        let mut clt = self.send_login(session_id, self.config.password, sequence_number, self.config.hbeat_timeout)?;
+        match self.recv(&mut clt, self.config.io_timeout) {
+            Inbound::Msg(SvcSoupBinTcpMsg::LoginAccepted(accepted)) => Ok((clt, accepted)),
+            inbound => Err(format!("expected LoginAccepted for sequence number: {}, got: {}", sequence_number, inbound_to_string(&inbound))),
+        }
+    }
+    /// Skips [Debug] packets which the spec requires to be ignored
+    fn recv(&self, clt: &mut HarnessClt<MAX_MSG_SIZE>, timeout: Duration) -> Inbound {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match clt.recv_busywait_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(RecvStatus::Completed(Some(SvcSoupBinTcpMsg::Dbg(_)))) => continue,
+                Ok(RecvStatus::Completed(Some(msg))) => return Inbound::Msg(msg),
+                Ok(RecvStatus::Completed(None)) | Err(_) => return Inbound::Closed,
+                Ok(RecvStatus::WouldBlock) => return Inbound::TimedOut,
+            }
+        }
+    }
+    fn expect_closed(&self, clt: &mut HarnessClt<MAX_MSG_SIZE>, timeout: Duration, allowed: impl Fn(&HarnessMsg) -> bool) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.recv(clt, deadline.saturating_duration_since(Instant::now())) {
+                Inbound::Msg(msg) if allowed(&msg) => continue,
+                Inbound::Msg(msg) => return Err(format!("expected socket to be closed, got: {:?}", msg)),
+                Inbound::Closed => return Ok(()),
+                Inbound::TimedOut => return Err(format!("socket not closed with in {:?}", timeout)),
+            }
+        }
+    }
+    fn expect_no_sequenced(&self, clt: &mut HarnessClt<MAX_MSG_SIZE>, timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.recv(clt, deadline.saturating_duration_since(Instant::now())) {
+                Inbound::Msg(msg @ SvcSoupBinTcpMsg::SPayload(_)) => return Err(format!("expected no sequenced messages, got: {:?}", msg)),
+                Inbound::Msg(_) => continue,
+                Inbound::Closed => return Err("socket closed unexpectedly".to_owned()),
+                Inbound::TimedOut => return Ok(()),
+            }
+        }
+    }
+    fn next_sequence_number(&self) -> Result<u64, String> {
+        let (_clt, accepted) = self.login(self.config.session_id, 0)?;
+        parse_sequence_number(&accepted)
+    }
+    /// Logs in at `sequence_number` and returns payloads of the first `count` [SPayload]s
+    fn replay(&self, sequence_number: u64, count: u64) -> Result<Vec<VecPayload>, String> {
+        let (mut clt, accepted) = self.login(self.config.session_id, sequence_number)?;
+        if parse_sequence_number(&accepted)? != sequence_number {
+            return Err(format!("expected LoginAccepted with requested sequence number: {}, got: {}", sequence_number, accepted));
+        }
+        let mut payloads = Vec::with_capacity(count as usize);
+        while (payloads.len() as u64) < count {
+            match self.recv(&mut clt, self.config.io_timeout) {
+                Inbound::Msg(SvcSoupBinTcpMsg::SPayload(msg)) => payloads.push(msg.payload),
+                Inbound::Msg(SvcSoupBinTcpMsg::HBeat(_) | SvcSoupBinTcpMsg::UPayload(_)) => continue,
+                inbound => {
+                    return Err(format!(
+                        "received {} of {} sequenced messages replayed from sequence {}, then got: {}",
+                        payloads.len(),
+                        count,
+                        sequence_number,
+                        inbound_to_string(&inbound)
+                    ))
+                }
+            }
+        }
+        Ok(payloads)
+    }
+}
+impl<const MAX_MSG_SIZE: usize> Debug for ConformanceHarness<MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConformanceHarness")
+            .field("config", &self.config)
+            .field("end_of_session_trigger", &self.end_of_session_trigger.is_some())
+            .finish()
+    }
+}
+
+fn parse_sequence_number(accepted: &LoginAccepted) -> Result<u64, String> {
+    accepted.sequence_number().try_to_u64().map_err(|e| format!("{}, msg: {}", e, accepted))
+}
+fn inbound_to_string(inbound: &Inbound) -> String {
+    match inbound {
+        Inbound::Msg(msg) => format!("{:?}", msg),
+        Inbound::Closed => "socket closed".to_owned(),
+        Inbound::TimedOut => "timeout".to_owned(),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{
+        num::NonZeroUsize,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    const MAX_MSG_SIZE: usize = 1024;
+    const N_SEQUENCED_PAYLOADS: usize = 10;
+    type SvcProtocol = SvcSoupBinTcpProtocolAuto<VecPayload, VecPayload>;
+    type SvcSender = SvcSoupBinTcpStreamTcpSenderRef<SvcProtocol, DevNullCallback<SvcProtocol>, MAX_MSG_SIZE>;
+
+    /// Returns a running svc whose session already holds [N_SEQUENCED_PAYLOADS]
+    fn svc_with_sequenced_payloads(addr: &str, username: UserName, password: Password, session_id: SessionId) -> SvcSender {
+        let io_timeout = setup::net::find_timeout();
+        let protocol = SvcProtocol::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let mut svc = SvcSoupBinTcpStreamTcp::<_, _, MAX_MSG_SIZE>::bind(addr, NonZeroUsize::new(ConformanceScenario::ALL.len() + 1).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/conformance"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let protocol = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+        let clt = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            protocol,
+            Some("clt/soupbintcp/conformance"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+        for i in 1..=N_SEQUENCED_PAYLOADS {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(VecPayload::new(format!("#{}", i).into_bytes())), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        drop(clt);
+        svc
+    }
+
+    #[test]
+    fn test_conformance_svc_auto() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"session".as_slice().into();
+
+        // one svc for all scenarios, rejected logins must not stop it from accepting the scenarios which follow
+        let addr = setup::net::rand_avail_addr_port();
+        let svc = Arc::new(Mutex::new(Some(svc_with_sequenced_payloads(addr, username, password, session_id))));
+
+        let mut config = ConformanceConfig::new(addr, username, password, session_id);
+        config.io_timeout = setup::net::find_timeout();
+        config.end_of_session_timeout = setup::net::find_timeout();
+        let harness = ConformanceHarness::<MAX_MSG_SIZE>::new(config).with_end_of_session_trigger(move || drop(svc.lock().unwrap().take()));
+        let report = harness.run();
+        info!("{}", report);
+        assert!(report.is_pass());
+        assert_eq!(report.passed(), ConformanceScenario::ALL.len());
+    }
+}
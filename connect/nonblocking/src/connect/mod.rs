@@ -1,2 +1,8 @@
 pub mod clt;
+pub mod stream;
 pub mod svc;
+pub mod tcp;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(unix)]
+pub mod uds;
@@ -0,0 +1,524 @@
+use super::{FrameStream, StreamFrameReader, StreamFrameWriter, DEFAULT_HBEAT_HANDLER, DEFAULT_POLL_HANDLER};
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use std::{
+    fmt::Display,
+    io::Error,
+    ops::DerefMut,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Transport agnostic counterpart of [CltRecver], executes [ProtocolCore::on_recv] and [CallbackRecv::on_recv] on every message received
+///
+/// # Warning
+/// Dropping [CltSoupBinTcpStreamRecver] will also result in termination of the connection in the `paired` [CltSoupBinTcpStreamSender] instance
+#[derive(Debug)]
+pub struct CltSoupBinTcpStreamRecver<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> {
+    frm_reader: StreamFrameReader<S, P, MAX_MSG_SIZE>,
+    callback: Arc<C>,
+    protocol: Arc<P>,
+    #[allow(dead_code)] // exists to indicate to SvcSoupBinTcpStream::accept that this connection no longer active when Self is dropped
+    acceptor_connection_gate: Option<RemoveConnectionBarrierOnDrop>,
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> RecvNonBlocking<P::RecvT> for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn recv(&mut self) -> Result<RecvStatus<P::RecvT>, Error> {
+        match self.frm_reader.read_frame()? {
+            RecvStatus::Completed(Some(frame)) => {
+                let msg = P::deserialize(&frame)?;
+                self.protocol.on_recv(self, &msg);
+                self.callback.on_recv(self.con_id(), &msg);
+                Ok(RecvStatus::Completed(Some(msg)))
+            }
+            RecvStatus::Completed(None) => Ok(RecvStatus::Completed(None)),
+            RecvStatus::WouldBlock => Ok(RecvStatus::WouldBlock),
+        }
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> ConnectionId for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        &self.frm_reader.con_id
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> ConnectionStatus for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.protocol.is_connected()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> PollRead for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    fn on_readable_event(&mut self) -> Result<PollEventStatus, Error> {
+        use RecvStatus::*;
+        match self.recv()? {
+            Completed(Some(_)) => Ok(PollEventStatus::Completed),
+            WouldBlock => Ok(PollEventStatus::WouldBlock),
+            Completed(None) => Ok(PollEventStatus::Terminate),
+        }
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> PollAble for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    fn source(&mut self) -> Box<&mut dyn mio::event::Source> {
+        Box::new(&mut self.frm_reader.stream)
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecv<P>, const MAX_MSG_SIZE: usize> Display for CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(
+            f,
+            "{}<{}, RecvT:{}, SendT:{}, {}>",
+            asserted_short_name!("CltSoupBinTcpStreamRecver", Self),
+            self.con_id(),
+            recv_t,
+            send_t,
+            MAX_MSG_SIZE
+        )
+    }
+}
+
+/// Transport agnostic counterpart of [CltSender], executes [ProtocolCore::on_send], [ProtocolCore::on_sent]/[ProtocolCore::on_wouldblock]/[ProtocolCore::on_error]
+/// and [CallbackSend::on_sent] on every message sent
+///
+/// # Warning
+/// Dropping [CltSoupBinTcpStreamSender] will send [ProtocolCore::on_disconnect] message and terminate the connection in the `paired` [CltSoupBinTcpStreamRecver] instance
+#[derive(Debug)]
+pub struct CltSoupBinTcpStreamSender<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> {
+    frm_writer: StreamFrameWriter<S>,
+    callback: Arc<C>,
+    protocol: Arc<P>,
+    #[allow(dead_code)] // exists to indicate to SvcSoupBinTcpStream::accept that this connection no longer active when Self is dropped
+    acceptor_connection_gate: Option<RemoveConnectionBarrierOnDrop>,
+    shutdown_issued: bool,
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn write(&mut self, msg: &P::SendT) -> Result<SendStatus, Error> {
+        let (bytes, len) = P::serialize::<MAX_MSG_SIZE>(msg)?;
+        self.frm_writer.write_frame(&bytes[..len])
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> SendNonBlocking<P::SendT> for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn send(&mut self, msg: &mut P::SendT) -> Result<SendStatus, Error> {
+        self.protocol.on_send(self, msg);
+        match self.write(msg) {
+            Ok(SendStatus::Completed) => {
+                self.protocol.on_sent(self, msg);
+                self.callback.on_sent(self.con_id(), msg);
+                Ok(SendStatus::Completed)
+            }
+            Ok(SendStatus::WouldBlock) => {
+                self.protocol.on_wouldblock(self, msg);
+                Ok(SendStatus::WouldBlock)
+            }
+            Err(e) => {
+                self.protocol.on_error(self, msg, &e);
+                Err(e)
+            }
+        }
+    }
+    #[inline(always)]
+    fn send_busywait_timeout(&mut self, msg: &mut P::SendT, timeout: Duration) -> Result<SendStatus, Error> {
+        // NOTE: default implementation is overridden to ensure on_send is issued only once
+        let start = Instant::now();
+        self.protocol.on_send(self, msg);
+        loop {
+            match self.write(msg) {
+                Ok(SendStatus::Completed) => {
+                    self.protocol.on_sent(self, msg);
+                    self.callback.on_sent(self.con_id(), msg);
+                    return Ok(SendStatus::Completed);
+                }
+                Ok(SendStatus::WouldBlock) => {
+                    if start.elapsed() > timeout {
+                        self.protocol.on_wouldblock(self, msg);
+                        return Ok(SendStatus::WouldBlock);
+                    }
+                }
+                Err(e) => {
+                    self.protocol.on_error(self, msg, &e);
+                    return Err(e);
+                }
+            }
+        }
+    }
+    #[inline(always)]
+    fn send_busywait(&mut self, msg: &mut P::SendT) -> Result<(), Error> {
+        // NOTE: default implementation is overridden to ensure on_send is issued only once
+        self.protocol.on_send(self, msg);
+        loop {
+            match self.write(msg) {
+                Ok(SendStatus::Completed) => {
+                    self.protocol.on_sent(self, msg);
+                    self.callback.on_sent(self.con_id(), msg);
+                    return Ok(());
+                }
+                Ok(SendStatus::WouldBlock) => continue,
+                Err(e) => {
+                    self.protocol.on_error(self, msg, &e);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> ReSendNonBlocking<P::SendT> for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    fn re_send(&mut self, msg: &P::SendT) -> Result<SendStatus, Error> {
+        self.write(msg)
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> ConnectionId for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        &self.frm_writer.con_id
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> ConnectionStatus for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.protocol.is_connected()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Display for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(
+            f,
+            "{}<{}, RecvT:{}, SendT:{}, {}>",
+            asserted_short_name!("CltSoupBinTcpStreamSender", Self),
+            self.con_id(),
+            recv_t,
+            send_t,
+            MAX_MSG_SIZE
+        )
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Shutdown for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    fn shutdown(&mut self) {
+        if self.shutdown_issued {
+            return;
+        }
+        if let Some((timeout, mut msg)) = self.protocol.on_disconnect() {
+            match self.send_busywait_timeout(&mut msg, timeout) {
+                Ok(SendStatus::Completed) => {
+                    if log_enabled!(log::Level::Debug) {
+                        debug!(
+                            "Clean {}::on_disconnect msg: {:?}, con_id: {}",
+                            asserted_short_name!("CltSoupBinTcpStreamSender", Self),
+                            msg,
+                            self.con_id()
+                        );
+                    }
+                }
+                Ok(SendStatus::WouldBlock) => warn!(
+                    "Timed out {}::on_disconnect timeout: {:?}, msg: {:?}, con_id: {}",
+                    asserted_short_name!("CltSoupBinTcpStreamSender", Self),
+                    timeout,
+                    msg,
+                    self.con_id()
+                ),
+                Err(err) => warn!(
+                    "Failed to complete {}::on_disconnect msg: {:?}, con_id: {}, err: {}",
+                    asserted_short_name!("CltSoupBinTcpStreamSender", Self),
+                    msg,
+                    self.con_id(),
+                    err
+                ),
+            }
+        }
+        // shutdown writer to ensure that reader is notified of termination
+        self.frm_writer.shutdown(std::net::Shutdown::Both);
+        self.shutdown_issued = true;
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Drop for CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Transport agnostic counterpart of [CltRecverRef], a reference counted [CltSoupBinTcpStreamRecver] protected by a [spin::Mutex] which
+/// issues [Protocol::send_reply] via a clone of the `paired` [CltSoupBinTcpStreamSenderRef]
+///
+/// # Warning
+/// Dropping any of the clones will terminate the connection across all remaining instances
+#[derive(Debug)]
+pub struct CltSoupBinTcpStreamRecverRef<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> {
+    con_id: ConId,
+    clt_recver: Arc<spin::Mutex<CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE>>>,
+    clt_sender: CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE>,
+    protocol: Arc<P>,
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> RecvNonBlocking<P::RecvT> for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    /// Delegates to [CltSoupBinTcpStreamRecver] and calls [Protocol::send_reply] when a message is received
+    #[inline(always)]
+    fn recv(&mut self) -> Result<RecvStatus<P::RecvT>, Error> {
+        let res = self.clt_recver.lock().recv();
+        if let Ok(RecvStatus::Completed(Some(ref msg))) = res {
+            self.protocol.send_reply(msg, &mut self.clt_sender)?;
+        }
+        res
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ConnectionId for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        &self.con_id
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ConnectionStatus for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.protocol.is_connected()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> PollRead for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    fn on_readable_event(&mut self) -> Result<PollEventStatus, Error> {
+        use RecvStatus::*;
+        match self.recv()? {
+            Completed(Some(_)) => Ok(PollEventStatus::Completed),
+            WouldBlock => Ok(PollEventStatus::WouldBlock),
+            Completed(None) => Ok(PollEventStatus::Terminate),
+        }
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> PollAble for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    fn register(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        registry.register(&mut self.clt_recver.lock().frm_reader.stream, token, interests)
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> Result<(), Error> {
+        registry.deregister(&mut self.clt_recver.lock().frm_reader.stream)
+    }
+    fn source(&mut self) -> Box<&mut dyn mio::event::Source> {
+        panic!("Invalid API usage. PollAble::register and PollAble::deregister are overridden and this call shall never be issued.")
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> Display for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(
+            f,
+            "{}<{}, RecvT:{}, SendT:{}, {}>",
+            asserted_short_name!("CltSoupBinTcpStreamRecverRef", Self),
+            self.con_id(),
+            recv_t,
+            send_t,
+            MAX_MSG_SIZE
+        )
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> Drop for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    fn drop(&mut self) {
+        // any of the dropped clones terminates the connection for all remaining clones of both recver & sender
+        self.clt_recver.lock().frm_reader.shutdown(std::net::Shutdown::Both);
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> Clone for CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE> {
+    fn clone(&self) -> Self {
+        Self {
+            con_id: self.con_id.clone(),
+            clt_recver: self.clt_recver.clone(),
+            clt_sender: self.clt_sender.clone(),
+            protocol: self.protocol.clone(),
+        }
+    }
+}
+
+/// Transport agnostic counterpart of [CltSenderRef], a reference counted [CltSoupBinTcpStreamSender] protected by a [spin::Mutex], a clone of which
+/// is scheduled on [static@DEFAULT_HBEAT_HANDLER] to issue [Protocol::send_heart_beat]
+///
+/// # Warning
+/// Dropping any of the clones will terminate the connection across all remaining instances
+#[derive(Debug)]
+pub struct CltSoupBinTcpStreamSenderRef<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> {
+    con_id: ConId,
+    clt_sender: Arc<spin::Mutex<CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE>>>,
+    protocol: Arc<P>,
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    fn send_heart_beat(&self) -> Result<SendStatus, Error> {
+        let mut guard = self.clt_sender.lock();
+        self.protocol.send_heart_beat(guard.deref_mut())
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> SendNonBlocking<P::SendT> for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    /// Delegates to [CltSoupBinTcpStreamSender] once a spin lock is acquired
+    #[inline(always)]
+    fn send(&mut self, msg: &mut P::SendT) -> Result<SendStatus, Error> {
+        self.clt_sender.lock().send(msg)
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> ConnectionId for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        &self.con_id
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> ConnectionStatus for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.protocol.is_connected()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Display for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(
+            f,
+            "{}<{}, RecvT:{}, SendT:{}, {}>",
+            asserted_short_name!("CltSoupBinTcpStreamSenderRef", Self),
+            self.con_id(),
+            recv_t,
+            send_t,
+            MAX_MSG_SIZE
+        )
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Shutdown for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    fn shutdown(&mut self) {
+        self.clt_sender.lock().shutdown()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Drop for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    fn drop(&mut self) {
+        self.shutdown()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackSend<P>, const MAX_MSG_SIZE: usize> Clone for CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+    fn clone(&self) -> Self {
+        Self {
+            con_id: self.con_id.clone(),
+            clt_sender: self.clt_sender.clone(),
+            protocol: self.protocol.clone(),
+        }
+    }
+}
+
+/// Transport agnostic counterpart of [CltSoupBinTcp], runs [SoupBinTcpFramer] framing, messengers and protocols over a [FrameStream], see
+/// [CltSoupBinTcpUds](crate::connect::uds::CltSoupBinTcpUds) for an example
+///
+/// * Use [CltSoupBinTcpStream::into_split] to get its parts [CltSoupBinTcpStreamRecver]/[CltSoupBinTcpStreamSender]
+/// * Use [CltSoupBinTcpStream::into_split_ref] to get its parts [CltSoupBinTcpStreamRecverRef]/[CltSoupBinTcpStreamSenderRef]
+#[derive(Debug)]
+pub struct CltSoupBinTcpStream<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> {
+    // CRITICAL sender must be dropped before recver so that it is able to deliver ProtocolCore::on_disconnect message
+    clt_sender: CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE>, // DON'T MOVE below clt_recver
+    clt_recver: CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE>,
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    /// Takes a `paired` reader & writer of the same connection and issues [ProtocolCore::on_connect]
+    pub(crate) fn from_split(reader: S, writer: S, con_id: ConId, callback: Arc<C>, protocol: P, acceptor_connection_gate: Option<RemoveConnectionBarrierOnDrop>) -> Result<Self, Error> {
+        let protocol = Arc::new(protocol);
+        let mut con = Self {
+            clt_sender: CltSoupBinTcpStreamSender {
+                frm_writer: StreamFrameWriter::new(con_id.clone(), writer),
+                callback: callback.clone(),
+                protocol: protocol.clone(),
+                acceptor_connection_gate: acceptor_connection_gate.clone(),
+                shutdown_issued: false,
+            },
+            clt_recver: CltSoupBinTcpStreamRecver {
+                frm_reader: StreamFrameReader::new(con_id, reader),
+                callback,
+                protocol: protocol.clone(),
+                acceptor_connection_gate,
+            },
+        };
+        protocol.on_connect(&mut con)?;
+        Ok(con)
+    }
+    /// Will split into [CltSoupBinTcpStreamRecver]/[CltSoupBinTcpStreamSender] which only support [ProtocolCore] features
+    pub fn into_split(self) -> (CltSoupBinTcpStreamRecver<S, P, C, MAX_MSG_SIZE>, CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE>) {
+        (self.clt_recver, self.clt_sender)
+    }
+    /// Will split into [CltSoupBinTcpStreamRecverRef]/[CltSoupBinTcpStreamSenderRef] which support `all` [Protocol] features, a clone of
+    /// [CltSoupBinTcpStreamSenderRef] is moved to [static@DEFAULT_HBEAT_HANDLER] to periodically trigger [Protocol::send_heart_beat]
+    pub fn into_split_ref(self) -> (CltSoupBinTcpStreamRecverRef<S, P, C, MAX_MSG_SIZE>, CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE>) {
+        let (recver, sender) = self.into_split();
+        let sender = CltSoupBinTcpStreamSenderRef {
+            con_id: sender.con_id().clone(),
+            protocol: sender.protocol.clone(),
+            clt_sender: Arc::new(spin::Mutex::new(sender)),
+        };
+        let recver = CltSoupBinTcpStreamRecverRef {
+            con_id: recver.con_id().clone(),
+            protocol: recver.protocol.clone(),
+            clt_recver: Arc::new(spin::Mutex::new(recver)),
+            clt_sender: sender.clone(),
+        };
+        if let Some(interval) = sender.protocol.conf_heart_beat_interval() {
+            DEFAULT_HBEAT_HANDLER.schedule(sender.con_id().to_string().as_str(), interval, {
+                let sender = sender.clone();
+                move || match sender.send_heart_beat() {
+                    Ok(SendStatus::Completed) => TimerTaskStatus::Completed,
+                    Ok(SendStatus::WouldBlock) => TimerTaskStatus::RetryAfter(Duration::from_secs(0)),
+                    Err(err) => {
+                        warn!("{} Failed to send heart beat. Will no longer attempt to send. err:\n{}", sender.con_id(), err);
+                        TimerTaskStatus::Terminate
+                    }
+                }
+            });
+        }
+        (recver, sender)
+    }
+    /// Will split using [CltSoupBinTcpStream::into_split] and move [CltSoupBinTcpStreamRecver] to run in the [static@DEFAULT_POLL_HANDLER] thread
+    pub fn into_sender_with_spawned_recver(self) -> CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE> {
+        let (recver, sender) = self.into_split();
+        DEFAULT_POLL_HANDLER.add_recver(Box::new(recver));
+        sender
+    }
+    /// Will split using [CltSoupBinTcpStream::into_split_ref] and move [CltSoupBinTcpStreamRecverRef] to run in the [static@DEFAULT_POLL_HANDLER] thread
+    pub fn into_sender_with_spawned_recver_ref(self) -> CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE> {
+        let (recver, sender) = self.into_split_ref();
+        DEFAULT_POLL_HANDLER.add_recver(Box::new(recver));
+        sender
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SendNonBlocking<P::SendT> for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn send(&mut self, msg: &mut P::SendT) -> Result<SendStatus, Error> {
+        self.clt_sender.send(msg)
+    }
+    #[inline(always)]
+    fn send_busywait_timeout(&mut self, msg: &mut P::SendT, timeout: Duration) -> Result<SendStatus, Error> {
+        self.clt_sender.send_busywait_timeout(msg, timeout)
+    }
+    #[inline(always)]
+    fn send_busywait(&mut self, msg: &mut P::SendT) -> Result<(), Error> {
+        self.clt_sender.send_busywait(msg)
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> RecvNonBlocking<P::RecvT> for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn recv(&mut self) -> Result<RecvStatus<P::RecvT>, Error> {
+        self.clt_recver.recv()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ReSendNonBlocking<P::SendT> for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    fn re_send(&mut self, msg: &P::SendT) -> Result<SendStatus, Error> {
+        self.clt_sender.re_send(msg)
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ConnectionId for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn con_id(&self) -> &ConId {
+        self.clt_recver.con_id()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ConnectionStatus for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    #[inline(always)]
+    fn is_connected(&self) -> bool {
+        self.clt_recver.is_connected()
+    }
+}
+impl<S: FrameStream, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> Display for CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}<{}, {}>", asserted_short_name!("CltSoupBinTcpStream", Self), self.clt_recver, self.clt_sender)
+    }
+}
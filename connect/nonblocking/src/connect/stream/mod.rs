@@ -0,0 +1,149 @@
+//! [CltSoupBinTcpStream] & [SvcSoupBinTcpStream] run the same [SoupBinTcpFramer], messengers & protocols as [CltSoupBinTcp] & [SvcSoupBinTcp] over any
+//! [FrameStream] transport, see [crate::connect::tcp], [crate::connect::uds] & `crate::connect::tls` for the available transports.
+//!
+//! Connections are serviced by the same [static@DEFAULT_POLL_HANDLER] & [static@DEFAULT_HBEAT_HANDLER] threads as tcp connections.
+//!
+//! # Important
+//! links [CltRecver], [CltSender] & [SvcAcceptor] can not be reused with a generic stream since they hold a concrete [mio::net::TcpStream] &
+//! [mio::net::TcpListener] inside links' `FrameReader`/`FrameWriter` and acceptor, none of which are parameterized over the socket type. Hence the
+//! recver, sender & acceptor are mirrored here, while everything which is not tied to the socket, [CltSendersPool], the poll & heartbeat handlers, the
+//! `*NonBlocking` traits, callbacks & protocols, is reused from links as is. The mirrored types shall keep the same behavior as their links counterparts.
+pub mod clt;
+pub mod svc;
+
+use crate::prelude::*;
+use bytes::{Bytes, BytesMut};
+use std::{
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind, Read, Write},
+    marker::PhantomData,
+    net::Shutdown,
+};
+
+pub use links_nonblocking::connect::{DEFAULT_HBEAT_HANDLER, DEFAULT_POLL_HANDLER};
+
+/// A non blocking byte stream which can be registered with [mio::Poll], one instance for reading & one for writing is created per connection
+pub trait FrameStream: Read + Write + mio::event::Source + Debug + Send + 'static {
+    /// Not connected errors shall be ignored since either side may have shut down the stream already
+    fn shutdown(&self, how: Shutdown);
+}
+
+/// [ConId] of an accepted connection along with its `paired` reader & writer
+pub type AcceptedSplit<S> = (ConId, S, S);
+
+/// A non blocking listener which can be registered with [mio::Poll] and accepts [FrameStream] connections
+pub trait FrameStreamListener: mio::event::Source + Display + Debug + Send + 'static {
+    type Stream: FrameStream;
+    /// Returns the accepted connection whose [ConId] is derived from the acceptor's `con_id`, or
+    /// [AcceptStatus::Rejected] if the transport refused the connection, ex: failed handshake
+    fn accept_split(&self, con_id: &ConId) -> Result<AcceptStatus<AcceptedSplit<Self::Stream>>, Error>;
+}
+
+/// Reads exactly one frame at a time from a non blocking [FrameStream], mirrors [FrameReader]
+#[derive(Debug)]
+pub(crate) struct StreamFrameReader<S: FrameStream, F: Framer, const MAX_MSG_SIZE: usize> {
+    pub(crate) con_id: ConId,
+    pub(crate) stream: S,
+    buffer: BytesMut,
+    phantom: PhantomData<F>,
+}
+impl<S: FrameStream, F: Framer, const MAX_MSG_SIZE: usize> StreamFrameReader<S, F, MAX_MSG_SIZE> {
+    pub(crate) fn new(con_id: ConId, stream: S) -> Self {
+        Self {
+            con_id,
+            stream,
+            buffer: BytesMut::with_capacity(MAX_MSG_SIZE),
+            phantom: PhantomData,
+        }
+    }
+    /// Returns [RecvStatus::Completed] with `None` once the peer closed the connection and [RecvStatus::WouldBlock] only once the stream
+    /// itself would block, since streams which buffer internally, ex: tls, will not raise another readable event for the data they hold
+    #[inline(always)]
+    pub(crate) fn read_frame(&mut self) -> Result<RecvStatus<Bytes>, Error> {
+        if let Some(bytes) = F::get_frame(&mut self.buffer) {
+            return Ok(RecvStatus::Completed(Some(bytes)));
+        }
+        let mut buf = [0_u8; MAX_MSG_SIZE];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.shutdown(Shutdown::Write);
+                    return if self.buffer.is_empty() {
+                        Ok(RecvStatus::Completed(None))
+                    } else {
+                        Err(Error::new(
+                            ErrorKind::ConnectionReset,
+                            format!("{} read_frame connection reset by peer, residual buf: {:?}", self.con_id, &self.buffer[..]),
+                        ))
+                    };
+                }
+                Ok(len) => {
+                    self.buffer.extend_from_slice(&buf[..len]);
+                    if let Some(bytes) = F::get_frame(&mut self.buffer) {
+                        return Ok(RecvStatus::Completed(Some(bytes)));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(RecvStatus::WouldBlock),
+                Err(e) => {
+                    self.shutdown(Shutdown::Write);
+                    return Err(Error::new(e.kind(), format!("{} read_frame caused by: [{}] residual buf: {:?}", self.con_id, e, &self.buffer[..])));
+                }
+            }
+        }
+    }
+    pub(crate) fn shutdown(&mut self, how: Shutdown) {
+        self.stream.shutdown(how);
+    }
+}
+impl<S: FrameStream, F: Framer, const MAX_MSG_SIZE: usize> Drop for StreamFrameReader<S, F, MAX_MSG_SIZE> {
+    fn drop(&mut self) {
+        self.shutdown(Shutdown::Both)
+    }
+}
+
+/// Writes entire frame or no bytes at all to a non blocking [FrameStream], mirrors [FrameWriter]
+#[derive(Debug)]
+pub(crate) struct StreamFrameWriter<S: FrameStream> {
+    pub(crate) con_id: ConId,
+    stream: S,
+}
+impl<S: FrameStream> StreamFrameWriter<S> {
+    pub(crate) fn new(con_id: ConId, stream: S) -> Self {
+        Self { con_id, stream }
+    }
+    /// Returns [SendStatus::WouldBlock] only if no bytes were written, otherwise busy waits until the rest of the frame is written
+    #[inline(always)]
+    pub(crate) fn write_frame(&mut self, bytes: &[u8]) -> Result<SendStatus, Error> {
+        let mut residual = bytes;
+        while !residual.is_empty() {
+            match self.stream.write(residual) {
+                Ok(0) => {
+                    self.shutdown(Shutdown::Both);
+                    return Err(Error::new(
+                        ErrorKind::ConnectionReset,
+                        format!("{} write_frame connection reset by peer, residual len: {}", self.con_id, residual.len()),
+                    ));
+                }
+                Ok(len) => residual = &residual[len..],
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if residual.len() == bytes.len() {
+                        return Ok(SendStatus::WouldBlock);
+                    }
+                }
+                Err(e) => {
+                    self.shutdown(Shutdown::Both);
+                    return Err(Error::new(e.kind(), format!("{} write_frame caused by: [{}], residual len: {}", self.con_id, e, residual.len())));
+                }
+            }
+        }
+        Ok(SendStatus::Completed)
+    }
+    pub(crate) fn shutdown(&mut self, how: Shutdown) {
+        self.stream.shutdown(how);
+    }
+}
+impl<S: FrameStream> Drop for StreamFrameWriter<S> {
+    fn drop(&mut self) {
+        self.shutdown(Shutdown::Both)
+    }
+}
@@ -0,0 +1,170 @@
+use super::{
+    clt::{CltSoupBinTcpStream, CltSoupBinTcpStreamRecver, CltSoupBinTcpStreamRecverRef, CltSoupBinTcpStreamSender, CltSoupBinTcpStreamSenderRef},
+    FrameStreamListener, DEFAULT_POLL_HANDLER,
+};
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    num::NonZeroUsize,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+};
+
+pub type SvcSoupBinTcpStreamSender<S, P, C, const MAX_MSG_SIZE: usize> = CltSendersPool<P, CltSoupBinTcpStreamSender<S, P, C, MAX_MSG_SIZE>>;
+pub type SvcSoupBinTcpStreamSenderRef<S, P, C, const MAX_MSG_SIZE: usize> = CltSendersPool<P, CltSoupBinTcpStreamSenderRef<S, P, C, MAX_MSG_SIZE>>;
+
+/// Splits an accepted connection into the recver kept by the acceptor & the sender transmitted to [CltSendersPool]
+type SplitFn<S, P, C, const MAX_MSG_SIZE: usize, R, SN> = fn(CltSoupBinTcpStream<S, P, C, MAX_MSG_SIZE>) -> (R, SN);
+
+/// Transport agnostic counterpart of [SvcSoupBinTcp], accepts [CltSoupBinTcpStream] connections from a [FrameStreamListener], see
+/// [SvcSoupBinTcpUds](crate::connect::uds::SvcSoupBinTcpUds) for an example
+///
+/// # Important
+/// Unlike [SvcSoupBinTcp] a connection whose [ProtocolCore::on_connect] fails, ex: rejected login, is dropped & reported as [AcceptStatus::Rejected]
+/// so that the acceptor keeps running.
+#[derive(Debug)]
+pub struct SvcSoupBinTcpStream<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> {
+    con_id: ConId,
+    listener: L,
+    acceptor_limiter: AcceptorConnectionGate,
+    max_connections: NonZeroUsize,
+    callback: Arc<C>,
+    protocol: P,
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE> {
+    pub(crate) fn from_listener(listener: L, con_id: ConId, max_connections: NonZeroUsize, callback: Arc<C>, protocol: P) -> Self {
+        Self {
+            con_id,
+            listener,
+            acceptor_limiter: AcceptorConnectionGate::new(max_connections),
+            max_connections,
+            callback,
+            protocol,
+        }
+    }
+    pub fn listener(&self) -> &L {
+        &self.listener
+    }
+    /// Moves the acceptor to run in the [static@DEFAULT_POLL_HANDLER] thread and returns the pool receiving the sender part of every accepted connection
+    fn spawn_transmitting_acceptor<R: PollRead, S: SendNonBlocking<P::SendT> + ConnectionStatus + Send + 'static>(self, split: SplitFn<L::Stream, P, C, MAX_MSG_SIZE, R, S>) -> CltSendersPool<P, S> {
+        let (tx_sender, rx_sender) = channel();
+        // make pool twice as big as acceptor will allow to be opened to ensure that acceptor is able to add new connections to the pool even
+        // if some of the connections in the pool are dead but not closed yet
+        let pool_size = NonZeroUsize::new(self.max_connections.get() * 2).unwrap();
+        let senders = CltSendersPool::new(self.con_id.clone(), rx_sender, pool_size);
+        DEFAULT_POLL_HANDLER.add_acceptor(Box::new(TransmittingSvcSoupBinTcpStream { acceptor: self, tx_sender, split }));
+        senders
+    }
+    /// Will move the acceptor to run in the [static@DEFAULT_POLL_HANDLER] thread which splits accepted connections using [CltSoupBinTcpStream::into_split]
+    /// and only return [SvcSoupBinTcpStreamSender] pool
+    ///
+    /// # Important
+    /// This configuration will support only 'subset' of [Protocol] features which are part of [ProtocolCore] trait
+    pub fn into_sender_with_spawned_recver(self) -> SvcSoupBinTcpStreamSender<L::Stream, P, C, MAX_MSG_SIZE> {
+        self.spawn_transmitting_acceptor::<CltSoupBinTcpStreamRecver<L::Stream, P, C, MAX_MSG_SIZE>, _>(CltSoupBinTcpStream::into_split)
+    }
+    /// Will move the acceptor to run in the [static@DEFAULT_POLL_HANDLER] thread which splits accepted connections using [CltSoupBinTcpStream::into_split_ref]
+    /// and only return [SvcSoupBinTcpStreamSenderRef] pool
+    ///
+    /// # Important
+    /// This configuration will support `all` [Protocol] features
+    pub fn into_sender_with_spawned_recver_ref(self) -> SvcSoupBinTcpStreamSenderRef<L::Stream, P, C, MAX_MSG_SIZE> {
+        self.spawn_transmitting_acceptor::<CltSoupBinTcpStreamRecverRef<L::Stream, P, C, MAX_MSG_SIZE>, _>(CltSoupBinTcpStream::into_split_ref)
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SvcAcceptorOfCltNonBlocking<CltSoupBinTcpStream<L::Stream, P, C, MAX_MSG_SIZE>>
+    for SvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE>
+{
+    fn accept(&self) -> Result<AcceptStatus<CltSoupBinTcpStream<L::Stream, P, C, MAX_MSG_SIZE>>, Error> {
+        match self.listener.accept_split(&self.con_id)? {
+            AcceptStatus::Accepted((con_id, reader, writer)) => {
+                if let Err(e) = self.acceptor_limiter.increment() {
+                    if log_enabled!(log::Level::Warn) {
+                        warn!("{} Rejected stream: {:?} due to error: {}", con_id, reader, e);
+                    }
+                    return Ok(AcceptStatus::Rejected);
+                }
+                if log_enabled!(log::Level::Debug) {
+                    debug!("{} Accepted", con_id);
+                }
+                let acceptor_connection_gate = Some(self.acceptor_limiter.get_new_connection_barrier());
+                match CltSoupBinTcpStream::<L::Stream, P, C, MAX_MSG_SIZE>::from_split(reader, writer, con_id, self.callback.clone(), self.protocol.clone(), acceptor_connection_gate) {
+                    Ok(clt) => Ok(AcceptStatus::Accepted(clt)),
+                    Err(e) => {
+                        if log_enabled!(log::Level::Warn) {
+                            warn!("{} Rejected connection due to on_connect error: {}", self.con_id, e);
+                        }
+                        Ok(AcceptStatus::Rejected)
+                    }
+                }
+            }
+            AcceptStatus::Rejected => Ok(AcceptStatus::Rejected),
+            AcceptStatus::WouldBlock => Ok(AcceptStatus::WouldBlock),
+        }
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> ConnectionId for SvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE> {
+    fn con_id(&self) -> &ConId {
+        &self.con_id
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> Display for SvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recv_t = std::any::type_name::<P::RecvT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        let send_t = std::any::type_name::<P::SendT>().split("::").last().unwrap_or("Unknown").replace('>', "");
+        write!(
+            f,
+            "{}<{}, {}, RecvT:{}, SendT:{}, {}>",
+            asserted_short_name!("SvcSoupBinTcpStream", Self),
+            self.con_id,
+            self.listener,
+            recv_t,
+            send_t,
+            MAX_MSG_SIZE
+        )
+    }
+}
+
+/// Runs in the [static@DEFAULT_POLL_HANDLER] thread, keeps the recver part of every accepted connection & transmits the sender part to [CltSendersPool]
+#[derive(Debug)]
+struct TransmittingSvcSoupBinTcpStream<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize, R, S> {
+    acceptor: SvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE>,
+    tx_sender: Sender<S>,
+    split: SplitFn<L::Stream, P, C, MAX_MSG_SIZE, R, S>,
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize, R: PollRead, S: Send + 'static> PollAccept<Box<dyn PollRead>>
+    for TransmittingSvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE, R, S>
+{
+    fn poll_accept(&mut self) -> Result<AcceptStatus<Box<dyn PollRead>>, Error> {
+        match self.acceptor.accept()? {
+            AcceptStatus::Accepted(clt) => {
+                let (recver, sender) = (self.split)(clt);
+                if let Err(e) = self.tx_sender.send(sender) {
+                    return Err(Error::new(ErrorKind::Other, e.to_string()));
+                }
+                Ok(AcceptStatus::Accepted(Box::new(recver)))
+            }
+            AcceptStatus::Rejected => Ok(AcceptStatus::Rejected),
+            AcceptStatus::WouldBlock => Ok(AcceptStatus::WouldBlock),
+        }
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize, R: PollRead, S: Send + 'static> PollAble for TransmittingSvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE, R, S> {
+    fn source(&mut self) -> Box<&mut dyn mio::event::Source> {
+        Box::new(&mut self.acceptor.listener)
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize, R, S> ConnectionId for TransmittingSvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE, R, S> {
+    fn con_id(&self) -> &ConId {
+        self.acceptor.con_id()
+    }
+}
+impl<L: FrameStreamListener, P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize, R, S> Display for TransmittingSvcSoupBinTcpStream<L, P, C, MAX_MSG_SIZE, R, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TransmittingSvcSoupBinTcpStream<{}>", self.acceptor)
+    }
+}
@@ -1,5 +1,8 @@
 use crate::prelude::*;
 
+/// # Important
+/// The links acceptor stops accepting once a connection's [ProtocolCore::on_connect] fails, ex: a rejected login, use
+/// [SvcSoupBinTcpStreamTcp] for a server which has to keep accepting after rejecting a login
 pub type SvcSoupBinTcp<P, C, const MAX_MSG_SIZE: usize> = Svc<P, C, MAX_MSG_SIZE>;
 
 pub type SvcSoupBinTcpSender<P, C, const MAX_MSG_SIZE: usize> = SvcSender<P, C, MAX_MSG_SIZE>;
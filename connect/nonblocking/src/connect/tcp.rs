@@ -0,0 +1,161 @@
+//! [SvcSoupBinTcpStreamTcp] accepts tcp connections using the transport agnostic [SvcSoupBinTcpStream] acceptor, which unlike [SvcSoupBinTcp] keeps
+//! accepting once a connection's [ProtocolCore::on_connect] fails, ex: a rejected login, see [SvcSoupBinTcpStream] for details.
+//!
+//! Clients connect using [CltSoupBinTcp] as usual.
+use crate::connect::stream::{
+    svc::{SvcSoupBinTcpStream, SvcSoupBinTcpStreamSender, SvcSoupBinTcpStreamSenderRef},
+    AcceptedSplit, FrameStream, FrameStreamListener,
+};
+use crate::prelude::*;
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+
+pub type SvcSoupBinTcpStreamTcpSender<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSender<mio::net::TcpStream, P, C, MAX_MSG_SIZE>;
+pub type SvcSoupBinTcpStreamTcpSenderRef<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSenderRef<mio::net::TcpStream, P, C, MAX_MSG_SIZE>;
+
+/// Tcp counterpart of [SvcSoupBinTcpUds](crate::connect::uds::SvcSoupBinTcpUds), see [SvcSoupBinTcpStream] for available methods.
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+/// use std::num::NonZeroUsize;
+///
+/// let svc = SvcSoupBinTcpStreamTcp::<_, _, 128>::bind(
+///     "127.0.0.1:0",
+///     NonZeroUsize::new(1).unwrap(),
+///     DevNullCallback::new_ref(),
+///     SvcSoupBinTcpProtocolManual::<Nil, Nil>::default(),
+///     Some("doctest"),
+/// )
+/// .unwrap();
+///
+/// assert!(svc.accept().unwrap().is_wouldblock());
+/// ```
+pub type SvcSoupBinTcpStreamTcp<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStream<TcpStreamListener, P, C, MAX_MSG_SIZE>;
+
+impl FrameStream for mio::net::TcpStream {
+    fn shutdown(&self, how: Shutdown) {
+        let _ = mio::net::TcpStream::shutdown(self, how);
+    }
+}
+
+/// Creates a `paired` reader & writer sharing the same socket, both non blocking
+fn into_split_tcp(stream: TcpStream) -> Result<(mio::net::TcpStream, mio::net::TcpStream), Error> {
+    stream.set_nonblocking(true)?;
+    stream.set_nodelay(true)?;
+    let reader = stream.try_clone()?;
+    Ok((mio::net::TcpStream::from_std(reader), mio::net::TcpStream::from_std(stream)))
+}
+
+/// A non blocking [TcpListener] which accepts [TcpStream]s so that they can be split while a [mio::net::TcpListener] cloned from it is registered with [mio::Poll]
+#[derive(Debug)]
+pub struct TcpStreamListener {
+    addr: SocketAddr,
+    listener: TcpListener,
+    source: mio::net::TcpListener,
+}
+impl TcpStreamListener {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+impl FrameStreamListener for TcpStreamListener {
+    type Stream = mio::net::TcpStream;
+    fn accept_split(&self, con_id: &ConId) -> Result<AcceptStatus<AcceptedSplit<Self::Stream>>, Error> {
+        match self.listener.accept() {
+            Ok((stream, addr)) => {
+                let (reader, writer) = into_split_tcp(stream)?;
+                let mut con_id = con_id.clone();
+                con_id.set_peer(addr);
+                Ok(AcceptStatus::Accepted((con_id, reader, writer)))
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(AcceptStatus::WouldBlock),
+            Err(e) => Err(e),
+        }
+    }
+}
+impl mio::event::Source for TcpStreamListener {
+    fn register(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.source.register(registry, token, interests)
+    }
+    fn reregister(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.source.reregister(registry, token, interests)
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> Result<(), Error> {
+        self.source.deregister(registry)
+    }
+}
+impl Display for TcpStreamListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tcp:{}", self.addr)
+    }
+}
+
+impl<P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SvcSoupBinTcpStreamTcp<P, C, MAX_MSG_SIZE> {
+    /// Binds to a given `addr`, use port `0` to let the os pick one and [Self::local_addr] to find out which
+    pub fn bind(addr: &str, max_connections: NonZeroUsize, callback: Arc<C>, protocol: P, name: Option<&str>) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let listener = TcpStreamListener {
+            addr,
+            source: mio::net::TcpListener::from_std(listener.try_clone()?),
+            listener,
+        };
+        Ok(Self::from_listener(listener, ConId::svc(name, &addr.to_string(), None), max_connections, callback, protocol))
+    }
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener().local_addr()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{num::NonZeroUsize, time::Duration};
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+
+    #[test]
+    fn test_rejected_login_keeps_accepting() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let max_hbeat_interval = Duration::from_secs(1);
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, io_timeout, max_hbeat_interval);
+        let mut svc = SvcSoupBinTcpStreamTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), LoggerCallback::new_ref(), protocol, Some("svc/soupbintcp/tcp/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        info!("svc: {}", svc);
+
+        let connect = |password: Password, name: &str| {
+            let protocol = CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, 0_u64.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+            CltSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(addr, setup::net::default_connect_timeout(), setup::net::default_connect_retry_after(), LoggerCallback::new_ref(), protocol, Some(name))
+                .map(|clt| clt.into_sender_with_spawned_recver_ref())
+        };
+
+        for i in 1..=2 {
+            let res = connect(b"wrong".as_slice().into(), "clt/soupbintcp/tcp/auto/rejected");
+            info!("#{} res: {:?}", i, res);
+            assert!(res.is_err());
+        }
+
+        let clt = connect(password, "clt/soupbintcp/tcp/auto").unwrap();
+        info!("clt: {}", clt);
+        assert!(clt.is_connected());
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+    }
+}
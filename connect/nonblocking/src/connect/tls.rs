@@ -0,0 +1,945 @@
+//! [CltSoupBinTcpTls] & [SvcSoupBinTcpTls] run the same [SoupBinTcpFramer], messengers & protocols as [CltSoupBinTcp] & [SvcSoupBinTcp] over a
+//! [rustls] session on top of a tcp socket.
+//!
+//! Certificates are configured using [CltSoupBinTcpTlsConfig] & [SvcSoupBinTcpTlsConfig], which support client authentication and selecting the
+//! server certificate based on the SNI server name sent by the client. The handshake completes before [ProtocolCore::on_connect] is issued and
+//! an acceptor rejects, rather than terminates on, connections which fail it.
+use crate::connect::stream::{
+    clt::{CltSoupBinTcpStream, CltSoupBinTcpStreamRecver, CltSoupBinTcpStreamRecverRef, CltSoupBinTcpStreamSender, CltSoupBinTcpStreamSenderRef},
+    svc::{SvcSoupBinTcpStream, SvcSoupBinTcpStreamSender, SvcSoupBinTcpStreamSenderRef},
+    AcceptedSplit, FrameStream, FrameStreamListener,
+};
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use rustls::{
+    server::{AllowAnyAuthenticatedClient, ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    Certificate, ClientConfig, ClientConnection, Connection, PrivateKey, RootCertStore, ServerConfig, ServerConnection, ServerName,
+};
+use std::{
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind, Read, Write},
+    net::{Shutdown, SocketAddr},
+    num::NonZeroUsize,
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub use rustls;
+
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub type CltSoupBinTcpTlsRecver<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamRecver<TlsStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpTlsSender<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamSender<TlsStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpTlsRecverRef<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamRecverRef<TlsStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpTlsSenderRef<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamSenderRef<TlsStream, P, C, MAX_MSG_SIZE>;
+pub type SvcSoupBinTcpTlsSender<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSender<TlsStream, P, C, MAX_MSG_SIZE>;
+pub type SvcSoupBinTcpTlsSenderRef<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSenderRef<TlsStream, P, C, MAX_MSG_SIZE>;
+
+/// Tls counterpart of [CltSoupBinTcp], see [CltSoupBinTcpStream] for available methods
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+/// use std::time::Duration;
+///
+/// let res = CltSoupBinTcpTls::<_, _, 128>::connect(
+///     "127.0.0.1:8080",
+///     &CltSoupBinTcpTlsConfig::new("localhost"),
+///     Duration::from_millis(100),
+///     Duration::from_millis(10),
+///     DevNullCallback::new_ref(),
+///     CltSoupBinTcpProtocolManual::<Nil, Nil>::default(),
+///     Some("doctest"),
+/// );
+/// assert!(res.is_err()); // no svc listening on addr
+/// ```
+pub type CltSoupBinTcpTls<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStream<TlsStream, P, C, MAX_MSG_SIZE>;
+
+/// Tls counterpart of [SvcSoupBinTcp], see [SvcSoupBinTcpStream] for available methods
+pub type SvcSoupBinTcpTls<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStream<TlsListener, P, C, MAX_MSG_SIZE>;
+
+/// Certificate chain, starting with the end entity certificate, and its private key presented to the peer
+#[derive(Clone)]
+pub struct TlsIdentity {
+    cert_chain: Vec<Certificate>,
+    key: PrivateKey,
+}
+impl TlsIdentity {
+    /// `cert_chain` & `key` are DER encoded
+    pub fn new(cert_chain: Vec<Certificate>, key: PrivateKey) -> Self {
+        Self { cert_chain, key }
+    }
+    /// Parses all certificates from `cert_chain_pem` and the first of pkcs8, rsa or ec private keys from `key_pem`
+    pub fn from_pem(cert_chain_pem: &[u8], key_pem: &[u8]) -> Result<Self, Error> {
+        let cert_chain = certs_from_pem(cert_chain_pem)?;
+        let key = rustls_pemfile::read_all(&mut &key_pem[..])?
+            .into_iter()
+            .find_map(|item| match item {
+                rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+                _ => None,
+            })
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "key_pem does not contain a private key"))?;
+        Ok(Self { cert_chain, key })
+    }
+    fn certified_key(&self) -> Result<Arc<CertifiedKey>, Error> {
+        let key = rustls::sign::any_supported_type(&self.key).map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Unsupported private key: {}", e)))?;
+        Ok(Arc::new(CertifiedKey::new(self.cert_chain.clone(), key)))
+    }
+}
+impl Debug for TlsIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(asserted_short_name!("TlsIdentity", Self))
+            .field("cert_chain_len", &self.cert_chain.len())
+            .field("key", &"********")
+            .finish()
+    }
+}
+
+fn certs_from_pem(pem: &[u8]) -> Result<Vec<Certificate>, Error> {
+    let certs = rustls_pemfile::certs(&mut &pem[..])?.into_iter().map(Certificate).collect::<Vec<_>>();
+    match certs.is_empty() {
+        true => Err(Error::new(ErrorKind::InvalidInput, "pem does not contain a certificate")),
+        false => Ok(certs),
+    }
+}
+fn add_roots_from_pem(roots: &mut RootCertStore, pem: &[u8]) -> Result<(), Error> {
+    for cert in certs_from_pem(pem)? {
+        roots.add(&cert).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    }
+    Ok(())
+}
+
+/// Tls settings of [CltSoupBinTcpTls]
+///
+/// * `server_name` - sent as SNI & verified against the server certificate, which must be issued by one of the root certificates
+/// * `identity` - presented only if the server requires client authentication
+#[derive(Debug, Clone)]
+pub struct CltSoupBinTcpTlsConfig {
+    server_name: String,
+    roots: RootCertStore,
+    identity: Option<TlsIdentity>,
+    handshake_timeout: Duration,
+}
+impl CltSoupBinTcpTlsConfig {
+    pub fn new(server_name: &str) -> Self {
+        Self {
+            server_name: server_name.to_owned(),
+            roots: RootCertStore::empty(),
+            identity: None,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+    pub fn with_root_certificate(mut self, cert: &Certificate) -> Result<Self, Error> {
+        self.roots.add(cert).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        Ok(self)
+    }
+    pub fn with_root_certificates_pem(mut self, pem: &[u8]) -> Result<Self, Error> {
+        add_roots_from_pem(&mut self.roots, pem)?;
+        Ok(self)
+    }
+    pub fn with_identity(mut self, identity: TlsIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+    fn build(&self) -> Result<(Arc<ClientConfig>, ServerName), Error> {
+        let builder = ClientConfig::builder().with_safe_defaults().with_root_certificates(self.roots.clone());
+        let config = match &self.identity {
+            Some(identity) => builder
+                .with_client_auth_cert(identity.cert_chain.clone(), identity.key.clone())
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
+            None => builder.with_no_client_auth(),
+        };
+        let server_name = ServerName::try_from(self.server_name.as_str()).map_err(|e| Error::new(ErrorKind::InvalidInput, format!("{} server_name: {}", e, self.server_name)))?;
+        Ok((Arc::new(config), server_name))
+    }
+}
+
+/// Tls settings of [SvcSoupBinTcpTls]
+///
+/// * `identity` - presented to clients which send no or an unknown SNI server name
+/// * `sni_identities` - presented to clients which send a matching SNI server name, ignoring case
+/// * `client_auth_roots` - if not empty, clients must authenticate with a certificate issued by one of them
+#[derive(Debug, Clone)]
+pub struct SvcSoupBinTcpTlsConfig {
+    identity: TlsIdentity,
+    sni_identities: Vec<(String, TlsIdentity)>,
+    client_auth_roots: RootCertStore,
+    handshake_timeout: Duration,
+}
+impl SvcSoupBinTcpTlsConfig {
+    pub fn new(identity: TlsIdentity) -> Self {
+        Self {
+            identity,
+            sni_identities: vec![],
+            client_auth_roots: RootCertStore::empty(),
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+    pub fn with_sni_identity(mut self, server_name: &str, identity: TlsIdentity) -> Self {
+        self.sni_identities.push((server_name.to_owned(), identity));
+        self
+    }
+    pub fn with_client_auth_root_certificate(mut self, cert: &Certificate) -> Result<Self, Error> {
+        self.client_auth_roots.add(cert).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        Ok(self)
+    }
+    pub fn with_client_auth_root_certificates_pem(mut self, pem: &[u8]) -> Result<Self, Error> {
+        add_roots_from_pem(&mut self.client_auth_roots, pem)?;
+        Ok(self)
+    }
+    /// Connections whose handshake does not complete with in `handshake_timeout` are rejected, see [TlsListener]
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+    fn build(&self) -> Result<Arc<ServerConfig>, Error> {
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match self.client_auth_roots.is_empty() {
+            true => builder.with_no_client_auth(),
+            false => builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(self.client_auth_roots.clone()).boxed()),
+        };
+        let mut sni_keys = Vec::with_capacity(self.sni_identities.len());
+        for (server_name, identity) in self.sni_identities.iter() {
+            sni_keys.push((server_name.clone(), identity.certified_key()?));
+        }
+        let resolver = SniCertResolver {
+            default_key: self.identity.certified_key()?,
+            sni_keys,
+        };
+        Ok(Arc::new(builder.with_cert_resolver(Arc::new(resolver))))
+    }
+}
+
+struct SniCertResolver {
+    default_key: Arc<CertifiedKey>,
+    sni_keys: Vec<(String, Arc<CertifiedKey>)>,
+}
+impl ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let sni_key = client_hello
+            .server_name()
+            .and_then(|server_name| self.sni_keys.iter().find(|(name, _)| name.eq_ignore_ascii_case(server_name)).map(|(_, key)| key));
+        Some(sni_key.unwrap_or(&self.default_key).clone())
+    }
+}
+
+#[derive(Debug)]
+struct TlsSession {
+    conn: Connection,
+    socket: mio::net::TcpStream,
+}
+
+/// Writes all pending tls records to the socket, returns [ErrorKind::WouldBlock] if the socket is unable to take all of them
+fn flush_tls(conn: &mut Connection, socket: &mut mio::net::TcpStream) -> Result<(), Error> {
+    while conn.wants_write() {
+        if conn.write_tls(socket)? == 0 {
+            return Err(Error::new(ErrorKind::WriteZero, "tls records not written"));
+        }
+    }
+    Ok(())
+}
+
+/// Advances the handshake as far as the non blocking socket allows, returns `true` once it is complete
+fn handshake_step(conn: &mut Connection, socket: &mut mio::net::TcpStream) -> Result<bool, Error> {
+    while conn.is_handshaking() || conn.wants_write() {
+        match conn.complete_io(socket) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Busy waits until the handshake is complete, the socket must be non blocking
+fn handshake(conn: &mut Connection, socket: &mut mio::net::TcpStream, timeout: Duration) -> Result<(), Error> {
+    let start = Instant::now();
+    while !handshake_step(conn, socket)? {
+        if start.elapsed() > timeout {
+            return Err(Error::new(ErrorKind::TimedOut, format!("tls handshake timeout: {:?}", timeout)));
+        }
+    }
+    Ok(())
+}
+
+/// One of the two `paired` handles, reader & writer, of a single [rustls] session & its tcp socket which are protected by a [spin::Mutex]
+///
+/// # Important
+/// Plaintext accepted by [Write::write] is written to the socket as far as it takes it without blocking, the rest stays in the session buffers.
+/// The socket is always registered for [mio::Interest::WRITABLE] too, so that once it drains the poll thread issues a [Read::read], which writes
+/// the remaining records before reading, rather then leaving them buffered until the next write. The lock is never held while waiting on the
+/// socket so that the `paired` handle is not blocked by a peer which stopped reading.
+#[derive(Debug)]
+pub struct TlsStream {
+    session: Arc<spin::Mutex<TlsSession>>,
+}
+impl TlsStream {
+    fn into_split(conn: Connection, socket: mio::net::TcpStream) -> (Self, Self) {
+        let session = Arc::new(spin::Mutex::new(TlsSession { conn, socket }));
+        (Self { session: session.clone() }, Self { session })
+    }
+}
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut session = self.session.lock();
+        let TlsSession { conn, socket } = &mut *session;
+        // records buffered by an earlier write, this is how they reach the socket once it raises a writable event
+        match flush_tls(conn, socket) {
+            Err(e) if e.kind() != ErrorKind::WouldBlock => return Err(e),
+            _ => {}
+        }
+        loop {
+            match conn.reader().read(buf) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                res => return res, // Ok(0) after close_notify, UnexpectedEof if socket closed without it
+            }
+            // no plaintext left, returns WouldBlock once the socket is drained
+            conn.read_tls(socket)?;
+            if let Err(e) = conn.process_new_packets() {
+                let _ = flush_tls(conn, socket); // best effort delivery of the alert
+                return Err(Error::new(ErrorKind::InvalidData, e));
+            }
+            // ex: key updates, whatever can't be written now is flushed by the next write
+            let _ = flush_tls(conn, socket);
+        }
+    }
+}
+impl Write for TlsStream {
+    /// Returns [ErrorKind::WouldBlock] without taking any plaintext while records pending from earlier calls can't be written to the socket,
+    /// records of this call which the socket can't take now are written once it becomes writable, see [TlsStream]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut session = self.session.lock();
+        let TlsSession { conn, socket } = &mut *session;
+        flush_tls(conn, socket)?;
+        let len = conn.writer().write(buf)?;
+        match flush_tls(conn, socket) {
+            Err(e) if e.kind() != ErrorKind::WouldBlock => Err(e),
+            _ => Ok(len),
+        }
+    }
+    /// Returns [ErrorKind::WouldBlock] if the socket is unable to take all pending records
+    fn flush(&mut self) -> Result<(), Error> {
+        let mut session = self.session.lock();
+        let TlsSession { conn, socket } = &mut *session;
+        flush_tls(conn, socket)
+    }
+}
+impl mio::event::Source for TlsStream {
+    /// Adds [mio::Interest::WRITABLE] to `interests` so that records buffered by [Write::write] are flushed, see [TlsStream]
+    fn register(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.session.lock().socket.register(registry, token, interests | mio::Interest::WRITABLE)
+    }
+    fn reregister(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.session.lock().socket.reregister(registry, token, interests | mio::Interest::WRITABLE)
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> Result<(), Error> {
+        self.session.lock().socket.deregister(registry)
+    }
+}
+impl FrameStream for TlsStream {
+    /// Sends close_notify unless only the read half is shut down
+    fn shutdown(&self, how: Shutdown) {
+        let mut session = self.session.lock();
+        let TlsSession { conn, socket } = &mut *session;
+        if how != Shutdown::Read {
+            conn.send_close_notify();
+            let _ = flush_tls(conn, socket);
+        }
+        let _ = socket.shutdown(how);
+    }
+}
+
+/// Server side of a tls handshake which has not completed yet
+#[derive(Debug)]
+struct PendingHandshake {
+    con_id: ConId,
+    conn: Connection,
+    socket: mio::net::TcpStream,
+    start: Instant,
+}
+
+#[derive(Debug, Default)]
+struct HandshakeDeadline {
+    deadline: Option<Instant>,
+    is_running: bool,
+}
+
+/// Raises an event under the [TlsListener]'s token once the earliest pending handshake expires, since a peer which went silent raises no events
+/// of its own. Events are raised by a helper thread sending a datagram to a loopback socket registered under that token.
+#[derive(Debug)]
+struct HandshakeTimer {
+    socket: mio::net::UdpSocket,
+    state: Arc<(Mutex<HandshakeDeadline>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+impl HandshakeTimer {
+    fn new(name: String) -> Result<Self, Error> {
+        let socket = mio::net::UdpSocket::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let sender = std::net::UdpSocket::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        sender.connect(socket.local_addr()?)?;
+        let state = Arc::new((Mutex::new(HandshakeDeadline { deadline: None, is_running: true }), Condvar::new()));
+        let thread = {
+            let state = state.clone();
+            thread::Builder::new().name(name).spawn(move || {
+                let (lock, cvar) = &*state;
+                let mut timer = lock.lock().expect("not poisoned");
+                while timer.is_running {
+                    timer = match timer.deadline {
+                        None => cvar.wait(timer).expect("not poisoned"),
+                        Some(deadline) if deadline > Instant::now() => cvar.wait_timeout(timer, deadline - Instant::now()).expect("not poisoned").0,
+                        Some(_) => {
+                            timer.deadline = None;
+                            let _ = sender.send(&[0]); // best effort, a full socket buffer already has an event pending
+                            timer
+                        }
+                    };
+                }
+            })?
+        };
+        Ok(Self { socket, state, thread: Some(thread) })
+    }
+    /// Replaces the deadline, `None` once there are no pending handshakes left
+    fn set(&self, deadline: Option<Instant>) {
+        let (lock, cvar) = &*self.state;
+        lock.lock().expect("not poisoned").deadline = deadline;
+        cvar.notify_one();
+    }
+    /// Discards datagrams of expired deadlines
+    fn drain(&self) {
+        let mut buf = [0_u8; 1];
+        while self.socket.recv(&mut buf).is_ok() {}
+    }
+}
+impl Drop for HandshakeTimer {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.state;
+        lock.lock().expect("not poisoned").is_running = false;
+        cvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A [mio::net::TcpListener] which completes the server side of the tls handshake for every accepted connection
+///
+/// # Important
+/// Handshakes never block the [static@crate::connect::stream::DEFAULT_POLL_HANDLER] thread, sockets of pending handshakes are registered under the
+/// same token as the listener so that each of their events advances all pending handshakes via [`FrameStreamListener::accept_split`].
+/// A timer raises an event under the same token once the earliest pending handshake expires, so that handshakes of silent peers are rejected on time.
+#[derive(Debug)]
+pub struct TlsListener {
+    addr: SocketAddr,
+    listener: mio::net::TcpListener,
+    config: Arc<ServerConfig>,
+    handshake_timeout: Duration,
+    registration: Option<(mio::Registry, mio::Token)>,
+    pending: spin::Mutex<Vec<PendingHandshake>>,
+    timer: HandshakeTimer,
+}
+impl TlsListener {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+    fn start_handshake(&self, con_id: &ConId, mut socket: mio::net::TcpStream, addr: SocketAddr) -> Result<PendingHandshake, Error> {
+        let mut con_id = con_id.clone();
+        con_id.set_peer(addr);
+        socket.set_nodelay(true)?;
+        if let Some((registry, token)) = &self.registration {
+            registry.register(&mut socket, *token, mio::Interest::READABLE | mio::Interest::WRITABLE)?;
+        }
+        let conn = Connection::from(ServerConnection::new(self.config.clone()).map_err(|e| Error::new(ErrorKind::Other, e))?);
+        Ok(PendingHandshake {
+            con_id,
+            conn,
+            socket,
+            start: Instant::now(),
+        })
+    }
+    fn finish_handshake(&self, mut pending: PendingHandshake) -> PendingHandshake {
+        if let Some((registry, _)) = &self.registration {
+            let _ = registry.deregister(&mut pending.socket); // stream registers it again under its own token
+        }
+        pending
+    }
+}
+impl FrameStreamListener for TlsListener {
+    type Stream = TlsStream;
+    /// Accepts all waiting connections and advances every pending handshake without blocking, returns the first one which completes.
+    /// Handshakes which fail or do not complete with in `handshake_timeout` are [AcceptStatus::Rejected].
+    fn accept_split(&self, con_id: &ConId) -> Result<AcceptStatus<AcceptedSplit<Self::Stream>>, Error> {
+        let mut pending = self.pending.lock();
+        self.timer.drain();
+        let res = self.advance(con_id, &mut pending);
+        // handshakes expire strictly after handshake_timeout
+        self.timer.set(pending.iter().map(|handshake| handshake.start + self.handshake_timeout + Duration::from_millis(1)).min());
+        res
+    }
+}
+impl TlsListener {
+    fn advance(&self, con_id: &ConId, pending: &mut Vec<PendingHandshake>) -> Result<AcceptStatus<AcceptedSplit<TlsStream>>, Error> {
+        loop {
+            match self.listener.accept() {
+                Ok((socket, addr)) => pending.push(self.start_handshake(con_id, socket, addr)?),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        let mut idx = 0;
+        while idx < pending.len() {
+            let handshake = &mut pending[idx];
+            let res = match handshake_step(&mut handshake.conn, &mut handshake.socket) {
+                Ok(false) if handshake.start.elapsed() > self.handshake_timeout => Err(Error::new(ErrorKind::TimedOut, format!("tls handshake timeout: {:?}", self.handshake_timeout))),
+                res => res,
+            };
+            match res {
+                Ok(false) => idx += 1,
+                Ok(true) => {
+                    let PendingHandshake { con_id, conn, socket, .. } = self.finish_handshake(pending.swap_remove(idx));
+                    let (reader, writer) = TlsStream::into_split(conn, socket);
+                    return Ok(AcceptStatus::Accepted((con_id, reader, writer)));
+                }
+                Err(e) => {
+                    let handshake = self.finish_handshake(pending.swap_remove(idx));
+                    if log_enabled!(log::Level::Warn) {
+                        warn!("{} Rejected connection due to tls handshake error: {}", handshake.con_id, e);
+                    }
+                    return Ok(AcceptStatus::Rejected);
+                }
+            }
+        }
+        Ok(AcceptStatus::WouldBlock)
+    }
+}
+impl mio::event::Source for TlsListener {
+    fn register(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.listener.register(registry, token, interests)?;
+        registry.register(&mut self.timer.socket, token, mio::Interest::READABLE)?;
+        for handshake in self.pending.get_mut().iter_mut() {
+            registry.register(&mut handshake.socket, token, mio::Interest::READABLE | mio::Interest::WRITABLE)?;
+        }
+        self.registration = Some((registry.try_clone()?, token));
+        Ok(())
+    }
+    fn reregister(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.listener.reregister(registry, token, interests)?;
+        registry.reregister(&mut self.timer.socket, token, mio::Interest::READABLE)?;
+        for handshake in self.pending.get_mut().iter_mut() {
+            registry.reregister(&mut handshake.socket, token, mio::Interest::READABLE | mio::Interest::WRITABLE)?;
+        }
+        self.registration = Some((registry.try_clone()?, token));
+        Ok(())
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> Result<(), Error> {
+        self.listener.deregister(registry)?;
+        let _ = registry.deregister(&mut self.timer.socket);
+        for handshake in self.pending.get_mut().iter_mut() {
+            let _ = registry.deregister(&mut handshake.socket);
+        }
+        self.registration = None;
+        Ok(())
+    }
+}
+impl Display for TlsListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tls:{}", self.addr)
+    }
+}
+
+impl<P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> CltSoupBinTcpTls<P, C, MAX_MSG_SIZE> {
+    /// Connects to a [SvcSoupBinTcpTls] listening on `addr`, retrying every `retry_after` until `timeout`, completes the tls handshake and then
+    /// issues [ProtocolCore::on_connect]. A failed handshake is not retried.
+    pub fn connect(addr: &str, config: &CltSoupBinTcpTlsConfig, timeout: Duration, retry_after: Duration, callback: Arc<C>, protocol: P, name: Option<&str>) -> Result<Self, Error> {
+        assert!(timeout > retry_after, "timeout: {:?}, retry_after: {:?}", timeout, retry_after);
+        let (tls_config, server_name) = config.build()?;
+        let mut con_id = ConId::clt(name, None, addr);
+        let now = Instant::now();
+        while now.elapsed() < timeout {
+            match std::net::TcpStream::connect(addr) {
+                Ok(stream) => {
+                    con_id.set_local(stream.local_addr()?);
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    let mut socket = mio::net::TcpStream::from_std(stream);
+                    let mut conn = Connection::from(ClientConnection::new(tls_config, server_name).map_err(|e| Error::new(ErrorKind::Other, e))?);
+                    handshake(&mut conn, &mut socket, config.handshake_timeout)
+                        .map_err(|e| Error::new(e.kind(), format!("{} tls handshake with server_name: {} failed: {}", con_id, config.server_name, e)))?;
+                    let (reader, writer) = TlsStream::into_split(conn, socket);
+                    return Self::from_split(reader, writer, con_id, callback, protocol, None);
+                }
+                Err(e) => {
+                    if log_enabled!(log::Level::Debug) {
+                        debug!("{} connection failed. e: {:?}", con_id, e);
+                    }
+                    sleep(retry_after);
+                }
+            }
+        }
+        Err(Error::new(ErrorKind::TimedOut, format!("{:?} connect timeout: {:?}", con_id, timeout)))
+    }
+}
+impl<P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SvcSoupBinTcpTls<P, C, MAX_MSG_SIZE> {
+    pub fn bind(addr: &str, config: &SvcSoupBinTcpTlsConfig, max_connections: NonZeroUsize, callback: Arc<C>, protocol: P, name: Option<&str>) -> Result<Self, Error> {
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let listener = TlsListener {
+            addr: local_addr,
+            listener: mio::net::TcpListener::from_std(listener),
+            config: config.build()?,
+            handshake_timeout: config.handshake_timeout,
+            registration: None,
+            pending: spin::Mutex::new(vec![]),
+            timer: HandshakeTimer::new(format!("soupbintcp-tls-handshake-{}", local_addr))?,
+        };
+        Ok(Self::from_listener(listener, ConId::svc(name, addr, None), max_connections, callback, protocol))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{num::NonZeroUsize, time::Duration};
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+    type UnitMsg = UniSoupBinTcpMsg<SamplePayload, SamplePayload>;
+
+    /// self signed certificate authority generated at test time
+    fn new_ca(name: &str) -> rcgen::Certificate {
+        let mut params = rcgen::CertificateParams::new(vec![]);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        params.distinguished_name.push(rcgen::DnType::CommonName, name);
+        rcgen::Certificate::from_params(params).unwrap()
+    }
+    fn new_identity(ca: &rcgen::Certificate, server_name: &str) -> TlsIdentity {
+        let cert = rcgen::Certificate::from_params(rcgen::CertificateParams::new(vec![server_name.to_owned()])).unwrap();
+        TlsIdentity::from_pem(cert.serialize_pem_with_signer(ca).unwrap().as_bytes(), cert.serialize_private_key_pem().as_bytes()).unwrap()
+    }
+    fn clt_config(ca: &rcgen::Certificate, server_name: &str) -> CltSoupBinTcpTlsConfig {
+        CltSoupBinTcpTlsConfig::new(server_name).with_root_certificates_pem(ca.serialize_pem().unwrap().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_config_invalid() {
+        setup::log::configure();
+        let res = TlsIdentity::from_pem(b"not a pem", b"not a pem");
+        info!("res: {:?}", res);
+        assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+        let res = CltSoupBinTcpTlsConfig::new("localhost").with_root_certificates_pem(b"not a pem");
+        info!("res: {:?}", res);
+        assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+        let res = CltSoupBinTcpTls::<_, _, 128>::connect(
+            setup::net::rand_avail_addr_port(),
+            &CltSoupBinTcpTlsConfig::new("not a dns name"),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            CltSoupBinTcpProtocolManual::<Nil, Nil>::default(),
+            Some("soupbintcp/unittest"),
+        );
+        info!("res: {:?}", res);
+        assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_protocol_manual() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let clt_count = CounterCallback::new_ref();
+        let svc_count = CounterCallback::new_ref();
+        let clt_clbk = ChainCallback::new_ref(vec![LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug), clt_count.clone()]);
+        let svc_clbk = ChainCallback::new_ref(vec![LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug), svc_count.clone()]);
+        let io_timeout = setup::net::default_io_timeout();
+        let addr = setup::net::rand_avail_addr_port();
+        let ca = new_ca("soupbintcp unittest ca");
+
+        let protocol = SvcSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+        let config = SvcSoupBinTcpTlsConfig::new(new_identity(&ca, "localhost"));
+        let mut svc_sender = SvcSoupBinTcpTls::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, &config, NonZeroUsize::new(1).unwrap(), svc_clbk, protocol, Some("svc/soupbintcp/tls/manual"))
+            .unwrap()
+            .into_sender_with_spawned_recver();
+
+        let protocol = CltSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+        let mut clt_sender = CltSoupBinTcpTls::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            &clt_config(&ca, "localhost"),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            clt_clbk,
+            protocol,
+            Some("clt/soupbintcp/tls/manual"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver();
+        info!("clt_sender: {}", clt_sender);
+
+        assert!(clt_sender.is_connected());
+        assert!(svc_sender.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        const N: usize = 10;
+        for i in 1..=N {
+            clt_sender
+                .send_busywait_timeout(&mut Debug::new(format!("Msg  #{}", i).as_bytes()).into(), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        assert_eq!(svc_count.recv_count_busywait_timeout(N, setup::net::find_timeout()), N);
+        assert_eq!(svc_count.sent_count(), 0);
+
+        for i in 1..=N {
+            svc_sender
+                .send_busywait_timeout(&mut Debug::new(format!("Msg  #{}", i).as_bytes()).into(), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        assert_eq!(clt_count.recv_count_busywait_timeout(N, setup::net::find_timeout()), N);
+        assert_eq!(clt_count.sent_count(), N);
+        info!("clt_count: {}, svc_count: {}", clt_count, svc_count);
+    }
+
+    #[test]
+    fn test_pending_handshake_does_not_block_acceptor() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let ca = new_ca("soupbintcp unittest ca");
+
+        let config = SvcSoupBinTcpTlsConfig::new(new_identity(&ca, "localhost")).with_handshake_timeout(Duration::from_secs(10));
+        let mut svc_sender = SvcSoupBinTcpTls::<_, _, 128>::bind(
+            addr,
+            &config,
+            NonZeroUsize::new(2).unwrap(),
+            LoggerCallback::new_ref(),
+            SvcSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("svc/soupbintcp/tls/pending"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver();
+
+        // plain tcp client never starts the handshake
+        let stalled = std::net::TcpStream::connect(addr).unwrap();
+
+        let clt = CltSoupBinTcpTls::<_, _, 128>::connect(
+            addr,
+            &clt_config(&ca, "localhost").with_handshake_timeout(Duration::from_millis(500)),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            LoggerCallback::new_ref(),
+            CltSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("clt/soupbintcp/tls/pending"),
+        )
+        .unwrap();
+        info!("clt: {}", clt);
+        assert!(svc_sender.all_connected_busywait_timeout(setup::net::find_timeout()));
+        drop(stalled);
+    }
+
+    #[test]
+    fn test_silent_handshake_times_out() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let ca = new_ca("soupbintcp unittest ca");
+
+        let config = SvcSoupBinTcpTlsConfig::new(new_identity(&ca, "localhost")).with_handshake_timeout(Duration::from_millis(100));
+        let _svc_sender = SvcSoupBinTcpTls::<_, _, 128>::bind(
+            addr,
+            &config,
+            NonZeroUsize::new(1).unwrap(),
+            LoggerCallback::new_ref(),
+            SvcSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("svc/soupbintcp/tls/silent"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver();
+
+        // plain tcp client never starts the handshake and no other connection raises a listener event
+        let mut stalled = std::net::TcpStream::connect(addr).unwrap();
+        stalled.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let start = std::time::Instant::now();
+        let res = std::io::Read::read(&mut stalled, &mut [0_u8; 1]);
+        info!("res: {:?}, elapsed: {:?}", res, start.elapsed());
+        // the svc closed the socket, rather then the read timing out
+        assert!(matches!(res, Ok(0)) || matches!(res, Err(ref e) if e.kind() == std::io::ErrorKind::ConnectionReset));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_sni() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let default_ca = new_ca("soupbintcp unittest default ca");
+        let sni_ca = new_ca("soupbintcp unittest sni ca");
+
+        let config = SvcSoupBinTcpTlsConfig::new(new_identity(&default_ca, "localhost")).with_sni_identity("sni.localhost", new_identity(&sni_ca, "sni.localhost"));
+        let svc = SvcSoupBinTcpTls::<_, _, 128>::bind(
+            addr,
+            &config,
+            NonZeroUsize::new(2).unwrap(),
+            LoggerCallback::new_ref(),
+            SvcSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("svc/soupbintcp/tls/sni"),
+        )
+        .unwrap();
+        info!("svc: {}", svc);
+
+        let connect = |config: &CltSoupBinTcpTlsConfig| {
+            CltSoupBinTcpTls::<_, _, 128>::connect(
+                addr,
+                config,
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                LoggerCallback::new_ref(),
+                CltSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+                Some("clt/soupbintcp/tls/sni"),
+            )
+        };
+
+        // server_name not matching any sni identity is presented the default certificate which is not trusted
+        std::thread::scope(|s| {
+            s.spawn(|| assert!(svc.accept_busywait_timeout(setup::net::default_connect_timeout()).unwrap().is_rejected()));
+            let res = connect(&clt_config(&sni_ca, "localhost"));
+            info!("res: {:?}", res);
+            assert!(res.is_err());
+        });
+
+        // matching server_name is presented the sni certificate
+        let (clt, svc_clt) = std::thread::scope(|s| {
+            let svc_clt = s.spawn(|| svc.accept_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_accepted());
+            (connect(&clt_config(&sni_ca, "sni.localhost")).unwrap(), svc_clt.join().unwrap())
+        });
+        info!("clt: {}", clt);
+        info!("svc_clt: {}", svc_clt);
+        drop(svc_clt);
+
+        // default certificate is presented when server_name is not matched
+        let (mut clt, mut svc_clt) = std::thread::scope(|s| {
+            let svc_clt = s.spawn(|| svc.accept_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_accepted());
+            (connect(&clt_config(&default_ca, "localhost")).unwrap(), svc_clt.join().unwrap())
+        });
+
+        let mut clt_msg = CltSoupBinTcpMsg::LoginRequest(LoginRequest::default());
+        clt.send_busywait_timeout(&mut clt_msg, setup::net::default_connect_timeout()).unwrap().unwrap_completed();
+        let svc_msg = svc_clt.recv_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_completed_some();
+        assert_eq!(clt_msg, svc_msg);
+
+        drop(svc_clt);
+        assert_eq!(clt.recv_busywait_timeout(setup::net::default_connect_timeout()).unwrap(), RecvStatus::Completed(None));
+    }
+
+    #[test]
+    fn test_protocol_auto_client_auth() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let max_hbeat_interval = Duration::from_secs_f64(2.5);
+        let svc_ca = new_ca("soupbintcp unittest svc ca");
+        let clt_ca = new_ca("soupbintcp unittest clt ca");
+
+        let clt_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_count = CounterCallback::new_ref();
+        let svc_count = CounterCallback::new_ref();
+        let clt_clbk = ChainCallback::new_ref(vec![
+            StoreCallback::new_ref(clt_store.clone()),
+            clt_count.clone(),
+            LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info),
+        ]);
+        let svc_clbk = ChainCallback::new_ref(vec![svc_count.clone(), LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info)]);
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, io_timeout, max_hbeat_interval);
+        let config = SvcSoupBinTcpTlsConfig::new(new_identity(&svc_ca, "localhost"))
+            .with_client_auth_root_certificates_pem(clt_ca.serialize_pem().unwrap().as_bytes())
+            .unwrap();
+        let mut svc = SvcSoupBinTcpTls::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, &config, NonZeroUsize::new(1).unwrap(), svc_clbk, protocol, Some("svc/soupbintcp/tls/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let connect = |config: &CltSoupBinTcpTlsConfig, name: &str, callback| {
+            let protocol = CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, 0_usize.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+            CltSoupBinTcpTls::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+                addr,
+                config,
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                callback,
+                protocol,
+                Some(name),
+            )
+            .map(|clt| clt.into_sender_with_spawned_recver_ref())
+        };
+
+        // clients without a certificate or with one not issued by the client auth root are rejected and do not terminate the acceptor
+        let res = connect(
+            &clt_config(&svc_ca, "localhost"),
+            "clt/soupbintcp/tls/auto/anonymous",
+            ChainCallback::new_ref(vec![LoggerCallback::new_ref()]),
+        );
+        info!("res: {:?}", res);
+        assert!(res.is_err());
+        let res = connect(
+            &clt_config(&svc_ca, "localhost").with_identity(new_identity(&svc_ca, "clt.localhost")),
+            "clt/soupbintcp/tls/auto/untrusted",
+            ChainCallback::new_ref(vec![LoggerCallback::new_ref()]),
+        );
+        info!("res: {:?}", res);
+        assert!(res.is_err());
+
+        let clt = connect(
+            &clt_config(&svc_ca, "localhost").with_identity(new_identity(&clt_ca, "clt.localhost")),
+            "clt/soupbintcp/tls/auto",
+            clt_clbk,
+        )
+        .unwrap();
+        info!("clt: {}", clt);
+        assert!(clt.is_connected());
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        let found = clt_store.find_recv(
+            "clt/soupbintcp/tls/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::HBeat(_))),
+            Some(max_hbeat_interval), // hbeats are only sent once connection is idle
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+
+        const N_SEQUENCED_PAYLOADS: usize = 10;
+        for i in 1..=N_SEQUENCED_PAYLOADS {
+            let payload = SamplePayload::new(format!("#{} SPayload", i).as_bytes().into());
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload), io_timeout).unwrap().unwrap_completed();
+        }
+        let found = clt_store.find_recv(
+            "clt/soupbintcp/tls/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::SPayload(SPayload { payload, .. })) if payload == &SamplePayload::new(format!("#{} SPayload", N_SEQUENCED_PAYLOADS).as_bytes().into())),
+            setup::net::optional_find_timeout(),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        info!("clt_count: {}, svc_count: {}", clt_count, svc_count);
+
+        drop(svc);
+
+        let found = clt_store.find_recv(
+            "clt/soupbintcp/tls/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::EndOfSession(_))),
+            setup::net::optional_find_timeout(),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        assert!(!clt.is_connected());
+    }
+}
@@ -0,0 +1,530 @@
+//! [CltSoupBinTcpUds] & [SvcSoupBinTcpUds] run the same [SoupBinTcpFramer], messengers & protocols as [CltSoupBinTcp] & [SvcSoupBinTcp] over unix domain
+//! stream sockets, avoiding tcp loopback overhead between co-located processes.
+//!
+//! [ConId] requires a socket address, hence the socket path is only part of the [std::fmt::Display] output and connections are told apart by a stand in
+//! address counting connections instead, ex: the second connection accepted by a listener has peer address `[::2]:0`.
+use crate::connect::stream::{
+    clt::{CltSoupBinTcpStream, CltSoupBinTcpStreamRecver, CltSoupBinTcpStreamRecverRef, CltSoupBinTcpStreamSender, CltSoupBinTcpStreamSenderRef},
+    svc::{SvcSoupBinTcpStream, SvcSoupBinTcpStreamSender, SvcSoupBinTcpStreamSenderRef},
+    AcceptedSplit, FrameStream, FrameStreamListener,
+};
+use crate::prelude::*;
+use log::{debug, log_enabled};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    net::{Ipv6Addr, Shutdown, SocketAddr},
+    num::NonZeroUsize,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const UNSPECIFIED_ADDR: &str = "0.0.0.0:0";
+
+/// Stands in for the missing socket address of the `count`-th connection, see [module level](self) docs
+fn uds_addr(count: usize) -> SocketAddr {
+    SocketAddr::from((Ipv6Addr::from(count as u128), 0))
+}
+
+pub type CltSoupBinTcpUdsRecver<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamRecver<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpUdsSender<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamSender<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpUdsRecverRef<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamRecverRef<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+pub type CltSoupBinTcpUdsSenderRef<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStreamSenderRef<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+pub type SvcSoupBinTcpUdsSender<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSender<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+pub type SvcSoupBinTcpUdsSenderRef<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStreamSenderRef<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+
+/// Unix domain socket counterpart of [CltSoupBinTcp], see [CltSoupBinTcpStream] for available methods
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+/// use std::time::Duration;
+///
+/// let path = std::env::temp_dir().join("soupbintcp-doctest-clt.sock");
+/// let res = CltSoupBinTcpUds::<_, _, 128>::connect(
+///     &path,
+///     Duration::from_millis(100),
+///     Duration::from_millis(10),
+///     DevNullCallback::new_ref(),
+///     CltSoupBinTcpProtocolManual::<Nil, Nil>::default(),
+///     Some("doctest"),
+/// );
+/// assert!(res.is_err()); // no svc bound to path
+/// ```
+pub type CltSoupBinTcpUds<P, C, const MAX_MSG_SIZE: usize> = CltSoupBinTcpStream<mio::net::UnixStream, P, C, MAX_MSG_SIZE>;
+
+/// Unix domain socket counterpart of [SvcSoupBinTcp], see [SvcSoupBinTcpStream] for available methods. The socket file is removed when the acceptor is dropped.
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+/// use std::num::NonZeroUsize;
+///
+/// let path = std::env::temp_dir().join(format!("soupbintcp-doctest-{}-svc.sock", std::process::id()));
+/// let svc = SvcSoupBinTcpUds::<_, _, 128>::bind(
+///     &path,
+///     NonZeroUsize::new(1).unwrap(),
+///     DevNullCallback::new_ref(),
+///     SvcSoupBinTcpProtocolManual::<Nil, Nil>::default(),
+///     Some("doctest"),
+/// )
+/// .unwrap();
+///
+/// assert!(svc.accept().unwrap().is_wouldblock());
+/// drop(svc);
+/// assert!(!path.exists());
+/// ```
+pub type SvcSoupBinTcpUds<P, C, const MAX_MSG_SIZE: usize> = SvcSoupBinTcpStream<UdsListener, P, C, MAX_MSG_SIZE>;
+
+impl FrameStream for mio::net::UnixStream {
+    fn shutdown(&self, how: Shutdown) {
+        let _ = mio::net::UnixStream::shutdown(self, how);
+    }
+}
+
+/// Creates a `paired` reader & writer sharing the same socket, both non blocking
+fn into_split_uds(stream: UnixStream) -> Result<(mio::net::UnixStream, mio::net::UnixStream), Error> {
+    stream.set_nonblocking(true)?;
+    let reader = stream.try_clone()?;
+    Ok((mio::net::UnixStream::from_std(reader), mio::net::UnixStream::from_std(stream)))
+}
+
+/// A non blocking [UnixListener] bound to a file system `path` which is removed on drop, accepts [UnixStream]s so that they can be split
+/// while a [mio::net::UnixListener] cloned from it is registered with [mio::Poll]
+#[derive(Debug)]
+pub struct UdsListener {
+    path: PathBuf,
+    accepted: AtomicUsize,
+    listener: UnixListener,
+    source: mio::net::UnixListener,
+}
+impl UdsListener {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+impl FrameStreamListener for UdsListener {
+    type Stream = mio::net::UnixStream;
+    /// Unix domain sockets have no peer address, hence the accepted connection's peer is set to a stand in address unique to this listener
+    fn accept_split(&self, con_id: &ConId) -> Result<AcceptStatus<AcceptedSplit<Self::Stream>>, Error> {
+        match self.listener.accept() {
+            Ok((stream, _addr)) => {
+                let (reader, writer) = into_split_uds(stream)?;
+                let mut con_id = con_id.clone();
+                con_id.set_peer(uds_addr(self.accepted.fetch_add(1, Ordering::Relaxed) + 1));
+                Ok(AcceptStatus::Accepted((con_id, reader, writer)))
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(AcceptStatus::WouldBlock),
+            Err(e) => Err(e),
+        }
+    }
+}
+impl mio::event::Source for UdsListener {
+    fn register(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.source.register(registry, token, interests)
+    }
+    fn reregister(&mut self, registry: &mio::Registry, token: mio::Token, interests: mio::Interest) -> Result<(), Error> {
+        self.source.reregister(registry, token, interests)
+    }
+    fn deregister(&mut self, registry: &mio::Registry) -> Result<(), Error> {
+        self.source.deregister(registry)
+    }
+}
+impl Display for UdsListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "path:{}", self.path.display())
+    }
+}
+impl Drop for UdsListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl<P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> CltSoupBinTcpUds<P, C, MAX_MSG_SIZE> {
+    /// Connects to a [SvcSoupBinTcpUds] bound to `path`, retrying every `retry_after` until `timeout` and then issues [ProtocolCore::on_connect]
+    pub fn connect(path: impl AsRef<Path>, timeout: Duration, retry_after: Duration, callback: Arc<C>, protocol: P, name: Option<&str>) -> Result<Self, Error> {
+        assert!(timeout > retry_after, "timeout: {:?}, retry_after: {:?}", timeout, retry_after);
+        static CONNECTED: AtomicUsize = AtomicUsize::new(0);
+        let path = path.as_ref();
+        let mut con_id = ConId::clt(name, None, UNSPECIFIED_ADDR);
+        con_id.set_local(uds_addr(CONNECTED.fetch_add(1, Ordering::Relaxed) + 1));
+        let now = Instant::now();
+        while now.elapsed() < timeout {
+            match UnixStream::connect(path) {
+                Ok(stream) => {
+                    let (reader, writer) = into_split_uds(stream)?;
+                    return Self::from_split(reader, writer, con_id, callback, protocol, None);
+                }
+                Err(e) => {
+                    if log_enabled!(log::Level::Debug) {
+                        debug!("{} connection to {} failed. e: {:?}", con_id, path.display(), e);
+                    }
+                    sleep(retry_after);
+                }
+            }
+        }
+        Err(Error::new(ErrorKind::TimedOut, format!("{:?} connect to {} timeout: {:?}", con_id, path.display(), timeout)))
+    }
+}
+impl<P: Protocol, C: CallbackRecvSend<P>, const MAX_MSG_SIZE: usize> SvcSoupBinTcpUds<P, C, MAX_MSG_SIZE> {
+    /// Binds to a given `path` which must not exist, returns [ErrorKind::AddrInUse] otherwise
+    pub fn bind(path: impl AsRef<Path>, max_connections: NonZeroUsize, callback: Arc<C>, protocol: P, name: Option<&str>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        let listener = UdsListener {
+            path: path.to_path_buf(),
+            accepted: AtomicUsize::new(0),
+            source: mio::net::UnixListener::from_std(listener.try_clone()?),
+            listener,
+        };
+        Ok(Self::from_listener(listener, ConId::svc(name, UNSPECIFIED_ADDR, None), max_connections, callback, protocol))
+    }
+    pub fn path(&self) -> &Path {
+        self.listener().path()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{
+        num::NonZeroUsize,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+    type UnitMsg = UniSoupBinTcpMsg<SamplePayload, SamplePayload>;
+
+    /// unique per test socket path which does not exist
+    fn rand_avail_path() -> PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("soupbintcp-unittest-{}-{}.sock", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed)));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_clt_not_connected() {
+        setup::log::configure();
+
+        let res = CltSoupBinTcpUds::<_, _, 128>::connect(
+            rand_avail_path(),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            CltSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("soupbintcp/unittest"),
+        );
+        info!("{:?} not connected", res);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_clt_svc_connected() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let path = rand_avail_path();
+
+        let svc = SvcSoupBinTcpUds::<_, _, 128>::bind(
+            &path,
+            NonZeroUsize::new(1).unwrap(),
+            LoggerCallback::new_ref(),
+            SvcSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("soupbintcp/unittest"),
+        )
+        .unwrap();
+        info!("svc: {}", svc);
+
+        let mut clt = CltSoupBinTcpUds::<_, _, 128>::connect(
+            &path,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            LoggerCallback::new_ref(),
+            CltSoupBinTcpProtocolIsConnected::<Nil, Nil>::default(),
+            Some("soupbintcp/unittest"),
+        )
+        .unwrap();
+        info!("clt: {}", clt);
+
+        let mut svc_clt = svc.accept_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_accepted();
+        info!("svc_clt: {}", svc_clt);
+
+        let mut clt_msg = CltSoupBinTcpMsg::LoginRequest(LoginRequest::default());
+        clt.send_busywait_timeout(&mut clt_msg, setup::net::default_connect_timeout()).unwrap().unwrap_completed();
+        let svc_msg = svc_clt.recv_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_completed_some();
+        assert_eq!(clt_msg, svc_msg);
+
+        let mut svc_msg = SvcSoupBinTcpMsg::LoginAccepted(LoginAccepted::default());
+        svc_clt.send_busywait_timeout(&mut svc_msg, setup::net::default_connect_timeout()).unwrap().unwrap_completed();
+        let clt_msg = clt.recv_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_completed_some();
+        assert_eq!(svc_msg, clt_msg);
+
+        drop(svc_clt);
+        assert_eq!(clt.recv_busywait_timeout(setup::net::default_connect_timeout()).unwrap(), RecvStatus::Completed(None));
+    }
+
+    #[test]
+    fn test_protocol_manual() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let clt_count = CounterCallback::new_ref();
+        let svc_count = CounterCallback::new_ref();
+        let clt_clbk = ChainCallback::new_ref(vec![LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug), clt_count.clone()]);
+        let svc_clbk = ChainCallback::new_ref(vec![LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug), svc_count.clone()]);
+        let io_timeout = setup::net::default_io_timeout();
+        let path = rand_avail_path();
+
+        let protocol = SvcSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+        let mut svc_sender = SvcSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(&path, NonZeroUsize::new(1).unwrap(), svc_clbk, protocol, Some("svc/soupbintcp/uds/manual"))
+            .unwrap()
+            .into_sender_with_spawned_recver();
+
+        let protocol = CltSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+        let mut clt_sender = CltSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            &path,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            clt_clbk,
+            protocol,
+            Some("clt/soupbintcp/uds/manual"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver();
+        info!("clt_sender: {}", clt_sender);
+
+        assert!(clt_sender.is_connected());
+        assert!(svc_sender.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        const N: usize = 10;
+        for i in 1..=N {
+            clt_sender
+                .send_busywait_timeout(&mut Debug::new(format!("Msg  #{}", i).as_bytes()).into(), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        assert_eq!(svc_count.recv_count_busywait_timeout(N, setup::net::find_timeout()), N);
+        assert_eq!(svc_count.sent_count(), 0);
+
+        for i in 1..=N {
+            svc_sender
+                .send_busywait_timeout(&mut Debug::new(format!("Msg  #{}", i).as_bytes()).into(), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        assert_eq!(clt_count.recv_count_busywait_timeout(N, setup::net::find_timeout()), N);
+        assert_eq!(clt_count.sent_count(), N);
+        info!("clt_count: {}, svc_count: {}", clt_count, svc_count);
+    }
+
+    #[test]
+    fn test_con_id_unique() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let io_timeout = setup::net::default_io_timeout();
+        let path = rand_avail_path();
+
+        let svc_metrics = SoupBinTcpMetricsCallback::<SvcSoupBinTcpProtocolManual<SamplePayload, SamplePayload>>::new_ref();
+        let protocol = SvcSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+        let _svc = SvcSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(&path, NonZeroUsize::new(2).unwrap(), svc_metrics.clone(), protocol, Some("svc/soupbintcp/uds/con_id"))
+            .unwrap()
+            .into_sender_with_spawned_recver();
+
+        let connect = || {
+            let protocol = CltSoupBinTcpProtocolManual::<SamplePayload, SamplePayload>::default();
+            CltSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+                &path,
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                LoggerCallback::new_ref(),
+                protocol,
+                Some("clt/soupbintcp/uds/con_id"),
+            )
+            .unwrap()
+            .into_sender_with_spawned_recver()
+        };
+        // both clients stay connected concurrently
+        let mut clt_1 = connect();
+        let mut clt_2 = connect();
+        info!("clt_1: {}, clt_2: {}", clt_1, clt_2);
+        for clt in [&mut clt_1, &mut clt_2] {
+            clt.send_busywait_timeout(&mut Debug::new(b"hello".as_slice()).into(), io_timeout).unwrap().unwrap_completed();
+        }
+
+        let start = Instant::now();
+        let snapshot = loop {
+            let snapshot = svc_metrics.snapshot();
+            if snapshot.connections.iter().filter(|con| con.recv.messages == 1).count() == 2 || start.elapsed() > setup::net::find_timeout() {
+                break snapshot;
+            }
+            sleep(Duration::from_millis(10));
+        };
+        info!("snapshot: {:?}", snapshot);
+        assert_eq!(snapshot.connections.len(), 2);
+        assert_ne!(snapshot.connections[0].con_id, snapshot.connections[1].con_id);
+        assert!(snapshot.connections.iter().all(|con| con.recv.messages == 1));
+    }
+
+    #[test]
+    fn test_protocol_is_connected() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let clt_count = CounterCallback::new_ref();
+        let svc_count = CounterCallback::new_ref();
+        let clt_clbk = ChainCallback::new_ref(vec![clt_count.clone(), LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug)]);
+        let svc_clbk = ChainCallback::new_ref(vec![svc_count.clone(), LoggerCallback::with_level_ref(log::Level::Info, log::Level::Debug)]);
+        let path = rand_avail_path();
+
+        let protocol = SvcSoupBinTcpProtocolIsConnected::<SamplePayload, SamplePayload>::default();
+        let mut svc_sender = SvcSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(&path, NonZeroUsize::new(1).unwrap(), svc_clbk, protocol, Some("svc/soupbintcp/uds/is_connected"))
+            .unwrap()
+            .into_sender_with_spawned_recver();
+
+        let protocol = CltSoupBinTcpProtocolIsConnected::<SamplePayload, SamplePayload>::default();
+        let mut clt_sender = CltSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            &path,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            clt_clbk,
+            protocol,
+            Some("clt/soupbintcp/uds/is_connected"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver();
+
+        assert_eq!(clt_count.sent_count(), 0);
+        assert!(!clt_sender.is_connected());
+
+        let timeout = setup::net::default_connect_timeout();
+        clt_sender.send_busywait_timeout(&mut LoginRequest::default().into(), timeout).unwrap().unwrap_completed();
+        assert_eq!(svc_count.recv_count_busywait_timeout(1, setup::net::find_timeout()), 1);
+        svc_sender.send_busywait_timeout(&mut LoginAccepted::default().into(), timeout).unwrap().unwrap_completed();
+
+        assert!(clt_sender.is_connected_busywait_timeout(timeout));
+        assert!(svc_sender.is_next_connected());
+
+        assert_eq!(clt_count.sent_count(), 1);
+        assert_eq!(svc_count.sent_count(), 1);
+    }
+
+    #[test]
+    fn test_protocol_auto() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let path = rand_avail_path();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"favsession".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let max_hbeat_interval = Duration::from_secs_f64(2.5);
+
+        let clt_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_count = CounterCallback::new_ref();
+        let svc_count = CounterCallback::new_ref();
+        let clt_clbk = ChainCallback::new_ref(vec![
+            StoreCallback::new_ref(clt_store.clone()),
+            clt_count.clone(),
+            LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info),
+        ]);
+        let svc_clbk = ChainCallback::new_ref(vec![svc_count.clone(), LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info)]);
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, io_timeout, max_hbeat_interval);
+        let mut svc = SvcSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(&path, NonZeroUsize::new(1).unwrap(), svc_clbk, protocol, Some("svc/soupbintcp/uds/auto"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let connect = |password: Password, sequence_number: usize, name: &str, callback| {
+            let protocol = CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_id, sequence_number.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+            CltSoupBinTcpUds::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(&path, setup::net::default_connect_timeout(), setup::net::default_connect_retry_after(), callback, protocol, Some(name))
+                .map(|clt| clt.into_sender_with_spawned_recver_ref())
+        };
+
+        // rejected login does not terminate the acceptor
+        let res = connect(
+            b"wrong".as_slice().into(),
+            0,
+            "clt/soupbintcp/uds/auto/rejected",
+            ChainCallback::new_ref(vec![LoggerCallback::new_ref()]),
+        );
+        info!("res: {:?}", res);
+        assert!(res.is_err());
+
+        let clt = connect(password, 0, "clt/soupbintcp/uds/auto", clt_clbk).unwrap();
+        info!("clt: {}", clt);
+        assert!(clt.is_connected());
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // login accepted & login request followed by at least one hbeat in each direction
+        let found = clt_store.find_recv(
+            "clt/soupbintcp/uds/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::HBeat(_))),
+            Some(max_hbeat_interval), // hbeats are only sent once connection is idle
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        const HAND_SHAKE_COUNT: usize = 2;
+        assert!(clt_count.recv_count_busywait_timeout(HAND_SHAKE_COUNT, setup::net::find_timeout()) >= HAND_SHAKE_COUNT);
+        assert!(svc_count.recv_count_busywait_timeout(HAND_SHAKE_COUNT, setup::net::find_timeout()) >= HAND_SHAKE_COUNT);
+        assert!(clt.is_connected());
+        assert!(svc.all_connected());
+
+        const N_SEQUENCED_PAYLOADS: usize = 10;
+        const N_UN_SEQUENCED_PAYLOADS: usize = 10;
+        for _ in 1..=N_UN_SEQUENCED_PAYLOADS {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::udata(SamplePayload::default()), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        for i in 1..=N_SEQUENCED_PAYLOADS {
+            let payload = SamplePayload::new(format!("#{} SPayload", i).as_bytes().into());
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload), io_timeout).unwrap().unwrap_completed();
+        }
+        let expected_recv = HAND_SHAKE_COUNT + N_SEQUENCED_PAYLOADS + N_UN_SEQUENCED_PAYLOADS;
+        assert!(clt_count.recv_count_busywait_timeout(expected_recv, setup::net::find_timeout()) >= expected_recv);
+        info!("clt_count: {}, svc_count: {}", clt_count, svc_count);
+
+        drop(clt);
+
+        // reconnect gets sequenced payloads replayed starting at requested sequence number
+        let clt_store_reconnect = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let clt_count_reconnect = CounterCallback::new_ref();
+        let clt_clbk_reconnect = ChainCallback::new_ref(vec![
+            StoreCallback::new_ref(clt_store_reconnect.clone()),
+            clt_count_reconnect.clone(),
+            LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info),
+        ]);
+        let reconnect_sequence_number = 6_usize;
+        let clt_reconnect = connect(password, reconnect_sequence_number, "clt_reconnect/soupbintcp/uds/auto", clt_clbk_reconnect).unwrap();
+        assert!(clt_reconnect.is_connected());
+        let expected_recv = 1 + N_SEQUENCED_PAYLOADS - reconnect_sequence_number + 1; // login accepted + replayed
+        assert!(clt_count_reconnect.recv_count_busywait_timeout(expected_recv, setup::net::find_timeout()) >= expected_recv);
+
+        let found = clt_store_reconnect.find_recv(
+            "clt_reconnect/soupbintcp/uds/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::SPayload(SPayload { payload, .. })) if payload == &SamplePayload::new(format!("#{} SPayload", N_SEQUENCED_PAYLOADS).as_bytes().into())),
+            setup::net::optional_find_timeout(),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+
+        drop(svc);
+
+        let found = clt_store_reconnect.find_recv(
+            "clt_reconnect/soupbintcp/uds/auto",
+            |msg| matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::EndOfSession(_))),
+            setup::net::optional_find_timeout(),
+        );
+        info!("found: {:?}", found);
+        assert!(found.is_some());
+        assert!(!clt_reconnect.is_connected());
+    }
+}
@@ -1,2 +1,10 @@
 pub mod prelude;
-pub mod connect;
\ No newline at end of file
+pub mod connect;
+pub mod conformance;
+pub mod moldudp64;
+pub mod relay;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+#[cfg(feature = "unittest")]
+pub mod unittest;
@@ -0,0 +1,568 @@
+use super::{deserialize_moldudp64_packet, spawn, MoldUdp64Handle};
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    io::{Error, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Default of [`MoldUdp64Consumer::with_max_pending`], number of sequence numbers past a gap whose payloads are held until the gap is filled
+pub const MOLDUDP64_DEFAULT_MAX_PENDING: usize = 65_536;
+
+/// Receives the merged, in order & de-duplicated stream of [MoldUdp64Consumer]
+///
+/// # Important
+/// Called from the thread polling [MoldUdp64Consumer], implementation must not block
+pub trait MoldUdp64Callback<P>: Debug + Send + Sync + 'static {
+    /// Called exactly once per `sequence_number` of a session, in sequence number order starting with `1`
+    fn on_payload(&self, session_id: SessionId, sequence_number: u64, payload: &P);
+    /// Called once the publisher ended `session_id` and all of its payloads were delivered, `next_sequence_number` follows the last [`Self::on_payload`] of the session
+    fn on_end_of_session(&self, session_id: SessionId, next_sequence_number: u64) {
+        let _ = (session_id, next_sequence_number);
+    }
+}
+
+/// Receives MoldUDP64 packets disseminated by [MoldUdp64Publisher] and delivers their message blocks deserialized into `P` to a [MoldUdp64Callback]
+///
+/// Sequence gaps, ex: lost packets or joining a session after it started, are filled by a [CltSoupBinTcpProtocolAuto] login into the same session at the
+/// missing sequence number, known as a rewind session, which is logged out as soon as the gap is filled. The rewind session runs on a helper thread
+/// and its payloads are delivered by subsequent calls to [`Self::poll`], which never blocks. Packets received out of order
+/// are held until the gap in front of them is filled, payloads too far past the gap, see [`Self::with_max_pending`], are dropped & recovered by the rewind session instead.
+///
+/// # Important
+/// `MAX_PACKET_SIZE` is also used as `MAX_MSG_SIZE` of the rewind [CltSoupBinTcp], since every message block which fits into a packet also fits into a frame.
+/// A session other then the one currently consumed starts at sequence number `1`, and messages of the previous session which were not received yet are abandoned.
+/// Sessions only move forward, late or duplicate packets of any session consumed before are ignored.
+#[derive(Debug)]
+pub struct MoldUdp64Consumer<P: SoupBinTcpPayload<P>, C: MoldUdp64Callback<P>, const MAX_PACKET_SIZE: usize> {
+    socket: UdpSocket,
+    rewind_addr: String,
+    username: UserName,
+    password: Password,
+    io_timeout: Duration,
+    callback: Arc<C>,
+    session_id: Option<SessionId>,
+    abandoned_session_ids: Vec<SessionId>,
+    next_sequence_number: u64,
+    announced_sequence_number: u64, // sequence number following the last one announced by any packet of the session
+    pending: BTreeMap<u64, P>,
+    max_pending: usize,
+    is_ended: bool,
+    is_end_delivered: bool,
+    rewind: Option<Receiver<Result<(u64, P), Error>>>,
+    recovered_count: usize,
+}
+impl<P: SoupBinTcpPayload<P>, C: MoldUdp64Callback<P>, const MAX_PACKET_SIZE: usize> MoldUdp64Consumer<P, C, MAX_PACKET_SIZE> {
+    /// Binds a non blocking socket to `addr` which receives packets, gaps are filled by logging into [SvcSoupBinTcp] at `rewind_addr` with `username` & `password`
+    ///
+    /// # Arguments
+    /// * `io_timeout` - timeout for connecting, logging into and receiving each message of the rewind session
+    pub fn bind<A: ToSocketAddrs>(addr: A, rewind_addr: &str, username: UserName, password: Password, io_timeout: Duration, callback: Arc<C>) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            rewind_addr: rewind_addr.to_owned(),
+            username,
+            password,
+            io_timeout,
+            callback,
+            session_id: None,
+            abandoned_session_ids: vec![],
+            next_sequence_number: 1,
+            announced_sequence_number: 1,
+            pending: BTreeMap::new(),
+            max_pending: MOLDUDP64_DEFAULT_MAX_PENDING,
+            is_ended: false,
+            is_end_delivered: false,
+            rewind: None,
+            recovered_count: 0,
+        })
+    }
+    /// Payloads with sequence numbers at or beyond `max_pending` past [`Self::next_sequence_number`] are dropped rather then held until the gap is filled,
+    /// which bounds memory when packets arrive far ahead of a gap, defaults to [MOLDUDP64_DEFAULT_MAX_PENDING]
+    pub fn with_max_pending(self, max_pending: usize) -> Self {
+        Self { max_pending, ..self }
+    }
+    #[inline(always)]
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.socket.local_addr()
+    }
+    /// Session currently being consumed, `None` until the first packet is received
+    #[inline(always)]
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+    /// Sequence number of the next payload to be delivered to [MoldUdp64Callback]
+    #[inline(always)]
+    pub fn next_sequence_number(&self) -> u64 {
+        self.next_sequence_number
+    }
+    /// Number of payloads delivered from rewind sessions rather then packets
+    #[inline(always)]
+    pub fn recovered_count(&self) -> usize {
+        self.recovered_count
+    }
+    /// Receives every pending packet & payloads replayed so far by the rewind session, starts a new rewind session if they reveal a sequence gap
+    /// which is not being filled yet, returns the number of payloads delivered to [MoldUdp64Callback]
+    ///
+    /// # Errors
+    /// Returns the error of the rewind session if the gap could not be filled, in which case the next call will retry
+    pub fn poll(&mut self) -> Result<usize, Error> {
+        let mut delivered = 0;
+        let mut buf = [0_u8; MAX_PACKET_SIZE];
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            };
+            let (header, payloads) = match deserialize_moldudp64_packet::<P>(&buf[..len]) {
+                Ok(packet) => packet,
+                Err(e) => {
+                    warn!("{} dropping malformed packet error: {}", self, e);
+                    continue;
+                }
+            };
+            delivered += self.on_packet(header, payloads);
+        }
+        delivered += self.recv_rewind()?;
+        if self.rewind.is_none() && self.next_sequence_number < self.announced_sequence_number {
+            self.rewind = Some(self.spawn_rewind()?);
+        }
+        if self.is_ended && !self.is_end_delivered && self.next_sequence_number >= self.announced_sequence_number {
+            self.is_end_delivered = true;
+            self.callback.on_end_of_session(self.session_id.unwrap_or_default(), self.next_sequence_number);
+        }
+        Ok(delivered)
+    }
+    /// Calls [`Self::poll`] on a dedicated thread until the returned [MoldUdp64Handle] is dropped, sleeps for `poll_interval` whenever nothing was delivered
+    pub fn spawn(mut self, poll_interval: Duration) -> Result<MoldUdp64Handle, Error> {
+        spawn("soupbintcp-moldudp64-consumer", poll_interval, move || self.poll())
+    }
+    fn on_packet(&mut self, header: MoldUdp64Header, payloads: Vec<P>) -> usize {
+        if self.abandoned_session_ids.contains(&header.session_id) {
+            if log_enabled!(log::Level::Debug) {
+                debug!("{} ignoring packet of abandoned session: {:?}, sequence number: {}", self, header.session_id, header.sequence_number);
+            }
+            return 0;
+        }
+        if self.session_id != Some(header.session_id) {
+            if log_enabled!(log::Level::Debug) {
+                debug!("{} starting session: {:?}, abandoning session: {:?}", self, header.session_id, self.session_id);
+            }
+            if let Some(session_id) = self.session_id.replace(header.session_id) {
+                self.abandoned_session_ids.push(session_id);
+            }
+            self.next_sequence_number = 1;
+            self.announced_sequence_number = 1;
+            self.pending.clear();
+            self.is_ended = false;
+            self.is_end_delivered = false;
+            self.rewind = None; // rewind thread exits once it is unable to hand over the next payload
+        }
+        let next_sequence_number = header.sequence_number + payloads.len() as u64;
+        self.announced_sequence_number = self.announced_sequence_number.max(next_sequence_number);
+        let mut delivered = 0;
+        let mut dropped = 0;
+        for (sequence_number, payload) in (header.sequence_number..).zip(payloads) {
+            if sequence_number == self.next_sequence_number {
+                delivered += self.deliver(payload);
+            } else if sequence_number >= self.next_sequence_number.saturating_add(self.max_pending as u64) {
+                dropped += 1;
+            } else if sequence_number > self.next_sequence_number {
+                self.pending.insert(sequence_number, payload);
+            }
+        }
+        if dropped > 0 {
+            warn!(
+                "{} dropped: {} payloads of packet sequence number: {} beyond max_pending: {} past next_sequence_number: {}, rewind will recover them",
+                self, dropped, header.sequence_number, self.max_pending, self.next_sequence_number
+            );
+        }
+        self.is_ended |= header.is_end_of_session();
+        delivered
+    }
+    /// Delivers `payload` with [`Self::next_sequence_number`] followed by any pending payloads which are now in order
+    fn deliver(&mut self, payload: P) -> usize {
+        let session_id = self.session_id.unwrap_or_default();
+        self.callback.on_payload(session_id, self.next_sequence_number, &payload);
+        self.next_sequence_number += 1;
+        let mut delivered = 1;
+        while let Some(payload) = self.pending.remove(&self.next_sequence_number) {
+            self.callback.on_payload(session_id, self.next_sequence_number, &payload);
+            self.next_sequence_number += 1;
+            delivered += 1;
+        }
+        self.pending.retain(|sequence_number, _| *sequence_number >= self.next_sequence_number);
+        delivered
+    }
+    /// Delivers payloads replayed so far by the running rewind session, if any, which is released once it ends
+    fn recv_rewind(&mut self) -> Result<usize, Error> {
+        let rewind = match self.rewind.take() {
+            Some(rewind) => rewind,
+            None => return Ok(0),
+        };
+        let mut delivered = 0;
+        loop {
+            match rewind.try_recv() {
+                Ok(Ok((sequence_number, payload))) => {
+                    if sequence_number == self.next_sequence_number {
+                        delivered += self.deliver(payload);
+                        self.recovered_count += 1;
+                    }
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(TryRecvError::Empty) => {
+                    self.rewind = Some(rewind);
+                    return Ok(delivered);
+                }
+                Err(TryRecvError::Disconnected) => return Ok(delivered),
+            }
+        }
+    }
+    /// Starts a rewind session on a helper thread which logs into the current session at [`Self::next_sequence_number`] and hands over replayed payloads
+    /// until the gap up to the last announced sequence number is filled
+    fn spawn_rewind(&self) -> Result<Receiver<Result<(u64, P), Error>>, Error> {
+        let session_id = self.session_id.unwrap_or_default();
+        if log_enabled!(log::Level::Debug) {
+            debug!(
+                "{} rewinding session: {:?} from: {} to: {}",
+                self, session_id, self.next_sequence_number, self.announced_sequence_number
+            );
+        }
+        let (tx, rx) = channel();
+        let rewind = MoldUdp64Rewind {
+            name: self.to_string(),
+            rewind_addr: self.rewind_addr.clone(),
+            username: self.username,
+            password: self.password,
+            io_timeout: self.io_timeout,
+            session_id,
+            from: self.next_sequence_number,
+            to: self.announced_sequence_number,
+        };
+        thread::Builder::new().name("soupbintcp-moldudp64-rewind".to_owned()).spawn(move || {
+            if let Err(e) = rewind.run::<P, MAX_PACKET_SIZE>(&tx) {
+                let _ = tx.send(Err(e));
+            }
+        })?;
+        Ok(rx)
+    }
+}
+
+/// Rewind session of [MoldUdp64Consumer] replaying sequence numbers `from` up to but excluding `to`
+#[derive(Debug)]
+struct MoldUdp64Rewind {
+    name: String,
+    rewind_addr: String,
+    username: UserName,
+    password: Password,
+    io_timeout: Duration,
+    session_id: SessionId,
+    from: u64,
+    to: u64,
+}
+impl MoldUdp64Rewind {
+    /// Connects, logs in & hands over every replayed payload along with its sequence number, returns early once `tx` is disconnected
+    fn run<P: SoupBinTcpPayload<P>, const MAX_PACKET_SIZE: usize>(&self, tx: &Sender<Result<(u64, P), Error>>) -> Result<(), Error> {
+        let protocol = CltSoupBinTcpProtocolAuto::<P, Nil>::new(
+            self.username,
+            self.password,
+            self.session_id,
+            (self.from as usize).into(),
+            self.io_timeout,
+            self.io_timeout,
+            self.io_timeout,
+        );
+        let login = Arc::new(MoldUdp64RewindLogin::default());
+        let mut clt = CltSoupBinTcp::<_, _, MAX_PACKET_SIZE>::connect(
+            self.rewind_addr.as_str(),
+            self.io_timeout,
+            self.io_timeout / 10,
+            login.clone(),
+            protocol,
+            Some("clt/soupbintcp/moldudp64/rewind"),
+        )?;
+        // replayed payloads are labelled by counting from the sequence number the server accepted, which must be the one requested
+        match login.accepted_sequence_number() {
+            Some(Ok(sequence_number)) if sequence_number == self.from => {}
+            accepted => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} rewind session requested: {} but LoginAccepted sequence number is: {:?}", self.name, self.from, accepted),
+                ))
+            }
+        }
+        let mut sequence_number = self.from;
+        let mut last_recv = Instant::now();
+        while sequence_number < self.to {
+            let remaining = self.io_timeout.saturating_sub(last_recv.elapsed());
+            match clt.recv_busywait_timeout(remaining)? {
+                RecvStatus::Completed(Some(SvcSoupBinTcpMsg::SPayload(msg))) => {
+                    if tx.send(Ok((sequence_number, msg.payload))).is_err() {
+                        return Ok(()); // consumer moved on to another session
+                    }
+                    sequence_number += 1;
+                    last_recv = Instant::now();
+                }
+                RecvStatus::Completed(Some(SvcSoupBinTcpMsg::EndOfSession(_))) | RecvStatus::Completed(None) => {
+                    return Err(Error::new(
+                        ErrorKind::ConnectionAborted,
+                        format!("{} rewind session ended at: {} before reaching: {}", self.name, sequence_number, self.to),
+                    ));
+                }
+                RecvStatus::Completed(Some(_)) => {}
+                RecvStatus::WouldBlock => {
+                    return Err(Error::new(
+                        ErrorKind::TimedOut,
+                        format!(
+                            "{} rewind session stalled at: {} before reaching: {} with io_timeout: {:?}",
+                            self.name, sequence_number, self.to, self.io_timeout
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+/// Callback of the [MoldUdp64Rewind] connection, remembers the sequence number of the [LoginAccepted] received while connecting
+#[derive(Debug, Default)]
+struct MoldUdp64RewindLogin {
+    accepted: Mutex<Option<SequenceNumber>>,
+}
+impl MoldUdp64RewindLogin {
+    fn accepted_sequence_number(&self) -> Option<Result<u64, Error>> {
+        self.accepted.lock().expect("not poisoned").map(|sequence_number| sequence_number.try_to_u64())
+    }
+}
+impl<P: SoupBinTcpPayload<P>> CallbackRecv<CltSoupBinTcpProtocolAuto<P, Nil>> for MoldUdp64RewindLogin {
+    fn on_recv(&self, _con_id: &ConId, msg: &<CltSoupBinTcpProtocolAuto<P, Nil> as Messenger>::RecvT) {
+        if let SvcSoupBinTcpMsg::LoginAccepted(msg) = msg {
+            *self.accepted.lock().expect("not poisoned") = Some(msg.sequence_number());
+        }
+    }
+}
+impl<P: SoupBinTcpPayload<P>> CallbackSend<CltSoupBinTcpProtocolAuto<P, Nil>> for MoldUdp64RewindLogin {
+    fn on_sent(&self, _con_id: &ConId, _msg: &<CltSoupBinTcpProtocolAuto<P, Nil> as Messenger>::SendT) {}
+}
+impl<P: SoupBinTcpPayload<P>> CallbackRecvSend<CltSoupBinTcpProtocolAuto<P, Nil>> for MoldUdp64RewindLogin {}
+impl std::fmt::Display for MoldUdp64RewindLogin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MoldUdp64RewindLogin")
+    }
+}
+impl<P: SoupBinTcpPayload<P>, C: MoldUdp64Callback<P>, const MAX_PACKET_SIZE: usize> std::fmt::Display for MoldUdp64Consumer<P, C, MAX_PACKET_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let local_addr = self.socket.local_addr().map_or_else(|_| "unknown".to_owned(), |addr| addr.to_string());
+        write!(f, "MoldUdp64Consumer<{}, rewind: {}, {}>", local_addr, self.rewind_addr, MAX_PACKET_SIZE)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{
+        io::Error,
+        net::UdpSocket,
+        num::NonZeroUsize,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+    const MAX_PACKET_SIZE: usize = 128;
+
+    #[derive(Debug, Default)]
+    struct StoreMoldUdp64Callback {
+        payloads: Mutex<Vec<(SessionId, u64, SamplePayload)>>,
+        ends: Mutex<Vec<(SessionId, u64)>>,
+    }
+    impl MoldUdp64Callback<SamplePayload> for StoreMoldUdp64Callback {
+        fn on_payload(&self, session_id: SessionId, sequence_number: u64, payload: &SamplePayload) {
+            self.payloads.lock().unwrap().push((session_id, sequence_number, payload.clone()));
+        }
+        fn on_end_of_session(&self, session_id: SessionId, next_sequence_number: u64) {
+            self.ends.lock().unwrap().push((session_id, next_sequence_number));
+        }
+    }
+
+    /// Polls until `expected` payloads were delivered, the rewind session delivers them over several polls
+    fn poll_busywait_timeout<C: MoldUdp64Callback<SamplePayload>>(consumer: &mut MoldUdp64Consumer<SamplePayload, C, MAX_PACKET_SIZE>, expected: usize, timeout: Duration) -> usize {
+        let started = Instant::now();
+        let mut delivered = consumer.poll().unwrap();
+        while delivered < expected && started.elapsed() < timeout {
+            delivered += consumer.poll().unwrap();
+        }
+        delivered
+    }
+    /// Polls until the rewind session fails
+    fn poll_err_busywait_timeout<C: MoldUdp64Callback<SamplePayload>>(consumer: &mut MoldUdp64Consumer<SamplePayload, C, MAX_PACKET_SIZE>, timeout: Duration) -> Option<Error> {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if let Err(e) = consumer.poll() {
+                return Some(e);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_consumer_rewind() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_a: SessionId = b"session #a".as_slice().into();
+        let session_b: SessionId = b"session #b".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let max_hbeat_interval = Duration::from_secs(1);
+
+        let svc_protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_a, io_timeout, max_hbeat_interval);
+        let mut svc = SvcSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(2).unwrap(), DevNullCallback::new_ref(), svc_protocol.clone(), Some("svc/soupbintcp/moldudp64"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let clt_protocol = CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_a, 0_usize.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+        let _clt = CltSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            clt_protocol,
+            Some("clt/soupbintcp/moldudp64"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(io_timeout));
+
+        // publisher sends to a lossy relay which forwards selected packets to the consumer
+        let callback = Arc::new(StoreMoldUdp64Callback::default());
+        let mut consumer = MoldUdp64Consumer::<SamplePayload, _, MAX_PACKET_SIZE>::bind("127.0.0.1:0", addr, username, password, io_timeout, callback.clone()).unwrap();
+        info!("consumer: {}", consumer);
+        let relay = UdpSocket::bind("127.0.0.1:0").unwrap();
+        relay.set_read_timeout(Some(io_timeout)).unwrap();
+        let consumer_addr = consumer.local_addr().unwrap();
+        let recv_packet = || {
+            let mut buf = [0_u8; MAX_PACKET_SIZE];
+            let len = relay.recv(&mut buf).unwrap();
+            buf[..len].to_vec()
+        };
+        let forward = |packet: &[u8]| {
+            relay.send_to(packet, consumer_addr).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let mut publisher = MoldUdp64Publisher::<_, _, _, _, MAX_PACKET_SIZE>::bind(svc_protocol.clone(), "127.0.0.1:0", relay.local_addr().unwrap())
+            .unwrap()
+            .with_hbeat_interval(Duration::ZERO);
+
+        const N_SEQUENCED_PAYLOADS: usize = 12;
+        let payloads = (1..=N_SEQUENCED_PAYLOADS)
+            .map(|i| SamplePayload::new(format!("#{} session a", i).as_bytes().into()))
+            .collect::<Vec<_>>();
+        for payload in payloads.iter() {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        }
+        assert_eq!(publisher.publish().unwrap(), N_SEQUENCED_PAYLOADS);
+        let packets = (0..3).map(|_| recv_packet()).collect::<Vec<_>>();
+
+        // second packet is lost, third arrives before duplicate of the first
+        forward(&packets[0]);
+        forward(&packets[2]);
+        forward(&packets[0]);
+        assert_eq!(poll_busywait_timeout(&mut consumer, N_SEQUENCED_PAYLOADS, io_timeout), N_SEQUENCED_PAYLOADS);
+        info!("consumer: {}, recovered_count: {}", consumer, consumer.recovered_count());
+        assert_eq!(consumer.session_id(), Some(session_a));
+        assert_eq!(consumer.next_sequence_number(), N_SEQUENCED_PAYLOADS as u64 + 1);
+        assert_eq!(consumer.recovered_count(), 4);
+        let expected = payloads.iter().enumerate().map(|(i, payload)| (session_a, i as u64 + 1, payload.clone())).collect::<Vec<_>>();
+        assert_eq!(*callback.payloads.lock().unwrap(), expected);
+
+        // payloads beyond max_pending past the gap are dropped & recovered by the rewind session instead of being held
+        let callback_window = Arc::new(StoreMoldUdp64Callback::default());
+        let mut consumer_window = MoldUdp64Consumer::<SamplePayload, _, MAX_PACKET_SIZE>::bind("127.0.0.1:0", addr, username, password, io_timeout, callback_window.clone())
+            .unwrap()
+            .with_max_pending(2);
+        let consumer_window_addr = consumer_window.local_addr().unwrap();
+        relay.send_to(&packets[0], consumer_window_addr).unwrap();
+        relay.send_to(&packets[2], consumer_window_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(poll_busywait_timeout(&mut consumer_window, N_SEQUENCED_PAYLOADS, io_timeout), N_SEQUENCED_PAYLOADS);
+        assert_eq!(consumer_window.recovered_count(), N_SEQUENCED_PAYLOADS - 4);
+        assert!(consumer_window.pending.is_empty());
+        assert_eq!(*callback_window.payloads.lock().unwrap(), expected);
+
+        // late packet & heartbeat deliver nothing
+        assert_eq!(publisher.publish().unwrap(), 0);
+        forward(&recv_packet());
+        forward(&packets[1]);
+        assert_eq!(consumer.poll().unwrap(), 0);
+        assert_eq!(callback.payloads.lock().unwrap().len(), N_SEQUENCED_PAYLOADS);
+
+        // end of session is delivered once
+        svc_protocol.rollover(session_b).unwrap();
+        assert_eq!(publisher.publish().unwrap(), 0);
+        let end_of_session = recv_packet();
+        forward(&end_of_session);
+        forward(&end_of_session);
+        assert_eq!(consumer.poll().unwrap(), 0);
+        assert_eq!(*callback.ends.lock().unwrap(), vec![(session_a, N_SEQUENCED_PAYLOADS as u64 + 1)]);
+
+        // rewind times out when the announced sequence numbers were never sent
+        forward(&recv_packet()); // heartbeat of session b
+        assert_eq!(consumer.poll().unwrap(), 0);
+        assert_eq!(consumer.session_id(), Some(session_b));
+
+        // late packets of the abandoned session a neither switch back nor rewind it
+        forward(&packets[0]);
+        forward(&end_of_session);
+        assert_eq!(consumer.poll().unwrap(), 0);
+        assert_eq!(consumer.session_id(), Some(session_b));
+        assert_eq!(consumer.next_sequence_number(), 1);
+        assert_eq!(callback.payloads.lock().unwrap().len(), N_SEQUENCED_PAYLOADS);
+        assert_eq!(callback.ends.lock().unwrap().len(), 1);
+
+        let hbeat = MoldUdp64Header::heartbeat(session_b, 3);
+        forward(byteserde::prelude::to_serializer_stack::<MOLDUDP64_HEADER_BYTE_LEN, _>(&hbeat).unwrap().as_slice());
+        let err = poll_err_busywait_timeout(&mut consumer, io_timeout * 2).unwrap();
+        info!("err: {}", err);
+        assert_eq!(consumer.next_sequence_number(), 1);
+    }
+
+    #[test]
+    fn test_consumer_max_pending() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let session_id: SessionId = b"session #a".as_slice().into();
+        let callback = Arc::new(StoreMoldUdp64Callback::default());
+        let mut consumer = MoldUdp64Consumer::<SamplePayload, _, MAX_PACKET_SIZE>::bind("127.0.0.1:0", "127.0.0.1:0", b"userid".as_slice().into(), b"passwd".as_slice().into(), setup::net::find_timeout(), callback.clone())
+            .unwrap()
+            .with_max_pending(4);
+
+        // packet far past the gap is dropped, only announced sequence numbers advance so the rewind session recovers it
+        assert_eq!(consumer.on_packet(MoldUdp64Header::heartbeat(session_id, 1), vec![]), 0);
+        let payloads = vec![SamplePayload::default(); 3];
+        assert_eq!(consumer.on_packet(MoldUdp64Header::new(session_id, 1_000_000, 3), payloads.clone()), 0);
+        assert!(consumer.pending.is_empty());
+        assert_eq!(consumer.announced_sequence_number, 1_000_003);
+
+        // packet straddling the window end is held up to max_pending past the gap
+        assert_eq!(consumer.on_packet(MoldUdp64Header::new(session_id, 3, 3), payloads.clone()), 0);
+        assert_eq!(consumer.pending.keys().copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(consumer.on_packet(MoldUdp64Header::new(session_id, 1, 3), payloads), 4);
+        info!("consumer: {}, next_sequence_number: {}", consumer, consumer.next_sequence_number());
+        assert_eq!(consumer.next_sequence_number(), 5);
+        assert!(consumer.pending.is_empty());
+        assert!(callback.payloads.lock().unwrap().iter().map(|(_, sequence_number, _)| *sequence_number).eq(1..=4));
+    }
+}
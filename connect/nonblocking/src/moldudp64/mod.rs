@@ -0,0 +1,192 @@
+//! Disseminates the session log of [SvcSoupBinTcpProtocolAuto] as MoldUDP64 packets, the same sequenced stream delivered to SoupBinTCP clients.
+//!
+//! * [MoldUdp64Publisher] - packs every [SPayload] appended to the session log into MoldUDP64 packets and sends them to a UDP address
+//! * [MoldUdp64RetransmitResponder] - answers MoldUDP64 request packets from the same session log
+//! * [MoldUdp64Consumer] - receives the packets & fills sequence gaps from a SoupBinTCP rewind session
+//!
+//! See [MoldUdp64Header] for the wire format, all use non blocking std UDP sockets & can be polled manually or on a dedicated thread.
+pub mod consumer;
+pub mod publisher;
+
+use crate::prelude::*;
+use byteserde::prelude::{from_slice, ByteDeserializerSlice, ByteSerializerStack};
+use log::warn;
+use std::{
+    io::{Error, ErrorKind},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Largest packet which fits into a standard 1500 byte ethernet frame, 1500 - 20 byte IP header - 8 byte UDP header
+pub const MOLDUDP64_MTU_PACKET_SIZE: usize = 1472;
+
+/// Downstream packet under construction, message blocks are appended until the next one would exceed `MAX_PACKET_SIZE`
+#[derive(Debug)]
+pub struct MoldUdp64Packet<const MAX_PACKET_SIZE: usize> {
+    header: MoldUdp64Header,
+    blocks: ByteSerializerStack<MAX_PACKET_SIZE>,
+}
+impl<const MAX_PACKET_SIZE: usize> MoldUdp64Packet<MAX_PACKET_SIZE> {
+    /// Empty packet whose first message block will have `sequence_number`, serializes as a heartbeat until a message block is appended
+    pub fn new(session_id: SessionId, sequence_number: u64) -> Self {
+        Self {
+            header: MoldUdp64Header::heartbeat(session_id, sequence_number),
+            blocks: ByteSerializerStack::default(),
+        }
+    }
+    /// Appends `payload` as the next message block, returns `false` without appending if the packet is full
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::InvalidInput] if `payload` does not fit into an empty packet & can never be sent
+    pub fn push<P: SoupBinTcpPayload<P>>(&mut self, payload: &P) -> Result<bool, Error> {
+        let block_len = MOLDUDP64_MESSAGE_BLOCK_HEADER_BYTE_LEN + payload.byte_len();
+        if MOLDUDP64_HEADER_BYTE_LEN + block_len > MAX_PACKET_SIZE {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("payload: {:?} exceeds MAX_PACKET_SIZE: {}", payload, MAX_PACKET_SIZE)));
+        }
+        if MOLDUDP64_HEADER_BYTE_LEN + self.blocks.len() + block_len > MAX_PACKET_SIZE || self.header.message_count == MOLDUDP64_END_OF_SESSION_MESSAGE_COUNT - 1 {
+            return Ok(false);
+        }
+        self.blocks.serialize_be(payload.byte_len() as u16).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        self.blocks.serialize(payload).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        self.header.message_count += 1;
+        Ok(true)
+    }
+    #[inline(always)]
+    pub fn header(&self) -> &MoldUdp64Header {
+        &self.header
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.header.message_count == 0
+    }
+    /// Serialized header followed by all message blocks
+    pub fn serialize(&self) -> Result<ByteSerializerStack<MAX_PACKET_SIZE>, Error> {
+        let mut ser = ByteSerializerStack::<MAX_PACKET_SIZE>::default();
+        ser.serialize(&self.header).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        ser.serialize_bytes_slice(self.blocks.as_slice()).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(ser)
+    }
+}
+
+/// Deserializes a downstream packet into its header & the payload of every message block
+///
+/// # Errors
+/// Returns [ErrorKind::InvalidData] if the packet is truncated or a message block fails to deserialize into `P`
+pub fn deserialize_moldudp64_packet<P: SoupBinTcpPayload<P>>(packet: &[u8]) -> Result<(MoldUdp64Header, Vec<P>), Error> {
+    let des = &mut ByteDeserializerSlice::new(packet);
+    let header = des.deserialize::<MoldUdp64Header>().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    // each block takes at least its two byte length, so a bogus count can not be used to allocate more then the packet could hold
+    let mut payloads = Vec::with_capacity(header.message_block_count().min(packet.len() / 2));
+    for _ in 0..header.message_block_count() {
+        let len = des.deserialize_be::<2, u16>().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let block = des.deserialize_bytes_slice(len as usize).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        payloads.push(from_slice::<P>(block).map_err(|e| Error::new(ErrorKind::InvalidData, e))?);
+    }
+    Ok((header, payloads))
+}
+
+/// Deserializes a request packet, which consists of [MoldUdp64Header] alone
+pub fn deserialize_moldudp64_request(packet: &[u8]) -> Result<MoldUdp64Header, Error> {
+    if packet.len() != MOLDUDP64_HEADER_BYTE_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid request packet len: {}, expected: {}", packet.len(), MOLDUDP64_HEADER_BYTE_LEN),
+        ));
+    }
+    from_slice::<MoldUdp64Header>(packet).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Runs `poll` on a dedicated thread until the returned [MoldUdp64Handle] is dropped, sleeps for `poll_interval` whenever `poll` returns `0`, errors are logged
+pub(crate) fn spawn<F: FnMut() -> Result<usize, Error> + Send + 'static>(name: &str, poll_interval: Duration, mut poll: F) -> Result<MoldUdp64Handle, Error> {
+    let is_running = Arc::new(AtomicBool::new(true));
+    let thread = {
+        let is_running = is_running.clone();
+        let name = name.to_owned();
+        thread::Builder::new().name(name.clone()).spawn(move || {
+            while is_running.load(Ordering::Relaxed) {
+                match poll() {
+                    Ok(0) => thread::sleep(poll_interval),
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!("{} poll error: {}", name, e);
+                        thread::sleep(poll_interval);
+                    }
+                }
+            }
+        })?
+    };
+    Ok(MoldUdp64Handle { is_running, thread: Some(thread) })
+}
+
+/// Stops the thread started by [`MoldUdp64Publisher::spawn`], [`MoldUdp64RetransmitResponder::spawn`] or [`MoldUdp64Consumer::spawn`] when dropped
+#[derive(Debug)]
+pub struct MoldUdp64Handle {
+    is_running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Drop for MoldUdp64Handle {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use super::*;
+    use links_core::unittest::setup;
+    use log::info;
+
+    #[test]
+    fn test_packet_round_trip() {
+        setup::log::configure();
+        let session_id: SessionId = b"session #1".as_slice().into();
+
+        let mut packet = MoldUdp64Packet::<64>::new(session_id, 7);
+        assert!(packet.is_empty());
+        assert!(packet.header().is_heartbeat());
+        let payloads = (1..=10).map(|i| SamplePayload::new(format!("#{}", i).as_bytes().into())).collect::<Vec<_>>();
+        let mut pushed = 0;
+        for payload in payloads.iter() {
+            if !packet.push(payload).unwrap() {
+                break;
+            }
+            pushed += 1;
+        }
+        info!("packet: {:?}", packet);
+        assert!(0 < pushed && pushed < payloads.len());
+
+        let ser = packet.serialize().unwrap();
+        assert!(ser.len() <= 64);
+        let (header, out) = deserialize_moldudp64_packet::<SamplePayload>(ser.as_slice()).unwrap();
+        info!("header: {}, out: {:?}", header, out);
+        assert_eq!(header, MoldUdp64Header::new(session_id, 7, pushed as u16));
+        assert_eq!(out, payloads[..pushed]);
+
+        // truncated
+        let err = deserialize_moldudp64_packet::<SamplePayload>(&ser.as_slice()[..ser.len() - 1]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // never fits
+        let err = MoldUdp64Packet::<32>::new(session_id, 1)
+            .push(&SamplePayload::new(b"too large to fit into any packet".as_slice().into()))
+            .unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // end of session & request
+        let eos = MoldUdp64Header::end_of_session(session_id, 8);
+        let (header, out) = deserialize_moldudp64_packet::<SamplePayload>(byteserde::prelude::to_serializer_stack::<64, _>(&eos).unwrap().as_slice()).unwrap();
+        assert_eq!(header, eos);
+        assert!(out.is_empty());
+        assert_eq!(deserialize_moldudp64_request(ser.as_slice()).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,401 @@
+use super::{deserialize_moldudp64_request, spawn, MoldUdp64Handle, MoldUdp64Packet};
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use std::{
+    io::{Error, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+pub const MOLDUDP64_DEFAULT_HBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+fn to_socket_addr<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr, Error> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "addr did not resolve to any SocketAddr"))
+}
+
+/// Packs every [SPayload] appended to the session log of [SvcSoupBinTcpProtocolAuto] into MoldUDP64 packets of at most `MAX_PACKET_SIZE` bytes
+/// and sends them to a UDP address, typically a multicast group, see [MOLDUDP64_MTU_PACKET_SIZE]
+///
+/// # Important
+/// `protocol` is typically a clone of the instance passed to [`Svc::bind`], since the session log is shared by all clones.
+/// Once the session is ended by [`SvcSoupBinTcpProtocolAuto::rollover`] the [SPayload]s appended to it after the last [`Self::publish`] are sent from
+/// [`SvcSoupBinTcpProtocolAuto::archived_session`] followed by an end of session packet. Only the most recently archived session is retained, hence
+/// the tail of a session is lost if it was rolled over more then once between two calls to [`Self::publish`].
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+/// use std::{net::UdpSocket, time::Duration};
+///
+/// let protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(b"userid".as_slice().into(), b"passwd".as_slice().into(), b"session #1".as_slice().into(), Duration::from_secs(1), Duration::from_secs(1));
+/// let subscriber = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let mut publisher = MoldUdp64Publisher::<_, _, _, _, MOLDUDP64_MTU_PACKET_SIZE>::bind(protocol, "127.0.0.1:0", subscriber.local_addr().unwrap()).unwrap();
+///
+/// // nothing was sent to clients yet, first publish is a heartbeat
+/// assert_eq!(publisher.publish().unwrap(), 0);
+/// let mut buf = [0_u8; MOLDUDP64_MTU_PACKET_SIZE];
+/// let len = subscriber.recv(&mut buf).unwrap();
+/// assert_eq!(&buf[..10], b"session #1");
+/// assert_eq!(len, MOLDUDP64_HEADER_BYTE_LEN);
+/// ```
+#[derive(Debug)]
+pub struct MoldUdp64Publisher<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> {
+    protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>,
+    socket: UdpSocket,
+    destination: SocketAddr,
+    session_id: SessionId,
+    next_sequence_number: usize,
+    hbeat_interval: Duration,
+    last_sent: Option<Instant>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> MoldUdp64Publisher<RecvP, SendP, Clk, Dec, MAX_PACKET_SIZE> {
+    /// Binds a non blocking socket to `addr` which sends packets to `destination`, starting with sequence number `1` of the current session
+    pub fn bind<A: ToSocketAddrs, D: ToSocketAddrs>(protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>, addr: A, destination: D) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            session_id: protocol.session_id(),
+            protocol,
+            socket,
+            destination: to_socket_addr(destination)?,
+            next_sequence_number: 1,
+            hbeat_interval: MOLDUDP64_DEFAULT_HBEAT_INTERVAL,
+            last_sent: None,
+        })
+    }
+    /// Interval after which a heartbeat packet is sent by [`Self::publish`] when there were no new [SPayload]s, defaults to [MOLDUDP64_DEFAULT_HBEAT_INTERVAL]
+    pub fn with_hbeat_interval(self, hbeat_interval: Duration) -> Self {
+        Self { hbeat_interval, ..self }
+    }
+    #[inline(always)]
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.socket.local_addr()
+    }
+    #[inline(always)]
+    pub fn destination(&self) -> SocketAddr {
+        self.destination
+    }
+    /// Session currently being published
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+    /// Sequence number of the next [SPayload] to be published
+    #[inline(always)]
+    pub fn next_sequence_number(&self) -> usize {
+        self.next_sequence_number
+    }
+    /// Sends every [SPayload] appended to the session log since the previous call, followed by an end of session packet once the session was
+    /// rolled over, or a heartbeat packet if nothing was sent within [`Self::with_hbeat_interval`]. Returns the number of [SPayload]s sent.
+    ///
+    /// # Errors
+    /// Returns [ErrorKind::WouldBlock] if the socket send buffer is full, in which case unsent [SPayload]s are retried on the next call
+    pub fn publish(&mut self) -> Result<usize, Error> {
+        let mut published = 0;
+        loop {
+            let mut packet = MoldUdp64Packet::<MAX_PACKET_SIZE>::new(self.session_id, self.next_sequence_number as u64);
+            let mut push_err = None;
+            let session_id = self.protocol.visit_sequenced(self.next_sequence_number, |msg| match packet.push(&msg.payload) {
+                Ok(pushed) => pushed,
+                Err(e) => {
+                    push_err = Some(e);
+                    false
+                }
+            });
+            if session_id != self.session_id {
+                published += self.publish_archived()?;
+                self.send(MoldUdp64Header::end_of_session(self.session_id, self.next_sequence_number as u64))?;
+                if log_enabled!(log::Level::Debug) {
+                    debug!("{} published end of session: {:?}, next session: {:?}", self, self.session_id, session_id);
+                }
+                self.session_id = session_id;
+                self.next_sequence_number = 1;
+                continue;
+            }
+            if let Some(e) = push_err {
+                return Err(e);
+            }
+            if packet.is_empty() {
+                break;
+            }
+            self.send_packet(&packet)?;
+            let count = packet.header().message_count as usize;
+            self.next_sequence_number += count;
+            published += count;
+        }
+        if published == 0 && self.last_sent.map_or(true, |last_sent| last_sent.elapsed() >= self.hbeat_interval) {
+            self.send(MoldUdp64Header::heartbeat(self.session_id, self.next_sequence_number as u64))?;
+        }
+        Ok(published)
+    }
+    /// Calls [`Self::publish`] on a dedicated thread until the returned [MoldUdp64Handle] is dropped, sleeps for `poll_interval` whenever there is nothing to publish
+    pub fn spawn(mut self, poll_interval: Duration) -> Result<MoldUdp64Handle, Error> {
+        spawn("soupbintcp-moldudp64-publisher", poll_interval, move || self.publish())
+    }
+    /// Sends [SPayload]s of the archive of the session being published which were not sent yet, returns the number sent
+    fn publish_archived(&mut self) -> Result<usize, Error> {
+        let archive = match self.protocol.archived_session() {
+            Some(archive) if archive.session_id() == self.session_id => archive,
+            _ => {
+                warn!("{} archive of session: {:?} not found, payloads from sequence number: {} are not published", self, self.session_id, self.next_sequence_number);
+                return Ok(0);
+            }
+        };
+        let mut published = 0;
+        let mut payloads = archive.sequenced().skip(self.next_sequence_number - 1).peekable();
+        while payloads.peek().is_some() {
+            let mut packet = MoldUdp64Packet::<MAX_PACKET_SIZE>::new(self.session_id, self.next_sequence_number as u64);
+            while let Some(msg) = payloads.peek() {
+                if !packet.push(&msg.payload)? {
+                    break;
+                }
+                payloads.next();
+            }
+            self.send_packet(&packet)?;
+            let count = packet.header().message_count as usize;
+            self.next_sequence_number += count;
+            published += count;
+        }
+        Ok(published)
+    }
+    fn send(&mut self, header: MoldUdp64Header) -> Result<(), Error> {
+        self.send_packet(&MoldUdp64Packet::<MAX_PACKET_SIZE> { header, blocks: Default::default() })
+    }
+    fn send_packet(&mut self, packet: &MoldUdp64Packet<MAX_PACKET_SIZE>) -> Result<(), Error> {
+        let ser = packet.serialize()?;
+        self.socket.send_to(ser.as_slice(), self.destination)?;
+        self.last_sent = Some(Instant::now());
+        Ok(())
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> std::fmt::Display for MoldUdp64Publisher<RecvP, SendP, Clk, Dec, MAX_PACKET_SIZE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let local_addr = self.socket.local_addr().map_or_else(|_| "unknown".to_owned(), |addr| addr.to_string());
+        write!(f, "MoldUdp64Publisher<{}->{}, {}>", local_addr, self.destination, MAX_PACKET_SIZE)
+    }
+}
+
+/// Answers MoldUDP64 request packets with a single downstream packet holding as many of the requested [SPayload]s from the session log of
+/// [SvcSoupBinTcpProtocolAuto] as fit into `MAX_PACKET_SIZE` bytes, sent back to the requester.
+///
+/// Requests for a session other then the current one, or for sequence numbers which were not sent yet, are ignored.
+/// `Dec` is the [DecodeErrorPolicy] of `protocol` and has no effect on the responder.
+#[derive(Debug)]
+pub struct MoldUdp64RetransmitResponder<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> {
+    protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>,
+    socket: UdpSocket,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> MoldUdp64RetransmitResponder<RecvP, SendP, Clk, Dec, MAX_PACKET_SIZE> {
+    /// Binds a non blocking socket to `addr` which receives request packets, `protocol` is typically a clone of the instance passed to [`Svc::bind`]
+    pub fn bind<A: ToSocketAddrs>(protocol: SvcSoupBinTcpProtocolAuto<RecvP, SendP, Clk, Dec>, addr: A) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { protocol, socket })
+    }
+    #[inline(always)]
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.socket.local_addr()
+    }
+    /// Answers every pending request packet, returns the number of [SPayload]s re-sent, malformed & ignored requests are logged at debug level
+    pub fn respond(&mut self) -> Result<usize, Error> {
+        let mut resent = 0;
+        let mut buf = [0_u8; MAX_PACKET_SIZE];
+        loop {
+            let (len, requester) = match self.socket.recv_from(&mut buf) {
+                Ok(recv) => recv,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(resent),
+                Err(e) => return Err(e),
+            };
+            let request = match deserialize_moldudp64_request(&buf[..len]) {
+                Ok(request) => request,
+                Err(e) => {
+                    if log_enabled!(log::Level::Debug) {
+                        debug!("{} ignoring request from: {}, error: {}", self, requester, e);
+                    }
+                    continue;
+                }
+            };
+            // copied out of the session log so that it is not locked while packing, each block takes at least two bytes which caps the count
+            let max_count = (request.message_count as usize).min(MAX_PACKET_SIZE / 2);
+            let mut payloads = vec![];
+            let session_id = self.protocol.visit_sequenced(request.sequence_number as usize, |msg| {
+                if payloads.len() == max_count {
+                    return false;
+                }
+                payloads.push(msg.payload.clone());
+                true
+            });
+            let mut packet = MoldUdp64Packet::<MAX_PACKET_SIZE>::new(request.session_id, request.sequence_number);
+            for payload in payloads.iter() {
+                if !packet.push(payload)? {
+                    break;
+                }
+            }
+            if session_id != request.session_id || request.sequence_number == 0 || packet.is_empty() {
+                if log_enabled!(log::Level::Debug) {
+                    debug!("{} ignoring request from: {}, request: {}, current session_id: {}", self, requester, request, session_id);
+                }
+                continue;
+            }
+            self.socket.send_to(packet.serialize()?.as_slice(), requester)?;
+            resent += packet.header().message_count as usize;
+        }
+    }
+    /// Calls [`Self::respond`] on a dedicated thread until the returned [MoldUdp64Handle] is dropped, sleeps for `poll_interval` whenever there are no requests
+    pub fn spawn(mut self, poll_interval: Duration) -> Result<MoldUdp64Handle, Error> {
+        spawn("soupbintcp-moldudp64-responder", poll_interval, move || self.respond())
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, Clk: Clock, Dec: DecodeErrorPolicy, const MAX_PACKET_SIZE: usize> std::fmt::Display
+    for MoldUdp64RetransmitResponder<RecvP, SendP, Clk, Dec, MAX_PACKET_SIZE>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let local_addr = self.socket.local_addr().map_or_else(|_| "unknown".to_owned(), |addr| addr.to_string());
+        write!(f, "MoldUdp64RetransmitResponder<{}, {}>", local_addr, MAX_PACKET_SIZE)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use byteserde::prelude::to_serializer_stack;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{net::UdpSocket, num::NonZeroUsize, time::Duration};
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+    const MAX_PACKET_SIZE: usize = 128;
+
+    fn recv_packet(socket: &UdpSocket) -> Option<(MoldUdp64Header, Vec<SamplePayload>)> {
+        let mut buf = [0_u8; MAX_PACKET_SIZE];
+        match socket.recv(&mut buf) {
+            Ok(len) => Some(deserialize_moldudp64_packet::<SamplePayload>(&buf[..len]).unwrap()),
+            Err(e) => {
+                info!("recv error: {}", e);
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_publisher_responder() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_a: SessionId = b"session #a".as_slice().into();
+        let session_b: SessionId = b"session #b".as_slice().into();
+        let io_timeout = setup::net::find_timeout();
+        let max_hbeat_interval = Duration::from_secs(1);
+
+        let svc_protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_a, io_timeout, max_hbeat_interval);
+        let mut svc = SvcSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), svc_protocol.clone(), Some("svc/soupbintcp/moldudp64"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let clt_protocol = CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_a, 0_usize.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+        let _clt = CltSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            clt_protocol,
+            Some("clt/soupbintcp/moldudp64"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(io_timeout));
+
+        let subscriber = UdpSocket::bind("127.0.0.1:0").unwrap();
+        subscriber.set_read_timeout(Some(io_timeout)).unwrap();
+        let mut publisher = MoldUdp64Publisher::<_, _, _, _, MAX_PACKET_SIZE>::bind(svc_protocol.clone(), "127.0.0.1:0", subscriber.local_addr().unwrap())
+            .unwrap()
+            .with_hbeat_interval(Duration::from_secs(60));
+        info!("publisher: {}", publisher);
+
+        // only sequenced payloads sent to clients are published, packed into as many packets as required
+        const N_SEQUENCED_PAYLOADS: usize = 10;
+        let payloads = (1..=N_SEQUENCED_PAYLOADS)
+            .map(|i| SamplePayload::new(format!("#{} session a", i).as_bytes().into()))
+            .collect::<Vec<_>>();
+        for payload in payloads.iter() {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        }
+        svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::udata(SamplePayload::new(b"unsequenced".as_slice().into())), io_timeout)
+            .unwrap()
+            .unwrap_completed();
+        assert_eq!(publisher.publish().unwrap(), N_SEQUENCED_PAYLOADS);
+        assert_eq!(publisher.next_sequence_number(), N_SEQUENCED_PAYLOADS + 1);
+
+        let mut published = vec![];
+        while published.len() < N_SEQUENCED_PAYLOADS {
+            let (header, packet_payloads) = recv_packet(&subscriber).unwrap();
+            info!("header: {}, payloads: {:?}", header, packet_payloads);
+            assert_eq!(header.session_id, session_a);
+            assert_eq!(header.sequence_number, published.len() as u64 + 1);
+            assert!(!header.is_heartbeat());
+            published.extend(packet_payloads);
+        }
+        assert_eq!(published, payloads);
+
+        // heartbeat is only sent once idle for hbeat interval
+        assert_eq!(publisher.publish().unwrap(), 0);
+        subscriber.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        assert!(recv_packet(&subscriber).is_none());
+        let mut publisher = publisher.with_hbeat_interval(Duration::ZERO);
+        assert_eq!(publisher.publish().unwrap(), 0);
+        let (header, _) = recv_packet(&subscriber).unwrap();
+        assert_eq!(header, MoldUdp64Header::heartbeat(session_a, N_SEQUENCED_PAYLOADS as u64 + 1));
+
+        // retransmission
+        let mut responder = MoldUdp64RetransmitResponder::<_, _, _, _, MAX_PACKET_SIZE>::bind(svc_protocol.clone(), "127.0.0.1:0").unwrap();
+        info!("responder: {}", responder);
+        let requester = UdpSocket::bind("127.0.0.1:0").unwrap();
+        requester.set_read_timeout(Some(io_timeout)).unwrap();
+        requester.connect(responder.local_addr().unwrap()).unwrap();
+        let request = |header: MoldUdp64Header| requester.send(to_serializer_stack::<MOLDUDP64_HEADER_BYTE_LEN, _>(&header).unwrap().as_slice()).unwrap();
+
+        request(MoldUdp64Header::new(session_a, 3, 2));
+        request(MoldUdp64Header::new(session_b, 3, 2)); // ignored, not current session
+        request(MoldUdp64Header::new(session_a, N_SEQUENCED_PAYLOADS as u64 + 1, 2)); // ignored, not sent yet
+        requester.send(b"malformed").unwrap(); // ignored
+        request(MoldUdp64Header::new(session_a, N_SEQUENCED_PAYLOADS as u64, 5)); // only last one is available
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(responder.respond().unwrap(), 3);
+
+        let (header, resent) = recv_packet(&requester).unwrap();
+        info!("header: {}, resent: {:?}", header, resent);
+        assert_eq!(header, MoldUdp64Header::new(session_a, 3, 2));
+        assert_eq!(resent, payloads[2..4]);
+        let (header, resent) = recv_packet(&requester).unwrap();
+        info!("header: {}, resent: {:?}", header, resent);
+        assert_eq!(header, MoldUdp64Header::new(session_a, N_SEQUENCED_PAYLOADS as u64, 1));
+        assert_eq!(resent, payloads[N_SEQUENCED_PAYLOADS - 1..]);
+
+        // rollover publishes the tail of the ended session from its archive followed by end of session
+        let tail = (1..=2).map(|i| SamplePayload::new(format!("#{} tail a", i).as_bytes().into())).collect::<Vec<_>>();
+        for payload in tail.iter() {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        }
+        svc_protocol.rollover(session_b).unwrap();
+        assert_eq!(publisher.publish().unwrap(), tail.len());
+        assert_eq!(publisher.session_id(), session_b);
+        assert_eq!(publisher.next_sequence_number(), 1);
+        let (header, published) = recv_packet(&subscriber).unwrap();
+        assert_eq!(header, MoldUdp64Header::new(session_a, N_SEQUENCED_PAYLOADS as u64 + 1, tail.len() as u16));
+        assert_eq!(published, tail);
+        let (header, _) = recv_packet(&subscriber).unwrap();
+        assert_eq!(header, MoldUdp64Header::end_of_session(session_a, (N_SEQUENCED_PAYLOADS + tail.len()) as u64 + 1));
+        assert_eq!(publisher.publish().unwrap(), 0);
+        let (header, _) = recv_packet(&subscriber).unwrap();
+        assert_eq!(header, MoldUdp64Header::heartbeat(session_b, 1));
+
+        // spawned responder
+        let _responder = responder.spawn(Duration::from_millis(1)).unwrap();
+        request(MoldUdp64Header::new(session_a, 1, 1)); // ignored, session ended
+        requester.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        assert!(recv_packet(&requester).is_none());
+    }
+}
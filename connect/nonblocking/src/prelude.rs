@@ -4,5 +4,36 @@ pub use crate::connect::clt::{CltSoupBinTcpSender, CltSoupBinTcpSenderRef};
 pub use crate::connect::svc::SvcSoupBinTcp;
 pub use crate::connect::svc::{SvcSoupBinTcpSender, SvcSoupBinTcpSenderRef};
 
+pub use crate::connect::stream::{
+    clt::{CltSoupBinTcpStream, CltSoupBinTcpStreamRecver, CltSoupBinTcpStreamRecverRef, CltSoupBinTcpStreamSender, CltSoupBinTcpStreamSenderRef},
+    svc::{SvcSoupBinTcpStream, SvcSoupBinTcpStreamSender, SvcSoupBinTcpStreamSenderRef},
+    FrameStream, FrameStreamListener,
+};
+
+pub use crate::connect::tcp::{SvcSoupBinTcpStreamTcp, SvcSoupBinTcpStreamTcpSender, SvcSoupBinTcpStreamTcpSenderRef, TcpStreamListener};
+
+#[cfg(unix)]
+pub use crate::connect::uds::{
+    CltSoupBinTcpUds, CltSoupBinTcpUdsRecver, CltSoupBinTcpUdsRecverRef, CltSoupBinTcpUdsSender, CltSoupBinTcpUdsSenderRef, SvcSoupBinTcpUds, SvcSoupBinTcpUdsSender, SvcSoupBinTcpUdsSenderRef,
+    UdsListener,
+};
+
+#[cfg(feature = "tls")]
+pub use crate::connect::tls::{CltSoupBinTcpTls, CltSoupBinTcpTlsConfig, SvcSoupBinTcpTls, SvcSoupBinTcpTlsConfig, TlsIdentity, TlsListener, TlsStream};
+
 pub use soupbintcp_connect_core::prelude::asserted_short_name;
 pub use soupbintcp_connect_core::prelude::*;
+
+pub use crate::conformance::{ConformanceConfig, ConformanceHarness, ConformanceOutcome, ConformanceReport, ConformanceResult, ConformanceScenario};
+
+pub use crate::moldudp64::{
+    consumer::{MoldUdp64Callback, MoldUdp64Consumer, MOLDUDP64_DEFAULT_MAX_PENDING},
+    deserialize_moldudp64_packet, deserialize_moldudp64_request,
+    publisher::{MoldUdp64Publisher, MoldUdp64RetransmitResponder, MOLDUDP64_DEFAULT_HBEAT_INTERVAL},
+    MoldUdp64Handle, MoldUdp64Packet, MOLDUDP64_MTU_PACKET_SIZE,
+};
+
+pub use crate::relay::{SvcSoupBinTcpRelay, SvcSoupBinTcpRelayHandle};
+
+#[cfg(feature = "prometheus")]
+pub use crate::prometheus::{PrometheusExporter, SoupBinTcpMetricsSource, CONTENT_TYPE};
@@ -0,0 +1,511 @@
+//! Serves [SoupBinTcpMetricsSnapshot]s in the Prometheus text exposition format over a minimal HTTP listener, enabled with the `prometheus` feature
+use crate::prelude::*;
+use std::{
+    fmt::{Display, Write as _},
+    io::{Error, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+const IO_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+/// Requests served concurrently, each on its own thread, connections beyond are closed without a response
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Anything which can produce a [SoupBinTcpMetricsSnapshot] on demand, implemented for [SoupBinTcpMetricsCallback]
+pub trait SoupBinTcpMetricsSource: Send + Sync + 'static {
+    /// Must not change what the next snapshot reports, since it is called on every scrape
+    fn snapshot(&self) -> SoupBinTcpMetricsSnapshot;
+    /// Stops reporting closed connections, returns the number of connections removed
+    fn remove_closed(&self) -> usize;
+}
+impl<M: Messenger + 'static, C: Clock> SoupBinTcpMetricsSource for SoupBinTcpMetricsCallback<M, C>
+where
+    Self: Send + Sync,
+{
+    fn snapshot(&self) -> SoupBinTcpMetricsSnapshot {
+        SoupBinTcpMetricsCallback::snapshot(self)
+    }
+    fn remove_closed(&self) -> usize {
+        SoupBinTcpMetricsCallback::remove_closed(self)
+    }
+}
+
+type Sources = Arc<Mutex<Vec<(String, Arc<dyn SoupBinTcpMetricsSource>)>>>;
+
+/// Local HTTP listener which answers `GET /metrics` with metrics of every registered source, each labeled with `session="<name>"`.
+///
+/// The [SoupBinTcpMetricsCallback] passed to a [SvcSoupBinTcp] or [CltSoupBinTcp] is registered as the source, to also count heartbeat timeouts
+/// & oversize frames register it with the protocol using `with_event_callback`. Listener thread is stopped when the exporter is dropped.
+///
+/// Scrapes never change the metrics, closed connections are reported until [`Self::remove_closed`] is called. Each request is served on its own
+/// thread so that a slow client can not delay other scrapes.
+///
+/// # Example
+/// ```
+/// use soupbintcp_connect_nonblocking::prelude::*;
+///
+/// let metrics = SoupBinTcpMetricsCallback::<SvcSoupBinTcpProtocolAuto<Nil, Nil>>::new_ref();
+/// let exporter = PrometheusExporter::bind("127.0.0.1:0").unwrap().with_source("svc", metrics);
+/// assert!(exporter.render().contains("soupbintcp_connections{session=\"svc\"} 0"));
+/// ```
+pub struct PrometheusExporter {
+    addr: SocketAddr,
+    sources: Sources,
+    scrape_count: Arc<AtomicUsize>,
+    is_running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl PrometheusExporter {
+    /// Binds to `addr`, use port `0` to pick any available port & [`Self::addr`] to find it
+    pub fn bind(addr: &str) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let sources: Sources = Default::default();
+        let scrape_count = Arc::new(AtomicUsize::new(0));
+        let is_running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let (sources, scrape_count, is_running) = (sources.clone(), scrape_count.clone(), is_running.clone());
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            thread::Builder::new().name(format!("prometheus-{}", addr)).spawn(move || {
+                while is_running.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, peer)) => {
+                            if in_flight.fetch_add(1, Ordering::Relaxed) >= MAX_CONCURRENT_REQUESTS {
+                                in_flight.fetch_sub(1, Ordering::Relaxed);
+                                log::debug!("prometheus exporter {} closed connection from {} exceeding {} concurrent requests", addr, peer, MAX_CONCURRENT_REQUESTS);
+                                continue;
+                            }
+                            let (sources, scrape_count, in_flight) = (sources.clone(), scrape_count.clone(), in_flight.clone());
+                            let res = thread::Builder::new().name(format!("prometheus-{}-{}", addr, peer)).spawn(move || {
+                                if let Err(e) = serve(stream, &sources) {
+                                    log::debug!("prometheus exporter {} failed to serve request from {}, error: {}", addr, peer, e);
+                                }
+                                scrape_count.fetch_add(1, Ordering::Relaxed);
+                                in_flight.fetch_sub(1, Ordering::Relaxed);
+                            });
+                            if let Err(e) = res {
+                                in_flight.fetch_sub(1, Ordering::Relaxed);
+                                log::warn!("prometheus exporter {} failed to spawn request thread, error: {}", addr, e);
+                            }
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => sleep(POLL_INTERVAL),
+                        Err(e) => {
+                            log::warn!("prometheus exporter {} stopped accepting, error: {}", addr, e);
+                            break;
+                        }
+                    }
+                }
+            })?
+        };
+        Ok(Self {
+            addr,
+            sources,
+            scrape_count,
+            is_running,
+            thread: Some(thread),
+        })
+    }
+    pub fn with_source(self, session: &str, source: Arc<dyn SoupBinTcpMetricsSource>) -> Self {
+        self.add_source(session, source);
+        self
+    }
+    /// Replaces the source if one with the same `session` name was already registered
+    pub fn add_source(&self, session: &str, source: Arc<dyn SoupBinTcpMetricsSource>) {
+        let mut sources = self.sources.lock().unwrap();
+        sources.retain(|(name, _)| name != session);
+        sources.push((session.to_owned(), source));
+    }
+    pub fn remove_source(&self, session: &str) {
+        self.sources.lock().unwrap().retain(|(name, _)| name != session);
+    }
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+    /// Number of HTTP requests served, including the ones answered with an error status
+    pub fn scrape_count(&self) -> usize {
+        self.scrape_count.load(Ordering::Relaxed)
+    }
+    /// Body of the `GET /metrics` response
+    pub fn render(&self) -> String {
+        render(&self.sources)
+    }
+    /// Stops reporting closed connections of every registered source, returns the number of connections removed
+    pub fn remove_closed(&self) -> usize {
+        self.sources.lock().unwrap().iter().map(|(_, source)| source.remove_closed()).sum()
+    }
+}
+impl Display for PrometheusExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sessions = self.sources.lock().unwrap().iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        write!(f, "PrometheusExporter<{}, sessions: {:?}, scrapes: {}>", self.addr, sessions, self.scrape_count())
+    }
+}
+impl Drop for PrometheusExporter {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, sources: &Sources) -> Result<(), Error> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut request = Vec::with_capacity(512);
+    let mut buf = [0_u8; 512];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "connection closed before end of request headers"));
+        }
+        request.extend_from_slice(&buf[..n]);
+        if request.len() > MAX_REQUEST_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, format!("request exceeds {} bytes", MAX_REQUEST_SIZE)));
+        }
+    }
+
+    let request_line = String::from_utf8_lossy(request.split(|b| *b == b'\r').next().unwrap_or_default()).into_owned();
+    let mut parts = request_line.split(' ');
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics" | "/")) => ("200 OK", CONTENT_TYPE, render(sources)),
+        (Some("GET"), Some(_)) => ("404 Not Found", "text/plain", "Not Found\n".to_owned()),
+        _ => ("405 Method Not Allowed", "text/plain", "Method Not Allowed\n".to_owned()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+fn render(sources: &Sources) -> String {
+    let snapshots = sources.lock().unwrap().iter().map(|(name, source)| (name.clone(), source.snapshot())).collect::<Vec<_>>();
+    let mut out = Encoder::default();
+
+    out.family("soupbintcp_connections", "gauge", "Connections which did not terminate the session", |out| {
+        for (session, snapshot) in &snapshots {
+            out.sample(&[("session", session)], snapshot.open_connections());
+        }
+    });
+    out.family(
+        "soupbintcp_tracked_connections",
+        "gauge",
+        "Connections tracked including terminated ones until removed, see PrometheusExporter::remove_closed",
+        |out| {
+            for (session, snapshot) in &snapshots {
+                out.sample(&[("session", session)], snapshot.connections.len());
+            }
+        },
+    );
+    out.family("soupbintcp_login_requests_total", "counter", "LoginRequest messages exchanged", |out| {
+        for (session, snapshot) in &snapshots {
+            out.sample(&[("session", session)], snapshot.logins.requested);
+        }
+    });
+    out.family("soupbintcp_logins_total", "counter", "Login responses exchanged by result", |out| {
+        for (session, snapshot) in &snapshots {
+            out.sample(&[("session", session), ("result", "accepted")], snapshot.logins.accepted);
+            out.sample(&[("session", session), ("result", "rejected_not_authorized")], snapshot.logins.rejected_not_authorized);
+            out.sample(&[("session", session), ("result", "rejected_session_not_available")], snapshot.logins.rejected_session_not_available);
+        }
+    });
+    out.family(
+        "soupbintcp_session_sequence_number",
+        "gauge",
+        "Highest sequence number published on any connection, server side only",
+        |out| {
+            for (session, snapshot) in &snapshots {
+                if let Some(sequence_number) = snapshot.session_sequence_number {
+                    out.sample(&[("session", session)], sequence_number);
+                }
+            }
+        },
+    );
+
+    out.family("soupbintcp_messages_total", "counter", "Messages exchanged by direction & packet type", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                for (direction, metrics) in [("recv", &con.recv), ("sent", &con.sent)] {
+                    for (packet_type, packet) in &metrics.by_packet_type {
+                        out.sample(
+                            &[("session", session), ("con_id", &con.con_id), ("direction", direction), ("packet_type", &packet_type.to_string())],
+                            packet.messages,
+                        );
+                    }
+                }
+            }
+        }
+    });
+    out.family(
+        "soupbintcp_bytes_total",
+        "counter",
+        "Bytes exchanged by direction & packet type including the packet_length field",
+        |out| {
+            for (session, snapshot) in &snapshots {
+                for con in &snapshot.connections {
+                    for (direction, metrics) in [("recv", &con.recv), ("sent", &con.sent)] {
+                        for (packet_type, packet) in &metrics.by_packet_type {
+                            out.sample(
+                                &[("session", session), ("con_id", &con.con_id), ("direction", direction), ("packet_type", &packet_type.to_string())],
+                                packet.bytes,
+                            );
+                        }
+                    }
+                }
+            }
+        },
+    );
+    out.family("soupbintcp_hbeats_total", "counter", "Heartbeats exchanged by direction", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                out.sample(&[("session", session), ("con_id", &con.con_id), ("direction", "recv")], con.recv.hbeats);
+                out.sample(&[("session", session), ("con_id", &con.con_id), ("direction", "sent")], con.sent.hbeats);
+            }
+        }
+    });
+    out.family("soupbintcp_hbeat_timeouts_total", "counter", "Connections terminated because the peer missed its heartbeats", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                out.sample(&[("session", session), ("con_id", &con.con_id)], con.events.hbeat_timeouts);
+            }
+        }
+    });
+    out.family("soupbintcp_frames_too_large_total", "counter", "Inbound frames which exceeded the maximum frame size", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                out.sample(&[("session", session), ("con_id", &con.con_id)], con.events.frames_too_large);
+            }
+        }
+    });
+    out.family("soupbintcp_connection_open", "gauge", "1 while the connection did not terminate the session", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                out.sample(&[("session", session), ("con_id", &con.con_id)], u8::from(con.is_open));
+            }
+        }
+    });
+    out.family("soupbintcp_next_sequence_number", "gauge", "Sequence number of the next sequenced message on the connection", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                if let Some(next_sequence_number) = con.next_sequence_number {
+                    out.sample(&[("session", session), ("con_id", &con.con_id)], next_sequence_number);
+                }
+            }
+        }
+    });
+    out.family(
+        "soupbintcp_replay_size",
+        "gauge",
+        "Sequenced messages replayed to the connection after login, server side only",
+        |out| {
+            for (session, snapshot) in &snapshots {
+                for con in &snapshot.connections {
+                    if let Some(replay_size) = con.replay_size {
+                        out.sample(&[("session", session), ("con_id", &con.con_id)], replay_size);
+                    }
+                }
+            }
+        },
+    );
+    out.family("soupbintcp_sequence_lag", "gauge", "Sequenced messages the connection is behind the session, server side only", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                if let Some(sequence_lag) = con.sequence_lag {
+                    out.sample(&[("session", session), ("con_id", &con.con_id)], sequence_lag);
+                }
+            }
+        }
+    });
+    out.family("soupbintcp_seconds_since_last_recv", "gauge", "Seconds since the last inbound frame on the connection", |out| {
+        for (session, snapshot) in &snapshots {
+            for con in &snapshot.connections {
+                if let Some(since_last_recv) = con.since_last_recv {
+                    out.sample(&[("session", session), ("con_id", &con.con_id)], since_last_recv.as_secs_f64());
+                }
+            }
+        }
+    });
+    out.text
+}
+
+#[derive(Default)]
+struct Encoder {
+    text: String,
+    name: &'static str,
+}
+impl Encoder {
+    fn family(&mut self, name: &'static str, kind: &str, help: &str, samples: impl FnOnce(&mut Self)) {
+        self.name = name;
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+        samples(self);
+    }
+    fn sample(&mut self, labels: &[(&str, &str)], value: impl Display) {
+        self.text.push_str(self.name);
+        self.text.push('{');
+        for (i, (name, value)) in labels.iter().enumerate() {
+            if i > 0 {
+                self.text.push(',');
+            }
+            let _ = write!(self.text, "{}=\"", name);
+            for c in value.chars() {
+                match c {
+                    '\\' => self.text.push_str("\\\\"),
+                    '"' => self.text.push_str("\\\""),
+                    '\n' => self.text.push_str("\\n"),
+                    c => self.text.push(c),
+                }
+            }
+            self.text.push('"');
+        }
+        let _ = writeln!(self.text, "}} {}", value);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        num::NonZeroUsize,
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    const MAX_MSG_SIZE: usize = 128;
+
+    fn scrape(exporter: &PrometheusExporter, path: &str) -> String {
+        let mut stream = TcpStream::connect(exporter.addr()).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn scrape_until(exporter: &PrometheusExporter, expected: &[&str]) -> String {
+        let start = Instant::now();
+        loop {
+            let response = scrape(exporter, "/metrics");
+            if expected.iter().all(|line| response.lines().any(|l| l == *line)) || start.elapsed() > Duration::from_secs(1) {
+                return response;
+            }
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_prometheus_exporter() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let addr = setup::net::rand_avail_addr_port();
+        let io_timeout = setup::net::find_timeout();
+        let username: UserName = b"userid".as_slice().into();
+        let password: Password = b"passwd".as_slice().into();
+        let session_id: SessionId = b"session".as_slice().into();
+
+        let svc_metrics = SoupBinTcpMetricsCallback::<SvcSoupBinTcpProtocolAuto<VecPayload, VecPayload>>::new_ref();
+        let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, Duration::from_secs(1)).with_event_callback(svc_metrics.clone());
+        let mut svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(addr, NonZeroUsize::new(2).unwrap(), svc_metrics.clone(), protocol, Some("svc/soupbintcp/prometheus"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+
+        let clt_metrics = SoupBinTcpMetricsCallback::<CltSoupBinTcpProtocolAuto<VecPayload, VecPayload>>::new_ref();
+        let protocol = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+        let _clt = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            addr,
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            clt_metrics.clone(),
+            protocol,
+            Some("clt/soupbintcp/prometheus"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        for i in 1..=3 {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(VecPayload::new(format!("#{}", i).into_bytes())), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+
+        let exporter = PrometheusExporter::bind("127.0.0.1:0").unwrap().with_source("svc", svc_metrics).with_source("clt", clt_metrics);
+        info!("exporter: {}", exporter);
+
+        let response = scrape_until(&exporter, &["soupbintcp_session_sequence_number{session=\"svc\"} 3"]);
+        info!("response:\n{}", response);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(&format!("Content-Type: {}\r\n", CONTENT_TYPE)));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        for expected in [
+            "# TYPE soupbintcp_logins_total counter",
+            "soupbintcp_connections{session=\"svc\"} 1",
+            "soupbintcp_connections{session=\"clt\"} 1",
+            "soupbintcp_login_requests_total{session=\"svc\"} 1",
+            "soupbintcp_logins_total{session=\"svc\",result=\"accepted\"} 1",
+            "soupbintcp_logins_total{session=\"clt\",result=\"accepted\"} 1",
+            "soupbintcp_logins_total{session=\"svc\",result=\"rejected_not_authorized\"} 0",
+            "soupbintcp_session_sequence_number{session=\"svc\"} 3",
+        ] {
+            assert!(body.lines().any(|line| line == expected), "missing line: {}", expected);
+        }
+        // con_id label depends on the ephemeral port, hence only match the remaining labels & value
+        let find = |prefix: &str, suffix: &str| body.lines().any(|line| line.starts_with(prefix) && line.ends_with(suffix));
+        assert!(find("soupbintcp_messages_total{session=\"svc\"", "direction=\"sent\",packet_type=\"S\"} 3"));
+        assert!(find("soupbintcp_messages_total{session=\"clt\"", "direction=\"recv\",packet_type=\"S\"} 3"));
+        assert!(find("soupbintcp_bytes_total{session=\"svc\"", "direction=\"sent\",packet_type=\"A\"} 33"));
+        assert!(find("soupbintcp_replay_size{session=\"svc\"", "} 0"));
+        assert!(find("soupbintcp_sequence_lag{session=\"svc\"", "} 0"));
+        assert!(find("soupbintcp_next_sequence_number{session=\"clt\"", "} 4"));
+        assert!(find("soupbintcp_hbeat_timeouts_total{session=\"svc\"", "} 0"));
+        assert!(find("soupbintcp_seconds_since_last_recv{session=\"clt\"", ""));
+
+        // stalled client does not delay other scrapes
+        let _stalled = TcpStream::connect(exporter.addr()).unwrap();
+        let start = Instant::now();
+        assert!(scrape(&exporter, "/metrics").starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(start.elapsed() < Duration::from_secs(1), "elapsed: {:?}", start.elapsed());
+
+        assert!(scrape(&exporter, "/unknown").starts_with("HTTP/1.1 404 Not Found\r\n"));
+        exporter.remove_source("clt");
+        assert!(!exporter.render().contains("session=\"clt\""));
+        assert!(exporter.scrape_count() >= 2);
+    }
+
+    #[test]
+    fn test_prometheus_remove_closed() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let metrics = SoupBinTcpMetricsCallback::<SvcSoupBinTcpProtocolAuto<Nil, Nil>>::new_ref();
+        let con_id = ConId::svc(Some("svc"), "127.0.0.1:8080", None);
+        CallbackSend::on_sent(metrics.as_ref(), &con_id, &SvcSoupBinTcpMsg::hbeat());
+        SoupBinTcpEventCallback::on_event(metrics.as_ref(), &con_id, &SoupBinTcpEvent::PeerLogout);
+
+        let exporter = PrometheusExporter::bind("127.0.0.1:0").unwrap().with_source("svc", metrics);
+        // rendering does not prune, closed connection is reported on every scrape until removed
+        for _ in 0..2 {
+            assert!(exporter.render().contains("soupbintcp_tracked_connections{session=\"svc\"} 1"));
+        }
+        assert_eq!(exporter.remove_closed(), 1);
+        assert!(exporter.render().contains("soupbintcp_tracked_connections{session=\"svc\"} 0"));
+    }
+}
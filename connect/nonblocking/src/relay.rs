@@ -0,0 +1,334 @@
+//! Re-publishes an upstream SoupBinTCP session to many downstream clients, see [SvcSoupBinTcpRelay]
+use crate::prelude::*;
+use log::{debug, log_enabled, warn};
+use std::{
+    fmt::Display,
+    io::Error,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+type SvcSoupBinTcpRelayUpstream<RecvP, SendP, const MAX_MSG_SIZE: usize> = CltSoupBinTcpSenderRef<CltSoupBinTcpProtocolAuto<SendP, RecvP>, SoupBinTcpRelayUpstreamCallback<RecvP, SendP>, MAX_MSG_SIZE>;
+
+/// Interval at which the acceptor thread started by [`SvcSoupBinTcpRelay::bind`] checks for new connections
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Accepted downstream client & upstream sequence number of the next [SPayload] due to it
+type SvcSoupBinTcpRelayDownstream<RecvP, SendP, C, const MAX_MSG_SIZE: usize> = (CltSoupBinTcpSenderRef<SvcSoupBinTcpProtocolRelay<RecvP, SendP>, C, MAX_MSG_SIZE>, usize);
+
+/// Maintains a single [CltSoupBinTcpProtocolAuto] session with an upstream server, appends every [SPayload] it receives to [SoupBinTcpRelayLog]
+/// and serves downstream clients logging in with [SvcSoupBinTcpProtocolRelay] from that log, replaying from the requested sequence number & then live.
+/// Downstream clients see the upstream session id & sequence numbers, and receive [EndOfSession] once the upstream session ended, ex: upstream sent
+/// [EndOfSession] or the connection was lost, and all [SPayload]s were delivered to them.
+///
+/// # Important
+/// * Logins are handled by a dedicated thread started by [`Self::bind`], so a client which connects without logging in delays other logins for up to
+///   [`SvcSoupBinTcpProtocolRelay::io_timeout`] but not the delivery by [`Self::poll`]
+/// * Upstream is not reconnected, a relay whose upstream session ended must be replaced
+/// * Unsequenced data sent by downstream clients is delivered to their callback `C` only, it is not forwarded upstream
+#[derive(Debug)]
+pub struct SvcSoupBinTcpRelay<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<SvcSoupBinTcpProtocolRelay<RecvP, SendP>>, const MAX_MSG_SIZE: usize> {
+    upstream: SvcSoupBinTcpRelayUpstream<RecvP, SendP, MAX_MSG_SIZE>,
+    acceptor: String,
+    accepted: Receiver<SvcSoupBinTcpRelayDownstream<RecvP, SendP, C, MAX_MSG_SIZE>>,
+    _acceptor_handle: SvcSoupBinTcpRelayHandle, // stops the acceptor thread when dropped
+    protocol: SvcSoupBinTcpProtocolRelay<RecvP, SendP>,
+    downstream: Vec<SvcSoupBinTcpRelayDownstream<RecvP, SendP, C, MAX_MSG_SIZE>>,
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<SvcSoupBinTcpProtocolRelay<RecvP, SendP>>, const MAX_MSG_SIZE: usize>
+    SvcSoupBinTcpRelay<RecvP, SendP, C, MAX_MSG_SIZE>
+{
+    /// Connects to `upstream_addr` using `upstream_protocol` and once logged in binds to `addr` in the same way as [`SvcSoupBinTcp::bind`],
+    /// downstream logins are handled by a dedicated thread which hands accepted clients over to [`Self::poll`]
+    ///
+    /// # Arguments
+    /// * `upstream_protocol` - its sequence number determines the first [SPayload] available to downstream clients, typically `1` to relay the entire session
+    /// * `protocol` - authenticates downstream clients, [`SvcSoupBinTcpProtocolRelay::io_timeout`] is also used to connect to `upstream_addr`
+    pub fn bind(
+        upstream_addr: &str,
+        upstream_protocol: CltSoupBinTcpProtocolAuto<SendP, RecvP>,
+        addr: &str,
+        max_connections: NonZeroUsize,
+        callback: Arc<C>,
+        protocol: SvcSoupBinTcpProtocolRelay<RecvP, SendP>,
+        name: Option<&str>,
+    ) -> Result<Self, Error>
+    where
+        SvcAcceptor<SvcSoupBinTcpProtocolRelay<RecvP, SendP>, C, MAX_MSG_SIZE>: Send + 'static,
+        SvcSoupBinTcpRelayDownstream<RecvP, SendP, C, MAX_MSG_SIZE>: Send + 'static,
+        SvcSoupBinTcpProtocolRelay<RecvP, SendP>: Send + 'static,
+    {
+        let upstream = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(upstream_addr, protocol.io_timeout(), protocol.io_timeout() / 10, protocol.upstream_callback(), upstream_protocol, name)?
+            .into_sender_with_spawned_recver_ref();
+        let acceptor: SvcAcceptor<_, _, MAX_MSG_SIZE> = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(addr, max_connections, callback, protocol.clone(), name)?.into();
+        let acceptor_name = acceptor.to_string();
+        let (tx_accepted, accepted) = channel();
+        let is_running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let (acceptor_name, is_running, protocol) = (acceptor_name.clone(), is_running.clone(), protocol.clone());
+            thread::Builder::new().name("soupbintcp-relay-acceptor".to_owned()).spawn(move || {
+                while is_running.load(Ordering::Relaxed) {
+                    match acceptor.accept() {
+                        Ok(AcceptStatus::Accepted(clt)) => {
+                            let next_sequence_number = match protocol.take_accepted_sequence_number(clt.con_id()) {
+                                Some(next_sequence_number) => next_sequence_number,
+                                None => protocol.relay_log().lock().next_sequence_number(),
+                            };
+                            let sender = clt.into_sender_with_spawned_recver_ref();
+                            if log_enabled!(log::Level::Debug) {
+                                debug!("{} accepted downstream: {} at sequence number: {}", acceptor_name, sender, next_sequence_number);
+                            }
+                            if tx_accepted.send((sender, next_sequence_number)).is_err() {
+                                return; // relay dropped
+                            }
+                        }
+                        Ok(AcceptStatus::Rejected) => {}
+                        Ok(AcceptStatus::WouldBlock) => thread::sleep(ACCEPT_POLL_INTERVAL),
+                        Err(e) => warn!("{} Rejected connection due to on_connect error: {}", acceptor_name, e),
+                    }
+                }
+            })?
+        };
+        Ok(Self {
+            upstream,
+            acceptor: acceptor_name,
+            accepted,
+            _acceptor_handle: SvcSoupBinTcpRelayHandle { is_running, thread: Some(thread) },
+            protocol,
+            downstream: Vec::new(),
+        })
+    }
+    /// Upstream session, learned from its [LoginAccepted]
+    #[inline(always)]
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.protocol.relay_log().lock().session_id()
+    }
+    /// Upstream sequence number of the next [SPayload] to be received
+    #[inline(always)]
+    pub fn next_sequence_number(&self) -> usize {
+        self.protocol.relay_log().lock().next_sequence_number()
+    }
+    /// `true` once the upstream session ended, no further logins are accepted
+    #[inline(always)]
+    pub fn is_ended(&self) -> bool {
+        self.protocol.relay_log().lock().is_ended()
+    }
+    /// Number of downstream clients being served
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.downstream.len()
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.downstream.is_empty()
+    }
+    /// Takes over downstream clients logged in since the previous call & sends every [SPayload] due to each downstream client, returns the number of
+    /// clients taken over & messages sent. Clients which disconnected are dropped, once the upstream session ended clients which received all [SPayload]s
+    /// are dropped with [EndOfSession].
+    pub fn poll(&mut self) -> usize {
+        let count_before = self.downstream.len();
+        self.downstream.extend(self.accepted.try_iter());
+        let mut count = self.downstream.len() - count_before;
+        if !self.upstream.is_connected() {
+            self.protocol.relay_log().lock().end();
+        }
+        let relay_log = self.protocol.relay_log();
+        self.downstream.retain_mut(|(sender, next_sequence_number)| {
+            loop {
+                let Some(msg) = relay_log.lock().get(*next_sequence_number).cloned() else {
+                    break;
+                };
+                match sender.send(&mut SvcSoupBinTcpMsg::SPayload(msg)) {
+                    Ok(SendStatus::Completed) => {
+                        *next_sequence_number += 1;
+                        count += 1;
+                    }
+                    Ok(SendStatus::WouldBlock) => return true,
+                    Err(e) => {
+                        if log_enabled!(log::Level::Debug) {
+                            debug!("{} dropping downstream at sequence number: {} due to error: {}", sender, next_sequence_number, e);
+                        }
+                        return false;
+                    }
+                }
+            }
+            let relay_log = relay_log.lock();
+            !(relay_log.is_ended() && *next_sequence_number >= relay_log.next_sequence_number())
+        });
+        count
+    }
+    /// Calls [`Self::poll`] on a dedicated thread until the returned [SvcSoupBinTcpRelayHandle] is dropped, sleeps for `poll_interval` whenever there was nothing to do
+    pub fn spawn(mut self, poll_interval: Duration) -> Result<SvcSoupBinTcpRelayHandle, Error>
+    where
+        Self: Send + 'static,
+    {
+        let is_running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let is_running = is_running.clone();
+            thread::Builder::new().name("soupbintcp-relay".to_owned()).spawn(move || {
+                while is_running.load(Ordering::Relaxed) {
+                    if self.poll() == 0 {
+                        thread::sleep(poll_interval);
+                    }
+                }
+            })?
+        };
+        Ok(SvcSoupBinTcpRelayHandle { is_running, thread: Some(thread) })
+    }
+}
+impl<RecvP: SoupBinTcpPayload<RecvP>, SendP: SoupBinTcpPayload<SendP>, C: CallbackRecvSend<SvcSoupBinTcpProtocolRelay<RecvP, SendP>>, const MAX_MSG_SIZE: usize> Display
+    for SvcSoupBinTcpRelay<RecvP, SendP, C, MAX_MSG_SIZE>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}<upstream: {}, {}, downstream: {}>",
+            asserted_short_name!("SvcSoupBinTcpRelay", Self),
+            self.upstream.con_id(),
+            self.acceptor,
+            self.downstream.len()
+        )
+    }
+}
+
+/// Stops the thread started by [`SvcSoupBinTcpRelay::spawn`] when dropped, which disconnects upstream & all downstream clients
+#[derive(Debug)]
+pub struct SvcSoupBinTcpRelayHandle {
+    is_running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Drop for SvcSoupBinTcpRelayHandle {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unittest")]
+mod test {
+    use crate::prelude::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{
+        num::NonZeroUsize,
+        time::{Duration, Instant},
+    };
+
+    const SOUP_BIN_MAX_FRAME_SIZE: usize = SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+
+    fn recv_skipping_hbeats<R: RecvNonBlocking<SvcSoupBinTcpMsg<SamplePayload>>>(clt: &mut R, timeout: Duration) -> SvcSoupBinTcpMsg<SamplePayload> {
+        loop {
+            match clt.recv_busywait_timeout(timeout).unwrap().unwrap_completed_some() {
+                SvcSoupBinTcpMsg::HBeat(_) => {}
+                msg => return msg,
+            }
+        }
+    }
+
+    #[test]
+    fn test_relay() {
+        setup::log::configure_level(log::LevelFilter::Info);
+        let upstream_addr = setup::net::rand_avail_addr_port();
+        let addr = setup::net::rand_avail_addr_port();
+        let upstream_username: UserName = b"upstrm".as_slice().into();
+        let upstream_password: Password = b"upstrm".as_slice().into();
+        let username: UserName = b"dnstrm".as_slice().into();
+        let password: Password = b"dnstrm".as_slice().into();
+        let session_a: SessionId = b"session #a".as_slice().into();
+        let session_b: SessionId = b"session #b".as_slice().into();
+        let io_timeout = setup::net::default_connect_timeout();
+        let max_hbeat_interval = Duration::from_secs(1);
+
+        let svc_protocol = SvcSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(upstream_username, upstream_password, session_a, io_timeout, max_hbeat_interval);
+        let mut svc = SvcSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(
+            upstream_addr,
+            NonZeroUsize::new(1).unwrap(),
+            DevNullCallback::new_ref(),
+            svc_protocol.clone(),
+            Some("svc/soupbintcp/upstream"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+
+        // relay is the only upstream client, so every payload sent by svc is delivered to it
+        let upstream_protocol =
+            CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(upstream_username, upstream_password, session_a, 1_usize.into(), io_timeout, max_hbeat_interval, max_hbeat_interval);
+        let relay_protocol = SvcSoupBinTcpProtocolRelay::<SamplePayload, SamplePayload>::new(username, password, io_timeout, Duration::from_secs(5));
+        let mut relay = SvcSoupBinTcpRelay::<_, _, _, SOUP_BIN_MAX_FRAME_SIZE>::bind(
+            upstream_addr,
+            upstream_protocol,
+            addr,
+            NonZeroUsize::new(2).unwrap(),
+            DevNullCallback::new_ref(),
+            relay_protocol.clone(),
+            Some("svc/soupbintcp/relay"),
+        )
+        .unwrap();
+        info!("relay: {}", relay);
+        assert!(svc.all_connected_busywait_timeout(io_timeout));
+        assert_eq!(relay.session_id(), Some(session_a));
+
+        const N_REPLAYED: usize = 5;
+        const N_LIVE: usize = 3;
+        let payloads = (1..=N_REPLAYED + N_LIVE).map(|i| SamplePayload::new(format!("#{} relayed", i).as_bytes().into())).collect::<Vec<_>>();
+        for payload in payloads[..N_REPLAYED].iter() {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        }
+        let started = Instant::now();
+        while relay.next_sequence_number() <= N_REPLAYED && started.elapsed() < io_timeout {
+            assert_eq!(relay.poll(), 0);
+        }
+        assert_eq!(relay.next_sequence_number(), N_REPLAYED + 1);
+        let _relay = relay.spawn(Duration::from_millis(1)).unwrap();
+
+        // downstream credentials are independent of upstream
+        let clt_protocol = |password: Password, sequence_number: usize| {
+            CltSoupBinTcpProtocolAuto::<SamplePayload, SamplePayload>::new(username, password, session_a, sequence_number.into(), io_timeout, Duration::from_secs(5), Duration::from_secs(5))
+        };
+        let connect = |protocol: CltSoupBinTcpProtocolAuto<SamplePayload, SamplePayload>| {
+            CltSoupBinTcp::<_, _, SOUP_BIN_MAX_FRAME_SIZE>::connect(
+                addr,
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                DevNullCallback::new_ref(),
+                protocol,
+                Some("clt/soupbintcp/relay"),
+            )
+        };
+        let err = connect(clt_protocol(upstream_password, 1)).unwrap_err();
+        info!("err: {}", err);
+
+        // replay from the start & from the current sequence number
+        let mut clt_replay = connect(clt_protocol(password, 1)).unwrap();
+        let mut clt_live = connect(clt_protocol(password, 0)).unwrap();
+        for payload in payloads[..N_REPLAYED].iter() {
+            assert_eq!(recv_skipping_hbeats(&mut clt_replay, io_timeout), SvcSoupBinTcpMsg::sdata(payload.clone()));
+        }
+        for payload in payloads[N_REPLAYED..].iter() {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(payload.clone()), io_timeout).unwrap().unwrap_completed();
+        }
+        for payload in payloads[N_REPLAYED..].iter() {
+            assert_eq!(recv_skipping_hbeats(&mut clt_replay, io_timeout), SvcSoupBinTcpMsg::sdata(payload.clone()));
+            assert_eq!(recv_skipping_hbeats(&mut clt_live, io_timeout), SvcSoupBinTcpMsg::sdata(payload.clone()));
+        }
+        assert_eq!(relay_protocol.relay_log().lock().next_sequence_number(), N_REPLAYED + N_LIVE + 1);
+
+        // upstream end of session is relayed downstream
+        svc_protocol.rollover(session_b).unwrap();
+        let timeout = max_hbeat_interval + io_timeout;
+        assert_eq!(recv_skipping_hbeats(&mut clt_replay, timeout), SvcSoupBinTcpMsg::EndOfSession(EndOfSession::default()));
+        assert_eq!(recv_skipping_hbeats(&mut clt_live, timeout), SvcSoupBinTcpMsg::EndOfSession(EndOfSession::default()));
+        assert!(relay_protocol.relay_log().lock().is_ended());
+        let err = connect(clt_protocol(password, 1)).unwrap_err();
+        info!("err: {}", err);
+    }
+}
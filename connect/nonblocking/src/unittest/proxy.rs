@@ -0,0 +1,586 @@
+//! Fault injecting loopback proxy which sits between a [CltSoupBinTcp] & [SvcSoupBinTcp] and applies scripted [FaultRule]s to individual SoupBinTcp frames
+use crate::prelude::*;
+use bytes::BytesMut;
+use std::{
+    fmt::Display,
+    io::{Error, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep},
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyDirection {
+    CltToSvc,
+    SvcToClt,
+}
+impl ProxyDirection {
+    #[inline(always)]
+    fn index(&self) -> usize {
+        match self {
+            Self::CltToSvc => 0,
+            Self::SvcToClt => 1,
+        }
+    }
+}
+
+/// Selects frames a [FaultRule] applies to, indexes are 1 based and restart with every proxied connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameMatch {
+    Any,
+    /// Nth frame of any packet type
+    Nth(usize),
+    /// Nth [SPayload] frame, example [SvcSoupBinTcpMsg::SPayload] when [ProxyDirection::SvcToClt]
+    NthSequenced(usize),
+    /// Every frame of the given packet type, example `b'H'`
+    PacketType(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Holds the frame before forwarding it, all following frames in the same direction are held behind it
+    Delay(Duration),
+    /// Does not forward the frame
+    Drop,
+    /// Forwards only the first `len` bytes of the frame with `packet_length` rewritten to match, so the peer receives a well framed but short packet
+    Truncate(usize),
+    /// Writes the frame in two segments split at byte `at` with a pause in between, exercising frame reassembly of the peer
+    Split { at: usize, pause: Duration },
+    /// Forwards the frame twice
+    Duplicate,
+    /// Holds the frame & forwards it right after the next frame in the same direction
+    Reorder,
+    /// Pauses the direction before forwarding the frame, until [`FaultProxy::resume`] is called
+    Pause,
+    /// Closes both sides of the connection instead of forwarding the frame
+    Cut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultRule {
+    pub direction: ProxyDirection,
+    pub matcher: FrameMatch,
+    pub fault: Fault,
+    /// Number of frames the rule is applied to across all connections, `None` means unlimited
+    pub times: Option<usize>,
+}
+impl FaultRule {
+    pub fn new(direction: ProxyDirection, matcher: FrameMatch, fault: Fault) -> Self {
+        Self {
+            direction,
+            matcher,
+            fault,
+            times: None,
+        }
+    }
+    /// Limits the rule to the first `times` matching frames, example `1` so that a [Fault::Cut] is not repeated after a reconnect
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+    fn is_match(&self, direction: ProxyDirection, frame: &[u8], counts: &FrameCounts) -> bool {
+        if self.direction != direction || self.times == Some(0) {
+            return false;
+        }
+        match self.matcher {
+            FrameMatch::Any => true,
+            FrameMatch::Nth(n) => counts.frames == n,
+            FrameMatch::NthSequenced(n) => is_sequenced(frame) && counts.sequenced == n,
+            FrameMatch::PacketType(packet_type) => frame.get(2) == Some(&packet_type),
+        }
+    }
+}
+impl Display for FaultRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {:?} {:?}", self.direction, self.matcher, self.fault)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FrameCounts {
+    frames: usize,
+    sequenced: usize,
+}
+
+#[derive(Debug)]
+struct ProxyShared {
+    svc_addr: String,
+    rules: Mutex<Vec<FaultRule>>,
+    paused: [AtomicBool; 2],
+    forwarded: [AtomicUsize; 2],
+    faults: AtomicUsize,
+    connections: Mutex<Vec<TcpStream>>,
+    connection_count: AtomicUsize,
+    terminate: AtomicBool,
+}
+impl ProxyShared {
+    /// First matching rule wins, a frame is subject to at most one [Fault]
+    fn next_fault(&self, direction: ProxyDirection, frame: &[u8], counts: &FrameCounts) -> Option<Fault> {
+        let mut rules = self.rules.lock().unwrap();
+        let rule = rules.iter_mut().find(|rule| rule.is_match(direction, frame, counts))?;
+        if let Some(times) = rule.times.as_mut() {
+            *times -= 1;
+        }
+        self.faults.fetch_add(1, Ordering::Relaxed);
+        Some(rule.fault.clone())
+    }
+    fn wait_while_paused(&self, direction: ProxyDirection) {
+        while self.paused[direction.index()].load(Ordering::Relaxed) && !self.terminate.load(Ordering::Relaxed) {
+            sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Loopback proxy that forwards SoupBinTcp frames between a client connected to [`FaultProxy::addr`] and a server at `svc_addr`, while applying scripted
+/// [FaultRule]s so that reconnect, heartbeat timeout, decode error & replay paths of the auto protocols can be tested deterministically.
+///
+/// Every accepted client connection gets its own upstream connection, dropping the proxy closes all of them.
+///
+/// # Example
+/// ```no_run
+/// use soupbintcp_connect_nonblocking::{prelude::*, unittest::proxy::*};
+///
+/// let proxy = FaultProxy::bind("127.0.0.1:8080").unwrap()
+///     .with_rule(FaultRule::new(ProxyDirection::SvcToClt, FrameMatch::NthSequenced(5), Fault::Cut).times(1));
+/// // connect CltSoupBinTcp to proxy.addr() instead of the svc
+/// ```
+#[derive(Debug)]
+pub struct FaultProxy {
+    addr: String,
+    shared: Arc<ProxyShared>,
+}
+impl FaultProxy {
+    /// Binds the proxy to a random loopback port and starts accepting connections, upstream connection to `svc_addr` is established for each accepted client
+    pub fn bind(svc_addr: &str) -> Result<Self, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?.to_string();
+        let shared = Arc::new(ProxyShared {
+            svc_addr: svc_addr.to_owned(),
+            rules: Mutex::new(vec![]),
+            paused: [AtomicBool::new(false), AtomicBool::new(false)],
+            forwarded: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            faults: AtomicUsize::new(0),
+            connections: Mutex::new(vec![]),
+            connection_count: AtomicUsize::new(0),
+            terminate: AtomicBool::new(false),
+        });
+        let accept_shared = shared.clone();
+        thread::Builder::new().name(format!("proxy-accept-{}", addr)).spawn(move || accept(listener, accept_shared))?;
+        log::info!("FaultProxy listening on {} forwarding to {}", addr, svc_addr);
+        Ok(Self { addr, shared })
+    }
+    pub fn with_rule(self, rule: FaultRule) -> Self {
+        self.add_rule(rule);
+        self
+    }
+    /// Rules can be added while connections are active, they are evaluated in the order they were added
+    pub fn add_rule(&self, rule: FaultRule) {
+        self.shared.rules.lock().unwrap().push(rule);
+    }
+    pub fn clear_rules(&self) {
+        self.shared.rules.lock().unwrap().clear();
+    }
+    /// Address clients should connect to
+    #[inline(always)]
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+    /// Stops forwarding frames in the given direction, frames are held by the proxy & socket buffers until [`Self::resume`]
+    pub fn pause(&self, direction: ProxyDirection) {
+        self.shared.paused[direction.index()].store(true, Ordering::Relaxed);
+    }
+    pub fn resume(&self, direction: ProxyDirection) {
+        self.shared.paused[direction.index()].store(false, Ordering::Relaxed);
+    }
+    /// Closes both sides of all proxied connections, new connections are still accepted
+    pub fn cut(&self) {
+        for stream in self.shared.connections.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+    /// Number of client connections accepted
+    pub fn connection_count(&self) -> usize {
+        self.shared.connection_count.load(Ordering::Relaxed)
+    }
+    /// Number of frames written in the given direction, a [Fault::Duplicate] counts twice, [Fault::Drop] & [Fault::Cut] are not counted
+    pub fn forwarded_count(&self, direction: ProxyDirection) -> usize {
+        self.shared.forwarded[direction.index()].load(Ordering::Relaxed)
+    }
+    /// Number of frames a [Fault] was applied to
+    pub fn fault_count(&self) -> usize {
+        self.shared.faults.load(Ordering::Relaxed)
+    }
+}
+impl Display for FaultProxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "FaultProxy {{ addr: {}, svc_addr: {}, connections: {}, forwarded clt->svc: {}, svc->clt: {}, faults: {} }}",
+            self.addr,
+            self.shared.svc_addr,
+            self.connection_count(),
+            self.forwarded_count(ProxyDirection::CltToSvc),
+            self.forwarded_count(ProxyDirection::SvcToClt),
+            self.fault_count()
+        )
+    }
+}
+impl Drop for FaultProxy {
+    fn drop(&mut self) {
+        self.shared.terminate.store(true, Ordering::Relaxed);
+        self.cut();
+    }
+}
+
+fn accept(listener: TcpListener, shared: Arc<ProxyShared>) {
+    while !shared.terminate.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((clt, _)) => {
+                if let Err(err) = connect(clt, &shared) {
+                    log::warn!("FaultProxy failed to proxy connection to {}, error: {}", shared.svc_addr, err);
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => sleep(POLL_INTERVAL),
+            Err(err) => {
+                log::warn!("FaultProxy accept failed, error: {}", err);
+                return;
+            }
+        }
+    }
+}
+
+fn connect(clt: TcpStream, shared: &Arc<ProxyShared>) -> Result<(), Error> {
+    clt.set_nonblocking(false)?;
+    let svc = match TcpStream::connect(&shared.svc_addr) {
+        Ok(svc) => svc,
+        Err(err) => {
+            let _ = clt.shutdown(Shutdown::Both);
+            return Err(err);
+        }
+    };
+    clt.set_nodelay(true)?;
+    svc.set_nodelay(true)?;
+    {
+        let mut connections = shared.connections.lock().unwrap();
+        connections.push(clt.try_clone()?);
+        connections.push(svc.try_clone()?);
+    }
+    let con_num = shared.connection_count.fetch_add(1, Ordering::Relaxed) + 1;
+
+    for (direction, from, to) in [(ProxyDirection::CltToSvc, clt.try_clone()?, svc.try_clone()?), (ProxyDirection::SvcToClt, svc, clt)] {
+        let shared = shared.clone();
+        thread::Builder::new().name(format!("proxy-{:?}-#{}", direction, con_num)).spawn(move || {
+            pump(direction, from, to, &shared);
+        })?;
+    }
+    Ok(())
+}
+
+/// Forwards frames until either side closes or a [Fault::Cut] is applied, then closes both sides
+fn pump(direction: ProxyDirection, mut from: TcpStream, mut to: TcpStream, shared: &ProxyShared) {
+    let mut buf = BytesMut::new();
+    let mut read_buf = [0_u8; 4096];
+    let mut counts = FrameCounts::default();
+    let mut held: Option<BytesMut> = None;
+    'read: loop {
+        let len = match from.read(&mut read_buf) {
+            Ok(0) | Err(_) => break 'read,
+            Ok(len) => len,
+        };
+        buf.extend_from_slice(&read_buf[..len]);
+        while let Some(frame_len) = SoupBinTcpFramer::get_frame_length(&buf) {
+            let frame = buf.split_to(frame_len);
+            counts.frames += 1;
+            if is_sequenced(&frame) {
+                counts.sequenced += 1;
+            }
+            shared.wait_while_paused(direction);
+            if forward(direction, frame, &mut to, &mut held, &counts, shared).is_err() {
+                break 'read;
+            }
+        }
+    }
+    let _ = from.shutdown(Shutdown::Both);
+    let _ = to.shutdown(Shutdown::Both);
+}
+
+fn forward(direction: ProxyDirection, frame: BytesMut, to: &mut TcpStream, held: &mut Option<BytesMut>, counts: &FrameCounts, shared: &ProxyShared) -> Result<(), Error> {
+    let fault = shared.next_fault(direction, &frame, counts);
+    if let Some(fault) = &fault {
+        log::info!("FaultProxy {:?} frame #{} {:?} excerpt: {:02x?}", direction, counts.frames, fault, &frame[..frame.len().min(3)]);
+    }
+    let written = match fault {
+        None => write(to, &[&frame])?,
+        Some(Fault::Delay(delay)) => {
+            sleep(delay);
+            write(to, &[&frame])?
+        }
+        Some(Fault::Drop) => 0,
+        Some(Fault::Truncate(len)) => {
+            let mut frame = frame;
+            frame.truncate(len.max(2));
+            let packet_length = (frame.len() - 2) as u16;
+            frame[..2].copy_from_slice(&packet_length.to_be_bytes());
+            write(to, &[&frame])?
+        }
+        Some(Fault::Split { at, pause }) => {
+            let at = at.min(frame.len());
+            write(to, &[&frame[..at]])?;
+            sleep(pause);
+            write(to, &[&frame[at..]])?;
+            1
+        }
+        Some(Fault::Duplicate) => write(to, &[&frame, &frame])?,
+        Some(Fault::Reorder) => {
+            // a frame held by an earlier reorder is released in its place so that at most one frame is held
+            if let Some(released) = held.replace(frame) {
+                let written = write(to, &[&released])?;
+                shared.forwarded[direction.index()].fetch_add(written, Ordering::Relaxed);
+            }
+            return Ok(());
+        }
+        Some(Fault::Pause) => {
+            shared.paused[direction.index()].store(true, Ordering::Relaxed);
+            shared.wait_while_paused(direction);
+            write(to, &[&frame])?
+        }
+        Some(Fault::Cut) => return Err(Error::new(std::io::ErrorKind::ConnectionAborted, "FaultProxy cut")),
+    };
+    shared.forwarded[direction.index()].fetch_add(written, Ordering::Relaxed);
+    if let Some(held) = held.take() {
+        let written = write(to, &[&held])?;
+        shared.forwarded[direction.index()].fetch_add(written, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Returns number of frames written
+fn write(to: &mut TcpStream, frames: &[&[u8]]) -> Result<usize, Error> {
+    for frame in frames {
+        to.write_all(frame)?;
+    }
+    to.flush()?;
+    Ok(frames.len())
+}
+
+#[inline(always)]
+fn is_sequenced(frame: &[u8]) -> bool {
+    frame.get(2) == Some(&b'S')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use links_core::unittest::setup;
+    use log::info;
+    use std::{num::NonZeroUsize, time::Instant};
+
+    const MAX_MSG_SIZE: usize = 1024;
+    type UnitMsg = UniSoupBinTcpMsg<VecPayload, VecPayload>;
+
+    fn credentials() -> (UserName, Password, SessionId) {
+        (b"userid".as_slice().into(), b"passwd".as_slice().into(), b"session".as_slice().into())
+    }
+    fn is_spayload(msg: &UnitMsg, text: &str) -> bool {
+        matches!(msg, UniSoupBinTcpMsg::Svc(SvcSoupBinTcpMsg::SPayload(msg)) if msg.payload.payload == text.as_bytes())
+    }
+
+    #[test]
+    fn test_fault_proxy_frames() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let svc_addr = setup::net::rand_avail_addr_port();
+        let io_timeout = setup::net::find_timeout();
+
+        let mut svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(
+            svc_addr,
+            NonZeroUsize::new(1).unwrap(),
+            DevNullCallback::new_ref(),
+            SvcSoupBinTcpProtocolManual::<VecPayload, VecPayload>::default(),
+            Some("svc/soupbintcp/proxy"),
+        )
+        .unwrap();
+        let proxy = FaultProxy::bind(svc_addr)
+            .unwrap()
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(2), Fault::Drop))
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(3), Fault::Duplicate))
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(4), Fault::Reorder))
+            .with_rule(FaultRule::new(
+                ProxyDirection::CltToSvc,
+                FrameMatch::Nth(6),
+                Fault::Split {
+                    at: 3,
+                    pause: Duration::from_millis(10),
+                },
+            ))
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(7), Fault::Delay(Duration::from_millis(10))))
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(8), Fault::Truncate(3)))
+            .with_rule(FaultRule::new(ProxyDirection::CltToSvc, FrameMatch::Nth(9), Fault::Cut));
+        let mut clt = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            proxy.addr(),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            CltSoupBinTcpProtocolManual::<VecPayload, VecPayload>::default(),
+            Some("clt/soupbintcp/proxy"),
+        )
+        .unwrap();
+        svc.accept_into_pool_busywait_timeout(setup::net::default_connect_timeout()).unwrap().unwrap_accepted();
+
+        for i in 1..=9_u8 {
+            clt.send_busywait_timeout(&mut CltSoupBinTcpMsg::udata(VecPayload::new(vec![b'0' + i])), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+
+        let expected: [&[u8]; 8] = [b"1", b"3", b"3", b"5", b"4", b"6", b"7", b""];
+        for expected in expected {
+            let msg = svc.recv_busywait_timeout(io_timeout).unwrap().unwrap_completed_some();
+            info!("svc recv: {:?}", msg);
+            assert_eq!(msg, CltSoupBinTcpMsg::udata(VecPayload::new(expected.to_vec())));
+        }
+        let res = svc.recv_busywait_timeout(io_timeout);
+        info!("svc recv after cut: {:?}", res);
+        assert!(!matches!(res, Ok(RecvStatus::Completed(Some(_)))));
+
+        info!("proxy: {}", proxy);
+        assert_eq!(proxy.forwarded_count(ProxyDirection::CltToSvc), 8);
+        assert_eq!(proxy.fault_count(), 7);
+    }
+
+    #[test]
+    fn test_fault_proxy_cut_at_nth_sequenced_then_replay() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        const N_SEQUENCED_PAYLOADS: usize = 10;
+        let svc_addr = setup::net::rand_avail_addr_port();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Some(Duration::from_secs(1));
+        let (username, password, session_id) = credentials();
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let mut svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(svc_addr, NonZeroUsize::new(2).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/proxy"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let proxy = FaultProxy::bind(svc_addr)
+            .unwrap()
+            .with_rule(FaultRule::new(ProxyDirection::SvcToClt, FrameMatch::NthSequenced(5), Fault::Cut).times(1));
+
+        let connect = |sequence_number: u64, store: Arc<CanonicalEntryStore<UnitMsg>>| {
+            let protocol = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, sequence_number.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+            CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+                proxy.addr(),
+                setup::net::default_connect_timeout(),
+                setup::net::default_connect_retry_after(),
+                ChainCallback::new_ref(vec![StoreCallback::new_ref(store), LoggerCallback::with_level_ref(log::Level::Info, log::Level::Info)]),
+                protocol,
+                Some("clt/soupbintcp/proxy"),
+            )
+            .unwrap()
+            .into_sender_with_spawned_recver_ref()
+        };
+
+        let clt_store = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let _clt = connect(0, clt_store.clone());
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // hold svc frames so that all payloads are written by the svc before the connection is cut
+        proxy.pause(ProxyDirection::SvcToClt);
+        for i in 1..=N_SEQUENCED_PAYLOADS {
+            svc.send_busywait_timeout(&mut SvcSoupBinTcpMsg::sdata(VecPayload::new(format!("#{}", i).into_bytes())), io_timeout)
+                .unwrap()
+                .unwrap_completed();
+        }
+        proxy.resume(ProxyDirection::SvcToClt);
+
+        assert!(clt_store.find_recv("clt/soupbintcp/proxy", |msg| is_spayload(msg, "#4"), wait_timeout).is_some());
+        assert!(clt_store.find_recv("clt/soupbintcp/proxy", |msg| is_spayload(msg, "#5"), Some(Duration::from_millis(100))).is_none());
+
+        // reconnect from the first sequence number not received
+        let clt_store_reconnect = CanonicalEntryStore::<UnitMsg>::new_ref();
+        let _clt_reconnect = connect(5, clt_store_reconnect.clone());
+        for i in 5..=N_SEQUENCED_PAYLOADS {
+            let found = clt_store_reconnect.find_recv("clt/soupbintcp/proxy", |msg| is_spayload(msg, &format!("#{}", i)), wait_timeout);
+            assert!(found.is_some(), "replay of #{} not received", i);
+        }
+        assert!(clt_store_reconnect.find_recv("clt/soupbintcp/proxy", |msg| is_spayload(msg, "#4"), None).is_none());
+        info!("proxy: {}", proxy);
+        assert_eq!(proxy.connection_count(), 2);
+    }
+
+    #[test]
+    fn test_fault_proxy_pause_clt_hbeat_timeout() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let svc_addr = setup::net::rand_avail_addr_port();
+        let io_timeout = setup::net::find_timeout();
+        let wait_timeout = Duration::from_secs(1);
+        let (username, password, session_id) = credentials();
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, Duration::from_secs(1))
+            .with_hbeat_policy(HeartbeatPolicy::new(Duration::from_millis(20), Duration::from_secs(1)));
+        let mut svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(svc_addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/proxy"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let proxy = FaultProxy::bind(svc_addr).unwrap();
+
+        let protocol = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1))
+            .with_hbeat_policy(HeartbeatPolicy::new(Duration::from_millis(50), Duration::from_millis(100)).with_grace_period(Duration::from_millis(50)));
+        let clt = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            proxy.addr(),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            protocol,
+            Some("clt/soupbintcp/proxy"),
+        )
+        .unwrap()
+        .into_sender_with_spawned_recver_ref();
+        assert!(svc.all_connected_busywait_timeout(setup::net::find_timeout()));
+
+        // svc heartbeats keep the clt connected
+        sleep(Duration::from_millis(300));
+        assert!(clt.is_connected());
+
+        proxy.pause(ProxyDirection::SvcToClt);
+        let start = Instant::now();
+        while clt.is_connected() && start.elapsed() < wait_timeout {
+            sleep(POLL_INTERVAL);
+        }
+        info!("clt.is_connected(): {}, elapsed: {:?}, proxy: {}", clt.is_connected(), start.elapsed(), proxy);
+        assert!(!clt.is_connected());
+    }
+
+    #[test]
+    fn test_fault_proxy_truncate_login_accepted() {
+        setup::log::configure_compact(log::LevelFilter::Info);
+        let svc_addr = setup::net::rand_avail_addr_port();
+        let io_timeout = setup::net::find_timeout();
+        let (username, password, session_id) = credentials();
+
+        let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, io_timeout, Duration::from_secs(1));
+        let _svc = SvcSoupBinTcp::<_, _, MAX_MSG_SIZE>::bind(svc_addr, NonZeroUsize::new(1).unwrap(), DevNullCallback::new_ref(), protocol, Some("svc/soupbintcp/proxy"))
+            .unwrap()
+            .into_sender_with_spawned_recver_ref();
+        let proxy = FaultProxy::bind(svc_addr)
+            .unwrap()
+            .with_rule(FaultRule::new(ProxyDirection::SvcToClt, FrameMatch::PacketType(b'A'), Fault::Truncate(5)));
+
+        let protocol = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(username, password, session_id, 0_u64.into(), io_timeout, Duration::from_secs(1), Duration::from_secs(1));
+        let res = CltSoupBinTcp::<_, _, MAX_MSG_SIZE>::connect(
+            proxy.addr(),
+            setup::net::default_connect_timeout(),
+            setup::net::default_connect_retry_after(),
+            DevNullCallback::new_ref(),
+            protocol,
+            Some("clt/soupbintcp/proxy"),
+        );
+        info!("res: {:?}", res.as_ref().map(|_| ()));
+        assert!(res.is_err());
+        assert_eq!(proxy.fault_count(), 1);
+    }
+}
@@ -0,0 +1,331 @@
+//! Derive macros for `soupbintcp_model` payloads, see [`macro@SoupBinTcpPayload`]
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, spanned::Spanned, Attribute, Data, DataEnum, DataStruct, DeriveInput, Error, Fields, Ident, LitByte, LitInt, Path, Result, Type};
+
+/// Derives `SoupBinTcpPayload` marker trait together with `Default` for `SPayload<T>` & `UPayload<T>` and serde `Serialize` & `Deserialize`.
+///
+/// # Structs
+/// The struct must also derive `byteserde` traits `ByteSerializeStack`, `ByteDeserializeSlice` & `ByteSerializedLenOf`.
+///
+/// # Enums
+/// Multiplexes several application messages keyed on a leading message type byte, each variant must wrap a single type and
+/// be annotated with `#[soupbintcp(tag = b'?')]`. The wrapped type is expected to serialize its own leading tag byte, the enum
+/// only peeks at it during deserialization to dispatch to the variant and fails to serialize if the wrapped type wrote a different one.
+/// `byteserde` traits and `From<Inner>` are generated for the enum.
+///
+/// Tags must be unique printable ascii bytes and each type may only be wrapped by one variant, otherwise compilation fails.
+///
+/// # Serde
+/// `Serialize` & `Deserialize` are implemented via generated shadow types with the same fields or variants, hence the json is the same as if
+/// they were derived, `#[serde(...)]` attributes are carried over to the shadow, except that `serialize_with` & `with` are not supported on fields.
+///
+/// # Attributes
+/// * `#[soupbintcp(no_default)]` - on the type, skips `Default` for `SPayload<T>` & `UPayload<T>`, required when `T` is not [Default]
+/// * `#[soupbintcp(no_serde)]` - on the type, skips `Serialize` & `Deserialize`, use when providing a custom implementation
+/// * `#[soupbintcp(max_byte_len = N)]` - on the type, sets `SoupBinTcpPayload::MAX_BYTE_LEN` so that longer payloads are rejected before they are decoded
+/// * `#[soupbintcp(crate = path)]` - on the type, path to `soupbintcp_model` when it is neither a direct dependency nor the crate being compiled, example `soupbintcp_connect_core`,
+///   `serde` & `byteserde` used by the generated code are also resolved through this path, hence the deriving crate does not need to depend on them directly
+/// * `#[soupbintcp(tag = b'?')]` - on each enum variant
+#[proc_macro_derive(SoupBinTcpPayload, attributes(soupbintcp, serde))]
+pub fn soupbintcp_payload(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "SoupBinTcpPayload derive does not support generic types"));
+    }
+    let ty = &input.ident;
+    let TypeAttrs { no_default, no_serde, max_byte_len, krate } = type_attrs(&input.attrs)?;
+    let krate = match krate {
+        Some(krate) => quote! { #krate },
+        None => model_crate(input.span())?,
+    };
+
+    let (payload, serde) = match &input.data {
+        Data::Struct(data) => (quote! {}, expand_struct_serde(&krate, ty, data, &input.attrs)),
+        Data::Enum(data) => (expand_enum(&krate, ty, data)?, expand_enum_serde(&krate, ty, data, &input.attrs)?),
+        Data::Union(_) => return Err(Error::new(input.span(), "SoupBinTcpPayload derive does not support unions")),
+    };
+    let serde = if no_serde { quote! {} } else { serde };
+    let defaults = if no_default {
+        quote! {}
+    } else {
+        quote! {
+            impl ::core::default::Default for #krate::prelude::SPayload<#ty> {
+                fn default() -> Self {
+                    #krate::prelude::SPayload::new(<#ty as ::core::default::Default>::default())
+                }
+            }
+            impl ::core::default::Default for #krate::prelude::UPayload<#ty> {
+                fn default() -> Self {
+                    #krate::prelude::UPayload::new(<#ty as ::core::default::Default>::default())
+                }
+            }
+        }
+    };
+
+    let max_byte_len = max_byte_len.map(|max_byte_len| quote! { const MAX_BYTE_LEN: usize = #max_byte_len; });
+
+    Ok(quote! {
+        #payload
+        impl #krate::prelude::SoupBinTcpPayload<#ty> for #ty {
+            #max_byte_len
+        }
+        #defaults
+        #serde
+    })
+}
+
+/// Path to `soupbintcp_model` as seen by the crate being compiled, `crate` when deriving with in the lib or unit tests of `soupbintcp_model` itself
+fn model_crate(span: proc_macro2::Span) -> Result<TokenStream2> {
+    match crate_name("soupbintcp_model") {
+        Ok(FoundCrate::Itself) if std::env::var("CARGO_CRATE_NAME").as_deref() == Ok("soupbintcp_model") => Ok(quote! { crate }),
+        Ok(FoundCrate::Itself) => Ok(quote! { ::soupbintcp_model }),
+        Ok(FoundCrate::Name(name)) => {
+            let name = Ident::new(&name, span);
+            Ok(quote! { ::#name })
+        }
+        Err(err) => Err(Error::new(span, format!("SoupBinTcpPayload derive failed to locate `soupbintcp_model`, add it as a dependency or use `#[soupbintcp(crate = path)]`, {}", err))),
+    }
+}
+
+fn expand_enum(krate: &TokenStream2, ty: &Ident, data: &DataEnum) -> Result<TokenStream2> {
+    let byteserde = quote! { #krate::__private::byteserde };
+    if data.variants.is_empty() {
+        return Err(Error::new(ty.span(), "SoupBinTcpPayload derive requires at least one variant"));
+    }
+    let mut tags: Vec<(u8, &Ident)> = Vec::with_capacity(data.variants.len());
+    let mut inners: Vec<(String, &Ident)> = Vec::with_capacity(data.variants.len());
+    let mut ser = vec![];
+    let mut len = vec![];
+    let mut des = vec![];
+    let mut from = vec![];
+    for variant in data.variants.iter() {
+        let var = &variant.ident;
+        let inner = variant_inner(variant)?;
+        let tag = variant_tag(variant.span(), &variant.attrs)?;
+        if !tag.value().is_ascii_graphic() {
+            return Err(Error::new(tag.span(), format!("Tag {:?} is not a printable ascii message type", tag.value())));
+        }
+        if let Some((_, other)) = tags.iter().find(|(t, _)| *t == tag.value()) {
+            return Err(Error::new(tag.span(), format!("Duplicate tag {:?} already used by variant `{}`", tag.value() as char, other)));
+        }
+        let inner_name = quote! { #inner }.to_string();
+        if let Some((_, other)) = inners.iter().find(|(t, _)| *t == inner_name) {
+            return Err(Error::new(inner.span(), format!("Type `{}` is already wrapped by variant `{}` with a different tag", inner_name, other)));
+        }
+        tags.push((tag.value(), var));
+        inners.push((inner_name, var));
+
+        ser.push(quote! { Self::#var(msg) => (#tag, #byteserde::prelude::ByteSerializeStack::byte_serialize_stack(msg, ser)), });
+        len.push(quote! { Self::#var(msg) => #byteserde::prelude::ByteSerializedLenOf::byte_len(msg), });
+        des.push(quote! { #tag => ::core::result::Result::Ok(Self::#var(<#inner as #byteserde::prelude::ByteDeserializeSlice<#inner>>::byte_deserialize(des)?)), });
+        from.push(quote! {
+            impl ::core::convert::From<#inner> for #ty {
+                #[inline(always)]
+                fn from(msg: #inner) -> Self {
+                    Self::#var(msg)
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #byteserde::prelude::ByteSerializeStack for #ty {
+            #[inline(always)]
+            fn byte_serialize_stack<const CAP: usize>(&self, ser: &mut #byteserde::prelude::ByteSerializerStack<CAP>) -> #byteserde::error::Result<()> {
+                let start = ser.len();
+                let (tag, res) = match self {
+                    #(#ser)*
+                };
+                res?;
+                match ser.as_slice().get(start) {
+                    ::core::option::Option::Some(leading) if *leading == tag => ::core::result::Result::Ok(()),
+                    leading => ::core::result::Result::Err(#byteserde::error::SerDesError {
+                        message: ::std::format!("{} tag: {:?} is inconsistent with the leading byte: {:?} of the wrapped message", stringify!(#ty), tag as char, leading.map(|b| *b as char)),
+                    }),
+                }
+            }
+        }
+        impl #byteserde::prelude::ByteSerializedLenOf for #ty {
+            #[inline(always)]
+            fn byte_len(&self) -> usize {
+                match self {
+                    #(#len)*
+                }
+            }
+        }
+        impl #byteserde::prelude::ByteDeserializeSlice<#ty> for #ty {
+            #[inline(always)]
+            fn byte_deserialize(des: &mut #byteserde::prelude::ByteDeserializerSlice) -> #byteserde::error::Result<#ty> {
+                let tag = des.peek_bytes_slice(1)?[0];
+                match tag {
+                    #(#des)*
+                    _ => ::core::result::Result::Err(#byteserde::error::SerDesError {
+                        message: ::std::format!("{} unknown message type tag: {:?}, des: {:x}", stringify!(#ty), tag as char, des),
+                    }),
+                }
+            }
+        }
+        #(#from)*
+    })
+}
+
+/// Shadow structs for serde, one borrowing the fields for serialization and one owning them for deserialization, same as if serde was derived on the struct
+fn expand_struct_serde(krate: &TokenStream2, ty: &Ident, data: &DataStruct, attrs: &[Attribute]) -> TokenStream2 {
+    let (ser_shadow, des_shadow) = (format_ident!("{}JsonSerShadow", ty), format_ident!("{}JsonDesShadow", ty));
+    let ty_attrs = serde_attrs(attrs);
+    let (ser_def, des_def, ser_new, des_new) = match &data.fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| &f.ident).collect::<Vec<_>>();
+            let tys = fields.named.iter().map(|f| &f.ty).collect::<Vec<_>>();
+            let f_attrs = fields.named.iter().map(|f| serde_attrs(&f.attrs)).collect::<Vec<_>>();
+            (
+                quote! { struct #ser_shadow<'a> { #(#(#f_attrs)* #names: &'a #tys,)* } },
+                quote! { struct #des_shadow { #(#(#f_attrs)* #names: #tys,)* } },
+                quote! { #ser_shadow { #(#names: &self.#names,)* } },
+                quote! { let #des_shadow { #(#names,)* } = shadow; #ty { #(#names,)* } },
+            )
+        }
+        Fields::Unnamed(fields) => {
+            let idxs = (0..fields.unnamed.len()).map(syn::Index::from).collect::<Vec<_>>();
+            let vars = (0..fields.unnamed.len()).map(|i| format_ident!("f{}", i)).collect::<Vec<_>>();
+            let tys = fields.unnamed.iter().map(|f| &f.ty).collect::<Vec<_>>();
+            let f_attrs = fields.unnamed.iter().map(|f| serde_attrs(&f.attrs)).collect::<Vec<_>>();
+            (
+                quote! { struct #ser_shadow<'a>(#(#(#f_attrs)* &'a #tys,)*); },
+                quote! { struct #des_shadow(#(#(#f_attrs)* #tys,)*); },
+                quote! { #ser_shadow(#(&self.#idxs,)*) },
+                quote! { let #des_shadow(#(#vars,)*) = shadow; #ty(#(#vars,)*) },
+            )
+        }
+        Fields::Unit => (
+            quote! { struct #ser_shadow; },
+            quote! { struct #des_shadow; },
+            quote! { #ser_shadow },
+            quote! { let #des_shadow = shadow; #ty },
+        ),
+    };
+    expand_serde(krate, ty, &des_shadow, &ty_attrs, Shadow { ser_def, des_def, ser_new, des_new })
+}
+
+/// Shadow enums for serde, one borrowing the wrapped types for serialization and one owning them for deserialization, same as if serde was derived on the enum
+fn expand_enum_serde(krate: &TokenStream2, ty: &Ident, data: &DataEnum, attrs: &[Attribute]) -> Result<TokenStream2> {
+    let (ser_shadow, des_shadow) = (format_ident!("{}JsonSerShadow", ty), format_ident!("{}JsonDesShadow", ty));
+    let ty_attrs = serde_attrs(attrs);
+    let vars = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let inners = data.variants.iter().map(variant_inner).collect::<Result<Vec<_>>>()?;
+    let v_attrs = data.variants.iter().map(|v| serde_attrs(&v.attrs)).collect::<Vec<_>>();
+    let shadow = Shadow {
+        ser_def: quote! { enum #ser_shadow<'a> { #(#(#v_attrs)* #vars(&'a #inners),)* } },
+        des_def: quote! { enum #des_shadow { #(#(#v_attrs)* #vars(#inners),)* } },
+        ser_new: quote! { match self { #(#ty::#vars(msg) => #ser_shadow::#vars(msg),)* } },
+        des_new: quote! { match shadow { #(#des_shadow::#vars(msg) => #ty::#vars(msg),)* } },
+    };
+    Ok(expand_serde(krate, ty, &des_shadow, &ty_attrs, shadow))
+}
+
+/// Shadow type definitions and the expressions converting `self` into the serialization shadow and the deserialized `shadow` into the type
+struct Shadow {
+    ser_def: TokenStream2,
+    des_def: TokenStream2,
+    ser_new: TokenStream2,
+    des_new: TokenStream2,
+}
+/// `serde` is referenced through `soupbintcp_model` re-export, hence the shadows are derived with `#[serde(crate = "...")]` pointing at it
+fn expand_serde(krate: &TokenStream2, ty: &Ident, des_shadow: &Ident, ty_attrs: &[&Attribute], shadow: Shadow) -> TokenStream2 {
+    let Shadow { ser_def, des_def, ser_new, des_new } = shadow;
+    let serde = quote! { #krate::__private::serde };
+    let serde_crate = serde.to_string();
+    quote! {
+        const _: () = {
+            #[derive(#serde::Serialize)]
+            #[serde(crate = #serde_crate)]
+            #(#ty_attrs)*
+            #ser_def
+            #[derive(#serde::Deserialize)]
+            #[serde(crate = #serde_crate)]
+            #(#ty_attrs)*
+            #des_def
+            impl #serde::Serialize for #ty {
+                fn serialize<S: #serde::Serializer>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error> {
+                    #serde::Serialize::serialize(&#ser_new, serializer)
+                }
+            }
+            impl<'de> #serde::Deserialize<'de> for #ty {
+                fn deserialize<D: #serde::Deserializer<'de>>(deserializer: D) -> ::core::result::Result<Self, D::Error> {
+                    let shadow = <#des_shadow as #serde::Deserialize>::deserialize(deserializer)?;
+                    ::core::result::Result::Ok({ #des_new })
+                }
+            }
+        };
+    }
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("serde")).collect()
+}
+
+fn variant_inner(variant: &syn::Variant) -> Result<&Type> {
+    match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(&fields.unnamed[0].ty),
+        _ => Err(Error::new(
+            variant.span(),
+            "SoupBinTcpPayload derive requires each variant to wrap exactly one type, example `Variant(Inner)`",
+        )),
+    }
+}
+
+#[derive(Default)]
+struct TypeAttrs {
+    no_default: bool,
+    no_serde: bool,
+    max_byte_len: Option<LitInt>,
+    krate: Option<Path>,
+}
+fn type_attrs(attrs: &[Attribute]) -> Result<TypeAttrs> {
+    let mut type_attrs = TypeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("soupbintcp")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("no_default") {
+                type_attrs.no_default = true;
+                Ok(())
+            } else if meta.path.is_ident("no_serde") {
+                type_attrs.no_serde = true;
+                Ok(())
+            } else if meta.path.is_ident("max_byte_len") {
+                type_attrs.max_byte_len = Some(meta.value()?.parse::<LitInt>()?);
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                type_attrs.krate = Some(meta.value()?.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported soupbintcp attribute, expected `no_default`, `no_serde`, `max_byte_len = N` or `crate = path`"))
+            }
+        })?;
+    }
+    Ok(type_attrs)
+}
+
+fn variant_tag(span: proc_macro2::Span, attrs: &[Attribute]) -> Result<LitByte> {
+    let mut tag = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("soupbintcp")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitByte>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported soupbintcp attribute, expected `tag = b'?'`"))
+            }
+        })?;
+    }
+    tag.ok_or_else(|| Error::new(span, "SoupBinTcpPayload derive requires `#[soupbintcp(tag = b'?')]` on each variant"))
+}
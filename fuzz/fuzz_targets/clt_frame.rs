@@ -0,0 +1,19 @@
+//! Frames sent by a client and decoded by a server
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use soupbintcp_connect_core::prelude::*;
+use soupbintcp_fuzz::{assert_round_trip, split_frames};
+
+type SvcMessenger = SvcSoupBinTcpMessenger<VecPayload, VecPayload>;
+
+fuzz_target!(|data: &[u8]| {
+    for frame in split_frames::<SoupBinTcpFramer>(data) {
+        let _ = SvcMessenger::deserialize(&frame[..]);
+        let _ = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::deserialize(&frame[..]);
+        if let Ok(view) = SvcMessenger::deserialize_view(&frame[..]) {
+            let _ = view.payload();
+            let _ = view.to_clt_msg::<VecPayload>();
+        }
+        assert_round_trip::<SvcMessenger>(&frame[..]);
+    }
+});
@@ -0,0 +1,24 @@
+//! Debug packets, which are the only free text packet and can be sent in both directions
+#![no_main]
+use byteserde::prelude::*;
+use libfuzzer_sys::fuzz_target;
+use soupbintcp_connect_core::prelude::*;
+
+fuzz_target!(|data: &[u8]| {
+    // fuzzer controls the body, header is forced to be a Debug packet with a length which may or may not match
+    let mut frame = Vec::with_capacity(data.len() + 3);
+    let packet_length = data.first().map_or(data.len() as u16 + 1, |len| *len as u16);
+    frame.extend_from_slice(&packet_length.to_be_bytes());
+    frame.push(b'+');
+    frame.extend_from_slice(data);
+
+    if let Ok(msg) = from_slice::<Debug>(&frame) {
+        let _ = msg.text();
+        let _ = format!("{:?}", msg);
+        if let Ok(json) = serde_json::to_string(&msg) {
+            let _ = serde_json::from_str::<Debug>(&json);
+        }
+    }
+    let _ = from_slice::<CltSoupBinTcpMsg<VecPayload>>(&frame);
+    let _ = from_slice::<SvcSoupBinTcpMsg<VecPayload>>(&frame);
+});
@@ -0,0 +1,19 @@
+//! Frames sent by a server and decoded by a client
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use soupbintcp_connect_core::prelude::*;
+use soupbintcp_fuzz::{assert_round_trip, split_frames};
+
+type CltMessenger = CltSoupBinTcpMessenger<VecPayload, VecPayload>;
+
+fuzz_target!(|data: &[u8]| {
+    for frame in split_frames::<SoupBinTcpFramer>(data) {
+        let _ = CltMessenger::deserialize(&frame[..]);
+        let _ = CltSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::deserialize(&frame[..]);
+        if let Ok(view) = CltMessenger::deserialize_view(&frame[..]) {
+            let _ = view.payload();
+            let _ = view.to_svc_msg::<VecPayload>();
+        }
+        assert_round_trip::<CltMessenger>(&frame[..]);
+    }
+});
@@ -0,0 +1,25 @@
+//! Server side login handshake followed by session messages, driven over an in memory connection
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use soupbintcp_connect_core::prelude::*;
+use soupbintcp_fuzz::InMemoryCon;
+use std::time::Duration;
+
+type SvcProtocol = SvcSoupBinTcpProtocolAuto<VecPayload, VecPayload, MonotonicClock, SkipOnDecodeError>;
+
+fuzz_target!(|data: &[u8]| {
+    let protocol = SvcSoupBinTcpProtocolAuto::<VecPayload, VecPayload>::new(b"userid".into(), b"passwd".as_slice().into(), b"session".as_slice().into(), Duration::ZERO, Duration::from_secs(1))
+        .with_decode_error_policy::<SkipOnDecodeError>();
+    let mut con = InMemoryCon::<SvcProtocol>::new(data);
+
+    if protocol.on_connect(&mut con).is_ok() {
+        assert!(matches!(con.sent.first(), Some(SvcSoupBinTcpMsg::LoginAccepted(_))));
+        while let Ok(RecvStatus::Completed(Some(msg))) = con.recv() {
+            protocol.on_recv(&con, &msg);
+            let mut sender = InMemoryCon::<SvcProtocol>::new(&[]);
+            if protocol.send_reply(&msg, &mut sender).is_err() {
+                break;
+            }
+        }
+    }
+});
@@ -0,0 +1,87 @@
+use bytes::{Bytes, BytesMut};
+use soupbintcp_connect_core::prelude::*;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{Error, ErrorKind},
+};
+
+/// Large enough to serialize any frame which can be described by a `u16` `packet_length`
+pub const MAX_MSG_SIZE: usize = u16::MAX as usize + 2;
+
+/// Splits `data` into frames using `F`, example [SoupBinTcpFramer], trailing bytes which do not form a complete frame are dropped
+pub fn split_frames<F: Framer>(data: &[u8]) -> Vec<Bytes> {
+    let mut buf = BytesMut::from(data);
+    let mut frames = vec![];
+    while let Some(frame) = F::get_frame(&mut buf) {
+        frames.push(frame);
+    }
+    frames
+}
+
+/// Any frame which decodes must encode back into a frame which decodes into the same message
+pub fn assert_round_trip<M: Messenger>(frame: &[u8])
+where
+    M::RecvT: PartialEq + byteserde::prelude::ByteSerializeStack,
+{
+    if let Ok(msg_out) = M::deserialize(frame) {
+        let ser = byteserde::prelude::to_bytes_stack::<MAX_MSG_SIZE, M::RecvT>(&msg_out).expect("decoded msg must serialize");
+        let msg_rt = M::deserialize(&ser.0[..ser.1]).expect("serialized msg must deserialize");
+        assert!(msg_rt == msg_out, "round trip mismatch frame: {:?}", frame);
+    }
+}
+
+/// In memory connection for driving [ProtocolCore::on_connect] without sockets, receives frames from a fuzzer supplied buffer
+/// and records all sent messages.
+#[derive(Debug)]
+pub struct InMemoryCon<M: Messenger> {
+    con_id: ConId,
+    frames: VecDeque<Bytes>,
+    pub sent: Vec<M::SendT>,
+}
+impl<M: Messenger> InMemoryCon<M> {
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            con_id: ConId::svc(Some("fuzz"), "127.0.0.1:0", None),
+            frames: split_frames::<M>(data).into(),
+            sent: vec![],
+        }
+    }
+}
+impl<M: Messenger> Display for InMemoryCon<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InMemoryCon {{ {}, pending frames: {} }}", self.con_id, self.frames.len())
+    }
+}
+impl<M: Messenger> ConnectionId for InMemoryCon<M> {
+    fn con_id(&self) -> &ConId {
+        &self.con_id
+    }
+}
+impl<M: Messenger> RecvNonBlocking<M::RecvT> for InMemoryCon<M> {
+    /// Returns [RecvStatus::Completed(None)] once all frames are consumed, same as a peer closing the connection
+    fn recv(&mut self) -> Result<RecvStatus<M::RecvT>, Error> {
+        match self.frames.pop_front() {
+            Some(frame) => Ok(RecvStatus::Completed(Some(M::deserialize(&frame[..])?))),
+            None => Ok(RecvStatus::Completed(None)),
+        }
+    }
+}
+impl<M: Messenger> SendNonBlocking<M::SendT> for InMemoryCon<M>
+where
+    M::SendT: Clone,
+{
+    fn send(&mut self, msg: &mut M::SendT) -> Result<SendStatus, Error> {
+        M::serialize::<MAX_MSG_SIZE>(msg).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        self.sent.push(msg.clone());
+        Ok(SendStatus::Completed)
+    }
+}
+impl<M: Messenger> ReSendNonBlocking<M::SendT> for InMemoryCon<M>
+where
+    M::SendT: Clone,
+{
+    fn re_send(&mut self, msg: &M::SendT) -> Result<SendStatus, Error> {
+        self.send(&mut msg.clone())
+    }
+}
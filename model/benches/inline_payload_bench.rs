@@ -0,0 +1,41 @@
+use byteserde::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use links_core::unittest::setup;
+use soupbintcp_model::prelude::*;
+
+fn soupbintcp_seq_inline_vs_vec(c: &mut Criterion) {
+    const MAX_FRAME_SIZE: usize = 1024;
+    const PAYLOAD_SIZE: usize = MAX_FRAME_SIZE - 3;
+    let bytes = setup::data::random_bytes(PAYLOAD_SIZE);
+
+    let vec_inp = SPayload::new(VecPayload::new(bytes.to_vec()));
+    let inline_inp = SPayload::new(InlinePayload::<PAYLOAD_SIZE>::try_from_slice(bytes).unwrap());
+
+    let mut group = c.benchmark_group("inline_payload_bench");
+    group.bench_function("vec_ser", |b| {
+        b.iter(|| {
+            let _: ([u8; MAX_FRAME_SIZE], usize) = black_box(to_bytes_stack(&vec_inp).unwrap());
+        })
+    });
+    group.bench_function("inline_ser", |b| {
+        b.iter(|| {
+            let _: ([u8; MAX_FRAME_SIZE], usize) = black_box(to_bytes_stack(&inline_inp).unwrap());
+        })
+    });
+
+    let (buf, size): ([u8; MAX_FRAME_SIZE], usize) = to_bytes_stack(&vec_inp).unwrap();
+    group.bench_function("vec_des", |b| {
+        b.iter(|| {
+            let _: SPayload<VecPayload> = black_box(from_slice(&buf[..size]).unwrap());
+        })
+    });
+    group.bench_function("inline_des", |b| {
+        b.iter(|| {
+            let _: SPayload<InlinePayload<PAYLOAD_SIZE>> = black_box(from_slice(&buf[..size]).unwrap());
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, soupbintcp_seq_inline_vs_vec);
+criterion_main!(benches);
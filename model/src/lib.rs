@@ -1,5 +1,12 @@
 pub mod model;
 pub mod prelude;
 
+/// Dependencies referenced by code generated with `soupbintcp_derive`, so that deriving crates do not need to depend on them directly
+#[doc(hidden)]
+pub mod __private {
+    pub use byteserde;
+    pub use serde;
+}
+
 #[cfg(feature = "unittest")]
 pub mod unittest;
@@ -15,7 +15,7 @@ pub struct Debug {
     packet_length: u16,
     #[serde(default, skip_serializing)]
     packet_type: PacketTypeDebug,
-    #[byteserde(deplete ( packet_length as usize - packet_type.byte_len() ))]
+    #[byteserde(deplete ( (packet_length as usize).saturating_sub(packet_type.byte_len()) ))]
     text: StringAscii,
 }
 impl Debug {
@@ -26,6 +26,10 @@ impl Debug {
             ..Default::default()
         }
     }
+    #[inline(always)]
+    pub fn text(&self) -> &[u8] {
+        self.text.bytes()
+    }
 }
 impl Default for Debug {
     fn default() -> Self {
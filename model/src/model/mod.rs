@@ -1,9 +1,11 @@
 pub mod debug;
+pub mod moldudp64;
 pub mod payload;
 pub mod sequenced_data;
 pub mod soup_bin;
 pub mod types;
 pub mod unsequenced_data;
+pub mod view;
 
 pub mod clt;
 pub mod svc;
@@ -13,4 +15,11 @@ pub mod sample_payload;
 use byteserde::prelude::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf};
 use std::fmt::Debug;
 
-pub trait SoupBinTcpPayload<P>: Debug + Clone + Send + Sync + 'static + ByteSerializeStack + ByteSerializedLenOf + ByteDeserializeSlice<P> + PartialEq {}
+/// Longest payload of [SPayload](crate::prelude::SPayload) & [UPayload](crate::prelude::UPayload), since `packet_length` is a `u16` which also counts the `packet_type`
+pub const SOUPBINTCP_MAX_PAYLOAD_BYTE_LEN: usize = u16::MAX as usize - 1;
+
+pub trait SoupBinTcpPayload<P>: Debug + Clone + Send + Sync + 'static + ByteSerializeStack + ByteSerializedLenOf + ByteDeserializeSlice<P> + PartialEq {
+    /// Longest [ByteSerializedLenOf::byte_len] of the payload, a peer announcing a longer [SPayload](crate::prelude::SPayload) or [UPayload](crate::prelude::UPayload)
+    /// is rejected as soon as the header arrives, see [max_frame_len](crate::prelude::max_frame_len). Set by the derive using `#[soupbintcp(max_byte_len = N)]`
+    const MAX_BYTE_LEN: usize = SOUPBINTCP_MAX_PAYLOAD_BYTE_LEN;
+}
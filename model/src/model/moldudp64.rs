@@ -0,0 +1,107 @@
+use byteserde_derive::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf};
+use std::fmt::Display;
+
+use crate::model::types::SessionId;
+
+pub const MOLDUDP64_HEADER_BYTE_LEN: usize = 20;
+/// Each message block in a MoldUDP64 packet is prefixed with a two byte big endian length
+pub const MOLDUDP64_MESSAGE_BLOCK_HEADER_BYTE_LEN: usize = 2;
+pub const MOLDUDP64_HEARTBEAT_MESSAGE_COUNT: u16 = 0;
+pub const MOLDUDP64_END_OF_SESSION_MESSAGE_COUNT: u16 = 0xFFFF;
+
+/// MoldUDP64 downstream packet header followed by `message_count` message blocks, a request packet sent to the retransmission server
+/// consists of this header alone with `message_count` set to the number of requested messages
+/// [MoldUDP64 Specification](https://www.nasdaqtrader.com/content/technicalsupport/specifications/dataproducts/moldudp64.pdf)
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, PartialEq, Debug, Clone, Copy)]
+#[byteserde(endian = "be")]
+pub struct MoldUdp64Header {
+    pub session_id: SessionId,
+    /// Sequence number of the first message block in the packet, or of the next expected message for heartbeat & end of session packets
+    pub sequence_number: u64,
+    pub message_count: u16,
+}
+impl MoldUdp64Header {
+    pub fn new(session_id: SessionId, sequence_number: u64, message_count: u16) -> Self {
+        Self {
+            session_id,
+            sequence_number,
+            message_count,
+        }
+    }
+    pub fn heartbeat(session_id: SessionId, next_sequence_number: u64) -> Self {
+        Self::new(session_id, next_sequence_number, MOLDUDP64_HEARTBEAT_MESSAGE_COUNT)
+    }
+    pub fn end_of_session(session_id: SessionId, next_sequence_number: u64) -> Self {
+        Self::new(session_id, next_sequence_number, MOLDUDP64_END_OF_SESSION_MESSAGE_COUNT)
+    }
+    #[inline(always)]
+    pub fn is_heartbeat(&self) -> bool {
+        self.message_count == MOLDUDP64_HEARTBEAT_MESSAGE_COUNT
+    }
+    #[inline(always)]
+    pub fn is_end_of_session(&self) -> bool {
+        self.message_count == MOLDUDP64_END_OF_SESSION_MESSAGE_COUNT
+    }
+    /// Number of message blocks following the header, `0` for heartbeat & end of session packets
+    #[inline(always)]
+    pub fn message_block_count(&self) -> usize {
+        if self.is_end_of_session() {
+            0
+        } else {
+            self.message_count as usize
+        }
+    }
+}
+impl Display for MoldUdp64Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MoldUdp64Header {{ session_id: {}, sequence_number: {}, message_count: {} }}",
+            self.session_id, self.sequence_number, self.message_count
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{model::moldudp64::MOLDUDP64_HEADER_BYTE_LEN, prelude::*};
+    use byteserde::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+
+    #[test]
+    fn test_moldudp64_header_byteserde() {
+        setup::log::configure_compact(LevelFilter::Info);
+
+        let msg_inp = MoldUdp64Header::new(b"session #1".as_slice().into(), 0x0102_0304_0506_0708, 3);
+        info!("msg_inp: {}", msg_inp);
+        let ser: ByteSerializerStack<128> = to_serializer_stack(&msg_inp).unwrap();
+        info!("ser: {:x}", ser);
+        assert_eq!(MOLDUDP64_HEADER_BYTE_LEN, ser.len());
+        assert_eq!(MOLDUDP64_HEADER_BYTE_LEN, msg_inp.byte_len());
+        assert_eq!(&ser.as_slice()[..10], b"session #1");
+        assert_eq!(&ser.as_slice()[10..], &[1, 2, 3, 4, 5, 6, 7, 8, 0, 3]);
+
+        let msg_out: MoldUdp64Header = from_serializer_stack(&ser).unwrap();
+        info!("msg_out: {}", msg_out);
+        assert_eq!(msg_out, msg_inp);
+    }
+
+    #[test]
+    fn test_moldudp64_header_heartbeat_end_of_session() {
+        setup::log::configure_compact(LevelFilter::Info);
+
+        let session_id = SessionId::default();
+        let hbeat = MoldUdp64Header::heartbeat(session_id, 10);
+        info!("hbeat: {}", hbeat);
+        assert!(hbeat.is_heartbeat() && !hbeat.is_end_of_session());
+        assert_eq!(hbeat.message_block_count(), 0);
+
+        let eos = MoldUdp64Header::end_of_session(session_id, 10);
+        info!("eos: {}", eos);
+        assert!(eos.is_end_of_session() && !eos.is_heartbeat());
+        assert_eq!(eos.message_block_count(), 0);
+
+        assert_eq!(MoldUdp64Header::new(session_id, 10, 2).message_block_count(), 2);
+    }
+}
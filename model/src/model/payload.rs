@@ -1,17 +1,20 @@
-use crate::prelude::{SoupBinTcpPayload, UPayload};
+use crate::prelude::{SPayload, SoupBinTcpPayload, UPayload};
+use byteserde::{
+    error::SerDesError,
+    prelude::{ByteDeserializeSlice, ByteDeserializerSlice, ByteSerializeStack, ByteSerializedLenOf, ByteSerializerStack},
+};
 use byteserde_derive::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::Debug,
+    io::{Error, ErrorKind},
+};
 
-#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, SoupBinTcpPayload, PartialEq, Debug, Clone, Copy, Default)]
+#[soupbintcp(max_byte_len = 0)]
 pub struct Nil;
-impl Default for UPayload<Nil> {
-    fn default() -> Self {
-        UPayload::new(Nil)
-    }
-}
-impl SoupBinTcpPayload<Nil> for Nil {}
 
-#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, SoupBinTcpPayload, PartialEq, Debug, Clone, Default)]
 pub struct VecPayload {
     pub payload: Vec<u8>,
 }
@@ -20,4 +23,255 @@ impl VecPayload {
         Self { payload }
     }
 }
-impl SoupBinTcpPayload<VecPayload> for VecPayload {}
+
+/// Opaque payload of up to `N` bytes stored inline, allows relaying arbitrary payloads without heap allocation.
+/// Serializes into json as a hex string, example `{"payload":"0a0b"}`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct InlinePayload<const N: usize> {
+    len: usize,
+    payload: [u8; N],
+}
+impl<const N: usize> InlinePayload<N> {
+    pub const CAPACITY: usize = N;
+    /// Returns [ErrorKind::InvalidInput] if `bytes` exceed capacity `N`
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() > N {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("InlinePayload<{}> capacity exceeded by payload of len: {}", N, bytes.len()),
+            ));
+        }
+        let mut payload = [0_u8; N];
+        payload[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self { len: bytes.len(), payload })
+    }
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.payload[..self.len]
+    }
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+impl<const N: usize> Default for InlinePayload<N> {
+    fn default() -> Self {
+        Self { len: 0, payload: [0_u8; N] }
+    }
+}
+impl<const N: usize> Debug for InlinePayload<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InlinePayload").field("cap", &N).field("payload", &self.as_slice()).finish()
+    }
+}
+impl<const N: usize> ByteSerializeStack for InlinePayload<N> {
+    #[inline(always)]
+    fn byte_serialize_stack<const CAP: usize>(&self, ser: &mut ByteSerializerStack<CAP>) -> byteserde::error::Result<()> {
+        ser.serialize_bytes_slice(self.as_slice())?;
+        Ok(())
+    }
+}
+impl<const N: usize> ByteSerializedLenOf for InlinePayload<N> {
+    #[inline(always)]
+    fn byte_len(&self) -> usize {
+        self.len
+    }
+}
+impl<const N: usize> ByteDeserializeSlice<InlinePayload<N>> for InlinePayload<N> {
+    /// Consumes all remaining bytes, which for [SPayload] & [UPayload] are limited to `packet_length`
+    #[inline(always)]
+    fn byte_deserialize(des: &mut ByteDeserializerSlice) -> byteserde::error::Result<InlinePayload<N>> {
+        if des.remaining() > N {
+            return Err(SerDesError {
+                message: format!("InlinePayload<{}> capacity exceeded by payload of len: {}, des: {:x}", N, des.remaining(), des),
+            });
+        }
+        let bytes = des.deserialize_bytes_slice_remaining();
+        let mut payload = [0_u8; N];
+        payload[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self { len: bytes.len(), payload })
+    }
+}
+impl<const N: usize> Serialize for InlinePayload<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut hex = String::with_capacity(self.len * 2);
+        for byte in self.as_slice() {
+            hex.push_str(&format!("{:02x}", byte));
+        }
+        let mut state = serializer.serialize_struct("InlinePayload", 1)?;
+        state.serialize_field("payload", &hex)?;
+        state.end()
+    }
+}
+impl<'de, const N: usize> Deserialize<'de> for InlinePayload<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct InlinePayloadJsonDesShadow {
+            payload: String,
+        }
+        let shadow = InlinePayloadJsonDesShadow::deserialize(deserializer)?;
+        let hex = shadow.payload.as_bytes();
+        if hex.len() % 2 != 0 {
+            return Err(serde::de::Error::custom(format!("InlinePayload<{}> hex payload has odd length: {}", N, hex.len())));
+        }
+        let mut bytes = Vec::with_capacity(hex.len() / 2);
+        for pair in hex.chunks(2) {
+            // `from_str_radix` alone accepts a leading sign, ex: `+f`
+            if !pair.iter().all(u8::is_ascii_hexdigit) {
+                return Err(serde::de::Error::custom(format!("InlinePayload<{}> hex payload has invalid digits: {:?}", N, String::from_utf8_lossy(pair))));
+            }
+            let pair = std::str::from_utf8(pair).map_err(serde::de::Error::custom)?;
+            bytes.push(u8::from_str_radix(pair, 16).map_err(serde::de::Error::custom)?);
+        }
+        Self::try_from_slice(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+impl<const N: usize> SoupBinTcpPayload<InlinePayload<N>> for InlinePayload<N> {
+    const MAX_BYTE_LEN: usize = N;
+}
+impl<const N: usize> Default for SPayload<InlinePayload<N>> {
+    fn default() -> Self {
+        SPayload::new(InlinePayload::default())
+    }
+}
+impl<const N: usize> Default for UPayload<InlinePayload<N>> {
+    fn default() -> Self {
+        UPayload::new(InlinePayload::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use byteserde::prelude::*;
+    use byteserde_derive::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf, ByteSerializedSizeOf};
+    use byteserde_types::const_char_ascii;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+    use serde::{Deserialize, Serialize};
+    use serde_json::{from_str, to_string};
+
+    const_char_ascii!(MsgTypeEnterOrder, b'O', true, #[derive(ByteSerializeStack, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+    const_char_ascii!(MsgTypeCancelOrder, b'X', true, #[derive(ByteSerializeStack, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+
+    #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+    #[byteserde(endian = "be")]
+    struct EnterOrder {
+        #[serde(default, skip_serializing)]
+        msg_type: MsgTypeEnterOrder,
+        qty: u32,
+    }
+    #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+    #[byteserde(endian = "be")]
+    struct CancelOrder {
+        #[serde(default, skip_serializing)]
+        msg_type: MsgTypeCancelOrder,
+        order_id: u64,
+    }
+
+    #[derive(SoupBinTcpPayload, PartialEq, Debug, Clone)]
+    #[soupbintcp(no_default)]
+    enum OrderPayload {
+        #[soupbintcp(tag = b'O')]
+        EnterOrder(EnterOrder),
+        #[soupbintcp(tag = b'X')]
+        CancelOrder(CancelOrder),
+    }
+
+    #[derive(SoupBinTcpPayload, PartialEq, Debug, Clone)]
+    #[soupbintcp(no_default)]
+    enum MistaggedPayload {
+        #[soupbintcp(tag = b'C')]
+        CancelOrder(CancelOrder),
+    }
+
+    #[test]
+    fn test_derive_struct_payload() {
+        setup::log::configure_compact(LevelFilter::Info);
+        assert_eq!(SPayload::<SamplePayload>::default(), SPayload::new(SamplePayload::default()));
+        assert_eq!(UPayload::<VecPayload>::default(), UPayload::new(VecPayload::default()));
+        assert_eq!(UPayload::<Nil>::default(), UPayload::new(Nil));
+    }
+
+    #[test]
+    fn test_inline_payload() {
+        setup::log::configure_compact(LevelFilter::Info);
+        type Payload = InlinePayload<16>;
+        let msgs_inp: [SvcSoupBinTcpMsg<Payload>; 3] = [
+            SvcSoupBinTcpMsg::sdata(Payload::try_from_slice(b"\x00\x01hello\xff").unwrap()),
+            SvcSoupBinTcpMsg::sdata(Payload::default()),
+            SvcSoupBinTcpMsg::sdata(Payload::try_from_slice(&[0xab; 16]).unwrap()),
+        ];
+        for msg_inp in msgs_inp.iter() {
+            let ser: ByteSerializerStack<128> = to_serializer_stack(msg_inp).unwrap();
+            info!("ser: {:#x}", ser);
+            let msg_out: SvcSoupBinTcpMsg<Payload> = from_slice(ser.as_slice()).unwrap();
+            assert_eq!(msg_inp, &msg_out);
+
+            let json_out = to_string(msg_inp).unwrap();
+            info!("json_out: {}", json_out);
+            let msg_out: SvcSoupBinTcpMsg<Payload> = from_str(&json_out).unwrap();
+            assert_eq!(msg_inp, &msg_out);
+        }
+        assert_eq!(to_string(&Payload::try_from_slice(b"\x0a\xff").unwrap()).unwrap(), r#"{"payload":"0aff"}"#);
+
+        let err = Payload::try_from_slice(&[0; 17]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let ser: ByteSerializerStack<128> = to_serializer_stack(&SvcSoupBinTcpMsg::sdata(InlinePayload::<32>::try_from_slice(&[0; 17]).unwrap())).unwrap();
+        let err = from_slice::<SvcSoupBinTcpMsg<Payload>>(ser.as_slice()).unwrap_err();
+        info!("err: {}", err);
+        assert!(err.message.contains("capacity exceeded"));
+
+        assert!(from_str::<Payload>(r#"{"payload":"0af"}"#).is_err());
+        assert!(from_str::<Payload>(r#"{"payload":"zz"}"#).is_err());
+        assert!(from_str::<Payload>(r#"{"payload":"+f"}"#).is_err());
+    }
+
+    #[test]
+    fn test_derive_enum_payload() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let msgs_inp: [CltSoupBinTcpMsg<OrderPayload>; 3] = [
+            CltSoupBinTcpMsg::sdata(EnterOrder { qty: 100, ..Default::default() }.into()),
+            CltSoupBinTcpMsg::udata(CancelOrder { order_id: 1, ..Default::default() }.into()),
+            CltSoupBinTcpMsg::hbeat(),
+        ];
+
+        let mut ser = ByteSerializerStack::<1024>::default();
+        for msg in msgs_inp.iter() {
+            info!("msg_inp: {:?}", msg);
+            let _ = ser.serialize(msg).unwrap();
+        }
+        info!("ser: {:#x}", ser);
+        let mut des = ByteDeserializerSlice::new(ser.as_slice());
+        let mut msgs_out = vec![];
+        while !des.is_empty() {
+            let msg = CltSoupBinTcpMsg::<OrderPayload>::byte_deserialize(&mut des).unwrap();
+            info!("msg_out: {:?}", msg);
+            msgs_out.push(msg);
+        }
+        assert_eq!(msgs_inp.as_slice(), msgs_out.as_slice());
+
+        for msg_inp in msgs_inp.iter() {
+            let json_out = to_string(msg_inp).unwrap();
+            info!("json_out: {}", json_out);
+            let msg_out: CltSoupBinTcpMsg<OrderPayload> = from_str(&json_out).unwrap();
+            assert_eq!(msg_inp, &msg_out);
+        }
+
+        assert_eq!(to_string(&OrderPayload::from(EnterOrder { qty: 100, ..Default::default() })).unwrap(), r#"{"EnterOrder":{"qty":100}}"#);
+
+        let err = from_slice::<OrderPayload>(b"Z unknown").unwrap_err();
+        info!("err: {}", err);
+        assert!(err.message.contains("unknown message type tag: 'Z'"));
+
+        let err = to_serializer_stack::<128, _>(&MistaggedPayload::from(CancelOrder::default())).unwrap_err();
+        info!("err: {}", err);
+        assert!(err.message.contains("tag: 'C' is inconsistent with the leading byte: Some('X')"));
+    }
+}
@@ -1,14 +1,14 @@
 use byteserde_derive::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf, ByteSerializedSizeOf};
 use byteserde_types::string_ascii_fixed;
-use serde::{Deserialize, Serialize};
 
-use crate::prelude::{SPayload, SoupBinTcpPayload, UPayload};
+use crate::prelude::SoupBinTcpPayload;
 
 string_ascii_fixed!(Context1, 10, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
 string_ascii_fixed!(Context2, 10, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
 
 #[rustfmt::skip]
-#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, SoupBinTcpPayload, PartialEq, Clone, Debug)]
+#[soupbintcp(max_byte_len = 20)]
 pub struct SamplePayload {
     pub context1: Context1,
     pub context2: Context2,
@@ -18,7 +18,6 @@ impl SamplePayload {
         Self { context1, ..Default::default() }
     }
 }
-impl SoupBinTcpPayload<SamplePayload> for SamplePayload {}
 impl Default for SamplePayload {
     fn default() -> Self {
         Self {
@@ -27,13 +26,3 @@ impl Default for SamplePayload {
         }
     }
 }
-impl Default for UPayload<SamplePayload> {
-    fn default() -> Self {
-        UPayload::new(SamplePayload::default())
-    }
-}
-impl Default for SPayload<SamplePayload> {
-    fn default() -> Self {
-        SPayload::new(SamplePayload::default())
-    }
-}
@@ -32,7 +32,7 @@ impl SPayloadHeader {
 pub struct SPayload<Payload: SoupBinTcpPayload<Payload>> {
     #[serde(skip)]
     header: SPayloadHeader,
-    #[byteserde(deplete ( header.packet_length as usize - 1 ))]
+    #[byteserde(deplete ( (header.packet_length as usize).saturating_sub(1) ))]
     #[serde(flatten)]
     pub payload: Payload,
 }
@@ -25,6 +25,16 @@ impl LoginAccepted {
             sequence_number,
         }
     }
+    /// Session the client is now logged into
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+    /// Sequence number of the next [SPayload](crate::prelude::SPayload) to be sent by the server
+    #[inline(always)]
+    pub fn sequence_number(&self) -> SequenceNumber {
+        self.sequence_number
+    }
 }
 #[cfg(feature = "unittest")]
 impl Default for LoginAccepted {
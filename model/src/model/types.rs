@@ -19,22 +19,118 @@ pub mod soupbintcp_packet_types {
 }
 
 pub mod soupbintcp_field_types {
-    use std::time::Duration;
+    use std::{
+        io::{Error, ErrorKind},
+        time::Duration,
+    };
 
     use super::*;
     use byteserde_types::{char_ascii, string_ascii_fixed};
     use links_core::core::macros::short_type_name;
 
+    /// Adds fallible constructors to types generated by `string_ascii_fixed!` macro, since their `From<&[u8]>` implementation silently truncates
+    /// and `From<&str>` panics
+    macro_rules! try_from_string_ascii_fixed {
+        ($NAME:ident) => {
+            impl $NAME {
+                /// Returns [ErrorKind::InvalidInput] if `bytes` are not ascii or exceed fixed length of the field
+                pub fn try_from_slice(bytes: &[u8]) -> Result<Self, Error> {
+                    validate_string_ascii_fixed::<Self>(bytes, Self::len())?;
+                    Ok(bytes.into())
+                }
+                /// Returns [ErrorKind::InvalidInput] if `value` is not ascii or exceeds fixed length of the field
+                pub fn try_from_str(value: &str) -> Result<Self, Error> {
+                    Self::try_from_slice(value.as_bytes())
+                }
+            }
+        };
+    }
+    fn validate_string_ascii_fixed<T>(bytes: &[u8], max_len: usize) -> Result<(), Error> {
+        if !bytes.is_ascii() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} '{}' contains non ASCII characters", short_type_name::<T>(), String::from_utf8_lossy(bytes)),
+            ));
+        }
+        if bytes.len() > max_len {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} '{}' byte length: {} exceeds max allowed byte length: {}", short_type_name::<T>(), String::from_utf8_lossy(bytes), bytes.len(), max_len),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_string_ascii_fixed<T, N: std::str::FromStr>(bytes: &[u8]) -> Result<N, Error> {
+        let digits = std::str::from_utf8(bytes).map_err(|_| Error::new(ErrorKind::InvalidData, format!("{} {:?} is not ascii", short_type_name::<T>(), bytes)))?.trim();
+        // `str::parse` accepts a leading `+` sign which is not a valid numeric field value
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} '{}' is not a number", short_type_name::<T>(), digits)));
+        }
+        digits.parse::<N>().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{} '{}' is not a valid {}", short_type_name::<T>(), digits, short_type_name::<N>()),
+            )
+        })
+    }
+
     string_ascii_fixed!(SessionId, 10, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+    try_from_string_ascii_fixed!(SessionId);
     impl Default for SessionId {
         fn default() -> Self {
             // all banks to log into the currently active session
             b"          ".into()
         }
     }
+    impl SessionId {
+        /// Creates `YYYYMMDD` session id, typically used to start a new session at the end of each trading day
+        ///
+        /// Returns [ErrorKind::InvalidInput] if `year`, `month` & `day` are not a valid calendar date
+        pub fn try_from_date(year: u16, month: u8, day: u8) -> Result<Self, Error> {
+            let is_leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+            let days_in_month = match month {
+                1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+                4 | 6 | 9 | 11 => 30,
+                2 if is_leap => 29,
+                2 => 28,
+                _ => 0,
+            };
+            if year > 9999 || day == 0 || day > days_in_month {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} year: {} month: {} day: {} is not a valid date", short_type_name::<Self>(), year, month, day),
+                ));
+            }
+            Ok(format!("{:04}{:02}{:02}", year, month, day).as_bytes().into())
+        }
+    }
 
     // TODO add docs https://stackoverflow.com/questions/33999341/generating-documentation-in-macros
     string_ascii_fixed!(SequenceNumber, 20, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+    impl SequenceNumber {
+        /// Returns [ErrorKind::InvalidInput] if `bytes` exceed fixed length of the field or are not ascii digits, padding spaces are allowed
+        pub fn try_from_slice(bytes: &[u8]) -> Result<Self, Error> {
+            validate_string_ascii_fixed::<Self>(bytes, Self::len())?;
+            let digits = std::str::from_utf8(bytes).expect("validated as ascii").trim();
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("{} '{}' is not a number", short_type_name::<Self>(), digits)));
+            }
+            Ok(bytes.into())
+        }
+        /// Returns [ErrorKind::InvalidInput] if `value` exceeds fixed length of the field or is not a number
+        pub fn try_from_str(value: &str) -> Result<Self, Error> {
+            Self::try_from_slice(value.as_bytes())
+        }
+        /// Returns [ErrorKind::InvalidData] if the field, typically received from a peer, is not a number, unlike `From<SequenceNumber> for usize` which panics
+        pub fn try_to_usize(&self) -> Result<usize, Error> {
+            parse_string_ascii_fixed::<Self, usize>(self.as_slice())
+        }
+        /// Returns [ErrorKind::InvalidData] if the field, typically received from a peer, is not a number
+        pub fn try_to_u64(&self) -> Result<u64, Error> {
+            parse_string_ascii_fixed::<Self, u64>(self.as_slice())
+        }
+    }
     impl From<u64> for SequenceNumber {
         fn from(v: u64) -> Self {
             v.to_string().as_bytes().into()
@@ -81,6 +177,26 @@ pub mod soupbintcp_field_types {
             Duration::from_millis(u64::from(v))
         }
     }
+    impl TimeoutMs {
+        /// Returns [ErrorKind::InvalidData] if the field, typically received from a peer, is not a number or exceeds `u16::MAX`, unlike `From<TimeoutMs> for u16` which panics
+        pub fn try_to_u16(&self) -> Result<u16, Error> {
+            parse_string_ascii_fixed::<Self, u16>(self.as_slice())
+        }
+        /// Returns [ErrorKind::InvalidData] if the field is not a valid number of milliseconds, see [Self::try_to_u16]
+        pub fn try_to_duration(&self) -> Result<Duration, Error> {
+            Ok(Duration::from_millis(self.try_to_u16()? as u64))
+        }
+        /// Returns [ErrorKind::InvalidInput] if `v` exceeds `u16::MAX` milliseconds, unlike `From<Duration>` which panics
+        pub fn try_from_duration(v: Duration) -> Result<Self, Error> {
+            if v.as_millis() > u16::MAX as u128 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} {:?} exceeds max of {:?}", short_type_name::<Self>(), v, Duration::from_millis(u16::MAX as u64)),
+                ));
+            }
+            Ok((v.as_millis() as u16).into())
+        }
+    }
     impl From<Duration> for TimeoutMs {
         fn from(v: Duration) -> Self {
             assert!(v.as_millis() <= u16::MAX as u128, "Duration {:?} exceeds max of {:?}", v, Duration::from_millis(u16::MAX as u64));
@@ -114,7 +230,10 @@ pub mod soupbintcp_field_types {
     }
 
     string_ascii_fixed!(UserName, 6, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+    try_from_string_ascii_fixed!(UserName);
     string_ascii_fixed!(Password, 10, b' ', true, true, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
+    try_from_string_ascii_fixed!(Password);
+
     char_ascii!(LoginRejectReason, #[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedSizeOf, ByteSerializedLenOf, PartialEq, Clone, Copy)]);
     impl LoginRejectReason {
         #[inline(always)]
@@ -204,4 +323,69 @@ pub mod soupbintcp_field_types {
             }
         }
     }
+
+    #[cfg(test)]
+    mod test_try_from {
+        use super::{Password, SequenceNumber, SessionId, TimeoutMs, UserName};
+        use links_core::unittest::setup;
+        use log::info;
+        use std::{io::ErrorKind, time::Duration};
+
+        #[test]
+        fn test_try_from_ok() {
+            setup::log::configure();
+            assert_eq!(UserName::try_from_str("userid").unwrap(), b"userid".into());
+            assert_eq!(Password::try_from_slice(b"passwd").unwrap(), b"passwd".as_slice().into());
+            assert_eq!(SessionId::try_from_str("").unwrap(), b"".as_slice().into());
+            assert_eq!(SessionId::try_from_date(2024, 2, 29).unwrap(), b"20240229".as_slice().into());
+            assert_eq!(SequenceNumber::try_from_str("12345678901234567890").unwrap(), 12345678901234567890_u64.into());
+            assert_eq!(SequenceNumber::try_from_str("  1").unwrap(), 1_u64.into());
+            assert_eq!(TimeoutMs::try_from_duration(Duration::from_millis(u16::MAX as u64)).unwrap(), u16::MAX.into());
+        }
+
+        #[test]
+        fn test_try_from_err() {
+            setup::log::configure();
+            let errs = [
+                UserName::try_from_str("1234567").unwrap_err(),
+                UserName::try_from_str("usérid").unwrap_err(),
+                Password::try_from_str("12345678901").unwrap_err(),
+                SessionId::try_from_slice(b"12345678901").unwrap_err(),
+                SessionId::try_from_date(2023, 2, 29).unwrap_err(),
+                SessionId::try_from_date(2024, 13, 1).unwrap_err(),
+                SequenceNumber::try_from_str("123456789012345678901").unwrap_err(),
+                SequenceNumber::try_from_str("1a").unwrap_err(),
+                SequenceNumber::try_from_str("+5").unwrap_err(),
+                SequenceNumber::try_from_str("").unwrap_err(),
+                TimeoutMs::try_from_duration(Duration::from_millis(u16::MAX as u64 + 1)).unwrap_err(),
+            ];
+            for err in errs {
+                info!("err: {}", err);
+                assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            }
+        }
+
+        #[test]
+        fn test_try_to() {
+            setup::log::configure();
+            assert_eq!(SequenceNumber::from(1_usize).try_to_usize().unwrap(), 1);
+            assert_eq!(SequenceNumber::from(u64::MAX).try_to_u64().unwrap(), u64::MAX);
+            assert_eq!(TimeoutMs::from(1000_u16).try_to_duration().unwrap(), Duration::from_millis(1000));
+
+            // peer supplied fields are not validated during deserialization
+            let errs = [
+                SequenceNumber::from(b"1a".as_slice()).try_to_usize().unwrap_err(),
+                SequenceNumber::from(b"".as_slice()).try_to_u64().unwrap_err(),
+                SequenceNumber::from(b"\xff".as_slice()).try_to_u64().unwrap_err(),
+                TimeoutMs::from(b"65536".as_slice()).try_to_u16().unwrap_err(),
+                TimeoutMs::from(b"-1".as_slice()).try_to_duration().unwrap_err(),
+                SequenceNumber::from(b"+5".as_slice()).try_to_usize().unwrap_err(),
+                TimeoutMs::from(b"+5".as_slice()).try_to_u16().unwrap_err(),
+            ];
+            for err in errs {
+                info!("err: {}", err);
+                assert_eq!(err.kind(), ErrorKind::InvalidData);
+            }
+        }
+    }
 }
@@ -30,7 +30,7 @@ impl UPayloadHeader {
 pub struct UPayload<Payload: SoupBinTcpPayload<Payload>> {
     #[serde(skip)]
     header: UPayloadHeader,
-    #[byteserde(deplete ( header.packet_length as usize - 1 ))]
+    #[byteserde(deplete ( (header.packet_length as usize).saturating_sub(1) ))]
     #[serde(flatten)]
     pub payload: Payload,
 }
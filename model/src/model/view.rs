@@ -0,0 +1,208 @@
+use crate::{
+    model::{
+        clt::login_request::LOGIN_REQUEST_BYTE_LEN,
+        svc::{login_accepted::LOGIN_ACCEPTED_BYTE_LEN, login_rejected::LOGIN_REJECTED_BYTE_LEN},
+    },
+    prelude::*,
+};
+use byteserde::prelude::{from_slice, ByteDeserializeSlice, ByteSerializedSizeOf};
+use std::{
+    fmt::{Debug, Display},
+    io::{Error, ErrorKind},
+};
+
+/// Length of the `packet_length` field which is not included in its own value
+pub const PACKET_LENGTH_BYTE_LEN: usize = 2;
+/// Length of the SoupBinTcp header, `packet_length` followed by `packet_type`
+pub const PACKET_HEADER_BYTE_LEN: usize = PACKET_LENGTH_BYTE_LEN + 1;
+
+/// Shortest frame of `packet_type` which can be decoded, frames shorter then this must be rejected before calling `from_slice`
+/// because byteserde `deserialize_u8` panics instead of failing when the buffer is exhausted, example [LoginRejected] with no reason
+#[inline(always)]
+pub fn min_frame_len(packet_type: u8) -> usize {
+    match packet_type {
+        b'J' => PACKET_HEADER_BYTE_LEN + LoginRejectReason::byte_size(),
+        _ => PACKET_HEADER_BYTE_LEN,
+    }
+}
+
+/// Longest frame of `packet_type`, including `packet_length`, which a peer may announce, `max_payload_len` applies to [SPayload] & [UPayload].
+/// Variable length [Debug](crate::prelude::Debug) and unknown packet types are only limited by the `u16` `packet_length`
+#[inline(always)]
+pub fn max_frame_len(packet_type: u8, max_payload_len: usize) -> usize {
+    match packet_type {
+        b'S' | b'U' => PACKET_HEADER_BYTE_LEN + max_payload_len,
+        b'H' | b'Z' | b'R' | b'O' => PACKET_HEADER_BYTE_LEN,
+        b'A' => LOGIN_ACCEPTED_BYTE_LEN,
+        b'J' => LOGIN_REJECTED_BYTE_LEN,
+        b'L' => LOGIN_REQUEST_BYTE_LEN,
+        _ => PACKET_LENGTH_BYTE_LEN + u16::MAX as usize,
+    }
+}
+
+/// Borrowed view over a single SoupBinTcp frame, exposes the header and payload slice without copying so that
+/// the payload can be decoded lazily, or not at all, by the user.
+///
+/// # Example
+/// ```
+/// use soupbintcp_model::prelude::*;
+///
+/// let frame = [0x00, 0x04, b'S', 1, 2, 3];
+/// let view = SoupBinTcpMsgView::try_new(&frame).unwrap();
+/// assert_eq!(view.packet_type(), b'S');
+/// assert_eq!(view.payload(), Some([1_u8, 2, 3].as_slice()));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SoupBinTcpMsgView<'a> {
+    frame: &'a [u8],
+}
+impl<'a> SoupBinTcpMsgView<'a> {
+    /// Validates that `frame` contains exactly one complete SoupBinTcp packet
+    pub fn try_new(frame: &'a [u8]) -> Result<Self, Error> {
+        if frame.len() < PACKET_HEADER_BYTE_LEN {
+            let msg = format!("SoupBinTcpMsgView frame too short, expected at least {} bytes, got {}", PACKET_HEADER_BYTE_LEN, frame.len());
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+        let packet_length = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        if packet_length + PACKET_LENGTH_BYTE_LEN != frame.len() {
+            let msg = format!("SoupBinTcpMsgView packet_length: {} does not match frame length: {}", packet_length, frame.len());
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+        Ok(Self { frame })
+    }
+    /// Value of the `packet_length` field, includes `packet_type` but not itself
+    #[inline(always)]
+    pub fn packet_length(&self) -> u16 {
+        u16::from_be_bytes([self.frame[0], self.frame[1]])
+    }
+    #[inline(always)]
+    pub fn packet_type(&self) -> u8 {
+        self.frame[PACKET_LENGTH_BYTE_LEN]
+    }
+    /// `packet_length` & `packet_type` bytes
+    #[inline(always)]
+    pub fn header(&self) -> &'a [u8] {
+        &self.frame[..PACKET_HEADER_BYTE_LEN]
+    }
+    /// All bytes following the header, for SoupBinTcp session messages these are the message fields
+    #[inline(always)]
+    pub fn body(&self) -> &'a [u8] {
+        &self.frame[PACKET_HEADER_BYTE_LEN..]
+    }
+    /// Entire frame including the header
+    #[inline(always)]
+    pub fn frame(&self) -> &'a [u8] {
+        self.frame
+    }
+    #[inline(always)]
+    pub fn is_sequenced_data(&self) -> bool {
+        self.packet_type() == b'S'
+    }
+    #[inline(always)]
+    pub fn is_unsequenced_data(&self) -> bool {
+        self.packet_type() == b'U'
+    }
+    /// Application payload bytes of [SPayload] or [UPayload] packets, `None` for all other packet types
+    #[inline(always)]
+    pub fn payload(&self) -> Option<&'a [u8]> {
+        if self.is_sequenced_data() || self.is_unsequenced_data() {
+            Some(self.body())
+        } else {
+            None
+        }
+    }
+    /// Decodes the application payload of [SPayload] or [UPayload] packets, returns `None` for all other packet types
+    pub fn decode_payload<P: ByteDeserializeSlice<P>>(&self) -> Option<Result<P, Error>> {
+        self.payload().map(|payload| from_slice::<P>(payload).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+    }
+    fn validate_min_frame_len(&self) -> Result<(), Error> {
+        if self.frame.len() < min_frame_len(self.packet_type()) {
+            let msg = format!("SoupBinTcpMsgView frame too short for packet_type: {:?}, got {} bytes", self.packet_type() as char, self.frame.len());
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+        Ok(())
+    }
+    /// Decodes the entire frame into an owned [CltSoupBinTcpMsg]
+    pub fn to_clt_msg<P: SoupBinTcpPayload<P>>(&self) -> Result<CltSoupBinTcpMsg<P>, Error> {
+        self.validate_min_frame_len()?;
+        from_slice::<CltSoupBinTcpMsg<P>>(self.frame).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+    /// Decodes the entire frame into an owned [SvcSoupBinTcpMsg]
+    pub fn to_svc_msg<P: SoupBinTcpPayload<P>>(&self) -> Result<SvcSoupBinTcpMsg<P>, Error> {
+        self.validate_min_frame_len()?;
+        from_slice::<SvcSoupBinTcpMsg<P>>(self.frame).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+impl Debug for SoupBinTcpMsgView<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SoupBinTcpMsgView")
+            .field("packet_length", &self.packet_length())
+            .field("packet_type", &(self.packet_type() as char))
+            .field("body", &self.body())
+            .finish()
+    }
+}
+impl Display for SoupBinTcpMsgView<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SoupBinTcpMsgView {{ packet_type: {:?}, packet_length: {} }}", self.packet_type() as char, self.packet_length())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use byteserde::prelude::*;
+    use links_core::unittest::setup;
+    use log::{info, LevelFilter};
+
+    #[test]
+    fn test_msg_view_payload() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let msg_inp: SvcSoupBinTcpMsg<SamplePayload> = SvcSoupBinTcpMsg::SPayload(SPayload::new(SamplePayload::default()));
+        let ser: ByteSerializerStack<1024> = to_serializer_stack(&msg_inp).unwrap();
+
+        let view = SoupBinTcpMsgView::try_new(ser.as_slice()).unwrap();
+        info!("view: {:?}", view);
+        assert!(view.is_sequenced_data());
+        assert_eq!(view.packet_length() as usize, ser.len() - 2);
+        assert_eq!(view.header().len(), 3);
+        assert_eq!(view.payload().unwrap().len(), SamplePayload::default().byte_len());
+
+        let payload = view.decode_payload::<SamplePayload>().unwrap().unwrap();
+        assert_eq!(payload, SamplePayload::default());
+        let msg_out = view.to_svc_msg::<SamplePayload>().unwrap();
+        assert_eq!(msg_out, msg_inp);
+    }
+
+    #[test]
+    fn test_msg_view_session_msg() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let msg_inp: CltSoupBinTcpMsg<Nil> = CltSoupBinTcpMsg::HBeat(CltHeartbeat::default());
+        let ser: ByteSerializerStack<128> = to_serializer_stack(&msg_inp).unwrap();
+
+        let view = SoupBinTcpMsgView::try_new(ser.as_slice()).unwrap();
+        info!("view: {}", view);
+        assert_eq!(view.packet_type(), b'R');
+        assert_eq!(view.payload(), None);
+        assert!(view.decode_payload::<Nil>().is_none());
+        assert_eq!(view.to_clt_msg::<Nil>().unwrap(), msg_inp);
+    }
+
+    #[test]
+    fn test_msg_view_invalid() {
+        setup::log::configure_compact(LevelFilter::Info);
+        let err = SoupBinTcpMsgView::try_new(&[0x00, 0x01]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let err = SoupBinTcpMsgView::try_new(&[0x00, 0x05, b'S', 1]).unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // LoginRejected without reason
+        let view = SoupBinTcpMsgView::try_new(&[0x00, 0x01, b'J']).unwrap();
+        let err = view.to_svc_msg::<Nil>().unwrap_err();
+        info!("err: {}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
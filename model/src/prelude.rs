@@ -16,7 +16,10 @@ pub use crate::model::unsequenced_data::UPayloadHeader;
 
 // default payloads
 pub use crate::model::SoupBinTcpPayload; // trait
+pub use crate::model::SOUPBINTCP_MAX_PAYLOAD_BYTE_LEN;
+pub use soupbintcp_derive::SoupBinTcpPayload; // derive
 
+pub use crate::model::payload::InlinePayload;
 pub use crate::model::payload::Nil;
 pub use crate::model::payload::VecPayload;
 pub use crate::model::sample_payload::SamplePayload;
@@ -24,6 +27,10 @@ pub use crate::model::soup_bin::CltSoupBinTcpMsg;
 pub use crate::model::soup_bin::UniSoupBinTcpMsg;
 pub use crate::model::soup_bin::SvcSoupBinTcpMsg;
 pub use crate::model::soup_bin::SOUPBINTCP_MAX_FRAME_SIZE_EXCLUDING_PAYLOAD_DEBUG;
+pub use crate::model::view::{max_frame_len, min_frame_len, SoupBinTcpMsgView, PACKET_HEADER_BYTE_LEN, PACKET_LENGTH_BYTE_LEN};
+
+// moldudp64 dissemination of sequenced payloads
+pub use crate::model::moldudp64::{MoldUdp64Header, MOLDUDP64_END_OF_SESSION_MESSAGE_COUNT, MOLDUDP64_HEADER_BYTE_LEN, MOLDUDP64_HEARTBEAT_MESSAGE_COUNT, MOLDUDP64_MESSAGE_BLOCK_HEADER_BYTE_LEN};
 
 
 // msg field types
@@ -1,3 +1,5 @@
+pub mod strategy;
+
 pub mod setup {
     pub mod model {
         use crate::prelude::*;
@@ -0,0 +1,178 @@
+//! [proptest] strategies for every SoupBinTcp field & packet, along with round trip checks which downstream payload crates
+//! can reuse with their own payload strategies, example `svc_msg(any_my_payload())`
+use crate::prelude::*;
+use byteserde::prelude::*;
+use proptest::{collection::vec, prelude::*, sample::select, test_runner::TestCaseError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// Upper bound for generated [Debug] text and [VecPayload] lengths, keeps frames well under `u16::MAX`
+pub const MAX_GENERATED_LEN: usize = 256;
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+fn ascii_alphanumeric(max_len: usize) -> impl Strategy<Value = Vec<u8>> + Clone {
+    vec(select(ALPHANUMERIC), 0..=max_len)
+}
+
+// fields
+pub fn session_id() -> impl Strategy<Value = SessionId> + Clone {
+    ascii_alphanumeric(SessionId::len()).prop_map(|bytes| SessionId::from(bytes.as_slice()))
+}
+pub fn sequence_number() -> impl Strategy<Value = SequenceNumber> + Clone {
+    any::<u64>().prop_map(SequenceNumber::from)
+}
+pub fn timeout_ms() -> impl Strategy<Value = TimeoutMs> + Clone {
+    any::<u16>().prop_map(TimeoutMs::from)
+}
+pub fn user_name() -> impl Strategy<Value = UserName> + Clone {
+    ascii_alphanumeric(UserName::len()).prop_map(|bytes| UserName::from(bytes.as_slice()))
+}
+pub fn password() -> impl Strategy<Value = Password> + Clone {
+    ascii_alphanumeric(Password::len()).prop_map(|bytes| Password::from(bytes.as_slice()))
+}
+/// Only reasons defined by the spec, json encodes any other reason as `UNKNOWN`
+pub fn login_reject_reason() -> impl Strategy<Value = LoginRejectReason> + Clone {
+    prop_oneof![Just(LoginRejectReason::not_authorized()), Just(LoginRejectReason::session_not_available())]
+}
+/// Printable ascii, including characters which require json escaping
+pub fn debug_text() -> impl Strategy<Value = Vec<u8>> + Clone {
+    vec(0x20_u8..=0x7E, 0..=MAX_GENERATED_LEN)
+}
+pub fn vec_payload() -> impl Strategy<Value = VecPayload> + Clone {
+    vec(any::<u8>(), 0..=MAX_GENERATED_LEN).prop_map(VecPayload::new)
+}
+
+// packets
+pub fn debug() -> impl Strategy<Value = Debug> + Clone {
+    debug_text().prop_map(|text| Debug::new(&text))
+}
+pub fn login_request() -> impl Strategy<Value = LoginRequest> + Clone {
+    (user_name(), password(), session_id(), sequence_number(), timeout_ms())
+        .prop_map(|(username, password, session_id, sequence_number, hbeat_timeout_ms)| LoginRequest::new(username, password, session_id, sequence_number, hbeat_timeout_ms))
+}
+pub fn login_accepted() -> impl Strategy<Value = LoginAccepted> + Clone {
+    (session_id(), sequence_number()).prop_map(|(session_id, sequence_number)| LoginAccepted::new(session_id, sequence_number))
+}
+pub fn login_rejected() -> impl Strategy<Value = LoginRejected> + Clone {
+    login_reject_reason().prop_map(|reason| {
+        if reason.is_not_authorized() {
+            LoginRejected::not_authorized()
+        } else {
+            LoginRejected::session_not_available()
+        }
+    })
+}
+pub fn spayload<P: SoupBinTcpPayload<P> + fmt::Debug>(payload: impl Strategy<Value = P>) -> impl Strategy<Value = SPayload<P>> {
+    payload.prop_map(SPayload::new)
+}
+pub fn upayload<P: SoupBinTcpPayload<P> + fmt::Debug>(payload: impl Strategy<Value = P>) -> impl Strategy<Value = UPayload<P>> {
+    payload.prop_map(UPayload::new)
+}
+
+// messages
+/// Any [CltSoupBinTcpMsg] with application payloads drawn from `payload`
+pub fn clt_msg<P: SoupBinTcpPayload<P> + fmt::Debug>(payload: impl Strategy<Value = P> + Clone) -> impl Strategy<Value = CltSoupBinTcpMsg<P>> {
+    prop_oneof![
+        Just(CltSoupBinTcpMsg::HBeat(CltHeartbeat::default())),
+        debug().prop_map(CltSoupBinTcpMsg::Dbg),
+        login_request().prop_map(CltSoupBinTcpMsg::LoginRequest),
+        Just(CltSoupBinTcpMsg::LogoutRequest(LogoutRequest::default())),
+        spayload(payload.clone()).prop_map(CltSoupBinTcpMsg::SPayload),
+        upayload(payload).prop_map(CltSoupBinTcpMsg::UPayload),
+    ]
+}
+/// Any [SvcSoupBinTcpMsg] with application payloads drawn from `payload`
+pub fn svc_msg<P: SoupBinTcpPayload<P> + fmt::Debug>(payload: impl Strategy<Value = P> + Clone) -> impl Strategy<Value = SvcSoupBinTcpMsg<P>> {
+    prop_oneof![
+        Just(SvcSoupBinTcpMsg::HBeat(SvcHeartbeat::default())),
+        debug().prop_map(SvcSoupBinTcpMsg::Dbg),
+        login_accepted().prop_map(SvcSoupBinTcpMsg::LoginAccepted),
+        login_rejected().prop_map(SvcSoupBinTcpMsg::LoginRejected),
+        Just(SvcSoupBinTcpMsg::EndOfSession(EndOfSession::default())),
+        spayload(payload.clone()).prop_map(SvcSoupBinTcpMsg::SPayload),
+        upayload(payload).prop_map(SvcSoupBinTcpMsg::UPayload),
+    ]
+}
+
+// checks
+/// Serializes `msg` and checks that it deserializes into an equal value and that [ByteSerializedLenOf::byte_len] matches the serialized length
+pub fn check_byteserde_round_trip<T>(msg: &T) -> Result<(), TestCaseError>
+where
+    T: ByteSerializeStack + ByteDeserializeSlice<T> + ByteSerializedLenOf + PartialEq + fmt::Debug,
+{
+    let ser: ByteSerializerStack<{ u16::MAX as usize + 2 }> = to_serializer_stack(msg).map_err(|e| TestCaseError::fail(e.to_string()))?;
+    prop_assert_eq!(msg.byte_len(), ser.len(), "byte_len() does not match serialized length, msg: {:?}", msg);
+    let msg_out: T = from_slice(ser.as_slice()).map_err(|e| TestCaseError::fail(e.to_string()))?;
+    prop_assert_eq!(&msg_out, msg);
+    Ok(())
+}
+/// Serializes `msg` into json and checks that it deserializes into an equal value
+pub fn check_serde_round_trip<T>(msg: &T) -> Result<(), TestCaseError>
+where
+    T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+{
+    let json = serde_json::to_string(msg).map_err(|e| TestCaseError::fail(e.to_string()))?;
+    let msg_out: T = serde_json::from_str(&json).map_err(|e| TestCaseError::fail(format!("{}, json: {}", e, json)))?;
+    prop_assert_eq!(&msg_out, msg, "json: {}", json);
+    Ok(())
+}
+/// Both [check_byteserde_round_trip] & [check_serde_round_trip]
+pub fn check_round_trip<T>(msg: &T) -> Result<(), TestCaseError>
+where
+    T: ByteSerializeStack + ByteDeserializeSlice<T> + ByteSerializedLenOf + Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+{
+    check_byteserde_round_trip(msg)?;
+    check_serde_round_trip(msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_proptest_fields(
+            session_id in session_id(),
+            sequence_number in sequence_number(),
+            timeout_ms in timeout_ms(),
+            user_name in user_name(),
+            password in password(),
+            reason in login_reject_reason(),
+        ) {
+            check_round_trip(&session_id)?;
+            check_round_trip(&sequence_number)?;
+            check_round_trip(&timeout_ms)?;
+            check_round_trip(&user_name)?;
+            check_round_trip(&password)?;
+            check_round_trip(&reason)?;
+        }
+
+        #[test]
+        fn test_proptest_packets(
+            debug in debug(),
+            login_request in login_request(),
+            login_accepted in login_accepted(),
+            login_rejected in login_rejected(),
+            spayload in spayload(vec_payload()),
+            upayload in upayload(vec_payload()),
+        ) {
+            check_round_trip(&debug)?;
+            check_round_trip(&login_request)?;
+            check_round_trip(&login_accepted)?;
+            check_round_trip(&login_rejected)?;
+            check_round_trip(&spayload)?;
+            check_round_trip(&upayload)?;
+        }
+
+        #[test]
+        fn test_proptest_clt_msg(msg in clt_msg(vec_payload())) {
+            check_round_trip(&msg)?;
+        }
+
+        #[test]
+        fn test_proptest_svc_msg(msg in svc_msg(vec_payload())) {
+            check_round_trip(&msg)?;
+        }
+    }
+}
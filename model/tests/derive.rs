@@ -0,0 +1,6 @@
+/// `SoupBinTcpPayload` derive must reject enums whose tags are inconsistent with the wrapped packet types at compile time
+#[test]
+fn test_derive_compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
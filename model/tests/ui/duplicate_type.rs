@@ -0,0 +1,26 @@
+use byteserde_derive::{ByteDeserializeSlice, ByteSerializeStack, ByteSerializedLenOf};
+use soupbintcp_model::prelude::*;
+
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, PartialEq, Debug, Clone, Default)]
+struct EnterOrder {
+    msg_type: u8,
+    qty: u32,
+}
+#[derive(ByteSerializeStack, ByteDeserializeSlice, ByteSerializedLenOf, PartialEq, Debug, Clone, Default)]
+struct CancelOrder {
+    msg_type: u8,
+    order_id: u64,
+}
+
+#[derive(SoupBinTcpPayload, PartialEq, Debug, Clone)]
+#[soupbintcp(no_default)]
+enum OrderPayload {
+    #[soupbintcp(tag = b'O')]
+    EnterOrder(EnterOrder),
+    #[soupbintcp(tag = b'X')]
+    CancelOrder(CancelOrder),
+    #[soupbintcp(tag = b'E')]
+    ReplaceOrder(EnterOrder),
+}
+
+fn main() {}